@@ -0,0 +1,19 @@
+fn main() {
+    #[cfg(feature = "ffi")]
+    generate_header();
+}
+
+/// Writes the C header for the `ffi` module to `include/json_schema_generator.h`.
+#[cfg(feature = "ffi")]
+fn generate_header() {
+    let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+    println!("cargo:rerun-if-changed=src/ffi.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+
+    cbindgen::Builder::new()
+        .with_src(format!("{}/src/ffi.rs", crate_dir))
+        .with_config(cbindgen::Config::from_file(format!("{}/cbindgen.toml", crate_dir)).unwrap())
+        .generate()
+        .expect("failed to generate the C header")
+        .write_to_file(format!("{}/include/json_schema_generator.h", crate_dir));
+}
@@ -0,0 +1,4 @@
+//! The C ABI of the `ffi` feature, built as a shared library. The functions are those of
+//! `json_schema_generator::ffi`, declared in `include/json_schema_generator.h`.
+
+pub use json_schema_generator::ffi::*;
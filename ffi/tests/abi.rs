@@ -0,0 +1,90 @@
+//! Calls the C ABI through the shared library built by this crate, as a C or Python caller would.
+
+use json_schema_generator::ffi::{JSG_ERR_DENIED_WARNING, JSG_ERR_INVALID_INPUT, JSG_OK};
+use libloading::{Library, Symbol};
+use std::env::consts::{DLL_PREFIX, DLL_SUFFIX};
+use std::ffi::{c_char, CStr, CString};
+use std::ptr;
+
+type Generate =
+    unsafe extern "C" fn(*const c_char, *const c_char, *mut *mut c_char, *mut *mut c_char) -> i32;
+type FreeString = unsafe extern "C" fn(*mut c_char);
+
+fn library() -> Library {
+    // Integration tests are built into the same directory as the library.
+    let dir = std::env::current_exe().unwrap();
+    let path = dir
+        .parent()
+        .unwrap()
+        .join(format!("{}json_schema_generator{}", DLL_PREFIX, DLL_SUFFIX));
+    unsafe { Library::new(&path) }.unwrap_or_else(|e| panic!("loading {:?}: {}", path, e))
+}
+
+/// Calls `jsg_generate`, copying and freeing the returned strings.
+fn generate(
+    library: &Library,
+    json: Option<&str>,
+    options: Option<&str>,
+) -> (i32, Option<String>, Option<String>) {
+    let generate: Symbol<Generate> = unsafe { library.get(b"jsg_generate") }.unwrap();
+    let free: Symbol<FreeString> = unsafe { library.get(b"jsg_free_string") }.unwrap();
+
+    let json = json.map(|json| CString::new(json).unwrap());
+    let options = options.map(|options| CString::new(options).unwrap());
+    let as_ptr = |s: &Option<CString>| s.as_ref().map_or(ptr::null(), |s| s.as_ptr());
+    let mut schema = ptr::null_mut();
+    let mut error = ptr::null_mut();
+    let status = unsafe { generate(as_ptr(&json), as_ptr(&options), &mut schema, &mut error) };
+
+    let take = |s: *mut c_char| {
+        if s.is_null() {
+            None
+        } else {
+            let copy = unsafe { CStr::from_ptr(s) }.to_str().unwrap().to_string();
+            unsafe { free(s) };
+            Some(copy)
+        }
+    };
+    (status, take(schema), take(error))
+}
+
+#[test]
+fn test_generate_through_the_abi() {
+    let library = library();
+
+    let (status, schema, error) = generate(&library, Some(r#"{"a": [1, 2]}"#), None);
+    assert_eq!(status, JSG_OK);
+    assert_eq!(error, None);
+    let schema: serde_json::Value = serde_json::from_str(&schema.unwrap()).unwrap();
+    assert_eq!(schema["properties"]["a"]["items"]["type"], "integer");
+
+    let (status, schema, error) = generate(&library, Some("{"), Some("{}"));
+    assert_eq!(status, JSG_ERR_INVALID_INPUT);
+    assert_eq!(schema, None);
+    assert!(error.unwrap().starts_with("invalid input"));
+
+    let (status, _, error) = generate(
+        &library,
+        Some(r#"{"a": null}"#),
+        Some(r#"{"deny_lints": ["always-null"]}"#),
+    );
+    assert_eq!(status, JSG_ERR_DENIED_WARNING);
+    assert!(error.unwrap().contains("[always-null]"));
+}
+
+#[test]
+fn test_null_arguments() {
+    let library = library();
+    let (status, schema, error) = generate(&library, None, None);
+    assert_eq!(status, json_schema_generator::ffi::JSG_ERR_INVALID_ARGUMENT);
+    assert_eq!(schema, None);
+    assert_eq!(error.as_deref(), Some("json must not be null"));
+
+    // Null out pointers discard the result, and freeing null is a no-op.
+    let generate: Symbol<Generate> = unsafe { library.get(b"jsg_generate") }.unwrap();
+    let free: Symbol<FreeString> = unsafe { library.get(b"jsg_free_string") }.unwrap();
+    let json = CString::new("1").unwrap();
+    let status = unsafe { generate(json.as_ptr(), ptr::null(), ptr::null_mut(), ptr::null_mut()) };
+    assert_eq!(status, JSG_OK);
+    unsafe { free(ptr::null_mut()) };
+}
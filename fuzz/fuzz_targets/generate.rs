@@ -0,0 +1,91 @@
+//! Generates schemas for arbitrary JSON values through every entry point, checking that
+//! none of them panics, overflows the stack or blows up in time or memory, and that the
+//! raw path agrees with the `Value` path.
+#![no_main]
+
+use arbitrary::Arbitrary;
+use json_schema_generator::{
+    generate_from_raw, generate_json_schema, lint_schema, schema_stats, SchemaGenerator,
+    SchemaOptions,
+};
+use libfuzzer_sys::fuzz_target;
+use serde_json::value::RawValue;
+use serde_json::{Map, Number, Value};
+
+/// A JSON value as built by `arbitrary`.
+#[derive(Debug, Arbitrary)]
+enum Json {
+    Null,
+    Bool(bool),
+    Integer(i64),
+    Unsigned(u64),
+    Float(f64),
+    String(String),
+    Reference(String),
+    Array(Vec<Json>),
+    Object(Vec<(Key, Json)>),
+}
+
+/// An object key. Numbered keys make it likely that the objects of an array share some
+/// of their properties.
+#[derive(Debug, Arbitrary)]
+enum Key {
+    Named(String),
+    Numbered(u16),
+}
+
+impl From<Key> for String {
+    fn from(key: Key) -> String {
+        match key {
+            Key::Named(name) => name,
+            Key::Numbered(n) => format!("k{}", n),
+        }
+    }
+}
+
+impl From<Json> for Value {
+    fn from(json: Json) -> Value {
+        match json {
+            Json::Null => Value::Null,
+            Json::Bool(b) => Value::Bool(b),
+            Json::Integer(n) => Value::from(n),
+            Json::Unsigned(n) => Value::from(n),
+            Json::Float(f) => Number::from_f64(f).map_or(Value::Null, Value::Number),
+            Json::String(s) => Value::String(s),
+            // Instance `$ref`s are copied into the schema, so they get their own case.
+            Json::Reference(s) => {
+                Value::Object(Map::from_iter([("$ref".to_string(), Value::String(s))]))
+            }
+            Json::Array(items) => Value::Array(items.into_iter().map(Value::from).collect()),
+            Json::Object(entries) => Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (String::from(key), Value::from(value)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+fuzz_target!(|input: Json| {
+    let instance = Value::from(input);
+    let options = SchemaOptions::default();
+
+    let schema = generate_json_schema(&instance);
+    lint_schema(&schema);
+    schema_stats(&schema);
+
+    // serde_json refuses documents nested more than 128 levels deep.
+    let json = instance.to_string();
+    if let Ok(raw) = serde_json::from_str::<&RawValue>(&json) {
+        assert_eq!(generate_from_raw(raw, &options).as_ref(), Ok(&schema));
+    }
+
+    if let Value::Array(records) = &instance {
+        let mut generator = SchemaGenerator::new(options);
+        for record in records {
+            generator.observe(record).unwrap();
+        }
+        generator.warnings();
+    }
+});
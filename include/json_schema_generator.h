@@ -0,0 +1,70 @@
+#ifndef JSON_SCHEMA_GENERATOR_H
+#define JSON_SCHEMA_GENERATOR_H
+
+/* Generated by cbindgen from src/ffi.rs. Do not edit. */
+
+#include <stdint.h>
+
+// The schema was generated.
+#define JSG_OK 0
+
+// A required pointer was null or a string was not valid UTF-8.
+#define JSG_ERR_INVALID_ARGUMENT 1
+
+// The input is not valid JSON.
+#define JSG_ERR_INVALID_INPUT 2
+
+// The options are not a valid options object.
+#define JSG_ERR_INVALID_OPTIONS 3
+
+// Generation was cancelled, see `GeneratorError::Cancelled`.
+#define JSG_ERR_CANCELLED 4
+
+// The input does not validate against the generated schema, see
+// `GeneratorError::Verification`.
+#define JSG_ERR_VERIFICATION 5
+
+// A warning listed in the `deny_lints` option was raised.
+#define JSG_ERR_DENIED_WARNING 6
+
+// The generator panicked.
+#define JSG_ERR_PANIC 7
+
+// With the `strict` option, the samples only merge into a wider schema, see
+// `GeneratorError::Conflict`.
+#define JSG_ERR_CONFLICT 8
+
+// An input exceeded a size limit of the generator, see `GeneratorError::LimitExceeded`.
+#define JSG_ERR_LIMIT_EXCEEDED 9
+
+// A post-processing hook rejected the schema, see `GeneratorError::Transform`.
+#define JSG_ERR_TRANSFORM 10
+
+// Generates the schema of the JSON document `json`.
+//
+// `options_json` is a JSON object such as `{"deny_lints": ["always-null"]}`, or null
+// for the default options. Both strings are borrowed for the duration of the call.
+//
+// Returns [`JSG_OK`] and stores the serialized schema in `*out_schema`, or returns
+// one of the `JSG_ERR_*` codes and stores a description in `*out_error`. The pointer
+// not written to is set to null. Each string stored is owned by the caller and must be
+// released with [`jsg_free_string`]. `out_schema` and `out_error` may be null, in which
+// case the corresponding string is discarded.
+//
+// # Safety
+//
+// `json` and `options_json` must be null or point to NUL-terminated strings, and
+// `out_schema` and `out_error` must be null or valid for writes.
+int32_t jsg_generate(const char *json,
+                     const char *options_json,
+                     char **out_schema,
+                     char **out_error);
+
+// Releases a string returned by [`jsg_generate`]. Passing null does nothing.
+//
+// # Safety
+//
+// `s` must be null or a string returned by this library that has not been freed yet.
+void jsg_free_string(char *s);
+
+#endif  /* JSON_SCHEMA_GENERATOR_H */
@@ -0,0 +1,5 @@
+"""Generate JSON Schema from JSON data."""
+
+from ._native import SchemaError, SchemaGenerator, generate_schema
+
+__all__ = ["SchemaError", "SchemaGenerator", "generate_schema"]
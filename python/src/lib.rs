@@ -0,0 +1,152 @@
+//! Python bindings for `json-schema-generator`.
+
+use json_schema_generator::{GeneratorError, SchemaOptions, Warning};
+use pyo3::create_exception;
+use pyo3::exceptions::{PyException, PyTypeError};
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyString};
+use serde_json::Value;
+
+create_exception!(
+    _native,
+    SchemaError,
+    PyException,
+    "Raised when a schema cannot be generated."
+);
+
+/// The keyword options accepted by `generate_schema` and `SchemaGenerator`.
+#[derive(Default)]
+struct Options {
+    schema: SchemaOptions,
+    deny_lints: Vec<String>,
+}
+
+impl Options {
+    fn extract(options: Option<&Bound<'_, PyDict>>) -> PyResult<Self> {
+        let mut parsed = Options::default();
+        let Some(options) = options else {
+            return Ok(parsed);
+        };
+        for (key, value) in options {
+            match key.extract::<String>()?.as_str() {
+                "verify" => parsed.schema.verify = value.extract()?,
+                "deny_lints" => parsed.deny_lints = value.extract()?,
+                other => {
+                    return Err(PyTypeError::new_err(format!(
+                        "unexpected keyword option '{}'",
+                        other
+                    )))
+                }
+            }
+        }
+        Ok(parsed)
+    }
+
+    /// Fails if one of `warnings` is listed in `deny_lints`.
+    fn check(&self, warnings: &[Warning]) -> PyResult<()> {
+        match warnings
+            .iter()
+            .find(|warning| self.deny_lints.contains(&warning.code))
+        {
+            Some(denied) => Err(SchemaError::new_err(format!("denied warning: {}", denied))),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Converts a JSON string or a Python object made of dicts, lists and scalars.
+fn to_value(data: &Bound<'_, PyAny>) -> PyResult<Value> {
+    if let Ok(json) = data.downcast::<PyString>() {
+        return serde_json::from_str(json.to_str()?)
+            .map_err(|e| SchemaError::new_err(format!("invalid JSON: {}", e)));
+    }
+    pythonize::depythonize(data)
+        .map_err(|e| SchemaError::new_err(format!("unsupported value: {}", e)))
+}
+
+fn to_python(py: Python<'_>, value: &Value) -> PyResult<PyObject> {
+    pythonize::pythonize(py, value)
+        .map(Bound::unbind)
+        .map_err(|e| SchemaError::new_err(e.to_string()))
+}
+
+fn generator_error(error: GeneratorError) -> PyErr {
+    SchemaError::new_err(error.to_string())
+}
+
+/// Generates the schema of `data`, a JSON string or a dict, list or scalar.
+///
+/// Keyword options: `verify` checks that `data` validates against the generated
+/// schema, and `deny_lints` is a list of warning codes that raise `SchemaError`.
+#[pyfunction]
+#[pyo3(signature = (data, **options))]
+fn generate_schema(
+    py: Python<'_>,
+    data: &Bound<'_, PyAny>,
+    options: Option<&Bound<'_, PyDict>>,
+) -> PyResult<PyObject> {
+    let options = Options::extract(options)?;
+    let instance = to_value(data)?;
+
+    let mut generator = json_schema_generator::SchemaGenerator::new(options.schema.clone());
+    generator.observe(&instance).map_err(generator_error)?;
+    options.check(&generator.warnings())?;
+    let schema = generator.finish().map_err(generator_error)?;
+    to_python(py, &schema)
+}
+
+/// Builds a single schema from a sequence of records, such as the rows of a DataFrame.
+///
+/// Accepts the same keyword options as `generate_schema`.
+#[pyclass(name = "SchemaGenerator")]
+struct PySchemaGenerator {
+    generator: json_schema_generator::SchemaGenerator,
+    options: Options,
+}
+
+#[pymethods]
+impl PySchemaGenerator {
+    #[new]
+    #[pyo3(signature = (**options))]
+    fn new(options: Option<&Bound<'_, PyDict>>) -> PyResult<Self> {
+        let options = Options::extract(options)?;
+        Ok(PySchemaGenerator {
+            generator: json_schema_generator::SchemaGenerator::new(options.schema.clone()),
+            options,
+        })
+    }
+
+    /// Merges the schema of `record` into the schema accumulated so far.
+    fn observe(&mut self, record: &Bound<'_, PyAny>) -> PyResult<()> {
+        let record = to_value(record)?;
+        self.generator.observe(&record).map_err(generator_error)
+    }
+
+    /// Returns the schema of all records observed so far.
+    fn schema(&self, py: Python<'_>) -> PyResult<PyObject> {
+        self.options.check(&self.generator.warnings())?;
+        let schema = self.generator.clone().finish().map_err(generator_error)?;
+        to_python(py, &schema)
+    }
+
+    /// Returns the warnings raised so far, as dicts with `code`, `pointer` and `message`.
+    fn warnings(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let warnings = serde_json::to_value(self.generator.warnings())
+            .map_err(|e| SchemaError::new_err(e.to_string()))?;
+        to_python(py, &warnings)
+    }
+
+    /// The number of records observed so far.
+    #[getter]
+    fn records(&self) -> usize {
+        self.generator.records()
+    }
+}
+
+#[pymodule]
+fn _native(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("SchemaError", m.py().get_type::<SchemaError>())?;
+    m.add_function(wrap_pyfunction!(generate_schema, m)?)?;
+    m.add_class::<PySchemaGenerator>()?;
+    Ok(())
+}
@@ -0,0 +1,54 @@
+import json
+
+import pytest
+
+from json_schema_generator import SchemaError, SchemaGenerator, generate_schema
+
+
+def test_generate_from_dict():
+    schema = generate_schema({"name": "John", "tags": ["a"], "address": {"city": "X"}})
+    assert schema["type"] == "object"
+    assert schema["properties"]["tags"] == {"type": "array", "items": {"type": "string"}}
+    assert schema["properties"]["address"]["required"] == ["city"]
+
+
+def test_generate_from_json_string():
+    assert generate_schema('[1, 2.5]') == {"type": "array", "items": {"type": "number"}}
+    assert generate_schema(json.dumps({"a": None}))["properties"]["a"] == {"type": "null"}
+
+
+def test_options():
+    assert generate_schema({"a": 1}, verify=True)["required"] == ["a"]
+    with pytest.raises(SchemaError, match=r"\[always-null\]"):
+        generate_schema({"a": None}, deny_lints=["always-null"])
+    with pytest.raises(TypeError, match="unexpected keyword option 'colour'"):
+        generate_schema({}, colour=True)
+
+
+def test_errors_raise_schema_error():
+    with pytest.raises(SchemaError, match="invalid JSON"):
+        generate_schema("{")
+    with pytest.raises(SchemaError):
+        generate_schema({1: "non-string key"})
+    assert issubclass(SchemaError, Exception)
+
+
+def test_incremental_generator():
+    rows = [{"id": 1, "score": 2}, {"id": 2, "score": 2.5, "note": "x"}]
+    generator = SchemaGenerator()
+    for row in rows:
+        generator.observe(row)
+
+    assert generator.records == 2
+    schema = generator.schema()
+    assert schema["properties"]["score"] == {"type": "number"}
+    assert set(schema["properties"]) == {"id", "score", "note"}
+    assert generator.warnings() == []
+
+
+def test_incremental_generator_warnings():
+    generator = SchemaGenerator(deny_lints=["empty-items"])
+    generator.observe('{"tags": []}')
+    assert generator.warnings()[0]["code"] == "empty-items"
+    with pytest.raises(SchemaError, match="denied warning"):
+        generator.schema()
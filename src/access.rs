@@ -0,0 +1,239 @@
+//! `readOnly` and `writeOnly` annotations for properties recognized by their names,
+//! such as an `id` set by the server or a `password` never sent back.
+
+use serde_json::Value;
+
+/// Which way a property travels in an API, see [`AccessRule`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    /// Set by the server and never sent by clients: `"readOnly": true`.
+    ReadOnly,
+    /// Sent by clients and never returned: `"writeOnly": true`.
+    WriteOnly,
+}
+
+impl Access {
+    const ALL: [Access; 2] = [Access::ReadOnly, Access::WriteOnly];
+
+    fn keyword(self) -> &'static str {
+        match self {
+            Access::ReadOnly => "readOnly",
+            Access::WriteOnly => "writeOnly",
+        }
+    }
+}
+
+/// Marks the properties whose names match `pattern` as `access`, see
+/// [`SchemaOptions::access_rules`](crate::SchemaOptions::access_rules).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccessRule {
+    /// A glob matched against the whole property name, where `*` matches any run of
+    /// characters and `?` any single one.
+    pub pattern: String,
+    pub access: Access,
+}
+
+impl AccessRule {
+    pub fn new(pattern: impl Into<String>, access: Access) -> Self {
+        AccessRule {
+            pattern: pattern.into(),
+            access,
+        }
+    }
+
+    /// The default rules: `id`, `created_at` and `updated_at` are read-only, `password`
+    /// and `*_secret` write-only.
+    pub fn defaults() -> Vec<AccessRule> {
+        vec![
+            AccessRule::new("id", Access::ReadOnly),
+            AccessRule::new("created_at", Access::ReadOnly),
+            AccessRule::new("updated_at", Access::ReadOnly),
+            AccessRule::new("password", Access::WriteOnly),
+            AccessRule::new("*_secret", Access::WriteOnly),
+        ]
+    }
+}
+
+/// Marks the schema `schema` of the property `name` as the first of `rules` matching it
+/// says.
+pub(crate) fn annotate(name: &str, schema: &mut Value, rules: &[AccessRule]) {
+    let Some(access) = access_of(name, rules) else {
+        return;
+    };
+    if let Some(obj) = schema.as_object_mut() {
+        obj.insert(access.keyword().to_string(), Value::Bool(true));
+    }
+}
+
+/// Returns the access the first of `rules` matching the property `name` gives it.
+pub(crate) fn access_of(name: &str, rules: &[AccessRule]) -> Option<Access> {
+    rules
+        .iter()
+        .find(|rule| glob_matches(&rule.pattern, name))
+        .map(|rule| rule.access)
+}
+
+/// Returns the access annotations either of two merged schemas carries, which the merge
+/// keeps as they don't constrain the values.
+pub(crate) fn carried(target: &Value, schema: &Value) -> Vec<Access> {
+    Access::ALL
+        .into_iter()
+        .filter(|access| {
+            let flagged = |schema: &Value| schema.get(access.keyword()) == Some(&Value::Bool(true));
+            flagged(target) || flagged(schema)
+        })
+        .collect()
+}
+
+/// Adds the annotations `carried` returned to the merged schema `schema`.
+pub(crate) fn restore(schema: &mut Value, carried: &[Access]) {
+    if let Some(obj) = schema.as_object_mut() {
+        for access in carried {
+            obj.insert(access.keyword().to_string(), Value::Bool(true));
+        }
+    }
+}
+
+/// Returns whether the glob `pattern` matches the whole of `name`.
+fn glob_matches(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // The position after the last `*` and the name position it was tried at.
+    let mut star = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p + 1, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                // Let the `*` match one more character.
+                Some((after, tried)) => {
+                    p = after;
+                    n = tried + 1;
+                    star = Some((after, tried + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SchemaGenerator, SchemaOptions};
+    use serde_json::json;
+
+    fn generate(records: &[Value]) -> Value {
+        let mut generator = SchemaGenerator::new(SchemaOptions {
+            annotate_access: true,
+            ..Default::default()
+        });
+        for record in records {
+            generator.observe(record).unwrap();
+        }
+        generator.schema()
+    }
+
+    #[test]
+    fn test_glob_matches() {
+        assert!(glob_matches("id", "id"));
+        assert!(!glob_matches("id", "user_id"));
+        assert!(glob_matches("*_secret", "api_secret"));
+        assert!(glob_matches("*_secret", "_secret"));
+        assert!(!glob_matches("*_secret", "secret"));
+        assert!(!glob_matches("*_secret", "api_secret_hint"));
+        assert!(glob_matches("*_id", "a_b_id"));
+        assert!(glob_matches("?id", "xid"));
+        assert!(!glob_matches("?id", "id"));
+        assert!(glob_matches("*", ""));
+        assert!(glob_matches("a*b*c", "aXbYbZc"));
+        assert!(!glob_matches("a*b*c", "aXbYbZ"));
+    }
+
+    #[test]
+    fn test_default_rules_at_any_depth() {
+        let schema = generate(&[json!({
+            "id": 1,
+            "name": "x",
+            "created_at": "2024-01-01",
+            "credentials": {"password": "hunter2", "api_secret": "s"},
+            "tokens": [{"id": "t", "client_secret": "c"}]
+        })]);
+        let properties = &schema["properties"];
+        assert_eq!(
+            properties["id"],
+            json!({"type": "integer", "readOnly": true})
+        );
+        assert_eq!(properties["created_at"]["readOnly"], true);
+        assert!(properties["name"].get("readOnly").is_none());
+        let credentials = &properties["credentials"]["properties"];
+        assert_eq!(credentials["password"]["writeOnly"], true);
+        assert_eq!(credentials["api_secret"]["writeOnly"], true);
+        let token = &properties["tokens"]["items"]["properties"];
+        assert_eq!(token["id"]["readOnly"], true);
+        assert_eq!(token["client_secret"]["writeOnly"], true);
+    }
+
+    #[test]
+    fn test_custom_rules() {
+        let mut generator = SchemaGenerator::new(SchemaOptions {
+            annotate_access: true,
+            access_rules: vec![
+                AccessRule::new("*_at", Access::ReadOnly),
+                AccessRule::new("deleted_*", Access::WriteOnly),
+            ],
+            ..Default::default()
+        });
+        generator
+            .observe(&json!({"id": 1, "deleted_at": null}))
+            .unwrap();
+        let schema = generator.schema();
+        assert!(schema["properties"]["id"].get("readOnly").is_none());
+        // The first matching rule wins.
+        assert_eq!(
+            schema["properties"]["deleted_at"],
+            json!({"type": "null", "readOnly": true})
+        );
+    }
+
+    #[test]
+    fn test_merging_keeps_flags() {
+        let schema = generate(&[json!({"id": 1}), json!({"id": 2.5}), json!({"id": "x"})]);
+        assert_eq!(
+            schema["properties"]["id"],
+            json!({"oneOf": [
+                {"type": "number", "readOnly": true},
+                {"type": "string", "readOnly": true}
+            ]})
+        );
+
+        let mut flagged =
+            json!({"type": "object", "properties": {"a": {"type": "integer", "writeOnly": true}}});
+        let plain = json!({"type": "object", "properties": {"a": {"type": "integer"}, "b": {"type": "string"}}});
+        let options = SchemaOptions::default();
+        let mut state = crate::State::default();
+        let mut ctx = crate::Context::new(&options, &mut state);
+        crate::merge_into(&mut flagged, &plain, &mut ctx, &crate::SchemaPath::root());
+        assert_eq!(flagged["properties"]["a"]["writeOnly"], true);
+        assert!(flagged["properties"]["b"].get("writeOnly").is_none());
+    }
+
+    #[test]
+    fn test_off_by_default() {
+        let mut generator = SchemaGenerator::new(SchemaOptions::default());
+        generator
+            .observe(&json!({"id": 1, "password": "x"}))
+            .unwrap();
+        let schema = generator.schema();
+        assert_eq!(schema["properties"]["id"], json!({"type": "integer"}));
+        assert_eq!(schema["properties"]["password"], json!({"type": "string"}));
+    }
+}
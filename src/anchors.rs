@@ -0,0 +1,359 @@
+//! Names for the definitions hoisted into the root `$defs`, and the anchors that let
+//! consumers refer to them by name rather than by location.
+
+use crate::Draft;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+/// Settings for naming the hoisted definitions by anchors, see
+/// [`SchemaOptions::anchors`](crate::SchemaOptions::anchors).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Anchors {
+    /// The draft to write the anchors for: `"$anchor": "Name"` from 2019-09 on,
+    /// `"$id": "#Name"` on draft-06 and draft-07 and `"id": "#Name"` on draft-04. Up to
+    /// draft-07, whose validators don't look for anchors in `$defs`, the definitions move
+    /// under `definitions`.
+    pub draft: Draft,
+    /// Refer to the definitions as `#Name` rather than `#/$defs/Name`.
+    pub rewrite_references: bool,
+}
+
+impl Default for Anchors {
+    fn default() -> Self {
+        Anchors {
+            draft: Draft::Draft7,
+            rewrite_references: false,
+        }
+    }
+}
+
+impl Anchors {
+    /// Returns the keyword and value naming a definition `name`.
+    fn anchor(&self, name: &str) -> (&'static str, String) {
+        match self.draft {
+            Draft::Draft4 => ("id", format!("#{}", name)),
+            Draft::Draft6 | Draft::Draft7 => ("$id", format!("#{}", name)),
+            Draft::Draft201909 | Draft::Draft202012 => ("$anchor", name.to_string()),
+        }
+    }
+}
+
+/// Returns an unused name for the definition of the variant with the discriminator
+/// `value`: its words in PascalCase, keeping only ASCII letters and digits, numbered if
+/// it is taken. The name is always a valid anchor.
+pub(crate) fn definition_name(value: &str, definitions: &Map<String, Value>) -> String {
+    let mut name: String = value
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            let first = chars.next().unwrap().to_ascii_uppercase();
+            std::iter::once(first).chain(chars).collect::<String>()
+        })
+        .collect();
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        name.insert_str(0, "Variant");
+    }
+
+    let mut candidate = name.clone();
+    let mut number = 2;
+    while definitions.contains_key(&candidate) {
+        candidate = format!("{}{}", name, number);
+        number += 1;
+    }
+    candidate
+}
+
+/// Returns whether `name` is an anchor in every draft having them: an ASCII letter
+/// followed by ASCII letters, digits, `-`, `_` and `.`.
+pub(crate) fn is_anchor(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+/// Names each definition in the root `$defs` of `schema` by an anchor, as `anchors`
+/// asks, moving them under `definitions` for earlier drafts, and rewrites the references
+/// to them. Definitions whose names aren't valid anchors are left without one.
+pub(crate) fn add(schema: &mut Value, anchors: &Anchors) {
+    place(schema, anchors.draft, Some(anchors));
+}
+
+/// Moves the definitions in the root `$defs` of `schema` under `definitions` if `draft`
+/// is draft-07 or earlier, rewriting the references to them.
+pub(crate) fn relocate(schema: &mut Value, draft: Draft) {
+    if draft.definitions_keyword() != "$defs" {
+        place(schema, draft, None);
+    }
+}
+
+/// Moves the definitions in the root `$defs` of `schema` where `draft` has them, naming
+/// them by anchors if `anchors` is set, and rewrites the references to them.
+fn place(schema: &mut Value, draft: Draft, anchors: Option<&Anchors>) {
+    let Some(obj) = schema.as_object_mut() else {
+        return;
+    };
+    let Some(Value::Object(mut definitions)) = obj.remove("$defs") else {
+        return;
+    };
+    let mut references = BTreeMap::new();
+    for (name, definition) in definitions.iter_mut() {
+        let anchors = anchors.filter(|_| is_anchor(name));
+        if let (Some(anchors), Some(definition)) = (anchors, definition.as_object_mut()) {
+            let (keyword, anchor) = anchors.anchor(name);
+            definition.insert(keyword.to_string(), Value::String(anchor));
+        }
+        let reference = match anchors {
+            Some(anchors) if anchors.rewrite_references => format!("#{}", name),
+            _ => format!("#/{}/{}", draft.definitions_keyword(), escape(name)),
+        };
+        references.insert(format!("#/$defs/{}", escape(name)), reference);
+    }
+    obj.insert(
+        draft.definitions_keyword().to_string(),
+        Value::Object(definitions),
+    );
+    rewrite(schema, &references);
+}
+
+/// Escapes `name` as a JSON Pointer segment.
+fn escape(name: &str) -> String {
+    name.replace('~', "~0").replace('/', "~1")
+}
+
+/// Rewrites the `$ref`s and discriminator mappings in `schema` that are keys of
+/// `references` into their values.
+fn rewrite(schema: &mut Value, references: &BTreeMap<String, String>) {
+    let rewritten = |reference: &mut Value| {
+        let replacement = reference
+            .as_str()
+            .and_then(|reference| references.get(reference));
+        if let Some(replacement) = replacement {
+            *reference = Value::String(replacement.clone());
+        }
+    };
+    match schema {
+        Value::Object(obj) => {
+            for (keyword, value) in obj.iter_mut() {
+                match (keyword.as_str(), value) {
+                    ("$ref", reference) => rewritten(reference),
+                    ("discriminator", Value::Object(discriminator)) => {
+                        if let Some(Value::Object(mapping)) = discriminator.get_mut("mapping") {
+                            mapping.values_mut().for_each(rewritten);
+                        }
+                    }
+                    (_, value) => rewrite(value, references),
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(|item| rewrite(item, references)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DiscriminatorStyle, Discriminators, SchemaGenerator, SchemaOptions};
+    use serde_json::json;
+
+    fn generate(records: &[Value], anchors: Anchors) -> Value {
+        let mut generator = SchemaGenerator::new(SchemaOptions {
+            discriminators: Some(Discriminators {
+                style: DiscriminatorStyle::OpenApi,
+                ..Default::default()
+            }),
+            anchors: Some(anchors),
+            ..Default::default()
+        });
+        for record in records {
+            generator.observe(record).unwrap();
+        }
+        let mut schema = generator.schema();
+        schema["$schema"] = json!(anchors_draft(&schema).uri());
+        schema
+    }
+
+    /// The draft the anchors of `schema` were written for.
+    fn anchors_draft(schema: &Value) -> Draft {
+        if schema.get("$defs").is_some() {
+            Draft::Draft202012
+        } else {
+            Draft::Draft7
+        }
+    }
+
+    fn events() -> Vec<Value> {
+        (0..4)
+            .map(|i| match i % 2 {
+                0 => json!({"type": "page_view", "id": i, "path": "/"}),
+                _ => json!({"type": "order.created/v2", "id": i, "total": 9.99}),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_definition_names() {
+        let mut definitions = Map::new();
+        assert_eq!(definition_name("page_view", &definitions), "PageView");
+        assert_eq!(
+            definition_name("order.created", &definitions),
+            "OrderCreated"
+        );
+        assert_eq!(definition_name("2fa", &definitions), "Variant2fa");
+        assert_eq!(definition_name("", &definitions), "Variant");
+        definitions.insert("Click".to_string(), json!({}));
+        assert_eq!(definition_name("click", &definitions), "Click2");
+        for value in ["café #1", "order.created/v2", "100%", "ünïcode"] {
+            let name = definition_name(value, &definitions);
+            assert!(is_anchor(&name), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_is_anchor() {
+        assert!(is_anchor("Click"));
+        assert!(is_anchor("a-b_c.d2"));
+        assert!(!is_anchor(""));
+        assert!(!is_anchor("2fa"));
+        assert!(!is_anchor("_private"));
+        assert!(!is_anchor("a/b"));
+        assert!(!is_anchor("a:b"));
+        assert!(!is_anchor("café"));
+    }
+
+    #[test]
+    fn test_anchors_by_draft() {
+        let later = generate(
+            &events(),
+            Anchors {
+                draft: Draft::Draft202012,
+                ..Default::default()
+            },
+        );
+        assert_eq!(later["$defs"]["PageView"]["$anchor"], "PageView");
+        // The value needed sanitizing into a name.
+        assert_eq!(
+            later["$defs"]["OrderCreatedV2"]["$anchor"],
+            "OrderCreatedV2"
+        );
+        assert_eq!(later["oneOf"][0], json!({"$ref": "#/$defs/OrderCreatedV2"}));
+
+        let earlier = generate(&events(), Anchors::default());
+        assert_eq!(earlier["definitions"]["PageView"]["$id"], "#PageView");
+        assert!(earlier["definitions"]["PageView"].get("$anchor").is_none());
+        assert!(earlier.get("$defs").is_none());
+        assert_eq!(
+            earlier["oneOf"][1],
+            json!({"$ref": "#/definitions/PageView"})
+        );
+
+        let mut schema = json!({
+            "$defs": {"Ok": {}, "not/ok": {}},
+            "oneOf": [{"$ref": "#/$defs/Ok"}, {"$ref": "#/$defs/not~1ok"}],
+        });
+        add(
+            &mut schema,
+            &Anchors {
+                draft: Draft::Draft4,
+                rewrite_references: true,
+            },
+        );
+        assert_eq!(
+            schema,
+            json!({
+                "definitions": {"Ok": {"id": "#Ok"}, "not/ok": {}},
+                "oneOf": [{"$ref": "#Ok"}, {"$ref": "#/definitions/not~1ok"}],
+            })
+        );
+    }
+
+    #[test]
+    fn test_rewrite_references() {
+        let schema = generate(
+            &events(),
+            Anchors {
+                draft: Draft::Draft202012,
+                rewrite_references: true,
+            },
+        );
+        assert_eq!(
+            schema["oneOf"],
+            json!([{"$ref": "#OrderCreatedV2"}, {"$ref": "#PageView"}])
+        );
+        assert_eq!(
+            schema["discriminator"]["mapping"],
+            json!({"order.created/v2": "#OrderCreatedV2", "page_view": "#PageView"})
+        );
+
+        // References to other locations are left alone.
+        let mut schema = json!({
+            "$defs": {"A": {"type": "string"}},
+            "properties": {
+                "a": {"$ref": "#/$defs/A"},
+                "b": {"$ref": "#/$defs/B"},
+                "c": {"$ref": "other.json#/$defs/A"},
+            },
+        });
+        add(
+            &mut schema,
+            &Anchors {
+                draft: Draft::Draft201909,
+                rewrite_references: true,
+            },
+        );
+        assert_eq!(
+            schema["properties"],
+            json!({
+                "a": {"$ref": "#A"},
+                "b": {"$ref": "#/$defs/B"},
+                "c": {"$ref": "other.json#/$defs/A"},
+            })
+        );
+    }
+
+    #[test]
+    fn test_relocate() {
+        let schema = json!({
+            "$defs": {"A": {"type": "string"}},
+            "oneOf": [{"$ref": "#/$defs/A"}, {"$ref": "#/$defs/B"}],
+        });
+        let mut earlier = schema.clone();
+        relocate(&mut earlier, Draft::Draft6);
+        assert_eq!(
+            earlier,
+            json!({
+                "definitions": {"A": {"type": "string"}},
+                "oneOf": [{"$ref": "#/definitions/A"}, {"$ref": "#/$defs/B"}],
+            })
+        );
+        let mut later = schema.clone();
+        relocate(&mut later, Draft::Draft201909);
+        assert_eq!(later, schema);
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_references_resolve() {
+        for draft in [Draft::Draft7, Draft::Draft202012] {
+            for rewrite_references in [false, true] {
+                let schema = generate(
+                    &events(),
+                    Anchors {
+                        draft,
+                        rewrite_references,
+                    },
+                );
+                for record in events() {
+                    assert!(
+                        crate::validation::validate_instance(&schema, &record).is_ok(),
+                        "{}",
+                        schema
+                    );
+                }
+                let mixed = json!({"type": "page_view", "id": "1", "path": "/"});
+                assert!(crate::validation::validate_instance(&schema, &mixed).is_err());
+            }
+        }
+    }
+}
@@ -0,0 +1,123 @@
+//! Callers' annotations, such as titles and descriptions, added to each schema of a
+//! finished schema by [`SchemaOptions::annotator`](crate::SchemaOptions::annotator).
+
+use crate::AnnotateHook;
+use serde_json::Value;
+
+/// Calls `hook` with each schema object in `schema`, whose location is `pointer`, and
+/// its location, before its subschemas.
+pub(crate) fn apply(schema: &mut Value, pointer: &str, hook: &AnnotateHook) {
+    let Some(obj) = schema.as_object_mut() else {
+        return;
+    };
+    hook(pointer, obj);
+    for (keyword, value) in obj.iter_mut() {
+        let at = format!("{}/{}", pointer, escape(keyword));
+        match (keyword.as_str(), value) {
+            (
+                "properties" | "patternProperties" | "$defs" | "definitions" | "dependentSchemas",
+                Value::Object(schemas),
+            ) => {
+                for (name, schema) in schemas.iter_mut() {
+                    apply(schema, &format!("{}/{}", at, escape(name)), hook);
+                }
+            }
+            ("items" | "prefixItems" | "oneOf" | "anyOf" | "allOf", Value::Array(schemas)) => {
+                for (index, schema) in schemas.iter_mut().enumerate() {
+                    apply(schema, &format!("{}/{}", at, index), hook);
+                }
+            }
+            (
+                "items"
+                | "additionalItems"
+                | "additionalProperties"
+                | "unevaluatedProperties"
+                | "not"
+                | "if"
+                | "then"
+                | "else",
+                schema,
+            ) => apply(schema, &at, hook),
+            _ => {}
+        }
+    }
+}
+
+/// Escapes `name` as a JSON Pointer segment.
+fn escape(name: &str) -> String {
+    name.replace('~', "~0").replace('/', "~1")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{SchemaGenerator, SchemaOptions, Tuples};
+    use serde_json::json;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_nested_description_survives_merging() {
+        let options = SchemaOptions::default().with_annotator(|pointer: &str, schema| {
+            if pointer == "/properties/users/items/properties/email" {
+                schema.insert("description".to_string(), json!("Where to write"));
+            }
+        });
+        let mut generator = SchemaGenerator::new(options);
+        for record in [
+            json!({"users": [{"email": "a@example.com"}, {"email": "b@example.com", "age": 3}]}),
+            json!({"users": [{"email": null}]}),
+        ] {
+            generator.observe(&record).unwrap();
+        }
+        assert_eq!(
+            generator.schema()["properties"]["users"]["items"]["properties"]["email"],
+            json!({"type": ["string", "null"], "description": "Where to write"})
+        );
+    }
+
+    #[test]
+    fn test_every_node() {
+        let pointers = Arc::new(Mutex::new(Vec::new()));
+        let seen = pointers.clone();
+        let options = SchemaOptions {
+            tuples: Some(Tuples::default()),
+            ..SchemaOptions::default().with_annotator(move |pointer: &str, schema| {
+                seen.lock().unwrap().push(pointer.to_string());
+                if schema.get("type") == Some(&json!("integer")) {
+                    schema.insert("x-kind".to_string(), json!("count"));
+                }
+            })
+        };
+        let instance = json!({"id": [1, "a"], "a/b": [{"c": true}], "pair": ["x", 2]});
+        let schema = crate::try_generate_json_schema(&instance, &options).unwrap();
+        let mut pointers = pointers.lock().unwrap().clone();
+        pointers.sort();
+        assert_eq!(
+            pointers,
+            [
+                "",
+                "/properties/a~1b",
+                "/properties/a~1b/items",
+                "/properties/a~1b/items/properties/c",
+                "/properties/id",
+                "/properties/id/items/0",
+                "/properties/id/items/1",
+                "/properties/pair",
+                "/properties/pair/items/0",
+                "/properties/pair/items/1",
+            ]
+        );
+        assert_eq!(schema["properties"]["pair"]["items"][1]["x-kind"], "count");
+
+        // Inside the branches of a union.
+        let options = SchemaOptions::default().with_annotator(|pointer: &str, schema| {
+            if pointer == "/items/oneOf/1" {
+                schema.insert("title".to_string(), json!("Text"));
+            }
+        });
+        let schema = crate::try_generate_json_schema(&json!([1, "a"]), &options).unwrap();
+        assert_eq!(
+            schema["items"]["oneOf"][1],
+            json!({"type": "string", "title": "Text"})
+        );
+    }
+}
@@ -0,0 +1,471 @@
+//! The bounds of the values seen at a location: the `minimum` and `maximum` of numbers,
+//! see [`SchemaOptions::numeric_bounds`](crate::SchemaOptions::numeric_bounds), the
+//! `minLength` and `maxLength` of strings, see
+//! [`SchemaOptions::string_lengths`](crate::SchemaOptions::string_lengths), and the
+//! `minItems` and `maxItems` of arrays, see
+//! [`SchemaOptions::array_lengths`](crate::SchemaOptions::array_lengths).
+
+use serde_json::{Number, Value};
+use std::cmp::Ordering;
+
+/// A pair of keywords bounding the values of some types.
+pub(crate) struct Bounds {
+    lower: &'static str,
+    upper: &'static str,
+    types: &'static [&'static str],
+}
+
+/// The `minimum` and `maximum` of numbers.
+pub(crate) const NUMBERS: Bounds = Bounds {
+    lower: "minimum",
+    upper: "maximum",
+    types: &["integer", "number"],
+};
+
+/// The `minLength` and `maxLength` of strings.
+pub(crate) const LENGTHS: Bounds = Bounds {
+    lower: "minLength",
+    upper: "maxLength",
+    types: &["string"],
+};
+
+/// The `minItems` and `maxItems` of arrays.
+pub(crate) const ITEMS: Bounds = Bounds {
+    lower: "minItems",
+    upper: "maxItems",
+    types: &["array"],
+};
+
+/// Settings for inferring the lengths of strings, see
+/// [`SchemaOptions::string_lengths`](crate::SchemaOptions::string_lengths).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StringLengths {
+    /// How many strings must be seen at a location for their lengths to be written. 1
+    /// constrains a string seen only once to its exact length.
+    pub min_samples: usize,
+}
+
+impl Default for StringLengths {
+    fn default() -> Self {
+        StringLengths { min_samples: 2 }
+    }
+}
+
+impl Bounds {
+    /// Writes the bounds `lower` and `upper` of the schema `schema`, unless it already has
+    /// one, such as those of a coordinate.
+    pub(crate) fn record(&self, schema: &mut Value, lower: Number, upper: Number) {
+        let Some(obj) = schema.as_object_mut() else {
+            return;
+        };
+        if obj.contains_key(self.lower) || obj.contains_key(self.upper) {
+            return;
+        }
+        obj.insert(self.lower.to_string(), Value::Number(lower));
+        obj.insert(self.upper.to_string(), Value::Number(upper));
+    }
+
+    /// Returns the bounds of the merge of two schemas with bounds: the lower of their
+    /// lower bounds and the higher of their upper ones.
+    pub(crate) fn widened(&self, target: &Value, schema: &Value) -> Option<(Value, Value)> {
+        let bounds = |schema: &'_ Value| -> Option<(Number, Number)> {
+            match (schema.get(self.lower)?, schema.get(self.upper)?) {
+                (Value::Number(lower), Value::Number(upper)) => {
+                    Some((lower.clone(), upper.clone()))
+                }
+                _ => None,
+            }
+        };
+        let (left, right) = (bounds(target)?, bounds(schema)?);
+        let lower = match compare(&left.0, &right.0) {
+            Ordering::Greater => right.0,
+            _ => left.0,
+        };
+        let upper = match compare(&left.1, &right.1) {
+            Ordering::Less => right.1,
+            _ => left.1,
+        };
+        Some((Value::Number(lower), Value::Number(upper)))
+    }
+
+    /// Adds the bounds `widened` returned to the merged schema `schema`, if it is of one
+    /// of the bounded types.
+    pub(crate) fn restore(&self, schema: &mut Value, widened: Option<(Value, Value)>) {
+        let Some((lower, upper)) = widened else {
+            return;
+        };
+        let bounded = schema
+            .get("type")
+            .and_then(Value::as_str)
+            .is_some_and(|kind| self.types.contains(&kind));
+        if let (true, Some(obj)) = (bounded, schema.as_object_mut()) {
+            obj.insert(self.lower.to_string(), lower);
+            obj.insert(self.upper.to_string(), upper);
+        }
+    }
+
+    /// Removes both bounds from the schema `schema`.
+    pub(crate) fn remove(&self, schema: &mut Value) {
+        if let Some(obj) = schema.as_object_mut() {
+            obj.remove(self.lower);
+            obj.remove(self.upper);
+        }
+    }
+
+    /// Whether `keyword` is one of the bounds.
+    pub(crate) fn contains(&self, keyword: &str) -> bool {
+        keyword == self.lower || keyword == self.upper
+    }
+}
+
+/// Compares two JSON numbers without rounding integers beyond 2^53 to floats.
+fn compare(a: &Number, b: &Number) -> Ordering {
+    let integer = |n: &Number| {
+        n.as_i64()
+            .map(i128::from)
+            .or_else(|| n.as_u64().map(i128::from))
+    };
+    let float = |n: &Number| n.as_f64().unwrap_or_default();
+    match (integer(a), integer(b)) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        (Some(a), None) => compare_to_float(a, float(b)),
+        (None, Some(b)) => compare_to_float(b, float(a)).reverse(),
+        (None, None) => float(a).total_cmp(&float(b)),
+    }
+}
+
+/// Compares the integer `i` to the float `f`.
+fn compare_to_float(i: i128, f: f64) -> Ordering {
+    // Far beyond the integers of JSON numbers, which fit in an i64 or u64.
+    let limit = 2f64.powi(70);
+    let whole = f.floor();
+    if whole >= limit {
+        return Ordering::Less;
+    }
+    if whole <= -limit {
+        return Ordering::Greater;
+    }
+    match i.cmp(&(whole as i128)) {
+        Ordering::Equal if f > whole => Ordering::Less,
+        ordering => ordering,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{generate_from_raw, SchemaGenerator, SchemaOptions};
+    use serde_json::json;
+
+    fn generate(records: &[Value]) -> Value {
+        let mut generator = SchemaGenerator::new(SchemaOptions {
+            numeric_bounds: true,
+            ..Default::default()
+        });
+        for record in records {
+            generator.observe(record).unwrap();
+        }
+        generator.schema()
+    }
+
+    #[test]
+    fn test_ages() {
+        let ages: Vec<Value> = (18..=65).rev().map(|age| json!({ "age": age })).collect();
+        let schema = generate(&ages);
+        assert_eq!(
+            schema["properties"]["age"],
+            json!({"type": "integer", "minimum": 18, "maximum": 65})
+        );
+
+        let options = SchemaOptions {
+            numeric_bounds: true,
+            ..Default::default()
+        };
+        let raw = serde_json::value::to_raw_value(&ages).unwrap();
+        assert_eq!(
+            generate_from_raw(&raw, &options).unwrap()["items"]["properties"],
+            schema["properties"]
+        );
+        // Off by default.
+        let plain = crate::generate_schema_from_samples(&ages);
+        assert_eq!(plain["properties"]["age"], json!({"type": "integer"}));
+    }
+
+    #[test]
+    fn test_integers_and_floats() {
+        let schema = generate(&[json!(3), json!(3.5)]);
+        assert_eq!(
+            schema,
+            json!({"type": "number", "minimum": 3, "maximum": 3.5})
+        );
+        let schema = generate(&[json!(-0.5), json!(2), json!(-1)]);
+        assert_eq!(
+            schema,
+            json!({"type": "number", "minimum": -1, "maximum": 2})
+        );
+    }
+
+    #[test]
+    fn test_precision() {
+        // Both are 2^63 as floats.
+        let schema = generate(&[json!(i64::MAX), json!(i64::MAX - 1)]);
+        assert_eq!(schema["minimum"], json!(i64::MAX - 1));
+        assert_eq!(schema["maximum"], json!(i64::MAX));
+        let schema = generate(&[json!(u64::MAX), json!(u64::MAX - 1), json!(-1)]);
+        assert_eq!(schema["minimum"], json!(-1));
+        assert_eq!(schema["maximum"], json!(u64::MAX));
+
+        let n = |value: Value| match value {
+            Value::Number(n) => n,
+            _ => unreachable!(),
+        };
+        assert_eq!(compare(&n(json!(3)), &n(json!(3.5))), Ordering::Less);
+        assert_eq!(compare(&n(json!(3.0)), &n(json!(3))), Ordering::Equal);
+        assert_eq!(compare(&n(json!(-3.5)), &n(json!(-3))), Ordering::Less);
+        assert_eq!(
+            compare(
+                &n(json!(9007199254740993_i64)),
+                &n(json!(9007199254740992.0))
+            ),
+            Ordering::Greater
+        );
+        assert_eq!(
+            compare(&n(json!(1e300)), &n(json!(u64::MAX))),
+            Ordering::Greater
+        );
+    }
+
+    #[test]
+    fn test_merge_widens() {
+        let options = SchemaOptions {
+            numeric_bounds: true,
+            ..Default::default()
+        };
+        let mut state = crate::State::default();
+        let mut ctx = crate::Context::new(&options, &mut state);
+        let mut target = json!({"type": "integer", "minimum": 18, "maximum": 65});
+        let path = crate::SchemaPath::root();
+        crate::merge_into(
+            &mut target,
+            &json!({"type": "integer", "minimum": 16, "maximum": 30}),
+            &mut ctx,
+            &path,
+        );
+        assert_eq!(
+            target,
+            json!({"type": "integer", "minimum": 16, "maximum": 65})
+        );
+        crate::merge_into(
+            &mut target,
+            &json!({"type": "number", "minimum": 70.5, "maximum": 70.5}),
+            &mut ctx,
+            &path,
+        );
+        assert_eq!(
+            target,
+            json!({"type": "number", "minimum": 16, "maximum": 70.5})
+        );
+        // A schema without bounds leaves the merge unbounded.
+        crate::merge_into(&mut target, &json!({"type": "number"}), &mut ctx, &path);
+        assert_eq!(target, json!({"type": "number"}));
+    }
+
+    #[test]
+    fn test_non_negative_minimum() {
+        let options = SchemaOptions {
+            numeric_bounds: true,
+            non_negative_minimum: true,
+            ..Default::default()
+        };
+        let generate =
+            |records: &[Value]| crate::try_generate_schema_from_samples(records, &options).unwrap();
+        let ids: Vec<Value> = (1..=500).map(|id| json!({ "id": id })).collect();
+        assert_eq!(
+            generate(&ids)["properties"]["id"],
+            json!({"type": "integer", "minimum": 0, "maximum": 500})
+        );
+        // A negative integer, or floats alone, keep the lowest seen.
+        assert_eq!(
+            generate(&[json!(4), json!(-2)]),
+            json!({"type": "integer", "minimum": -2, "maximum": 4})
+        );
+        assert_eq!(
+            generate(&[json!(1.5), json!(2.5)]),
+            json!({"type": "number", "minimum": 1.5, "maximum": 2.5})
+        );
+        assert_eq!(
+            generate(&[json!(3), json!(3.5)]),
+            json!({"type": "number", "minimum": 0, "maximum": 3.5})
+        );
+
+        let raw = serde_json::value::to_raw_value(&ids).unwrap();
+        assert_eq!(
+            generate_from_raw(&raw, &options).unwrap()["items"]["properties"],
+            generate(&ids)["properties"]
+        );
+        // Without numeric_bounds, nothing is bounded.
+        let unbounded = SchemaOptions {
+            non_negative_minimum: true,
+            ..Default::default()
+        };
+        let schema = crate::try_generate_schema_from_samples(&ids, &unbounded).unwrap();
+        assert_eq!(schema["properties"]["id"], json!({"type": "integer"}));
+    }
+
+    fn lengths(min_samples: usize) -> SchemaOptions {
+        SchemaOptions {
+            string_lengths: Some(StringLengths { min_samples }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_country_codes() {
+        let records: Vec<Value> = ["DE", "FR", "US", "JP"]
+            .iter()
+            .map(|code| json!({"country_code": code, "name": format!("{} office", code)}))
+            .collect();
+        let options = lengths(2);
+        let schema = crate::try_generate_schema_from_samples(&records, &options).unwrap();
+        assert_eq!(
+            schema["properties"]["country_code"],
+            json!({"type": "string", "minLength": 2, "maxLength": 2})
+        );
+        assert_eq!(
+            schema["properties"]["name"],
+            json!({"type": "string", "minLength": 9, "maxLength": 9})
+        );
+
+        let raw = serde_json::value::to_raw_value(&records).unwrap();
+        assert_eq!(
+            generate_from_raw(&raw, &options).unwrap()["items"]["properties"],
+            schema["properties"]
+        );
+        // Off by default.
+        let plain = crate::generate_schema_from_samples(&records);
+        assert_eq!(
+            plain["properties"]["country_code"],
+            json!({"type": "string"})
+        );
+    }
+
+    #[test]
+    fn test_characters_and_samples() {
+        let schema =
+            crate::try_generate_schema_from_samples(&[json!("äöü"), json!("€")], &lengths(2))
+                .unwrap();
+        assert_eq!(
+            schema,
+            json!({"type": "string", "minLength": 1, "maxLength": 3})
+        );
+
+        // A single string says little about the lengths of others.
+        let single = [json!({"code": "DE"})];
+        let schema = crate::try_generate_schema_from_samples(&single, &lengths(2)).unwrap();
+        assert_eq!(schema["properties"]["code"], json!({"type": "string"}));
+        let schema = crate::try_generate_schema_from_samples(&single, &lengths(1)).unwrap();
+        assert_eq!(
+            schema["properties"]["code"],
+            json!({"type": "string", "minLength": 2, "maxLength": 2})
+        );
+
+        let nullable = [json!({"c": "ab"}), json!({"c": null}), json!({"c": "abcd"})];
+        let schema = crate::try_generate_schema_from_samples(&nullable, &lengths(2)).unwrap();
+        assert_eq!(
+            schema["properties"]["c"],
+            json!({"type": ["string", "null"], "minLength": 2, "maxLength": 4})
+        );
+    }
+
+    #[test]
+    fn test_shortest_and_longest() {
+        let records: Vec<Value> = ["abc", "abcdefg", "abcdefghijkl"]
+            .iter()
+            .map(|name| json!({ "name": name }))
+            .collect();
+        let schema = crate::try_generate_schema_from_samples(&records, &lengths(2)).unwrap();
+        assert_eq!(
+            schema["properties"]["name"],
+            json!({"type": "string", "minLength": 3, "maxLength": 12})
+        );
+        // A single instance is no basis for lengths.
+        let schema = crate::generate_json_schema(&json!({"name": ["abc", "abcdefg"]}));
+        assert_eq!(
+            schema["properties"]["name"]["items"],
+            json!({"type": "string"})
+        );
+    }
+
+    #[test]
+    fn test_merge_widens_lengths() {
+        let options = lengths(1);
+        let mut state = crate::State::default();
+        let mut ctx = crate::Context::new(&options, &mut state);
+        let mut target = json!({"type": "string", "minLength": 2, "maxLength": 2});
+        let path = crate::SchemaPath::root();
+        crate::merge_into(
+            &mut target,
+            &json!({"type": "string", "minLength": 5, "maxLength": 8}),
+            &mut ctx,
+            &path,
+        );
+        assert_eq!(
+            target,
+            json!({"type": "string", "minLength": 2, "maxLength": 8})
+        );
+    }
+
+    #[test]
+    fn test_array_lengths() {
+        let options = SchemaOptions {
+            array_lengths: true,
+            ..Default::default()
+        };
+        let generate =
+            |records: &[Value]| crate::try_generate_schema_from_samples(records, &options).unwrap();
+        assert_eq!(
+            generate(&[json!([1, 2, 3]), json!([1])]),
+            json!({"type": "array", "items": {"type": "integer"}, "minItems": 1, "maxItems": 3})
+        );
+        let tags: Vec<Value> = (0..1000)
+            .map(|i| json!({"tags": vec!["t"; i % 4 + 1]}))
+            .collect();
+        assert_eq!(
+            generate(&tags)["properties"]["tags"],
+            json!({"type": "array", "items": {"type": "string"}, "minItems": 1, "maxItems": 4})
+        );
+        assert_eq!(
+            generate(&[json!([])]),
+            json!({"type": "array", "items": {}, "minItems": 0, "maxItems": 0})
+        );
+        let omit = SchemaOptions {
+            empty_containers: crate::EmptyContainers::Omit,
+            ..options.clone()
+        };
+        assert_eq!(
+            crate::try_generate_json_schema(&json!([]), &omit).unwrap(),
+            json!({"type": "array", "minItems": 0, "maxItems": 0})
+        );
+
+        let raw = serde_json::value::to_raw_value(&tags).unwrap();
+        assert_eq!(
+            generate_from_raw(&raw, &options).unwrap()["items"]["properties"],
+            generate(&tags)["properties"]
+        );
+        // Off by default.
+        let plain = crate::generate_schema_from_samples(&tags);
+        assert!(plain["properties"]["tags"].get("minItems").is_none());
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_samples_validate() {
+        let records = [json!({"n": 1, "x": -2.5}), json!({"n": 9, "x": 4})];
+        let schema = generate(&records);
+        for record in &records {
+            assert!(crate::validation::validate_instance(&schema, record).is_ok());
+        }
+        let outside = json!({"n": 10, "x": 0});
+        assert!(crate::validation::validate_instance(&schema, &outside).is_err());
+    }
+}
@@ -0,0 +1,705 @@
+//! Self-contained copies of schemas using `$ref`, for consumers that can't resolve
+//! references themselves.
+
+use crate::anchors::definition_name;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// Loads the documents the `$ref`s of a schema point into, see [`bundle_schema`].
+///
+/// Closures taking the location of a document and returning it are resolvers.
+pub trait RefResolver {
+    /// Returns the document at `uri`: a reference without its fragment, resolved against
+    /// the location of the document it appears in, such as `common/address.json` for
+    /// `address.json` in `common/user.json`. The root schema itself has no location, so
+    /// its references arrive as written.
+    fn resolve(&self, uri: &str) -> Result<Value, String>;
+}
+
+impl<F: Fn(&str) -> Result<Value, String>> RefResolver for F {
+    fn resolve(&self, uri: &str) -> Result<Value, String> {
+        self(uri)
+    }
+}
+
+/// Resolves references to JSON files relative to the directory `base`. References with a
+/// scheme, such as `https://`, are not fetched.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileResolver {
+    pub base: std::path::PathBuf,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl RefResolver for FileResolver {
+    fn resolve(&self, uri: &str) -> Result<Value, String> {
+        if has_scheme(uri) {
+            return Err("only references to files are resolved".to_string());
+        }
+        let path = self.base.join(percent_decode(uri));
+        let text = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
+        serde_json::from_str(&text).map_err(|e| e.to_string())
+    }
+}
+
+/// How [`bundle_schema`] makes a schema self-contained.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BundleMode {
+    /// Replace each `$ref` by what it points to. A reference back into a schema it is
+    /// part of, which would be inlined forever, stays a reference: into the root schema
+    /// as written, or else to a copy of its target under the root `$defs`.
+    #[default]
+    Inline,
+    /// Copy the target of each reference into another document under the root `$defs`,
+    /// and point the reference there. References within the root schema are left alone.
+    Defs,
+}
+
+impl fmt::Display for BundleMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            BundleMode::Inline => "inline",
+            BundleMode::Defs => "defs",
+        })
+    }
+}
+
+impl FromStr for BundleMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "inline" => Ok(BundleMode::Inline),
+            "defs" => Ok(BundleMode::Defs),
+            _ => Err(format!(
+                "unknown bundle mode '{}', expected inline or defs",
+                s
+            )),
+        }
+    }
+}
+
+/// Why [`bundle_schema`] failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BundleError {
+    /// The `$ref` `reference`, found at the JSON Pointer `location` in the document at
+    /// `document` (empty for the root schema), points nowhere.
+    Unresolvable {
+        document: String,
+        location: String,
+        reference: String,
+        reason: String,
+    },
+}
+
+impl fmt::Display for BundleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BundleError::Unresolvable {
+                document,
+                location,
+                reference,
+                reason,
+            } => write!(
+                f,
+                "cannot resolve $ref '{}' at {}#{}: {}",
+                reference, document, location, reason
+            ),
+        }
+    }
+}
+
+impl Error for BundleError {}
+
+/// Returns a copy of `root` needing no other document, with the documents its `$ref`s
+/// point into loaded through `resolver`, as `mode` asks.
+///
+/// Fragments can be JSON Pointers or the anchors named by `$anchor`, or by `$id` up to
+/// draft-07. An `$id` doesn't change the location later references are resolved against.
+/// A `$ref` with other keywords beside it becomes an `allOf` of its target next to them.
+///
+/// # Examples
+///
+/// ```
+/// use serde_json::{json, Value};
+/// use json_schema_generator::{bundle_schema, BundleMode};
+///
+/// let root = json!({"properties": {"home": {"$ref": "address.json"}}});
+/// let resolver = |uri: &str| -> Result<Value, String> {
+///     match uri {
+///         "address.json" => Ok(json!({"type": "object"})),
+///         _ => Err("not found".to_string()),
+///     }
+/// };
+///
+/// let inlined = bundle_schema(&root, &resolver, BundleMode::Inline).unwrap();
+/// assert_eq!(inlined["properties"]["home"], json!({"type": "object"}));
+///
+/// let collected = bundle_schema(&root, &resolver, BundleMode::Defs).unwrap();
+/// assert_eq!(collected["properties"]["home"], json!({"$ref": "#/$defs/Address"}));
+/// assert_eq!(collected["$defs"]["Address"], json!({"type": "object"}));
+/// ```
+pub fn bundle_schema(
+    root: &Value,
+    resolver: &dyn RefResolver,
+    mode: BundleMode,
+) -> Result<Value, BundleError> {
+    let mut taken = Map::new();
+    if let Some(Value::Object(defs)) = root.get("$defs") {
+        taken.extend(defs.keys().map(|name| (name.clone(), Value::Null)));
+    }
+    let mut bundler = Bundler {
+        resolver,
+        mode,
+        documents: HashMap::from([(String::new(), root.clone())]),
+        names: HashMap::new(),
+        taken,
+        pending: Vec::new(),
+    };
+    let mut bundled = bundler.bundle(root, "", &mut String::new(), &mut Vec::new())?;
+
+    let mut defs = Map::new();
+    while let Some(target) = bundler.pending.pop() {
+        let (document, pointer) = &target;
+        let schema = bundler.documents[document]
+            .pointer(pointer)
+            .unwrap()
+            .clone();
+        let name = bundler.names[&target].clone();
+        let mut stack = vec![target.clone()];
+        let schema = bundler.bundle(&schema, document, &mut pointer.clone(), &mut stack)?;
+        defs.insert(name, schema);
+    }
+    if let (false, Some(obj)) = (defs.is_empty(), bundled.as_object_mut()) {
+        let existing = obj
+            .entry("$defs")
+            .or_insert_with(|| Value::Object(Map::new()));
+        if let Some(existing) = existing.as_object_mut() {
+            existing.extend(defs);
+            existing.sort_keys();
+        }
+    }
+    Ok(bundled)
+}
+
+/// A location a reference points to: a document and a JSON Pointer into it.
+type Target = (String, String);
+
+struct Bundler<'r> {
+    resolver: &'r dyn RefResolver,
+    mode: BundleMode,
+    /// The documents loaded so far, by location, the root schema being at `""`.
+    documents: HashMap<String, Value>,
+    /// The names of the targets copied under the root `$defs`.
+    names: HashMap<Target, String>,
+    /// The names in the root `$defs`, whether or not they are copies.
+    taken: Map<String, Value>,
+    /// The copied targets not bundled yet.
+    pending: Vec<Target>,
+}
+
+impl Bundler<'_> {
+    /// Returns `schema`, found at `location` in `document`, with its references bundled.
+    /// `stack` holds the targets being inlined.
+    fn bundle(
+        &mut self,
+        schema: &Value,
+        document: &str,
+        location: &mut String,
+        stack: &mut Vec<Target>,
+    ) -> Result<Value, BundleError> {
+        match schema {
+            Value::Object(obj) => {
+                let mut bundled = Map::new();
+                for (keyword, value) in obj {
+                    if keyword == "$ref" && value.is_string() {
+                        continue;
+                    }
+                    let value = within(location, keyword, |location| {
+                        self.bundle(value, document, location, stack)
+                    })?;
+                    bundled.insert(keyword.clone(), value);
+                }
+                match obj.get("$ref") {
+                    Some(Value::String(reference)) => {
+                        self.reference(reference, bundled, document, location, stack)
+                    }
+                    _ => Ok(Value::Object(bundled)),
+                }
+            }
+            Value::Array(items) => {
+                let mut bundled = Vec::with_capacity(items.len());
+                for (index, item) in items.iter().enumerate() {
+                    bundled.push(within(location, &index.to_string(), |location| {
+                        self.bundle(item, document, location, stack)
+                    })?);
+                }
+                Ok(Value::Array(bundled))
+            }
+            _ => Ok(schema.clone()),
+        }
+    }
+
+    /// Returns the bundled form of the `$ref` `reference` with the bundled keywords
+    /// `siblings` beside it.
+    fn reference(
+        &mut self,
+        reference: &str,
+        mut siblings: Map<String, Value>,
+        document: &str,
+        location: &str,
+        stack: &mut Vec<Target>,
+    ) -> Result<Value, BundleError> {
+        let target =
+            self.resolve(reference, document)
+                .map_err(|reason| BundleError::Unresolvable {
+                    document: document.to_string(),
+                    location: location.to_string(),
+                    reference: reference.to_string(),
+                    reason,
+                })?;
+        let within_root = target.0.is_empty();
+        let kept = match self.mode {
+            BundleMode::Defs => within_root.then(|| reference.to_string()),
+            BundleMode::Inline if stack.contains(&target) => Some(if within_root {
+                reference.to_string()
+            } else {
+                self.copy(&target)
+            }),
+            BundleMode::Inline => None,
+        };
+        let kept = match (kept, self.mode) {
+            (Some(kept), _) => kept,
+            (None, BundleMode::Defs) => self.copy(&target),
+            (None, BundleMode::Inline) => {
+                let (document, pointer) = &target;
+                let schema = self.documents[document].pointer(pointer).unwrap().clone();
+                stack.push(target.clone());
+                let inlined = self.bundle(&schema, document, &mut pointer.clone(), stack);
+                stack.pop();
+                let inlined = inlined?;
+                if siblings.is_empty() {
+                    return Ok(inlined);
+                }
+                match siblings.get_mut("allOf") {
+                    Some(Value::Array(all_of)) => all_of.push(inlined),
+                    _ => {
+                        siblings.insert("allOf".to_string(), Value::Array(vec![inlined]));
+                    }
+                }
+                return Ok(Value::Object(siblings));
+            }
+        };
+        siblings.insert("$ref".to_string(), Value::String(kept));
+        Ok(Value::Object(siblings))
+    }
+
+    /// Returns the reference to the copy of `target` under the root `$defs`, naming it
+    /// after the last segment of its pointer, or else its document.
+    fn copy(&mut self, target: &Target) -> String {
+        if let Some(name) = self.names.get(target) {
+            return format!("#/$defs/{}", name);
+        }
+        let (document, pointer) = target;
+        let segment = pointer
+            .rsplit('/')
+            .next()
+            .filter(|segment| !segment.is_empty());
+        let file = document.rsplit('/').next().unwrap_or(document);
+        let stem = file.split('.').next().unwrap_or(file);
+        let base = segment
+            .map(|segment| segment.replace("~1", "/").replace("~0", "~"))
+            .unwrap_or_else(|| stem.to_string());
+        let name = definition_name(&base, &self.taken);
+        self.taken.insert(name.clone(), Value::Null);
+        self.names.insert(target.clone(), name.clone());
+        self.pending.push(target.clone());
+        format!("#/$defs/{}", name)
+    }
+
+    /// Returns the target of the `$ref` `reference` in `document`, loading the document it
+    /// points into.
+    fn resolve(&mut self, reference: &str, document: &str) -> Result<Target, String> {
+        let (uri, fragment) = reference.split_once('#').unwrap_or((reference, ""));
+        let uri = if uri.is_empty() {
+            document.to_string()
+        } else {
+            join(document, uri)
+        };
+        if !self.documents.contains_key(&uri) {
+            let loaded = self.resolver.resolve(&uri)?;
+            self.documents.insert(uri.clone(), loaded);
+        }
+        let loaded = &self.documents[&uri];
+        let fragment = percent_decode(fragment);
+        let pointer = if fragment.is_empty() || fragment.starts_with('/') {
+            fragment
+        } else {
+            find_anchor(loaded, &fragment, &mut String::new())
+                .ok_or_else(|| format!("no anchor '{}' in {}", fragment, display(&uri)))?
+        };
+        if loaded.pointer(&pointer).is_none() {
+            return Err(format!("nothing at {}#{}", display(&uri), pointer));
+        }
+        Ok((uri, pointer))
+    }
+}
+
+/// Returns how `document` is named in messages.
+fn display(document: &str) -> &str {
+    if document.is_empty() {
+        "the root schema"
+    } else {
+        document
+    }
+}
+
+/// Runs `f` with `segment`, escaped, appended to the JSON Pointer `path`.
+fn within<T>(path: &mut String, segment: &str, f: impl FnOnce(&mut String) -> T) -> T {
+    let len = path.len();
+    path.push('/');
+    path.push_str(&segment.replace('~', "~0").replace('/', "~1"));
+    let result = f(path);
+    path.truncate(len);
+    result
+}
+
+/// Returns the JSON Pointer to the schema in `schema`, found at `path`, named `anchor`
+/// by `$anchor`, or by an `$id` or `id` of `#anchor`.
+fn find_anchor(schema: &Value, anchor: &str, path: &mut String) -> Option<String> {
+    match schema {
+        Value::Object(obj) => {
+            let named = obj.get("$anchor").and_then(Value::as_str) == Some(anchor)
+                || ["$id", "id"].iter().any(|keyword| {
+                    obj.get(*keyword)
+                        .and_then(Value::as_str)
+                        .and_then(|id| id.strip_prefix('#'))
+                        == Some(anchor)
+                });
+            if named {
+                return Some(path.clone());
+            }
+            obj.iter().find_map(|(keyword, value)| {
+                within(path, keyword, |path| find_anchor(value, anchor, path))
+            })
+        }
+        Value::Array(items) => items.iter().enumerate().find_map(|(index, item)| {
+            within(path, &index.to_string(), |path| {
+                find_anchor(item, anchor, path)
+            })
+        }),
+        _ => None,
+    }
+}
+
+/// Returns whether `uri` starts with a scheme such as `https:`.
+fn has_scheme(uri: &str) -> bool {
+    match uri.split_once(':') {
+        Some((scheme, _)) => {
+            scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+        }
+        None => false,
+    }
+}
+
+/// Returns the reference `uri` resolved against `base`, the location of the document it
+/// appears in, with `.` and `..` segments removed.
+fn join(base: &str, uri: &str) -> String {
+    if has_scheme(uri) || uri.starts_with('/') {
+        return uri.to_string();
+    }
+    let dir = base.rfind('/').map_or("", |end| &base[..=end]);
+    let joined = format!("{}{}", dir, uri);
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in joined.split('/') {
+        match segment {
+            "." => {}
+            ".." if segments
+                .last()
+                .is_some_and(|last| !last.is_empty() && *last != "..") =>
+            {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+    segments.join("/")
+}
+
+/// Decodes the `%XX` escapes of `s`, leaving malformed ones as they are.
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escape = s
+            .get(i + 1..i + 3)
+            .filter(|_| bytes[i] == b'%')
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escape {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// A resolver over the documents `files`, by location.
+    fn files(files: Vec<(&'static str, Value)>) -> impl Fn(&str) -> Result<Value, String> {
+        let files: HashMap<_, _> = files.into_iter().collect();
+        move |uri| {
+            files
+                .get(uri)
+                .cloned()
+                .ok_or_else(|| "not found".to_string())
+        }
+    }
+
+    /// A user and a team referring to each other from files of their own.
+    fn cyclic() -> (Value, impl Fn(&str) -> Result<Value, String>) {
+        let root =
+            json!({"type": "object", "properties": {"owner": {"$ref": "schemas/user.json"}}});
+        let resolver = files(vec![
+            (
+                "schemas/user.json",
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "name": {"type": "string"},
+                        "team": {"$ref": "team.json#/definitions/Team"},
+                    },
+                }),
+            ),
+            (
+                "schemas/team.json",
+                json!({
+                    "definitions": {
+                        "Team": {
+                            "type": "object",
+                            "properties": {"members": {"type": "array", "items": {"$ref": "./user.json"}}},
+                        },
+                    },
+                }),
+            ),
+        ]);
+        (root, resolver)
+    }
+
+    #[test]
+    fn test_inline_cycle() {
+        let (root, resolver) = cyclic();
+        let bundled = bundle_schema(&root, &resolver, BundleMode::Inline).unwrap();
+        let owner = &bundled["properties"]["owner"];
+        assert_eq!(owner["properties"]["name"], json!({"type": "string"}));
+        let team = &owner["properties"]["team"];
+        // The user is already being inlined, so the team refers to a copy of it.
+        assert_eq!(
+            team["properties"]["members"]["items"],
+            json!({"$ref": "#/$defs/User"})
+        );
+        assert_eq!(
+            bundled["$defs"]["User"]["properties"]["team"]["properties"]["members"]["items"],
+            json!({"$ref": "#/$defs/User"})
+        );
+        assert_eq!(bundled["$defs"].as_object().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_defs() {
+        let (root, resolver) = cyclic();
+        let bundled = bundle_schema(&root, &resolver, BundleMode::Defs).unwrap();
+        assert_eq!(
+            bundled,
+            json!({
+                "type": "object",
+                "properties": {"owner": {"$ref": "#/$defs/User"}},
+                "$defs": {
+                    "Team": {
+                        "type": "object",
+                        "properties": {"members": {"type": "array", "items": {"$ref": "#/$defs/User"}}},
+                    },
+                    "User": {
+                        "type": "object",
+                        "properties": {
+                            "name": {"type": "string"},
+                            "team": {"$ref": "#/$defs/Team"},
+                        },
+                    },
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn test_internal_references() {
+        let root = json!({
+            "$defs": {
+                "Node": {
+                    "type": "object",
+                    "properties": {"children": {"type": "array", "items": {"$ref": "#/$defs/Node"}}},
+                },
+                "a/b": {"type": "integer"},
+            },
+            "properties": {
+                "tree": {"$ref": "#/$defs/Node"},
+                "ab": {"$ref": "#/$defs/a~1b", "description": "escaped"},
+                "anchored": {"$ref": "#leaf"},
+                "leaf": {"$anchor": "leaf", "type": "null"},
+            },
+        });
+        let bundled = bundle_schema(&root, &files(vec![]), BundleMode::Inline).unwrap();
+        let properties = &bundled["properties"];
+        // A cycle within the root keeps the reference as written.
+        assert_eq!(
+            properties["tree"]["properties"]["children"]["items"],
+            json!({"$ref": "#/$defs/Node"})
+        );
+        assert_eq!(
+            properties["ab"],
+            json!({"description": "escaped", "allOf": [{"type": "integer"}]})
+        );
+        assert_eq!(
+            properties["anchored"],
+            json!({"$anchor": "leaf", "type": "null"})
+        );
+        assert_eq!(bundled["$defs"]["a/b"], json!({"type": "integer"}));
+
+        // References within the root are left alone when collecting.
+        assert_eq!(
+            bundle_schema(&root, &files(vec![]), BundleMode::Defs).unwrap(),
+            root
+        );
+    }
+
+    #[test]
+    fn test_unresolvable() {
+        let root = json!({"items": [{"$ref": "missing.json"}]});
+        let error = bundle_schema(&root, &files(vec![]), BundleMode::Inline).unwrap_err();
+        assert_eq!(
+            error,
+            BundleError::Unresolvable {
+                document: String::new(),
+                location: "/items/0".to_string(),
+                reference: "missing.json".to_string(),
+                reason: "not found".to_string(),
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "cannot resolve $ref 'missing.json' at #/items/0: not found"
+        );
+
+        // A document pointing nowhere within itself or another.
+        let resolver = files(vec![("a.json", json!({"not": {"$ref": "#/$defs/Gone"}}))]);
+        let error = bundle_schema(&json!({"$ref": "a.json"}), &resolver, BundleMode::Defs)
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            error,
+            "cannot resolve $ref '#/$defs/Gone' at a.json#/not: nothing at a.json#/$defs/Gone"
+        );
+        let error = bundle_schema(&json!({"$ref": "#nowhere"}), &resolver, BundleMode::Inline)
+            .unwrap_err()
+            .to_string();
+        assert!(
+            error.ends_with("no anchor 'nowhere' in the root schema"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn test_names_do_not_clash() {
+        let root = json!({
+            "$defs": {"Address": {"type": "string"}},
+            "properties": {
+                "a": {"$ref": "address.json"},
+                "b": {"$ref": "other/address.json"},
+                "c": {"$ref": "address.json#"},
+            },
+        });
+        let resolver = files(vec![
+            ("address.json", json!({"type": "object"})),
+            ("other/address.json", json!({"type": "array"})),
+        ]);
+        let bundled = bundle_schema(&root, &resolver, BundleMode::Defs).unwrap();
+        assert_eq!(
+            bundled["properties"],
+            json!({
+                "a": {"$ref": "#/$defs/Address2"},
+                "b": {"$ref": "#/$defs/Address3"},
+                "c": {"$ref": "#/$defs/Address2"},
+            })
+        );
+        assert_eq!(bundled["$defs"]["Address"], json!({"type": "string"}));
+        assert_eq!(bundled["$defs"]["Address3"], json!({"type": "array"}));
+    }
+
+    #[test]
+    fn test_join() {
+        assert_eq!(join("", "a.json"), "a.json");
+        assert_eq!(join("schemas/user.json", "team.json"), "schemas/team.json");
+        assert_eq!(
+            join("schemas/user.json", "./x/../team.json"),
+            "schemas/team.json"
+        );
+        assert_eq!(join("schemas/user.json", "../../team.json"), "../team.json");
+        assert_eq!(
+            join("https://example.com/a/b.json", "../c.json"),
+            "https://example.com/c.json"
+        );
+        assert_eq!(
+            join("a/b.json", "https://x.org/c.json"),
+            "https://x.org/c.json"
+        );
+        assert!(has_scheme("urn:example:a"));
+        assert!(!has_scheme("a/b:c.json"));
+        assert_eq!(percent_decode("line%20item%2"), "line item%2");
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_bundled_schemas_validate() {
+        let (root, resolver) = cyclic();
+        let nested = json!({"owner": {"name": "a", "team": {"members": [{"name": "b", "team": {"members": []}}]}}});
+        let wrong = json!({"owner": {"team": {"members": [{"name": 1}]}}});
+        for mode in [BundleMode::Inline, BundleMode::Defs] {
+            let mut bundled = bundle_schema(&root, &resolver, mode).unwrap();
+            bundled["$schema"] = json!("https://json-schema.org/draft/2020-12/schema");
+            assert!(crate::validation::validate_instance(&bundled, &nested).is_ok());
+            assert!(crate::validation::validate_instance(&bundled, &wrong).is_err());
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_file_resolver() {
+        let dir = std::env::temp_dir().join(format!("jsg-bundle-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("common")).unwrap();
+        std::fs::write(dir.join("common/line item.json"), r#"{"type": "integer"}"#).unwrap();
+        let resolver = FileResolver { base: dir.clone() };
+        let root = json!({"$ref": "common/line%20item.json"});
+        let bundled = bundle_schema(&root, &resolver, BundleMode::Inline).unwrap();
+        assert_eq!(bundled, json!({"type": "integer"}));
+        let remote = json!({"$ref": "https://example.com/a.json"});
+        assert!(bundle_schema(&remote, &resolver, BundleMode::Inline).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,162 @@
+//! Canonical serialization of schemas, for hashing and byte-for-byte comparison.
+//!
+//! The canonical form is compact JSON with no insignificant whitespace, object keys
+//! sorted by their UTF-8 bytes, integers written without exponent, and floats written in
+//! their shortest round-trip form. Negative zero is written as `0.0`, since JSON Schema
+//! compares numbers by value. The timestamp of a recorded provenance is left out, so
+//! that reruns compare equal. The output does not depend on crate features (such as
+//! serde_json's `preserve_order`) or on the platform.
+
+use crate::{provenance, AsSchemaValue};
+use serde_json::{Number, Value};
+#[cfg(feature = "hash")]
+use sha2::{Digest, Sha256};
+
+/// Serializes `schema` into its canonical form.
+///
+/// `schema` can be a `Value` or, with the `schemars` feature, a schemars schema.
+///
+/// # Examples
+///
+/// ```
+/// use serde_json::json;
+/// use json_schema_generator::to_canonical_string;
+///
+/// let schema = json!({"type": "object", "required": ["b", "a"], "maximum": 1e21});
+/// assert_eq!(
+///     to_canonical_string(&schema),
+///     r#"{"maximum":1e21,"required":["b","a"],"type":"object"}"#
+/// );
+/// ```
+pub fn to_canonical_string(schema: &impl AsSchemaValue) -> String {
+    let schema = schema.as_schema_value();
+    let mut out = String::new();
+    match provenance::without_timestamp(&schema) {
+        Some(schema) => write_value(&mut out, &schema),
+        None => write_value(&mut out, &schema),
+    }
+    out
+}
+
+/// Returns the SHA-256 digest of the canonical form of `schema`.
+#[cfg(feature = "hash")]
+pub fn canonical_hash(schema: &impl AsSchemaValue) -> [u8; 32] {
+    Sha256::digest(to_canonical_string(schema).as_bytes()).into()
+}
+
+fn write_value(out: &mut String, value: &Value) {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Value::Number(n) => write_number(out, n),
+        Value::String(s) => write_string(out, s),
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_value(out, item);
+            }
+            out.push(']');
+        }
+        Value::Object(obj) => {
+            let mut entries: Vec<_> = obj.iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.as_bytes().cmp(b.as_bytes()));
+            out.push('{');
+            for (i, (key, value)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_string(out, key);
+                out.push(':');
+                write_value(out, value);
+            }
+            out.push('}');
+        }
+    }
+}
+
+fn write_number(out: &mut String, n: &Number) {
+    if let Some(i) = n.as_i64() {
+        out.push_str(&i.to_string());
+    } else if let Some(u) = n.as_u64() {
+        out.push_str(&u.to_string());
+    } else if let Some(f) = n.as_f64() {
+        if f == 0.0 {
+            out.push_str("0.0");
+        } else {
+            // serde_json formats finite floats with ryu, which yields the shortest
+            // representation that round-trips.
+            out.push_str(&Number::from_f64(f).map_or_else(|| "null".into(), |n| n.to_string()));
+        }
+    }
+}
+
+fn write_string(out: &mut String, s: &str) {
+    out.push_str(&serde_json::to_string(s).expect("serializing a string cannot fail"));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_float_formatting() {
+        assert_eq!(to_canonical_string(&json!(0.1)), "0.1");
+        assert_eq!(to_canonical_string(&json!(1e21)), "1e21");
+        assert_eq!(to_canonical_string(&json!(-0.0)), "0.0");
+        assert_eq!(to_canonical_string(&json!(0.0)), "0.0");
+        assert_eq!(to_canonical_string(&json!(1.5e-7)), "1.5e-7");
+        assert_eq!(to_canonical_string(&json!(2.0)), "2.0");
+    }
+
+    #[test]
+    fn test_integer_formatting() {
+        assert_eq!(
+            to_canonical_string(&json!(1_000_000_000_000_000_000u64)),
+            "1000000000000000000"
+        );
+        assert_eq!(
+            to_canonical_string(&json!(u64::MAX)),
+            "18446744073709551615"
+        );
+        assert_eq!(
+            to_canonical_string(&json!(i64::MIN)),
+            "-9223372036854775808"
+        );
+    }
+
+    #[test]
+    fn test_key_order_independence() {
+        let a: Value =
+            serde_json::from_str(r#"{"b": {"y": 1, "x": 2}, "a": [true, null]}"#).unwrap();
+        let b: Value =
+            serde_json::from_str(r#"{"a": [true, null], "b": {"x": 2, "y": 1}}"#).unwrap();
+        assert_eq!(
+            to_canonical_string(&a),
+            r#"{"a":[true,null],"b":{"x":2,"y":1}}"#
+        );
+        assert_eq!(to_canonical_string(&a), to_canonical_string(&b));
+        #[cfg(feature = "hash")]
+        assert_eq!(canonical_hash(&a), canonical_hash(&b));
+    }
+
+    #[test]
+    fn test_string_escaping() {
+        assert_eq!(
+            to_canonical_string(&json!("a\"b\n\u{1}é")),
+            r#""a\"b\n\u0001é""#
+        );
+    }
+
+    #[cfg(feature = "hash")]
+    #[test]
+    fn test_canonical_hash() {
+        let hash = canonical_hash(&json!({}));
+        // SHA-256 of the two bytes "{}".
+        assert_eq!(hash[..4], [0x44, 0x13, 0x6f, 0xa3]);
+        assert_ne!(canonical_hash(&json!({"type": "string"})), hash);
+    }
+}
@@ -0,0 +1,230 @@
+//! Detection of property names that differ only in casing, such as `userId` and
+//! `user_id` written by producers that disagree on a convention.
+
+use crate::{merge_into, Context, SchemaOptions, SchemaPath, State, Warning};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap};
+
+/// How often each property name was seen in the objects at one location.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Names {
+    counts: BTreeMap<String, usize>,
+}
+
+impl Names {
+    pub(crate) fn record(&mut self, properties: &Map<String, Value>) {
+        for name in properties.keys() {
+            match self.counts.get_mut(name) {
+                Some(count) => *count += 1,
+                None => {
+                    self.counts.insert(name.clone(), 1);
+                }
+            }
+        }
+    }
+
+    /// Returns the names that normalize alike, each group with at least two names, most
+    /// often seen first.
+    fn variants(&self) -> Vec<Vec<(&str, usize)>> {
+        let mut groups: BTreeMap<String, Vec<(&str, usize)>> = BTreeMap::new();
+        for (name, &count) in &self.counts {
+            groups
+                .entry(normalize(name))
+                .or_default()
+                .push((name, count));
+        }
+        groups
+            .into_values()
+            .filter(|group| group.len() > 1)
+            .map(|mut group| {
+                group.sort_by(|(a, m), (b, n)| n.cmp(m).then(a.cmp(b)));
+                group
+            })
+            .collect()
+    }
+}
+
+/// Returns `name` without its casing and the `_` and `-` separating its words, so that
+/// `userId`, `user_id` and `User-ID` all become `userid`.
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| !matches!(c, '_' | '-'))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Returns a `case-collision` warning for each group of property names differing only in
+/// casing, ordered by pointer.
+pub(crate) fn warnings(names: &HashMap<String, Names>, options: &SchemaOptions) -> Vec<Warning> {
+    let mut pointers: Vec<&String> = names.keys().collect();
+    pointers.sort();
+    let mut warnings = Vec::new();
+    for pointer in pointers {
+        for group in names[pointer].variants() {
+            let mut described: Vec<String> = group
+                .iter()
+                .map(|(name, count)| {
+                    format!(
+                        "{} ({} time{})",
+                        Value::from(*name),
+                        count,
+                        if *count == 1 { "" } else { "s" }
+                    )
+                })
+                .collect();
+            let last = described.pop().expect("a group has at least two names");
+            let mut message = format!(
+                "the property names {} and {} differ only in casing",
+                described.join(", "),
+                last
+            );
+            if options.merge_case_variants {
+                message.push_str(&format!(", merged into {}", Value::from(group[0].0)));
+            }
+            warnings.push(Warning {
+                code: "case-collision".to_string(),
+                pointer: pointer.clone(),
+                message,
+            });
+        }
+    }
+    warnings
+}
+
+/// Folds the properties of the object schema `schema` that differ only in casing into
+/// the one seen most often, merging their schemas into a union where they disagree.
+pub(crate) fn merge_variants(schema: &mut Value, names: &Names, options: &SchemaOptions) {
+    let Some(obj) = schema.as_object_mut() else {
+        return;
+    };
+    let mut folded = Vec::new();
+    if let Some(Value::Object(properties)) = obj.get_mut("properties") {
+        let mut scratch = State::default();
+        let mut ctx = Context::new(options, &mut scratch);
+        for group in names.variants() {
+            let canonical = group[0].0;
+            for &(variant, _) in &group[1..] {
+                let Some(schema) = properties.remove(variant) else {
+                    continue;
+                };
+                match properties.get_mut(canonical) {
+                    Some(target) => merge_into(target, &schema, &mut ctx, &SchemaPath::root()),
+                    None => {
+                        properties.insert(canonical.to_string(), schema);
+                    }
+                }
+                folded.push(variant.to_string());
+            }
+        }
+    }
+    if let Some(Value::Array(required)) = obj.get_mut("required") {
+        required.retain(|name| !folded.iter().any(|variant| name == variant.as_str()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SchemaGenerator;
+    use serde_json::json;
+
+    fn generator(records: &[Value], merge_case_variants: bool) -> SchemaGenerator {
+        let mut generator = SchemaGenerator::new(SchemaOptions {
+            casing_report: true,
+            merge_case_variants,
+            ..Default::default()
+        });
+        for record in records {
+            generator.observe(record).unwrap();
+        }
+        generator
+    }
+
+    fn collisions(generator: &SchemaGenerator) -> Vec<String> {
+        generator
+            .warnings()
+            .into_iter()
+            .filter(|warning| warning.code == "case-collision")
+            .map(|warning| warning.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("userId"), "userid");
+        assert_eq!(normalize("user_id"), "userid");
+        assert_eq!(normalize("User-ID"), "userid");
+        assert_ne!(normalize("user_ids"), normalize("user_id"));
+    }
+
+    #[test]
+    fn test_root_collision() {
+        let records = [
+            json!({"userId": 1, "name": "a"}),
+            json!({"userId": 2, "name": "b"}),
+            json!({"user_id": 3, "name": "c"}),
+        ];
+        let generator = generator(&records, false);
+        assert_eq!(
+            collisions(&generator),
+            [
+                r#"/ [case-collision]: the property names "userId" (2 times) and "user_id" (1 time) differ only in casing"#
+            ]
+        );
+        // Reporting leaves the schema alone.
+        let schema = generator.schema();
+        assert_eq!(schema["properties"]["user_id"], json!({"type": "integer"}));
+        assert_eq!(schema["properties"]["userId"], json!({"type": "integer"}));
+    }
+
+    #[test]
+    fn test_nested_collisions() {
+        let records = [
+            json!({"account": {"createdAt": "x", "CreatedAt": "y"}, "items": [{"item_id": 1}]}),
+            json!({"account": {"created_at": "z"}, "items": [{"itemId": 2}, {"item-id": 3}]}),
+        ];
+        assert_eq!(
+            collisions(&generator(&records, false)),
+            [
+                r#"/properties/account [case-collision]: the property names "CreatedAt" (1 time), "createdAt" (1 time) and "created_at" (1 time) differ only in casing"#,
+                r#"/properties/items/items [case-collision]: the property names "item-id" (1 time), "itemId" (1 time) and "item_id" (1 time) differ only in casing"#,
+            ]
+        );
+        assert!(collisions(&generator(&[json!({"a": {"b": 1}})], false)).is_empty());
+    }
+
+    #[test]
+    fn test_merge_variants() {
+        let records = [
+            json!({"user": {"userId": 1, "name": "a"}}),
+            json!({"user": {"userId": 2, "name": "b"}}),
+            json!({"user": {"user_id": "3", "name": "c"}}),
+        ];
+        let merged = generator(&records, true);
+        assert!(collisions(&merged)[0].ends_with(r#"differ only in casing, merged into "userId""#));
+        let user = &merged.schema()["properties"]["user"];
+        assert_eq!(
+            user["properties"],
+            json!({
+                "name": {"type": "string"},
+                "userId": {"oneOf": [{"type": "integer"}, {"type": "string"}]}
+            })
+        );
+
+        // Names seen equally often fold into the first in order.
+        let schema = generator(&[json!({"createdAt": 1, "created_at": 2})], true).schema();
+        assert_eq!(
+            schema["properties"],
+            json!({"createdAt": {"type": "integer"}})
+        );
+        assert_eq!(schema["required"], json!(["createdAt"]));
+    }
+
+    #[test]
+    fn test_off_by_default() {
+        let mut generator = SchemaGenerator::new(SchemaOptions::default());
+        generator.observe(&json!({"userId": 1})).unwrap();
+        generator.observe(&json!({"user_id": 1})).unwrap();
+        assert!(collisions(&generator).is_empty());
+    }
+}
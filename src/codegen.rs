@@ -0,0 +1,716 @@
+//! Type definitions generated from an instance: the schema generated for it, read back
+//! as structs whose fields have types, and written out as Rust or TypeScript.
+
+use crate::SchemaOptions;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fmt::Write;
+
+/// How type definitions are generated, see [`generate_rust_types`] and
+/// [`generate_typescript`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodegenOptions {
+    /// The name of the type of the instance itself. The types of the objects in it are
+    /// named after the properties they are under.
+    pub root_name: String,
+}
+
+impl Default for CodegenOptions {
+    fn default() -> Self {
+        CodegenOptions {
+            root_name: "Root".to_string(),
+        }
+    }
+}
+
+/// The type of a value, as the schema generated for it describes it.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Type {
+    String,
+    Integer,
+    Number,
+    Boolean,
+    Null,
+    /// Any value, for a schema that doesn't say, such as the items of an array that was
+    /// only seen empty.
+    Any,
+    /// An object without properties, mapping any names to any values.
+    Map,
+    Array(Box<Type>),
+    /// The [`Shape`] of this name.
+    Named(String),
+    Nullable(Box<Type>),
+    /// One of several types, for a oneOf.
+    Union(Vec<Type>),
+}
+
+/// An object with properties, which becomes a struct.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Shape {
+    pub(crate) name: String,
+    pub(crate) fields: Vec<Field>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Field {
+    /// The name of the property.
+    pub(crate) key: String,
+    pub(crate) ty: Type,
+    pub(crate) required: bool,
+}
+
+/// Names that the generated types can't take, as they would shadow the types of Rust or
+/// TypeScript the definitions use.
+const RESERVED_NAMES: &[&str] = &[
+    "Array",
+    "Boolean",
+    "Box",
+    "Deserialize",
+    "Err",
+    "Map",
+    "None",
+    "Number",
+    "Object",
+    "Ok",
+    "Option",
+    "Record",
+    "Result",
+    "Self",
+    "Serialize",
+    "Some",
+    "String",
+    "Value",
+    "Vec",
+];
+
+/// The types of an instance: that of the instance itself and the shapes of the objects
+/// in it, each named once.
+#[derive(Debug)]
+pub(crate) struct Model {
+    pub(crate) root: Type,
+    pub(crate) shapes: Vec<Shape>,
+    taken: HashSet<String>,
+}
+
+impl Model {
+    /// Reads the types of `instance` from the schema generated for it, merging the items
+    /// of each array as samples, so that a property missing from some of them is
+    /// optional.
+    pub(crate) fn new(instance: &Value, options: &CodegenOptions) -> Model {
+        let schema = crate::generate_json_schema_with_options(instance, &SchemaOptions::default());
+        let mut model = Model {
+            root: Type::Any,
+            shapes: Vec::new(),
+            taken: RESERVED_NAMES.iter().map(|name| name.to_string()).collect(),
+        };
+        let root_name = pascal_case(&options.root_name);
+        model.taken.insert(root_name.clone());
+        model.root = model.type_of(&schema, &root_name, true);
+        model
+    }
+
+    /// Returns the shapes in the order they are reached from the root, each before the
+    /// shapes of its fields.
+    pub(crate) fn ordered(&self) -> Vec<&Shape> {
+        let mut ordered = Vec::new();
+        let mut seen = HashSet::new();
+        self.visit(&self.root, &mut seen, &mut ordered);
+        ordered
+    }
+
+    fn visit<'a>(&'a self, ty: &Type, seen: &mut HashSet<&'a str>, ordered: &mut Vec<&'a Shape>) {
+        match ty {
+            Type::Array(ty) | Type::Nullable(ty) => self.visit(ty, seen, ordered),
+            Type::Union(types) => {
+                for ty in types {
+                    self.visit(ty, seen, ordered);
+                }
+            }
+            Type::Named(name) => {
+                let shape = self.shapes.iter().find(|shape| &shape.name == name);
+                if let Some(shape) = shape.filter(|shape| seen.insert(&shape.name)) {
+                    ordered.push(shape);
+                    for field in &shape.fields {
+                        self.visit(&field.ty, seen, ordered);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns the type `schema` describes, naming the shape of an object `name`, which
+    /// is `root` for the instance itself.
+    fn type_of(&mut self, schema: &Value, name: &str, root: bool) -> Type {
+        if let Some(branches) = schema.get("oneOf").and_then(Value::as_array) {
+            let types = branches
+                .iter()
+                .map(|branch| self.type_of(branch, name, root))
+                .collect();
+            return union(types);
+        }
+        match schema.get("type") {
+            Some(Value::String(ty)) => self.simple_type(schema, ty, name, root),
+            Some(Value::Array(types)) => {
+                let types = types
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .map(|ty| self.simple_type(schema, ty, name, root))
+                    .collect();
+                union(types)
+            }
+            _ => Type::Any,
+        }
+    }
+
+    fn simple_type(&mut self, schema: &Value, ty: &str, name: &str, root: bool) -> Type {
+        match ty {
+            "string" => Type::String,
+            "integer" => Type::Integer,
+            "number" => Type::Number,
+            "boolean" => Type::Boolean,
+            "null" => Type::Null,
+            "array" => {
+                let items = match schema.get("items") {
+                    Some(items) if items.is_object() => {
+                        self.type_of(items, &format!("{}Item", name), false)
+                    }
+                    _ => Type::Any,
+                };
+                Type::Array(Box::new(items))
+            }
+            "object" => self.object(schema, name, root),
+            _ => Type::Any,
+        }
+    }
+
+    fn object(&mut self, schema: &Value, name: &str, root: bool) -> Type {
+        let properties = match schema.get("properties").and_then(Value::as_object) {
+            Some(properties) if !properties.is_empty() => properties,
+            _ => return Type::Map,
+        };
+        let required: HashSet<&str> = schema
+            .get("required")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .collect();
+        // The name is taken before the fields are visited, so that an object is named
+        // before the objects inside it.
+        let name = if root {
+            name.to_string()
+        } else {
+            self.unused_name(name)
+        };
+        let fields = properties
+            .iter()
+            .map(|(key, property)| Field {
+                key: key.clone(),
+                ty: self.type_of(property, &pascal_case(key), false),
+                required: required.contains(key.as_str()),
+            })
+            .collect::<Vec<_>>();
+
+        // An object of the same shape as one seen before is of its type.
+        if !root {
+            if let Some(shape) = self.shapes.iter().find(|shape| shape.fields == fields) {
+                self.taken.remove(&name);
+                return Type::Named(shape.name.clone());
+            }
+        }
+        self.shapes.push(Shape {
+            name: name.clone(),
+            fields,
+        });
+        Type::Named(name)
+    }
+
+    /// Returns `name`, or `name` numbered if it is taken, and takes it.
+    fn unused_name(&mut self, name: &str) -> String {
+        let mut candidate = name.to_string();
+        let mut number = 2;
+        while self.taken.contains(&candidate) {
+            candidate = format!("{}{}", name, number);
+            number += 1;
+        }
+        self.taken.insert(candidate.clone());
+        candidate
+    }
+}
+
+/// Returns the type of a value of any of `types`: the nullable type of the others if one
+/// is null.
+fn union(types: Vec<Type>) -> Type {
+    let nullable = types.contains(&Type::Null);
+    let mut types: Vec<Type> = types.into_iter().filter(|ty| *ty != Type::Null).collect();
+    let ty = match types.len() {
+        0 => return Type::Null,
+        1 => types.remove(0),
+        _ => Type::Union(types),
+    };
+    if nullable {
+        Type::Nullable(Box::new(ty))
+    } else {
+        ty
+    }
+}
+
+/// Returns the words of `name` in PascalCase, keeping only ASCII letters and digits, as
+/// the name of a type.
+pub(crate) fn pascal_case(name: &str) -> String {
+    let name: String = name
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            let first = chars.next().unwrap().to_ascii_uppercase();
+            std::iter::once(first).chain(chars).collect::<String>()
+        })
+        .collect();
+    if name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        name
+    } else {
+        format!("Type{}", name)
+    }
+}
+
+/// The keywords of Rust, which fields are named by as raw identifiers.
+const RUST_KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn",
+    "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in", "let",
+    "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return",
+    "static", "struct", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use",
+    "virtual", "where", "while", "yield",
+];
+
+/// Generates Rust definitions for the types of `instance`: a struct deriving serde's
+/// `Serialize` and `Deserialize` for each shape of object in it, named after the
+/// property it is under, or a type alias if the instance isn't an object.
+///
+/// Fields are named in snake_case, renamed to their properties where those differ. A
+/// property missing from some of the objects of a shape, or sometimes null, is an
+/// `Option`, and one that is of several types is a `serde_json::Value` with a comment
+/// listing them. Objects of the same shape share a struct, and a name that is taken is
+/// numbered.
+///
+/// # Examples
+///
+/// ```
+/// use serde_json::json;
+/// use json_schema_generator::{generate_rust_types, CodegenOptions};
+///
+/// let instance = json!({"userId": 1, "address": {"city": "Oslo"}, "tags": ["a"]});
+/// let code = generate_rust_types(&instance, &CodegenOptions::default());
+/// assert!(code.contains("pub struct Root {"));
+/// assert!(code.contains("    #[serde(rename = \"userId\")]\n    pub user_id: i64,\n"));
+/// assert!(code.contains("    pub address: Address,\n"));
+/// assert!(code.contains("    pub tags: Vec<String>,\n"));
+/// ```
+pub fn generate_rust_types(instance: &Value, options: &CodegenOptions) -> String {
+    let model = Model::new(instance, options);
+    let mut code = String::from("use serde::{Deserialize, Serialize};\n");
+    if !matches!(model.root, Type::Named(_)) {
+        let name = pascal_case(&options.root_name);
+        let (ty, comment) = rust_type(&model.root);
+        code.push('\n');
+        if let Some(comment) = comment {
+            writeln!(code, "// oneOf: {}", comment).unwrap();
+        }
+        writeln!(code, "pub type {} = {};", name, ty).unwrap();
+    }
+    for shape in model.ordered() {
+        code.push('\n');
+        writeln!(
+            code,
+            "#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]\npub struct {} {{",
+            shape.name
+        )
+        .unwrap();
+        let mut fields = HashSet::new();
+        for field in &shape.fields {
+            let base = snake_case(&field.key);
+            let mut ident = base.clone();
+            let mut number = 2;
+            while !fields.insert(ident.clone()) {
+                ident = format!("{}_{}", base, number);
+                number += 1;
+            }
+            // Keywords that can't be raw identifiers get a trailing underscore.
+            if matches!(ident.as_str(), "self" | "super" | "crate") {
+                ident.push('_');
+            }
+            let (mut ty, comment) = rust_type(&field.ty);
+            if let Some(comment) = comment {
+                writeln!(code, "    // oneOf: {}", comment).unwrap();
+            }
+            if ident != field.key {
+                writeln!(code, "    #[serde(rename = {:?})]", field.key).unwrap();
+            }
+            if !field.required {
+                writeln!(
+                    code,
+                    "    #[serde(skip_serializing_if = \"Option::is_none\")]"
+                )
+                .unwrap();
+                if !matches!(field.ty, Type::Nullable(_) | Type::Null) {
+                    ty = format!("Option<{}>", ty);
+                }
+            }
+            let ident = if RUST_KEYWORDS.contains(&ident.as_str()) {
+                format!("r#{}", ident)
+            } else {
+                ident
+            };
+            writeln!(code, "    pub {}: {},", ident, ty).unwrap();
+        }
+        code.push_str("}\n");
+    }
+    code
+}
+
+/// Returns the Rust type of `ty`, and the types of the oneOf it stands for if it falls
+/// back to `serde_json::Value` for one.
+fn rust_type(ty: &Type) -> (String, Option<String>) {
+    match ty {
+        Type::String => ("String".to_string(), None),
+        Type::Integer => ("i64".to_string(), None),
+        Type::Number => ("f64".to_string(), None),
+        Type::Boolean => ("bool".to_string(), None),
+        Type::Null => ("Option<serde_json::Value>".to_string(), None),
+        Type::Any => ("serde_json::Value".to_string(), None),
+        Type::Map => (
+            "serde_json::Map<String, serde_json::Value>".to_string(),
+            None,
+        ),
+        Type::Array(items) => {
+            let (items, comment) = rust_type(items);
+            (format!("Vec<{}>", items), comment)
+        }
+        Type::Named(name) => (name.clone(), None),
+        Type::Nullable(ty) => {
+            let (ty, comment) = rust_type(ty);
+            (format!("Option<{}>", ty), comment)
+        }
+        Type::Union(types) => {
+            let branches: Vec<String> = types.iter().map(|ty| rust_type(ty).0).collect();
+            ("serde_json::Value".to_string(), Some(branches.join(" | ")))
+        }
+    }
+}
+
+/// Returns the words of the property name `key` in snake_case, as a Rust identifier:
+/// `userId` and `user-id` are `user_id`, and `HTTPServer` is `http_server`.
+fn snake_case(key: &str) -> String {
+    let chars: Vec<char> = key.chars().collect();
+    let mut ident = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if !c.is_ascii_alphanumeric() {
+            if !ident.is_empty() && !ident.ends_with('_') {
+                ident.push('_');
+            }
+            continue;
+        }
+        if c.is_ascii_uppercase() && i > 0 {
+            let previous = chars[i - 1];
+            let next_lower = chars.get(i + 1).is_some_and(char::is_ascii_lowercase);
+            let boundary = previous.is_ascii_lowercase()
+                || previous.is_ascii_digit()
+                || (previous.is_ascii_uppercase() && next_lower);
+            if boundary && !ident.is_empty() && !ident.ends_with('_') {
+                ident.push('_');
+            }
+        }
+        ident.push(c.to_ascii_lowercase());
+    }
+    let ident = ident.trim_end_matches('_').to_string();
+    match ident.as_str() {
+        "" => "field".to_string(),
+        _ if ident.starts_with(|c: char| c.is_ascii_digit()) => format!("field_{}", ident),
+        _ => ident,
+    }
+}
+
+/// Generates TypeScript declarations for the types of `instance`: an interface for each
+/// shape of object in it, named after the property it is under, or a type alias if the
+/// instance isn't an object.
+///
+/// A property missing from some of the objects of a shape is optional, marked `?`, one
+/// that is sometimes null is a union with `null`, and one of several types, a oneOf, is a
+/// union of them. Objects of the same shape share an interface, and a name that is taken
+/// is numbered. Property names that aren't identifiers, such as `foo-bar`, are quoted.
+///
+/// # Examples
+///
+/// ```
+/// use serde_json::json;
+/// use json_schema_generator::{generate_typescript, CodegenOptions};
+///
+/// let instance = json!({"id": [1, "a"], "user": {"name": null}, "foo-bar": true});
+/// assert_eq!(
+///     generate_typescript(&instance, &CodegenOptions::default()),
+///     "export interface Root {
+///   \"foo-bar\": boolean;
+///   id: Array<number | string>;
+///   user: User;
+/// }
+///
+/// export interface User {
+///   name: null;
+/// }
+/// "
+/// );
+/// ```
+pub fn generate_typescript(instance: &Value, options: &CodegenOptions) -> String {
+    let model = Model::new(instance, options);
+    let mut code = String::new();
+    if !matches!(model.root, Type::Named(_)) {
+        let name = pascal_case(&options.root_name);
+        writeln!(
+            code,
+            "export type {} = {};",
+            name,
+            typescript_type(&model.root)
+        )
+        .unwrap();
+    }
+    for shape in model.ordered() {
+        if !code.is_empty() {
+            code.push('\n');
+        }
+        writeln!(code, "export interface {} {{", shape.name).unwrap();
+        for field in &shape.fields {
+            let key = if is_identifier(&field.key) {
+                field.key.clone()
+            } else {
+                Value::from(field.key.as_str()).to_string()
+            };
+            let optional = if field.required { "" } else { "?" };
+            let ty = typescript_type(&field.ty);
+            writeln!(code, "  {}{}: {};", key, optional, ty).unwrap();
+        }
+        code.push_str("}\n");
+    }
+    code
+}
+
+/// Returns the TypeScript type of `ty`.
+fn typescript_type(ty: &Type) -> String {
+    match ty {
+        Type::String => "string".to_string(),
+        Type::Integer | Type::Number => "number".to_string(),
+        Type::Boolean => "boolean".to_string(),
+        Type::Null => "null".to_string(),
+        Type::Any => "unknown".to_string(),
+        Type::Map => "Record<string, unknown>".to_string(),
+        Type::Array(items) => format!("Array<{}>", typescript_type(items)),
+        Type::Named(name) => name.clone(),
+        Type::Nullable(ty) => format!("{} | null", typescript_type(ty)),
+        Type::Union(types) => {
+            // An integer and a number are both numbers.
+            let mut branches: Vec<String> = Vec::new();
+            for branch in types.iter().map(typescript_type) {
+                if !branches.contains(&branch) {
+                    branches.push(branch);
+                }
+            }
+            branches.join(" | ")
+        }
+    }
+}
+
+/// Returns whether the property name `key` can be written unquoted in TypeScript.
+fn is_identifier(key: &str) -> bool {
+    let mut chars = key.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+}
+
+// syn is a dev-dependency off wasm32 only.
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn fields(code: &str, name: &str) -> Vec<String> {
+        let file = syn::parse_file(code).unwrap();
+        let item = file.items.iter().find_map(|item| match item {
+            syn::Item::Struct(item) if item.ident == name => Some(item),
+            _ => None,
+        });
+        let item = item.unwrap_or_else(|| panic!("no struct {} in\n{}", name, code));
+        item.fields
+            .iter()
+            .map(|field| {
+                let ident = field.ident.as_ref().unwrap();
+                let ty = &field.ty;
+                format!("{}: {}", ident, quote::quote!(#ty)).replace(' ', "")
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_nested_sample() {
+        let instance = json!({
+            "userId": 7,
+            "type": "admin",
+            "score": 9.5,
+            "address": {"city": "Oslo", "zip": null},
+            "contacts": [
+                {"name": "a", "address": {"city": "Bergen", "zip": null}},
+                {"name": "b", "phone": "123"}
+            ],
+            "id": [1, "x"],
+            "self": true,
+            "2fa": false,
+            "meta": {},
+            "history": []
+        });
+        let code = generate_rust_types(&instance, &CodegenOptions::default());
+        assert_eq!(
+            fields(&code, "Root"),
+            [
+                "field_2fa:bool",
+                "address:Address",
+                "contacts:Vec<ContactsItem>",
+                "history:Vec<serde_json::Value>",
+                "id:Vec<serde_json::Value>",
+                "meta:serde_json::Map<String,serde_json::Value>",
+                "score:f64",
+                "self_:bool",
+                "r#type:String",
+                "user_id:i64",
+            ]
+        );
+        // The address of a contact has the shape of the other.
+        assert_eq!(
+            fields(&code, "ContactsItem"),
+            [
+                "address:Option<Address>",
+                "name:String",
+                "phone:Option<String>"
+            ]
+        );
+        assert_eq!(
+            fields(&code, "Address"),
+            ["city:String", "zip:Option<serde_json::Value>"]
+        );
+        assert!(
+            code.contains("    // oneOf: i64 | String\n    pub id:"),
+            "{}",
+            code
+        );
+        assert!(
+            code.contains("    #[serde(rename = \"2fa\")]\n"),
+            "{}",
+            code
+        );
+        assert!(code.find("struct Root").unwrap() < code.find("struct Address").unwrap());
+    }
+
+    #[test]
+    fn test_names() {
+        // Another shape under a taken name is numbered, and no type shadows String.
+        let instance = json!({
+            "item": {"a": 1},
+            "list": [{"item": {"b": true}}],
+            "string": {"c": "x"},
+            "userId": 1,
+            "user_id": 2
+        });
+        let code = generate_rust_types(&instance, &CodegenOptions::default());
+        assert_eq!(fields(&code, "Item"), ["a:i64"]);
+        assert_eq!(fields(&code, "ListItem"), ["item:Item2"]);
+        assert_eq!(fields(&code, "Item2"), ["b:bool"]);
+        assert_eq!(
+            fields(&code, "Root"),
+            [
+                "item:Item",
+                "list:Vec<ListItem>",
+                "string:String2",
+                "user_id:i64",
+                "user_id_2:i64"
+            ]
+        );
+
+        // An object is named before the objects inside it, and fields named after a
+        // keyword are numbered before it is escaped.
+        let instance = json!({"nested": {"nested": {"a": 1}}, "self": 1, "Self": 2});
+        let code = generate_rust_types(&instance, &CodegenOptions::default());
+        assert_eq!(fields(&code, "Nested"), ["nested:Nested2"]);
+        assert_eq!(fields(&code, "Nested2"), ["a:i64"]);
+        assert_eq!(
+            fields(&code, "Root"),
+            ["self_:i64", "nested:Nested", "self_2:i64"]
+        );
+        syn::parse_file(&code).unwrap();
+
+        let options = CodegenOptions {
+            root_name: "events".to_string(),
+        };
+        let code = generate_rust_types(&json!([{"at": 1}, {"at": "x"}]), &options);
+        assert!(
+            code.contains("pub type Events = Vec<EventsItem>;\n"),
+            "{}",
+            code
+        );
+        assert_eq!(fields(&code, "EventsItem"), ["at:serde_json::Value"]);
+        let code = generate_rust_types(&json!("x"), &options);
+        assert!(code.ends_with("pub type Events = String;\n"), "{}", code);
+        syn::parse_file(&code).unwrap();
+    }
+
+    #[test]
+    fn test_typescript() {
+        let instance = json!({
+            "home": {"city": "Oslo", "zip": "0150"},
+            "offices": [{"city": "Bergen", "zip": null}, {"city": "Oslo", "zip": "0150"}],
+            "contacts": [
+                {"name": "a", "home": {"city": "Oslo", "zip": "0150"}},
+                {"name": "b", "tags": []}
+            ],
+            "x-id": [1, 2.5],
+            "$ref": {},
+            "score": 1
+        });
+        let options = CodegenOptions {
+            root_name: "user profile".to_string(),
+        };
+        assert_eq!(
+            generate_typescript(&instance, &options),
+            "\
+export interface UserProfile {
+  $ref: Record<string, unknown>;
+  contacts: Array<ContactsItem>;
+  home: Home;
+  offices: Array<OfficesItem>;
+  score: number;
+  \"x-id\": Array<number>;
+}
+
+export interface ContactsItem {
+  home?: Home;
+  name: string;
+  tags?: Array<unknown>;
+}
+
+export interface Home {
+  city: string;
+  zip: string;
+}
+
+export interface OfficesItem {
+  city: string;
+  zip: string | null;
+}
+"
+        );
+        assert_eq!(
+            generate_typescript(&json!([{"a": 1}, null, "x"]), &options),
+            "export type UserProfile = Array<UserProfileItem | string | null>;\n\n\
+             export interface UserProfileItem {\n  a: number;\n}\n"
+        );
+    }
+}
@@ -0,0 +1,727 @@
+//! Structural compatibility checks between two versions of a schema.
+//!
+//! The checker walks both schemas side by side and reports every place where one of
+//! them rejects instances the other accepts. It is conservative: it compares keywords
+//! structurally rather than deciding set inclusion, so unusual but equivalent
+//! rewrites of a schema may be reported, and `$ref` targets are not followed.
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::fmt;
+
+/// Which direction of compatibility [`check_compatibility`] checks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompatMode {
+    /// The new schema accepts every instance the old schema accepted, so consumers
+    /// upgraded to the new schema still read old data.
+    Backward,
+    /// The old schema accepts every instance the new schema accepts, so consumers still
+    /// on the old schema read new data.
+    Forward,
+    /// Both backward and forward.
+    Full,
+}
+
+/// The rule broken by a [`CompatViolation`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CompatRule {
+    /// A property became required.
+    AddedRequired,
+    /// A type is no longer accepted.
+    NarrowedType,
+    /// A value was dropped from an `enum`.
+    RemovedEnumValue,
+    /// An `enum` was added to a schema that accepted any value of its type.
+    AddedEnum,
+    /// A `minimum`, `maxLength` or similar bound became stricter or was added.
+    TightenedBound,
+    /// A property was removed from an object that rejects unknown properties.
+    RemovedProperty,
+    /// `additionalProperties` was set to `false`.
+    ClosedAdditionalProperties,
+    /// A `pattern` was added or changed.
+    ChangedPattern,
+}
+
+impl CompatRule {
+    /// The stable identifier of the rule, as used in serialized reports.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CompatRule::AddedRequired => "added-required",
+            CompatRule::NarrowedType => "narrowed-type",
+            CompatRule::RemovedEnumValue => "removed-enum-value",
+            CompatRule::AddedEnum => "added-enum",
+            CompatRule::TightenedBound => "tightened-bound",
+            CompatRule::RemovedProperty => "removed-property",
+            CompatRule::ClosedAdditionalProperties => "closed-additional-properties",
+            CompatRule::ChangedPattern => "changed-pattern",
+        }
+    }
+}
+
+impl fmt::Display for CompatRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A place where one schema rejects instances the other accepts.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CompatViolation {
+    /// The direction that is broken, either [`CompatMode::Backward`] or
+    /// [`CompatMode::Forward`].
+    pub direction: CompatMode,
+    /// The rule that is broken.
+    pub rule: CompatRule,
+    /// The JSON Pointer of the schema node concerned, such as `/properties/id`.
+    pub pointer: String,
+    /// A human-readable description.
+    pub message: String,
+}
+
+impl fmt::Display for CompatViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let at = if self.pointer.is_empty() {
+            "/"
+        } else {
+            &self.pointer
+        };
+        write!(f, "{} [{}]: {}", at, self.rule, self.message)
+    }
+}
+
+/// The result of [`check_compatibility`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CompatReport {
+    /// The mode the schemas were checked in.
+    pub mode: CompatMode,
+    /// Every violation found, in schema order.
+    pub violations: Vec<CompatViolation>,
+}
+
+impl CompatReport {
+    /// Returns whether no violation was found.
+    pub fn is_compatible(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+/// A one-line summary such as `backward compatible` or `not forward compatible: 2 violations`.
+impl fmt::Display for CompatReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mode = match self.mode {
+            CompatMode::Backward => "backward",
+            CompatMode::Forward => "forward",
+            CompatMode::Full => "fully",
+        };
+        match self.violations.len() {
+            0 => write!(f, "{} compatible", mode),
+            n => write!(
+                f,
+                "not {} compatible: {} violation{}",
+                mode,
+                n,
+                if n == 1 { "" } else { "s" }
+            ),
+        }
+    }
+}
+
+/// Checks whether instances valid against one version of a schema remain valid
+/// against the other.
+///
+/// # Examples
+///
+/// ```
+/// use serde_json::json;
+/// use json_schema_generator::{check_compatibility, CompatMode, CompatRule};
+///
+/// let old = json!({"type": "object", "properties": {"id": {"type": "integer"}}});
+/// let new = json!({
+///     "type": "object",
+///     "properties": {"id": {"type": "integer"}},
+///     "required": ["id"]
+/// });
+///
+/// let report = check_compatibility(&old, &new, CompatMode::Backward);
+/// assert_eq!(report.violations[0].rule, CompatRule::AddedRequired);
+/// assert!(check_compatibility(&old, &new, CompatMode::Forward).is_compatible());
+/// ```
+pub fn check_compatibility(old: &Value, new: &Value, mode: CompatMode) -> CompatReport {
+    let mut violations = Vec::new();
+    if matches!(mode, CompatMode::Backward | CompatMode::Full) {
+        let mut walker = Walker::new(CompatMode::Backward, &mut violations);
+        walker.compare(old, new, "");
+    }
+    if matches!(mode, CompatMode::Forward | CompatMode::Full) {
+        let mut walker = Walker::new(CompatMode::Forward, &mut violations);
+        walker.compare(new, old, "");
+    }
+    CompatReport { mode, violations }
+}
+
+/// Returns whether `new` accepts every instance `old` accepted.
+pub fn is_backward_compatible(old: &Value, new: &Value) -> bool {
+    check_compatibility(old, new, CompatMode::Backward).is_compatible()
+}
+
+/// Returns whether `old` accepts every instance `new` accepts.
+pub fn is_forward_compatible(old: &Value, new: &Value) -> bool {
+    check_compatibility(old, new, CompatMode::Forward).is_compatible()
+}
+
+/// Lower bounds, which are tightened by increasing them.
+const LOWER_BOUNDS: [&str; 5] = [
+    "minimum",
+    "exclusiveMinimum",
+    "minLength",
+    "minItems",
+    "minProperties",
+];
+
+/// Upper bounds, which are tightened by decreasing them.
+const UPPER_BOUNDS: [&str; 5] = [
+    "maximum",
+    "exclusiveMaximum",
+    "maxLength",
+    "maxItems",
+    "maxProperties",
+];
+
+/// Compares a `wider` schema against a `narrower` one, reporting everything the
+/// narrower schema rejects that the wider one accepted.
+struct Walker<'a> {
+    direction: CompatMode,
+    /// What to call the wider and the narrower schema in messages.
+    wider: &'static str,
+    narrower: &'static str,
+    violations: &'a mut Vec<CompatViolation>,
+}
+
+impl<'a> Walker<'a> {
+    fn new(direction: CompatMode, violations: &'a mut Vec<CompatViolation>) -> Self {
+        let (wider, narrower) = match direction {
+            CompatMode::Forward => ("new", "old"),
+            _ => ("old", "new"),
+        };
+        Walker {
+            direction,
+            wider,
+            narrower,
+            violations,
+        }
+    }
+
+    fn report(&mut self, rule: CompatRule, pointer: &str, message: String) {
+        self.violations.push(CompatViolation {
+            direction: self.direction,
+            rule,
+            pointer: pointer.to_string(),
+            message,
+        });
+    }
+
+    fn compare(&mut self, wider: &Value, narrower: &Value, pointer: &str) {
+        if accepts_nothing(wider) || is_unconstrained(narrower) {
+            return;
+        }
+        let (Some(wider), Some(narrower)) = (wider.as_object(), narrower.as_object()) else {
+            // `false` on the narrower side while the wider one accepts something.
+            self.report(
+                CompatRule::NarrowedType,
+                pointer,
+                format!("the {} schema rejects every instance", self.narrower),
+            );
+            return;
+        };
+
+        let wider_branches = alternatives(wider);
+        let narrower_branches = alternatives(narrower);
+        if wider_branches.len() == 1 && narrower_branches.len() == 1 {
+            self.compare_objects(wider, narrower, pointer);
+            return;
+        }
+
+        // Pair each branch of the wider schema with a narrower branch accepting all of
+        // its types, and compare the two.
+        for branch in wider_branches {
+            let branch_types = types(branch);
+            let counterpart = narrower_branches.iter().find(|candidate| {
+                match (&branch_types, types(candidate)) {
+                    (_, None) => true,
+                    (None, Some(_)) => false,
+                    (Some(types), Some(accepted)) => types.iter().all(|ty| covers(&accepted, ty)),
+                }
+            });
+            match counterpart {
+                Some(counterpart) => self.compare_objects(branch, counterpart, pointer),
+                None => {
+                    let described = match &branch_types {
+                        Some(types) => format!("type `{}`", types.join("`, `")),
+                        None => "some instances".to_string(),
+                    };
+                    self.report(
+                        CompatRule::NarrowedType,
+                        pointer,
+                        format!(
+                            "the {} schema rejects {} accepted by the {} one",
+                            self.narrower, described, self.wider
+                        ),
+                    );
+                }
+            }
+        }
+    }
+
+    fn compare_objects(
+        &mut self,
+        wider: &Map<String, Value>,
+        narrower: &Map<String, Value>,
+        pointer: &str,
+    ) {
+        self.compare_types(wider, narrower, pointer);
+        self.compare_enums(wider, narrower, pointer);
+        self.compare_bounds(wider, narrower, pointer);
+
+        let wider_pattern = wider.get("pattern");
+        if let Some(pattern) = narrower.get("pattern") {
+            if wider_pattern != Some(pattern) {
+                self.report(
+                    CompatRule::ChangedPattern,
+                    pointer,
+                    format!(
+                        "the {} schema requires pattern {}, the {} one {}",
+                        self.narrower,
+                        pattern,
+                        self.wider,
+                        match wider_pattern {
+                            Some(pattern) => format!("requires {}", pattern),
+                            None => "has none".to_string(),
+                        }
+                    ),
+                );
+            }
+        }
+
+        self.compare_required(wider, narrower, pointer);
+        self.compare_properties(wider, narrower, pointer);
+
+        if let (Some(wider_items), Some(narrower_items)) =
+            (wider.get("items"), narrower.get("items"))
+        {
+            self.compare(wider_items, narrower_items, &format!("{}/items", pointer));
+        }
+    }
+
+    fn compare_types(
+        &mut self,
+        wider: &Map<String, Value>,
+        narrower: &Map<String, Value>,
+        pointer: &str,
+    ) {
+        let Some(accepted) = types(narrower) else {
+            return;
+        };
+        match types(wider) {
+            None => self.report(
+                CompatRule::NarrowedType,
+                pointer,
+                format!(
+                    "the {} schema only accepts type `{}`, the {} one accepts any type",
+                    self.narrower,
+                    accepted.join("`, `"),
+                    self.wider
+                ),
+            ),
+            Some(types) => {
+                for ty in types {
+                    if !covers(&accepted, ty) {
+                        self.report(
+                            CompatRule::NarrowedType,
+                            pointer,
+                            format!(
+                                "the {} schema rejects type `{}` accepted by the {} one",
+                                self.narrower, ty, self.wider
+                            ),
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    fn compare_enums(
+        &mut self,
+        wider: &Map<String, Value>,
+        narrower: &Map<String, Value>,
+        pointer: &str,
+    ) {
+        let Some(allowed) = narrower.get("enum").and_then(Value::as_array) else {
+            return;
+        };
+        match wider.get("enum").and_then(Value::as_array) {
+            None => self.report(
+                CompatRule::AddedEnum,
+                pointer,
+                format!(
+                    "the {} schema restricts values to an enum, the {} one does not",
+                    self.narrower, self.wider
+                ),
+            ),
+            Some(values) => {
+                for value in values.iter().filter(|value| !allowed.contains(value)) {
+                    self.report(
+                        CompatRule::RemovedEnumValue,
+                        pointer,
+                        format!(
+                            "the {} schema rejects enum value {} accepted by the {} one",
+                            self.narrower, value, self.wider
+                        ),
+                    );
+                }
+            }
+        }
+    }
+
+    fn compare_bounds(
+        &mut self,
+        wider: &Map<String, Value>,
+        narrower: &Map<String, Value>,
+        pointer: &str,
+    ) {
+        let bounds = LOWER_BOUNDS
+            .iter()
+            .map(|keyword| (*keyword, true))
+            .chain(UPPER_BOUNDS.iter().map(|keyword| (*keyword, false)));
+        for (keyword, lower) in bounds {
+            let Some(bound) = narrower.get(keyword).and_then(Value::as_f64) else {
+                continue;
+            };
+            let previous = wider.get(keyword).and_then(Value::as_f64);
+            let tightened = match previous {
+                Some(previous) if lower => bound > previous,
+                Some(previous) => bound < previous,
+                None => true,
+            };
+            if tightened {
+                self.report(
+                    CompatRule::TightenedBound,
+                    pointer,
+                    format!(
+                        "`{}` is {} in the {} schema but {} in the {} one",
+                        keyword,
+                        narrower[keyword],
+                        self.narrower,
+                        match wider.get(keyword) {
+                            Some(previous) => previous.to_string(),
+                            None => "unset".to_string(),
+                        },
+                        self.wider
+                    ),
+                );
+            }
+        }
+    }
+
+    fn compare_required(
+        &mut self,
+        wider: &Map<String, Value>,
+        narrower: &Map<String, Value>,
+        pointer: &str,
+    ) {
+        let Some(required) = narrower.get("required").and_then(Value::as_array) else {
+            return;
+        };
+        let previous = wider.get("required").and_then(Value::as_array);
+        for name in required.iter().filter_map(Value::as_str) {
+            let was_required = previous
+                .is_some_and(|previous| previous.iter().any(|entry| entry.as_str() == Some(name)));
+            if !was_required {
+                self.report(
+                    CompatRule::AddedRequired,
+                    pointer,
+                    format!(
+                        "property `{}` is required by the {} schema but not by the {} one",
+                        name, self.narrower, self.wider
+                    ),
+                );
+            }
+        }
+    }
+
+    fn compare_properties(
+        &mut self,
+        wider: &Map<String, Value>,
+        narrower: &Map<String, Value>,
+        pointer: &str,
+    ) {
+        let empty = Map::new();
+        let wider_properties = properties(wider).unwrap_or(&empty);
+        let narrower_properties = properties(narrower).unwrap_or(&empty);
+        let additional = narrower.get("additionalProperties");
+        let closed = additional == Some(&Value::Bool(false));
+
+        if closed && wider.get("additionalProperties") != Some(&Value::Bool(false)) {
+            self.report(
+                CompatRule::ClosedAdditionalProperties,
+                pointer,
+                format!(
+                    "the {} schema rejects properties it does not list, the {} one allows them",
+                    self.narrower, self.wider
+                ),
+            );
+        }
+
+        for (name, schema) in wider_properties {
+            let child = format!(
+                "{}/properties/{}",
+                pointer,
+                name.replace('~', "~0").replace('/', "~1")
+            );
+            match narrower_properties.get(name) {
+                Some(counterpart) => self.compare(schema, counterpart, &child),
+                None if closed => self.report(
+                    CompatRule::RemovedProperty,
+                    &child,
+                    format!(
+                        "the {} schema rejects property `{}` accepted by the {} one, \
+                         because it does not allow additional properties",
+                        self.narrower, name, self.wider
+                    ),
+                ),
+                // The property is now validated against `additionalProperties`.
+                None => {
+                    if let Some(additional) = additional {
+                        self.compare(schema, additional, &child);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The branches of a `oneOf` or `anyOf` schema, or the schema itself.
+fn alternatives(schema: &Map<String, Value>) -> Vec<&Map<String, Value>> {
+    let branches = schema
+        .get("oneOf")
+        .or_else(|| schema.get("anyOf"))
+        .and_then(Value::as_array);
+    match branches {
+        Some(branches) => branches.iter().filter_map(Value::as_object).collect(),
+        None => vec![schema],
+    }
+}
+
+/// The types accepted by `schema`, or `None` if it doesn't restrict them.
+fn types(schema: &Map<String, Value>) -> Option<Vec<&str>> {
+    match schema.get("type")? {
+        Value::String(ty) => Some(vec![ty.as_str()]),
+        Value::Array(types) => Some(types.iter().filter_map(Value::as_str).collect()),
+        _ => None,
+    }
+}
+
+/// Returns whether a schema accepting `accepted` types accepts all values of type `ty`.
+fn covers(accepted: &[&str], ty: &str) -> bool {
+    accepted.contains(&ty) || (ty == "integer" && accepted.contains(&"number"))
+}
+
+fn properties(schema: &Map<String, Value>) -> Option<&Map<String, Value>> {
+    schema.get("properties").and_then(Value::as_object)
+}
+
+/// Returns whether `schema` accepts every instance, ignoring annotation keywords.
+fn is_unconstrained(schema: &Value) -> bool {
+    match schema {
+        Value::Bool(accepts) => *accepts,
+        Value::Object(schema) => schema
+            .keys()
+            .all(|key| matches!(key.as_str(), "$schema" | "title" | "description")),
+        _ => false,
+    }
+}
+
+fn accepts_nothing(schema: &Value) -> bool {
+    schema == &Value::Bool(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn rules(report: &CompatReport) -> Vec<(CompatRule, &str)> {
+        report
+            .violations
+            .iter()
+            .map(|violation| (violation.rule, violation.pointer.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_identical_schemas_are_compatible() {
+        let schema = json!({
+            "type": "object",
+            "properties": {"id": {"type": "integer"}, "tags": {"type": "array", "items": {"type": "string"}}},
+            "required": ["id"]
+        });
+        let report = check_compatibility(&schema, &schema, CompatMode::Full);
+        assert!(report.is_compatible());
+        assert_eq!(report.to_string(), "fully compatible");
+    }
+
+    #[test]
+    fn test_added_required() {
+        let old = json!({"type": "object", "properties": {"id": {"type": "integer"}}});
+        let new = json!({"type": "object", "properties": {"id": {"type": "integer"}}, "required": ["id"]});
+
+        let report = check_compatibility(&old, &new, CompatMode::Backward);
+        assert_eq!(rules(&report), vec![(CompatRule::AddedRequired, "")]);
+        assert_eq!(
+            report.violations[0].message,
+            "property `id` is required by the new schema but not by the old one"
+        );
+        assert!(is_forward_compatible(&old, &new));
+    }
+
+    #[test]
+    fn test_narrowed_type() {
+        let old = json!({"type": "object", "properties": {"n": {"type": "number"}}});
+        let new = json!({"type": "object", "properties": {"n": {"type": "integer"}}});
+
+        let report = check_compatibility(&old, &new, CompatMode::Backward);
+        assert_eq!(
+            rules(&report),
+            vec![(CompatRule::NarrowedType, "/properties/n")]
+        );
+        // Widening `integer` to `number` is fine.
+        assert!(is_forward_compatible(&old, &new));
+    }
+
+    #[test]
+    fn test_narrowed_union() {
+        let old = json!({"oneOf": [{"type": "integer"}, {"type": "string"}]});
+        let new = json!({"type": "number"});
+
+        let report = check_compatibility(&old, &new, CompatMode::Backward);
+        assert_eq!(rules(&report), vec![(CompatRule::NarrowedType, "")]);
+        assert!(report.violations[0].message.contains("`string`"));
+        assert!(!is_backward_compatible(&new, &old));
+        assert!(is_backward_compatible(
+            &json!({"type": "string"}),
+            &json!({"type": ["string", "null"]})
+        ));
+    }
+
+    #[test]
+    fn test_removed_enum_value() {
+        let old = json!({"type": "string", "enum": ["a", "b"]});
+        let new = json!({"type": "string", "enum": ["a"]});
+
+        let report = check_compatibility(&old, &new, CompatMode::Full);
+        assert_eq!(rules(&report), vec![(CompatRule::RemovedEnumValue, "")]);
+        assert_eq!(report.violations[0].direction, CompatMode::Backward);
+        assert_eq!(
+            check_compatibility(&json!({"type": "string"}), &new, CompatMode::Backward).violations
+                [0]
+            .rule,
+            CompatRule::AddedEnum
+        );
+    }
+
+    #[test]
+    fn test_tightened_bound() {
+        let old = json!({"type": "string", "maxLength": 20, "minLength": 1});
+        let new = json!({"type": "string", "maxLength": 10, "minLength": 1, "minimum": 0});
+
+        let report = check_compatibility(&old, &new, CompatMode::Backward);
+        assert_eq!(
+            rules(&report),
+            vec![
+                (CompatRule::TightenedBound, ""),
+                (CompatRule::TightenedBound, "")
+            ]
+        );
+        assert_eq!(
+            report.violations[1].message,
+            "`maxLength` is 10 in the new schema but 20 in the old one"
+        );
+        assert!(!is_forward_compatible(&new, &old));
+        assert!(is_forward_compatible(
+            &json!({"type": "integer", "maximum": 5}),
+            &json!({"type": "integer", "maximum": 3})
+        ));
+    }
+
+    #[test]
+    fn test_removed_property_under_closed_object() {
+        let old = json!({
+            "type": "object",
+            "properties": {"a": {"type": "string"}, "b": {"type": "string"}},
+            "additionalProperties": false
+        });
+        let new = json!({
+            "type": "object",
+            "properties": {"a": {"type": "string"}},
+            "additionalProperties": false
+        });
+
+        let report = check_compatibility(&old, &new, CompatMode::Backward);
+        assert_eq!(
+            rules(&report),
+            vec![(CompatRule::RemovedProperty, "/properties/b")]
+        );
+
+        // Without `additionalProperties: false` the removed property is still accepted.
+        let open = json!({"type": "object", "properties": {"a": {"type": "string"}}});
+        assert!(is_backward_compatible(&old, &open));
+        assert_eq!(
+            rules(&check_compatibility(&open, &new, CompatMode::Backward)),
+            vec![(CompatRule::ClosedAdditionalProperties, "")]
+        );
+    }
+
+    #[test]
+    fn test_nested_paths_and_pattern() {
+        let old = json!({"type": "array", "items": {"type": "object", "properties": {"a/b": {"type": "string"}}}});
+        let new = json!({"type": "array", "items": {"type": "object", "properties": {"a/b": {"type": "string", "pattern": "^x"}}}});
+
+        let report = check_compatibility(&old, &new, CompatMode::Backward);
+        assert_eq!(
+            rules(&report),
+            vec![(CompatRule::ChangedPattern, "/items/properties/a~1b")]
+        );
+    }
+
+    #[test]
+    fn test_report_serialization() {
+        let report = check_compatibility(
+            &json!({"type": "integer"}),
+            &json!({"type": "string"}),
+            CompatMode::Full,
+        );
+        assert_eq!(report.to_string(), "not fully compatible: 2 violations");
+        assert_eq!(
+            serde_json::to_value(&report).unwrap(),
+            json!({
+                "mode": "full",
+                "violations": [
+                    {
+                        "direction": "backward",
+                        "rule": "narrowed-type",
+                        "pointer": "",
+                        "message": "the new schema rejects type `integer` accepted by the old one"
+                    },
+                    {
+                        "direction": "forward",
+                        "rule": "narrowed-type",
+                        "pointer": "",
+                        "message": "the old schema rejects type `string` accepted by the new one"
+                    }
+                ]
+            })
+        );
+    }
+}
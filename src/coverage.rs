@@ -0,0 +1,370 @@
+//! How much of a reference schema an inferred schema reproduces.
+//!
+//! Unlike a diff, the two sides are not peers: the reference is ground truth, and each
+//! of its keywords is either matched or missed by the inferred schema. Keywords only
+//! the inferred schema has are reported as extra.
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Keywords that document a schema rather than constrain it, and are not compared.
+const ANNOTATIONS: [&str; 8] = [
+    "$schema",
+    "$id",
+    "$comment",
+    "title",
+    "description",
+    "examples",
+    "default",
+    "deprecated",
+];
+
+/// A keyword's status in a [`CoverageReport`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CoverageStatus {
+    /// The inferred schema has the keyword with the reference value.
+    Matched,
+    /// The inferred schema lacks the keyword or has a different value.
+    Missing,
+    /// Only the inferred schema has the keyword.
+    Extra,
+}
+
+/// A keyword of either schema, aligned by its location.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CoverageEntry {
+    /// The JSON Pointer of the schema node holding the keyword, such as `/properties/id`.
+    pub pointer: String,
+    /// The keyword, such as `format`.
+    pub keyword: String,
+    /// Whether the inferred schema reproduces the keyword.
+    pub status: CoverageStatus,
+    /// The value in the reference schema.
+    pub reference: Option<Value>,
+    /// The value in the inferred schema.
+    pub inferred: Option<Value>,
+}
+
+/// Per-keyword totals in a [`CoverageReport`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct KeywordCoverage {
+    /// The number of reference keywords the inferred schema reproduces.
+    pub matched: usize,
+    /// The number of reference keywords the inferred schema misses.
+    pub missing: usize,
+    /// The number of keywords only the inferred schema has.
+    pub extra: usize,
+}
+
+impl KeywordCoverage {
+    /// The percentage of reference keywords matched, or 100 if there are none.
+    pub fn percent(&self) -> f64 {
+        match self.matched + self.missing {
+            0 => 100.0,
+            total => self.matched as f64 * 100.0 / total as f64,
+        }
+    }
+
+    fn add(&mut self, status: CoverageStatus) {
+        match status {
+            CoverageStatus::Matched => self.matched += 1,
+            CoverageStatus::Missing => self.missing += 1,
+            CoverageStatus::Extra => self.extra += 1,
+        }
+    }
+}
+
+/// The result of [`coverage_report`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CoverageReport {
+    /// Every compared keyword, in schema order.
+    pub entries: Vec<CoverageEntry>,
+    /// The totals over all keywords.
+    pub total: KeywordCoverage,
+    /// The totals for each keyword, such as `format` or `enum`.
+    pub by_keyword: BTreeMap<String, KeywordCoverage>,
+    /// The percentage of reference keywords matched.
+    pub percent: f64,
+}
+
+impl CoverageReport {
+    /// Returns the entries with the given status.
+    pub fn with_status(&self, status: CoverageStatus) -> impl Iterator<Item = &CoverageEntry> {
+        self.entries
+            .iter()
+            .filter(move |entry| entry.status == status)
+    }
+}
+
+/// A one-line summary such as `matched 12 of 20 reference keywords (60.0%), 3 extra`.
+impl fmt::Display for CoverageReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "matched {} of {} reference keywords ({:.1}%), {} extra",
+            self.total.matched,
+            self.total.matched + self.total.missing,
+            self.percent,
+            self.total.extra
+        )
+    }
+}
+
+/// Compares an `inferred` schema against a hand-written `reference` schema.
+///
+/// The schemas are aligned by property path: the nodes under `properties` and `items`
+/// are compared recursively, and every other keyword of the reference is matched if the
+/// inferred schema has it with the same value at the same location. `type`, `enum` and
+/// `required` are compared as sets. Annotations such as `title` and `description` are
+/// not compared.
+///
+/// # Examples
+///
+/// ```
+/// use serde_json::json;
+/// use json_schema_generator::{coverage_report, generate_json_schema};
+///
+/// let reference = json!({
+///     "type": "object",
+///     "properties": {"email": {"type": "string", "format": "email"}}
+/// });
+/// let inferred = generate_json_schema(&json!({"email": "a@example.com"}));
+///
+/// let report = coverage_report(&reference, &inferred);
+/// assert_eq!(report.by_keyword["format"].missing, 1);
+/// assert_eq!(report.by_keyword["type"].matched, 2);
+/// ```
+pub fn coverage_report(reference: &Value, inferred: &Value) -> CoverageReport {
+    let mut entries = Vec::new();
+    align(
+        reference.as_object(),
+        inferred.as_object(),
+        "",
+        &mut entries,
+    );
+
+    let mut total = KeywordCoverage::default();
+    let mut by_keyword: BTreeMap<String, KeywordCoverage> = BTreeMap::new();
+    for entry in &entries {
+        total.add(entry.status);
+        by_keyword
+            .entry(entry.keyword.clone())
+            .or_default()
+            .add(entry.status);
+    }
+
+    CoverageReport {
+        entries,
+        percent: total.percent(),
+        total,
+        by_keyword,
+    }
+}
+
+fn align(
+    reference: Option<&Map<String, Value>>,
+    inferred: Option<&Map<String, Value>>,
+    pointer: &str,
+    entries: &mut Vec<CoverageEntry>,
+) {
+    let empty = Map::new();
+    let reference = reference.unwrap_or(&empty);
+    let inferred = inferred.unwrap_or(&empty);
+
+    let compared = |keyword: &String| {
+        !ANNOTATIONS.contains(&keyword.as_str()) && keyword != "properties" && keyword != "items"
+    };
+    for (keyword, value) in reference.iter().filter(|(keyword, _)| compared(keyword)) {
+        let other = inferred.get(keyword);
+        let status = match other {
+            Some(other) if same_value(keyword, value, other) => CoverageStatus::Matched,
+            _ => CoverageStatus::Missing,
+        };
+        entries.push(CoverageEntry {
+            pointer: pointer.to_string(),
+            keyword: keyword.clone(),
+            status,
+            reference: Some(value.clone()),
+            inferred: other.cloned(),
+        });
+    }
+    for (keyword, value) in inferred.iter().filter(|(keyword, _)| compared(keyword)) {
+        if !reference.contains_key(keyword) {
+            entries.push(CoverageEntry {
+                pointer: pointer.to_string(),
+                keyword: keyword.clone(),
+                status: CoverageStatus::Extra,
+                reference: None,
+                inferred: Some(value.clone()),
+            });
+        }
+    }
+
+    let reference_properties = properties(reference).unwrap_or(&empty);
+    let inferred_properties = properties(inferred).unwrap_or(&empty);
+    let names = reference_properties.keys().chain(
+        inferred_properties
+            .keys()
+            .filter(|name| !reference_properties.contains_key(*name)),
+    );
+    for name in names {
+        let child = format!(
+            "{}/properties/{}",
+            pointer,
+            name.replace('~', "~0").replace('/', "~1")
+        );
+        align(
+            reference_properties.get(name).and_then(Value::as_object),
+            inferred_properties.get(name).and_then(Value::as_object),
+            &child,
+            entries,
+        );
+    }
+
+    let reference_items = reference.get("items").and_then(Value::as_object);
+    let inferred_items = inferred.get("items").and_then(Value::as_object);
+    if reference_items.is_some() || inferred_items.is_some() {
+        align(
+            reference_items,
+            inferred_items,
+            &format!("{}/items", pointer),
+            entries,
+        );
+    }
+}
+
+fn properties(schema: &Map<String, Value>) -> Option<&Map<String, Value>> {
+    schema.get("properties").and_then(Value::as_object)
+}
+
+/// Compares two values of `keyword`, ignoring order where the keyword holds a set.
+fn same_value(keyword: &str, reference: &Value, inferred: &Value) -> bool {
+    match (keyword, reference, inferred) {
+        ("type" | "enum" | "required", Value::Array(a), Value::Array(b)) => {
+            a.len() == b.len() && a.iter().all(|value| b.contains(value))
+        }
+        // `"type": "string"` and `"type": ["string"]` accept the same values.
+        ("type", Value::Array(a), single) | ("type", single, Value::Array(a)) => {
+            a.len() == 1 && &a[0] == single
+        }
+        _ => reference == inferred,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate_json_schema;
+    use serde_json::json;
+
+    fn statuses(report: &CoverageReport) -> Vec<(&str, &str, CoverageStatus)> {
+        report
+            .entries
+            .iter()
+            .map(|entry| (entry.pointer.as_str(), entry.keyword.as_str(), entry.status))
+            .collect()
+    }
+
+    #[test]
+    fn test_identical_schemas() {
+        let schema = generate_json_schema(&json!({"a": 1, "b": [{"c": "x"}]}));
+        let report = coverage_report(&schema, &schema);
+        assert_eq!(report.total.missing, 0);
+        assert_eq!(report.total.extra, 0);
+        assert_eq!(report.percent, 100.0);
+    }
+
+    #[test]
+    fn test_partially_matched_formats_and_enums() {
+        let reference = json!({
+            "$schema": "http://json-schema.org/draft-07/schema#",
+            "title": "User",
+            "type": "object",
+            "properties": {
+                "email": {"type": "string", "format": "email"},
+                "created": {"type": "string", "format": "date-time"},
+                "role": {"type": "string", "enum": ["admin", "user"]},
+                "age": {"type": "integer", "minimum": 0}
+            },
+            "required": ["email", "role"]
+        });
+        let inferred = json!({
+            "type": "object",
+            "properties": {
+                "email": {"type": "string", "format": "email"},
+                "created": {"type": "string"},
+                "role": {"type": "string", "enum": ["user", "admin"]},
+                "age": {"type": "integer"},
+                "nickname": {"type": "string"}
+            },
+            "required": ["role", "email", "age"]
+        });
+
+        let report = coverage_report(&reference, &inferred);
+        assert_eq!(
+            statuses(&report),
+            vec![
+                ("", "required", CoverageStatus::Missing),
+                ("", "type", CoverageStatus::Matched),
+                ("/properties/age", "minimum", CoverageStatus::Missing),
+                ("/properties/age", "type", CoverageStatus::Matched),
+                ("/properties/created", "format", CoverageStatus::Missing),
+                ("/properties/created", "type", CoverageStatus::Matched),
+                ("/properties/email", "format", CoverageStatus::Matched),
+                ("/properties/email", "type", CoverageStatus::Matched),
+                ("/properties/role", "enum", CoverageStatus::Matched),
+                ("/properties/role", "type", CoverageStatus::Matched),
+                ("/properties/nickname", "type", CoverageStatus::Extra),
+            ]
+        );
+        assert_eq!(
+            report.by_keyword["format"],
+            KeywordCoverage {
+                matched: 1,
+                missing: 1,
+                extra: 0
+            }
+        );
+        assert_eq!(report.by_keyword["enum"].percent(), 100.0);
+        assert_eq!(
+            report.to_string(),
+            "matched 7 of 10 reference keywords (70.0%), 1 extra"
+        );
+
+        let missing: Vec<_> = report.with_status(CoverageStatus::Missing).collect();
+        assert_eq!(missing[0].inferred, Some(json!(["role", "email", "age"])));
+        assert_eq!(missing[2].inferred, None);
+    }
+
+    #[test]
+    fn test_missing_subtree_and_items() {
+        let reference = json!({
+            "type": "array",
+            "items": {"type": "object", "properties": {"id/x": {"type": "integer", "maximum": 9}}}
+        });
+        let inferred = generate_json_schema(&json!([{"other": 1}]));
+
+        let report = coverage_report(&reference, &inferred);
+        let missing: Vec<_> = report
+            .with_status(CoverageStatus::Missing)
+            .map(|entry| (entry.pointer.as_str(), entry.keyword.as_str()))
+            .collect();
+        assert_eq!(
+            missing,
+            vec![
+                ("/items/properties/id~1x", "maximum"),
+                ("/items/properties/id~1x", "type"),
+            ]
+        );
+        assert_eq!(report.total.matched, 2);
+    }
+
+    #[test]
+    fn test_type_as_single_element_array() {
+        let report = coverage_report(&json!({"type": ["string"]}), &json!({"type": "string"}));
+        assert_eq!(report.total.matched, 1);
+    }
+}
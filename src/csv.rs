@@ -0,0 +1,410 @@
+//! Schema generation from CSV exports: each row is an object keyed by the header, whose
+//! cells are typed by their text.
+
+use crate::{GeneratorError, SchemaGenerator, SchemaOptions};
+use serde_json::{Map, Number, Value};
+use std::fmt::Display;
+use std::io::BufRead;
+
+/// How CSV is read, see [`csv_records`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsvOptions {
+    /// The character between the fields of a row.
+    pub delimiter: char,
+    /// Whether the first row names the columns. Without it the columns are named
+    /// `column1`, `column2` and so on.
+    pub header: bool,
+    /// What an empty unquoted cell stands for.
+    pub empty_cells: EmptyCells,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        CsvOptions {
+            delimiter: ',',
+            header: true,
+            empty_cells: EmptyCells::Null,
+        }
+    }
+}
+
+/// What an empty unquoted cell of a CSV row stands for. A quoted empty cell, `""`, is
+/// always the empty string.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EmptyCells {
+    /// `null`, so that a column with blank cells is nullable.
+    #[default]
+    Null,
+    /// Nothing: the row lacks the property, so that a column with blank cells is
+    /// optional.
+    Omit,
+    /// The empty string.
+    EmptyString,
+}
+
+/// The rows of a CSV document as JSON objects, see [`csv_records`].
+#[derive(Debug)]
+pub struct CsvRecords<R> {
+    reader: R,
+    options: CsvOptions,
+    header: Option<Vec<String>>,
+    /// How many lines were read.
+    line: usize,
+    buffer: String,
+}
+
+/// Reads the CSV document `reader` one row at a time, as an object for each row other
+/// than the header, mapping the name of each column to its cell.
+///
+/// A quoted cell is a string, so that IDs such as `"007"` keep their leading zeros. An
+/// unquoted cell is `true` or `false` as a boolean, a JSON number such as `42` or `-1.5`
+/// as a number, empty as [`CsvOptions::empty_cells`] says, and anything else, such as
+/// `007`, as a string. Quoted cells may hold the delimiter, line breaks, and quotes
+/// written twice. A row with more cells than the header fails with
+/// [`GeneratorError::InvalidInput`], naming its line counted from 1; a row with fewer
+/// lacks the properties of the last columns. A byte order mark at the start of the
+/// document, as in Excel's UTF-8 exports, is skipped.
+///
+/// # Examples
+///
+/// ```
+/// use serde_json::json;
+/// use json_schema_generator::{csv_records, CsvOptions};
+///
+/// let csv = "id,name,active\n\"007\",Bond,true\n8,,false\n";
+/// let rows: Vec<_> = csv_records(csv.as_bytes(), &CsvOptions::default())
+///     .collect::<Result<_, _>>()
+///     .unwrap();
+/// assert_eq!(rows, [
+///     json!({"id": "007", "name": "Bond", "active": true}),
+///     json!({"id": 8, "name": null, "active": false}),
+/// ]);
+/// ```
+pub fn csv_records<R: BufRead>(reader: R, options: &CsvOptions) -> CsvRecords<R> {
+    CsvRecords {
+        reader,
+        options: options.clone(),
+        header: None,
+        line: 0,
+        buffer: String::new(),
+    }
+}
+
+/// Generates the schema of the rows of the CSV document `reader`, read as
+/// [`csv_records`] reads them: an array whose `items` is the schema a
+/// [`SchemaGenerator`] observing each row gives, so that a column with blank cells is
+/// nullable or optional as [`CsvOptions::empty_cells`] says. The rows are read one at a
+/// time.
+///
+/// # Examples
+///
+/// ```
+/// use serde_json::json;
+/// use json_schema_generator::{generate_schema_from_csv, CsvOptions, EmptyCells, SchemaOptions};
+///
+/// let csv = "id;note\n1;\n2;late\n";
+/// let options = CsvOptions { delimiter: ';', empty_cells: EmptyCells::Omit, ..Default::default() };
+/// let schema = generate_schema_from_csv(csv.as_bytes(), &options, &SchemaOptions::default()).unwrap();
+/// assert_eq!(schema["type"], "array");
+/// assert_eq!(schema["items"]["required"], json!(["id"]));
+/// ```
+pub fn generate_schema_from_csv<R: BufRead>(
+    reader: R,
+    csv: &CsvOptions,
+    options: &SchemaOptions,
+) -> Result<Value, GeneratorError> {
+    let mut generator = SchemaGenerator::new(options.clone());
+    for record in csv_records(reader, csv) {
+        generator.observe(&record?)?;
+    }
+    let mut items = generator.finish()?;
+    // What describes the document rather than a row stays at the root.
+    let mut root = Map::new();
+    if let Some(obj) = items.as_object_mut() {
+        for keyword in ["$schema", "$id", "$defs", "definitions", "$comment"] {
+            if let Some(value) = obj.remove(keyword) {
+                root.insert(keyword.to_string(), value);
+            }
+        }
+        if let Some(value) = obj.remove(crate::provenance::EXTENSION) {
+            root.insert(crate::provenance::EXTENSION.to_string(), value);
+        }
+    }
+    root.insert("type".to_string(), Value::from("array"));
+    root.insert("items".to_string(), items);
+    Ok(Value::Object(root))
+}
+
+/// A cell of a CSV row.
+#[derive(Default)]
+struct Cell {
+    text: String,
+    quoted: bool,
+}
+
+impl<R: BufRead> CsvRecords<R> {
+    /// Reads the cells of the next row, which may span several lines, or `None` at the
+    /// end of the document.
+    fn read_row(&mut self) -> Result<Option<Vec<Cell>>, GeneratorError> {
+        let start = self.line + 1;
+        let mut cells = Vec::new();
+        let mut cell = Cell::default();
+        let mut in_quotes = false;
+        loop {
+            self.buffer.clear();
+            let read = self
+                .reader
+                .read_line(&mut self.buffer)
+                .map_err(|e| invalid(start, e))?;
+            if read == 0 {
+                if in_quotes {
+                    return Err(invalid(start, "unterminated quoted cell"));
+                }
+                if cells.is_empty() && !cell.quoted && cell.text.is_empty() {
+                    return Ok(None);
+                }
+                break;
+            }
+            // Excel starts its UTF-8 exports with a byte order mark, not part of a cell.
+            let text = match self.line {
+                0 => self.buffer.strip_prefix('\u{feff}').unwrap_or(&self.buffer),
+                _ => &self.buffer,
+            };
+            self.line += 1;
+            let mut chars = text.chars().peekable();
+            while let Some(c) = chars.next() {
+                if in_quotes {
+                    match c {
+                        '"' if chars.peek() == Some(&'"') => {
+                            chars.next();
+                            cell.text.push('"');
+                        }
+                        '"' => in_quotes = false,
+                        c => cell.text.push(c),
+                    }
+                } else if c == '"' && !cell.quoted && cell.text.is_empty() {
+                    in_quotes = true;
+                    cell.quoted = true;
+                } else if c == self.options.delimiter {
+                    cells.push(std::mem::take(&mut cell));
+                } else if c == '\n' || (c == '\r' && matches!(chars.peek(), Some('\n') | None)) {
+                    // The end of the row.
+                } else {
+                    cell.text.push(c);
+                }
+            }
+            if !in_quotes {
+                break;
+            }
+        }
+        cells.push(cell);
+        Ok(Some(cells))
+    }
+}
+
+impl<R: BufRead> Iterator for CsvRecords<R> {
+    type Item = Result<Value, GeneratorError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let cells = match self.read_row() {
+                Ok(Some(cells)) => cells,
+                Ok(None) => return None,
+                Err(e) => return Some(Err(e)),
+            };
+            // Blank lines hold no row.
+            if let [cell] = cells.as_slice() {
+                if !cell.quoted && cell.text.is_empty() {
+                    continue;
+                }
+            }
+            if self.options.header && self.header.is_none() {
+                self.header = Some(cells.into_iter().map(|cell| cell.text).collect());
+                continue;
+            }
+            return Some(self.record(cells));
+        }
+    }
+}
+
+impl<R> CsvRecords<R> {
+    /// The object of the row `cells`, which ended on the last line read.
+    fn record(&self, cells: Vec<Cell>) -> Result<Value, GeneratorError> {
+        let mut record = Map::new();
+        let columns = self.header.as_ref().map(Vec::len);
+        if let Some(columns) = columns.filter(|&columns| cells.len() > columns) {
+            return Err(invalid(
+                self.line,
+                format!("{} cells, but the header names {}", cells.len(), columns),
+            ));
+        }
+        for (index, cell) in cells.into_iter().enumerate() {
+            let name = match &self.header {
+                Some(header) => header[index].clone(),
+                None => format!("column{}", index + 1),
+            };
+            if let Some(value) = self.value(cell) {
+                record.insert(name, value);
+            }
+        }
+        Ok(Value::Object(record))
+    }
+
+    /// The value of `cell`, or `None` for an empty cell that is left out.
+    fn value(&self, cell: Cell) -> Option<Value> {
+        if cell.quoted {
+            return Some(Value::String(cell.text));
+        }
+        match cell.text.as_str() {
+            "" => match self.options.empty_cells {
+                EmptyCells::Null => Some(Value::Null),
+                EmptyCells::Omit => None,
+                EmptyCells::EmptyString => Some(Value::String(String::new())),
+            },
+            "true" => Some(Value::Bool(true)),
+            "false" => Some(Value::Bool(false)),
+            text => match number(text) {
+                Some(n) => Some(Value::Number(n)),
+                None => Some(Value::String(cell.text)),
+            },
+        }
+    }
+}
+
+/// Parses `text` if it is a JSON number, which has no leading zeros, as `007` has.
+fn number(text: &str) -> Option<Number> {
+    let starts_number = text.starts_with(|c: char| c == '-' || c.is_ascii_digit());
+    if !starts_number || !text.ends_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    serde_json::from_str(text).ok()
+}
+
+fn invalid(line: usize, error: impl Display) -> GeneratorError {
+    GeneratorError::InvalidInput(format!("line {}: {}", line, error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn rows(csv: &str, options: &CsvOptions) -> Vec<Value> {
+        csv_records(csv.as_bytes(), options)
+            .collect::<Result<_, _>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_sniffing() {
+        let csv = "id,code,count,ratio,big,flag,note\n\
+                   \"007\",007,12,-0.5,1e3,true,+1\n\
+                   8,0,-3,2.25,12345678901234567890,false,TRUE\n";
+        assert_eq!(
+            rows(csv, &CsvOptions::default()),
+            [
+                json!({"id": "007", "code": "007", "count": 12, "ratio": -0.5, "big": 1000.0,
+                       "flag": true, "note": "+1"}),
+                json!({"id": 8, "code": 0, "count": -3, "ratio": 2.25,
+                       "big": 12345678901234567890u64, "flag": false, "note": "TRUE"}),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_byte_order_mark() {
+        let csv = "\u{feff}id,name\r\n1,\u{feff}x\r\n";
+        assert_eq!(
+            rows(csv, &CsvOptions::default()),
+            [json!({"id": 1, "name": "\u{feff}x"})]
+        );
+        let options = CsvOptions {
+            header: false,
+            ..Default::default()
+        };
+        assert_eq!(
+            rows("\u{feff}\"a\",1\n", &options),
+            [json!({"column1": "a", "column2": 1})]
+        );
+    }
+
+    #[test]
+    fn test_quoting() {
+        let csv = "name,quote\r\n\"Smith, J\",\"said \"\"hi\"\"\nand left\"\r\n\r\n\"\",x\r\n";
+        assert_eq!(
+            rows(csv, &CsvOptions::default()),
+            [
+                json!({"name": "Smith, J", "quote": "said \"hi\"\nand left"}),
+                json!({"name": "", "quote": "x"}),
+            ]
+        );
+        let error = csv_records("a\n\"open\n".as_bytes(), &CsvOptions::default())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap_err();
+        assert_eq!(
+            error,
+            GeneratorError::InvalidInput("line 2: unterminated quoted cell".to_string())
+        );
+        let error = csv_records("a,b\n1,2\n1,2,3\n".as_bytes(), &CsvOptions::default())
+            .nth(1)
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(
+            error,
+            GeneratorError::InvalidInput("line 3: 3 cells, but the header names 2".to_string())
+        );
+    }
+
+    #[test]
+    fn test_options() {
+        let options = CsvOptions {
+            delimiter: '\t',
+            header: false,
+            empty_cells: EmptyCells::EmptyString,
+        };
+        assert_eq!(
+            rows("1\t\tx\n2\n", &options),
+            [
+                json!({"column1": 1, "column2": "", "column3": "x"}),
+                json!({"column1": 2}),
+            ]
+        );
+        assert!(rows("", &CsvOptions::default()).is_empty());
+        assert!(rows("a,b\n", &CsvOptions::default()).is_empty());
+    }
+
+    #[test]
+    fn test_blank_cells() {
+        let csv = "id,email,age\n1,a@example.com,30\n2,,\n3,c@example.com,41\n";
+        let schema = |empty_cells| {
+            let csv_options = CsvOptions {
+                empty_cells,
+                ..Default::default()
+            };
+            generate_schema_from_csv(csv.as_bytes(), &csv_options, &SchemaOptions::default())
+                .unwrap()
+        };
+        let nullable = schema(EmptyCells::Null);
+        assert_eq!(nullable["type"], "array");
+        assert_eq!(
+            nullable["$schema"],
+            "http://json-schema.org/draft-07/schema#"
+        );
+        assert_eq!(nullable["items"]["required"], json!(["age", "email", "id"]));
+        assert_eq!(
+            nullable["items"]["properties"]["age"],
+            json!({"type": ["integer", "null"]})
+        );
+        let optional = schema(EmptyCells::Omit);
+        assert_eq!(optional["items"]["required"], json!(["id"]));
+        assert_eq!(
+            optional["items"]["properties"]["email"],
+            json!({"type": "string"})
+        );
+
+        // As the schema of the rows as a JSON array.
+        let records = json!(rows(csv, &CsvOptions::default()));
+        let array = crate::try_generate_json_schema(&records, &SchemaOptions::default()).unwrap();
+        assert_eq!(nullable["items"], array["items"]);
+    }
+}
@@ -0,0 +1,322 @@
+//! Keywords added to every generated schema of a type, such as `"minLength": 1` on
+//! strings, unless a sample contradicts them.
+
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+/// Keywords added to the schemas of each type, see
+/// [`SchemaOptions::type_defaults`](crate::SchemaOptions::type_defaults).
+///
+/// A keyword never replaces one the schema already has, and survives merging as long as
+/// both merged schemas carry it with the same value.
+///
+/// # Examples
+///
+/// ```
+/// use serde_json::json;
+/// use json_schema_generator::{try_generate_json_schema, SchemaOptions, TypeDefaults};
+///
+/// let options = SchemaOptions {
+///     type_defaults: TypeDefaults::default()
+///         .with("string", json!({"minLength": 1}))
+///         .with("integer", json!({"x-unit": "cents"})),
+///     ..Default::default()
+/// };
+/// let schema = try_generate_json_schema(&json!({"name": "a", "price": 5}), &options).unwrap();
+/// assert_eq!(schema["properties"]["name"], json!({"type": "string", "minLength": 1}));
+/// assert_eq!(schema["properties"]["price"], json!({"type": "integer", "x-unit": "cents"}));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TypeDefaults {
+    /// The keywords for the schemas of each type, by the name of the type: `object`,
+    /// `array`, `string`, `integer`, `number`, `boolean` or `null`.
+    pub keywords: BTreeMap<String, Map<String, Value>>,
+    /// What happens to a keyword a sample contradicts.
+    pub on_conflict: DefaultConflict,
+}
+
+/// What happens to a [`TypeDefaults`] keyword a sample contradicts, such as
+/// `"minLength": 1` where an empty string was seen.
+///
+/// Only `minLength`, `maxLength`, `minimum`, `maximum`, `exclusiveMinimum`,
+/// `exclusiveMaximum`, `multipleOf`, `minItems`, `maxItems`, `minProperties` and
+/// `maxProperties` are checked against the samples.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DefaultConflict {
+    /// Leave the keyword out of the schema of the sample, so that it is also left out of
+    /// every schema the sample is merged into.
+    #[default]
+    Veto,
+    /// Add the keyword anyway, so that the schema rejects the sample.
+    Keep,
+}
+
+impl TypeDefaults {
+    /// Adds the keywords of the object `keywords` to those of the schemas of type `kind`.
+    /// Anything but an object adds nothing.
+    pub fn with(mut self, kind: &str, keywords: Value) -> Self {
+        if let Value::Object(keywords) = keywords {
+            self.keywords
+                .entry(kind.to_string())
+                .or_default()
+                .extend(keywords);
+        }
+        self
+    }
+
+    /// Adds the keywords for the type of the schema `schema`, generated from the value
+    /// `observed`.
+    pub(crate) fn apply(&self, schema: &mut Value, observed: Observed) {
+        let Some(keywords) = schema
+            .get("type")
+            .and_then(Value::as_str)
+            .and_then(|kind| self.keywords.get(kind))
+        else {
+            return;
+        };
+        let Some(obj) = schema.as_object_mut() else {
+            return;
+        };
+        for (keyword, value) in keywords {
+            let vetoed =
+                self.on_conflict == DefaultConflict::Veto && contradicts(keyword, value, observed);
+            if !vetoed && !obj.contains_key(keyword) {
+                obj.insert(keyword.clone(), value.clone());
+            }
+        }
+    }
+
+    /// Returns the keywords two merged schemas share with the same value, which the merge
+    /// keeps.
+    pub(crate) fn carried(&self, target: &Value, schema: &Value) -> Vec<(String, Value)> {
+        if self.keywords.is_empty() {
+            return Vec::new();
+        }
+        let (Some(target), Some(schema)) = (target.as_object(), schema.as_object()) else {
+            return Vec::new();
+        };
+        self.keywords
+            .values()
+            .flat_map(Map::keys)
+            .filter_map(|keyword| match (target.get(keyword), schema.get(keyword)) {
+                (Some(a), Some(b)) if a == b => Some((keyword.clone(), a.clone())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Adds the keywords `carried` returned to the merged schema `schema`, if they are
+    /// among those of its type.
+    pub(crate) fn restore(&self, schema: &mut Value, carried: Vec<(String, Value)>) {
+        let Some(keywords) = schema
+            .get("type")
+            .and_then(Value::as_str)
+            .and_then(|kind| self.keywords.get(kind))
+        else {
+            return;
+        };
+        let carried: Vec<_> = carried
+            .into_iter()
+            .filter(|(keyword, _)| keywords.contains_key(keyword))
+            .collect();
+        if let Some(obj) = schema.as_object_mut() {
+            for (keyword, value) in carried {
+                obj.entry(keyword).or_insert(value);
+            }
+        }
+    }
+}
+
+/// What a schema was generated from, as far as [`DefaultConflict::Veto`] checks it.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Observed<'v> {
+    /// An object with this many properties.
+    Object(usize),
+    /// An array with this many elements.
+    Array(usize),
+    String(&'v str),
+    Number(f64),
+    Other,
+}
+
+/// Returns whether the keyword `keyword` with the value `value` rejects `observed`.
+fn contradicts(keyword: &str, value: &Value, observed: Observed) -> bool {
+    let Some(limit) = value.as_f64() else {
+        return false;
+    };
+    let count = match observed {
+        Observed::Object(count) | Observed::Array(count) => count as f64,
+        Observed::String(s) => s.chars().count() as f64,
+        Observed::Number(n) => n,
+        Observed::Other => return false,
+    };
+    match (keyword, observed) {
+        ("minProperties", Observed::Object(_))
+        | ("minItems", Observed::Array(_))
+        | ("minLength", Observed::String(_))
+        | ("minimum", Observed::Number(_)) => count < limit,
+        ("maxProperties", Observed::Object(_))
+        | ("maxItems", Observed::Array(_))
+        | ("maxLength", Observed::String(_))
+        | ("maximum", Observed::Number(_)) => count > limit,
+        ("exclusiveMinimum", Observed::Number(n)) => n <= limit,
+        ("exclusiveMaximum", Observed::Number(n)) => n >= limit,
+        ("multipleOf", Observed::Number(n)) => limit > 0.0 && (n / limit).fract() != 0.0,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{generate_from_raw, try_generate_json_schema, SchemaGenerator, SchemaOptions};
+    use serde_json::json;
+
+    fn house_rules() -> TypeDefaults {
+        TypeDefaults::default()
+            .with("object", json!({"additionalProperties": false}))
+            .with("array", json!({"minItems": 1}))
+            .with("string", json!({"minLength": 1}))
+            .with("integer", json!({"x-house": "int"}))
+            .with("number", json!({"x-house": "num", "minimum": 0}))
+            .with("boolean", json!({"x-house": "bool"}))
+    }
+
+    fn generate(records: &[Value], type_defaults: TypeDefaults) -> Value {
+        let mut generator = SchemaGenerator::new(SchemaOptions {
+            type_defaults,
+            ..Default::default()
+        });
+        for record in records {
+            generator.observe(record).unwrap();
+        }
+        generator.schema()
+    }
+
+    #[test]
+    fn test_each_kind() {
+        let instance = json!({"s": "a", "i": 1, "n": 1.5, "b": true, "a": [1], "o": {"x": null}});
+        let options = SchemaOptions {
+            type_defaults: house_rules(),
+            ..Default::default()
+        };
+        let schema = try_generate_json_schema(&instance, &options).unwrap();
+        assert_eq!(schema["additionalProperties"], false);
+        let properties = &schema["properties"];
+        assert_eq!(properties["s"], json!({"type": "string", "minLength": 1}));
+        assert_eq!(
+            properties["i"],
+            json!({"type": "integer", "x-house": "int"})
+        );
+        assert_eq!(
+            properties["n"],
+            json!({"type": "number", "x-house": "num", "minimum": 0})
+        );
+        assert_eq!(
+            properties["b"],
+            json!({"type": "boolean", "x-house": "bool"})
+        );
+        assert_eq!(properties["a"]["minItems"], 1);
+        assert_eq!(properties["a"]["items"]["x-house"], "int");
+        assert_eq!(properties["o"]["additionalProperties"], false);
+        assert_eq!(properties["o"]["properties"]["x"], json!({"type": "null"}));
+
+        let raw = serde_json::value::to_raw_value(&instance).unwrap();
+        assert_eq!(generate_from_raw(&raw, &options).unwrap(), schema);
+    }
+
+    #[test]
+    fn test_survives_merging() {
+        let schema = generate(
+            &[
+                json!({"s": "a", "o": {"x": 1}}),
+                json!({"s": "b", "o": {"y": 2}}),
+            ],
+            house_rules(),
+        );
+        assert_eq!(schema["properties"]["s"]["minLength"], 1);
+        assert_eq!(schema["properties"]["o"]["additionalProperties"], false);
+        assert_eq!(
+            schema["properties"]["o"]["properties"]["y"]["x-house"],
+            "int"
+        );
+
+        // An integer and a number merge into a number, keeping what both carry.
+        let defaults = TypeDefaults::default()
+            .with("integer", json!({"x-house": "same", "x-int": true}))
+            .with("number", json!({"x-house": "same"}));
+        let schema = generate(&[json!(1), json!(1.5)], defaults);
+        assert_eq!(schema, json!({"type": "number", "x-house": "same"}));
+    }
+
+    #[test]
+    fn test_veto() {
+        let schema = generate(
+            &[json!({"s": "a", "n": 2.5}), json!({"s": "", "n": -1.5})],
+            house_rules(),
+        );
+        let properties = &schema["properties"];
+        assert_eq!(properties["s"], json!({"type": "string"}));
+        // Only the contradicted keyword is vetoed.
+        assert_eq!(properties["n"], json!({"type": "number", "x-house": "num"}));
+
+        let schema = generate(&[json!({"a": [], "o": {}})], house_rules());
+        assert!(schema["properties"]["a"].get("minItems").is_none());
+
+        let keep = TypeDefaults {
+            on_conflict: DefaultConflict::Keep,
+            ..house_rules()
+        };
+        let schema = generate(&[json!(""), json!("a")], keep);
+        assert_eq!(schema, json!({"type": "string", "minLength": 1}));
+    }
+
+    #[test]
+    fn test_generated_keywords_win() {
+        let defaults = TypeDefaults::default().with("array", json!({"items": false}));
+        let schema = generate(&[json!([1])], defaults);
+        assert_eq!(schema["items"], json!({"type": "integer"}));
+    }
+
+    #[test]
+    fn test_contradicts() {
+        let n = |n| Observed::Number(n);
+        assert!(contradicts("minimum", &json!(0), n(-1.0)));
+        assert!(!contradicts("minimum", &json!(0), n(0.0)));
+        assert!(contradicts("exclusiveMinimum", &json!(0), n(0.0)));
+        assert!(contradicts("exclusiveMaximum", &json!(10), n(10.0)));
+        assert!(contradicts("maximum", &json!(10), n(10.5)));
+        assert!(contradicts("multipleOf", &json!(0.5), n(0.75)));
+        assert!(!contradicts("multipleOf", &json!(0.5), n(1.5)));
+        assert!(contradicts("maxLength", &json!(2), Observed::String("äöü")));
+        assert!(!contradicts(
+            "maxLength",
+            &json!(3),
+            Observed::String("äöü")
+        ));
+        assert!(contradicts("maxProperties", &json!(1), Observed::Object(2)));
+        assert!(contradicts("minItems", &json!(1), Observed::Array(0)));
+        // Keywords of other types, and unchecked ones, never contradict.
+        assert!(!contradicts("minLength", &json!(5), Observed::Array(0)));
+        assert!(!contradicts(
+            "pattern",
+            &json!("^a$"),
+            Observed::String("b")
+        ));
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_samples_validate() {
+        let records = [
+            json!({"s": "abc", "n": 3.5, "tags": ["x"], "o": {"b": false}}),
+            json!({"s": "", "n": -2, "tags": [], "o": {"b": true}}),
+        ];
+        let schema = generate(&records, house_rules());
+        for record in &records {
+            assert!(crate::validation::validate_instance(&schema, record).is_ok());
+        }
+        let closed = json!({"s": "abc", "n": 1, "tags": [], "o": {"b": true, "c": 1}});
+        assert!(crate::validation::validate_instance(&schema, &closed).is_err());
+    }
+}
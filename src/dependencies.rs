@@ -0,0 +1,226 @@
+//! Inference of `dependentRequired` from optional properties that always appear
+//! together, such as a card number and its expiry date.
+
+use crate::Draft;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+/// Settings for inferring which optional properties imply others, see
+/// [`SchemaOptions::dependent_required`](crate::SchemaOptions::dependent_required).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DependentRequired {
+    /// How many of the observed objects must have a property before the properties it
+    /// implies are inferred.
+    pub min_occurrences: usize,
+    /// The objects at a location are not analysed once more distinct properties than
+    /// this are seen there, as every pair of properties is counted.
+    pub max_properties: usize,
+    /// The draft to write the keyword for: `dependencies` up to draft-07 and
+    /// `dependentRequired` from 2019-09 on.
+    pub draft: Draft,
+}
+
+impl Default for DependentRequired {
+    fn default() -> Self {
+        DependentRequired {
+            min_occurrences: 5,
+            max_properties: 32,
+            draft: Draft::Draft7,
+        }
+    }
+}
+
+impl DependentRequired {
+    /// Returns the keyword for `draft`, or for [`DependentRequired::draft`] if unset.
+    pub(crate) fn keyword(&self, draft: Option<Draft>) -> &'static str {
+        match draft.unwrap_or(self.draft) {
+            Draft::Draft4 | Draft::Draft6 | Draft::Draft7 => "dependencies",
+            Draft::Draft201909 | Draft::Draft202012 => "dependentRequired",
+        }
+    }
+}
+
+/// How often the properties of the objects at one location appear, alone and in pairs.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Presence {
+    samples: usize,
+    names: Vec<String>,
+    indices: HashMap<String, usize>,
+    counts: Vec<usize>,
+    /// How often two properties appear together, keyed by their indices, lower first.
+    pairs: HashMap<(usize, usize), usize>,
+    /// Set once more than [`DependentRequired::max_properties`] properties were seen,
+    /// after which nothing is tracked.
+    overflowed: bool,
+}
+
+impl Presence {
+    /// Counts an object with the properties `properties`.
+    pub(crate) fn record(&mut self, properties: &Map<String, Value>, max_properties: usize) {
+        if self.overflowed {
+            return;
+        }
+        let mut present = Vec::with_capacity(properties.len());
+        for name in properties.keys() {
+            let index = match self.indices.get(name) {
+                Some(&index) => index,
+                None => {
+                    if self.names.len() == max_properties {
+                        *self = Presence {
+                            overflowed: true,
+                            ..Default::default()
+                        };
+                        return;
+                    }
+                    self.names.push(name.clone());
+                    self.counts.push(0);
+                    self.indices.insert(name.clone(), self.names.len() - 1);
+                    self.names.len() - 1
+                }
+            };
+            present.push(index);
+        }
+
+        self.samples += 1;
+        present.sort_unstable();
+        for (n, &a) in present.iter().enumerate() {
+            self.counts[a] += 1;
+            for &b in &present[n + 1..] {
+                *self.pairs.entry((a, b)).or_default() += 1;
+            }
+        }
+    }
+
+    /// Returns the optional properties that imply others, with the optional properties
+    /// present in every object that has them, both sorted.
+    pub(crate) fn dependencies(&self, min_occurrences: usize) -> Map<String, Value> {
+        let mut dependencies = Map::new();
+        let optional = |index: usize| self.counts[index] < self.samples;
+        for a in (0..self.names.len()).filter(|&a| optional(a)) {
+            if self.counts[a] < min_occurrences.max(1) {
+                continue;
+            }
+            let mut implied: Vec<&String> = (0..self.names.len())
+                .filter(|&b| b != a && optional(b))
+                .filter(|&b| self.pairs.get(&(a.min(b), a.max(b))) == Some(&self.counts[a]))
+                .map(|b| &self.names[b])
+                .collect();
+            if implied.is_empty() {
+                continue;
+            }
+            implied.sort();
+            let implied = implied.into_iter().cloned().map(Value::String).collect();
+            dependencies.insert(self.names[a].clone(), Value::Array(implied));
+        }
+        dependencies
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SchemaGenerator, SchemaOptions};
+    use serde_json::json;
+
+    fn generate(records: &[Value], settings: DependentRequired) -> Value {
+        let mut generator = SchemaGenerator::new(SchemaOptions {
+            dependent_required: Some(settings),
+            ..Default::default()
+        });
+        for record in records {
+            generator.observe(record).unwrap();
+        }
+        generator.schema()
+    }
+
+    /// Payments, half of them by card.
+    fn payments(count: usize) -> Vec<Value> {
+        (0..count)
+            .map(|i| match i % 2 {
+                0 => json!({"id": i, "card_number": "4111", "card_expiry": "01/30"}),
+                _ => json!({"id": i, "iban": "DE00"}),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_pair_always_together() {
+        let schema = generate(&payments(20), DependentRequired::default());
+        assert_eq!(
+            schema["dependencies"],
+            json!({
+                "card_expiry": ["card_number"],
+                "card_number": ["card_expiry"]
+            })
+        );
+        assert!(schema.get("dependentRequired").is_none());
+    }
+
+    #[test]
+    fn test_pair_mostly_together() {
+        // The expiry is missing from one of 20 card payments.
+        let mut records = payments(40);
+        records[0].as_object_mut().unwrap().remove("card_expiry");
+        let schema = generate(&records, DependentRequired::default());
+        assert_eq!(
+            schema["dependencies"],
+            json!({"card_expiry": ["card_number"]})
+        );
+    }
+
+    #[test]
+    fn test_later_drafts() {
+        let settings = DependentRequired {
+            draft: Draft::Draft202012,
+            ..Default::default()
+        };
+        let schema = generate(&payments(20), settings);
+        assert_eq!(
+            schema["dependentRequired"]["card_number"],
+            json!(["card_expiry"])
+        );
+        assert!(schema.get("dependencies").is_none());
+    }
+
+    #[test]
+    fn test_nested_objects_and_array_items() {
+        let records: Vec<Value> = payments(20)
+            .into_iter()
+            .map(|payment| json!({"payments": [payment], "payer": payment}))
+            .collect();
+        let schema = generate(&records, DependentRequired::default());
+        let expected = json!({"card_expiry": ["card_number"], "card_number": ["card_expiry"]});
+        assert_eq!(
+            schema["properties"]["payments"]["items"]["dependencies"],
+            expected
+        );
+        assert_eq!(schema["properties"]["payer"]["dependencies"], expected);
+        assert!(schema.get("dependencies").is_none());
+    }
+
+    #[test]
+    fn test_thresholds() {
+        let rare = DependentRequired {
+            min_occurrences: 11,
+            ..Default::default()
+        };
+        assert!(generate(&payments(20), rare).get("dependencies").is_none());
+
+        let narrow = DependentRequired {
+            max_properties: 3,
+            ..Default::default()
+        };
+        assert!(generate(&payments(20), narrow)
+            .get("dependencies")
+            .is_none());
+    }
+
+    #[test]
+    fn test_off_by_default() {
+        let mut generator = SchemaGenerator::new(SchemaOptions::default());
+        for record in payments(20) {
+            generator.observe(&record).unwrap();
+        }
+        assert!(generator.schema().get("dependencies").is_none());
+    }
+}
@@ -0,0 +1,547 @@
+//! Inference of discriminated unions from objects whose shape depends on a string
+//! property, such as the `type` of the events in a log.
+
+use crate::anchors::definition_name;
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap};
+
+/// How a discriminated union is written, see [`Discriminators::style`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DiscriminatorStyle {
+    /// A `oneOf` of the variants, each with a `const` discriminator.
+    #[default]
+    OneOf,
+    /// A chain of `if`/`then`/`else` testing the discriminator against each value in
+    /// turn, rejecting objects with any other value.
+    IfThenElse,
+    /// An OpenAPI 3.1 union: a `oneOf` of references to the variants, which are hoisted
+    /// into the root definitions (`$defs` from 2019-09) under names derived from their
+    /// discriminator values, and a `discriminator` object mapping each value to its
+    /// variant.
+    OpenApi,
+}
+
+/// Settings for detecting objects told apart by a discriminator property, see
+/// [`SchemaOptions::discriminators`](crate::SchemaOptions::discriminators).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Discriminators {
+    /// The properties that may tell the variants apart, tried in order.
+    pub fields: Vec<String>,
+    /// How many objects each variant needs before a union is inferred.
+    pub min_samples: usize,
+    /// A property with more distinct values than this is not a discriminator.
+    pub max_variants: usize,
+    /// How the union is written.
+    pub style: DiscriminatorStyle,
+}
+
+impl Default for Discriminators {
+    fn default() -> Self {
+        Discriminators {
+            fields: vec!["type".to_string(), "kind".to_string()],
+            min_samples: 2,
+            max_variants: 16,
+            style: DiscriminatorStyle::default(),
+        }
+    }
+}
+
+/// The values one candidate discriminator took in the objects at one location, with
+/// the properties of the objects having each value.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Candidate {
+    variants: BTreeMap<String, Variant>,
+    /// Set once an object lacked the property or had a value other than a string, or
+    /// more than [`Discriminators::max_variants`] values were seen, after which nothing
+    /// is tracked.
+    broken: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Variant {
+    samples: usize,
+    /// How many of the objects had each property.
+    keys: BTreeMap<String, usize>,
+}
+
+impl Candidate {
+    /// Counts an object with the properties `properties`, whose discriminator is `value`.
+    pub(crate) fn record(
+        &mut self,
+        value: Option<&str>,
+        properties: &Map<String, Value>,
+        max_variants: usize,
+    ) {
+        if self.broken {
+            return;
+        }
+        let Some(value) = value else {
+            *self = Candidate {
+                broken: true,
+                ..Default::default()
+            };
+            return;
+        };
+        if !self.variants.contains_key(value) {
+            if self.variants.len() == max_variants {
+                *self = Candidate {
+                    broken: true,
+                    ..Default::default()
+                };
+                return;
+            }
+            self.variants.insert(value.to_string(), Variant::default());
+        }
+
+        let variant = self.variants.get_mut(value).unwrap();
+        variant.samples += 1;
+        for key in properties.keys() {
+            *variant.keys.entry(key.clone()).or_default() += 1;
+        }
+    }
+
+    /// Returns whether the variants partition the properties: every property `field`
+    /// aside is either present in every object, or only ever seen with one value.
+    fn partitions(&self, field: &str, min_samples: usize) -> bool {
+        if self.broken
+            || self.variants.len() < 2
+            || self
+                .variants
+                .values()
+                .any(|variant| variant.samples < min_samples.max(1))
+        {
+            return false;
+        }
+
+        let mut owners: HashMap<&str, usize> = HashMap::new();
+        for variant in self.variants.values() {
+            for key in variant.keys.keys().filter(|key| *key != field) {
+                *owners.entry(key).or_default() += 1;
+            }
+        }
+        let shared = |key: &str| {
+            self.variants
+                .values()
+                .all(|variant| variant.keys.get(key) == Some(&variant.samples))
+        };
+        let mut distinct = false;
+        for (key, owners) in owners {
+            if shared(key) {
+                continue;
+            }
+            if owners > 1 {
+                return false;
+            }
+            distinct = true;
+        }
+        // Variants differing in nothing but the discriminator are left merged.
+        distinct
+    }
+}
+
+/// Returns the discriminated union replacing the object schema `schema`, if one of the
+/// `candidates` tracked at its location cleanly partitions its properties.
+pub(crate) fn split(
+    schema: &Value,
+    candidates: &[Candidate],
+    settings: &Discriminators,
+) -> Option<(String, Vec<(String, Value)>)> {
+    let properties = schema.get("properties")?.as_object()?;
+    let (field, candidate) = settings
+        .fields
+        .iter()
+        .zip(candidates)
+        .find(|(field, candidate)| candidate.partitions(field, settings.min_samples))?;
+
+    let mut variants = Vec::with_capacity(candidate.variants.len());
+    for (value, variant) in &candidate.variants {
+        let mut variant_properties = Map::new();
+        for key in variant.keys.keys() {
+            let property = if key == field {
+                let mut property = Map::new();
+                property.insert("type".to_string(), Value::String("string".to_string()));
+                property.insert("const".to_string(), Value::String(value.clone()));
+                Value::Object(property)
+            } else {
+                // Left out of the merged schema by the property limit.
+                properties.get(key)?.clone()
+            };
+            variant_properties.insert(key.clone(), property);
+        }
+        let required = variant
+            .keys
+            .iter()
+            .filter(|(_, &count)| count == variant.samples)
+            .map(|(key, _)| Value::String(key.clone()))
+            .collect();
+
+        let mut variant_schema = Map::new();
+        variant_schema.insert("type".to_string(), Value::String("object".to_string()));
+        variant_schema.insert("properties".to_string(), Value::Object(variant_properties));
+        variant_schema.insert("required".to_string(), Value::Array(required));
+        variants.push((value.clone(), Value::Object(variant_schema)));
+    }
+    Some((field.clone(), variants))
+}
+
+/// Writes `variants`, told apart by `field`, as `style` asks, keeping the `$schema` of
+/// the object schema `schema` they replace. Hoisted variants are added to `definitions`.
+pub(crate) fn union(
+    schema: &Value,
+    field: String,
+    variants: Vec<(String, Value)>,
+    style: DiscriminatorStyle,
+    definitions: &mut Map<String, Value>,
+) -> Value {
+    let mut union = Map::new();
+    if let Some(dialect) = schema.get("$schema") {
+        union.insert("$schema".to_string(), dialect.clone());
+    }
+
+    match style {
+        DiscriminatorStyle::OneOf => {
+            let branches = variants.into_iter().map(|(_, variant)| variant).collect();
+            union.insert("oneOf".to_string(), Value::Array(branches));
+        }
+        DiscriminatorStyle::OpenApi => {
+            // The mapping is only sound if each variant names its value exactly.
+            let exact = variants
+                .iter()
+                .all(|(value, variant)| names_value(&variant["properties"][&field], value));
+            let mut branches = Vec::with_capacity(variants.len());
+            let mut mapping = Map::new();
+            for (value, variant) in variants {
+                let name = definition_name(&value, definitions);
+                definitions.insert(name.clone(), variant);
+                let reference = Value::String(format!("#/$defs/{}", name));
+                let mut branch = Map::new();
+                branch.insert("$ref".to_string(), reference.clone());
+                branches.push(Value::Object(branch));
+                mapping.insert(value, reference);
+            }
+            union.insert("oneOf".to_string(), Value::Array(branches));
+            if exact {
+                let mut discriminator = Map::new();
+                discriminator.insert("propertyName".to_string(), Value::String(field));
+                discriminator.insert("mapping".to_string(), Value::Object(mapping));
+                union.insert("discriminator".to_string(), Value::Object(discriminator));
+            }
+        }
+        DiscriminatorStyle::IfThenElse => {
+            let mut otherwise = Value::Bool(false);
+            for (value, variant) in variants.into_iter().rev() {
+                let mut discriminator = Map::new();
+                discriminator.insert("const".to_string(), Value::String(value));
+                let mut properties = Map::new();
+                properties.insert(field.clone(), Value::Object(discriminator));
+                let mut condition = Map::new();
+                condition.insert("properties".to_string(), Value::Object(properties));
+                condition.insert(
+                    "required".to_string(),
+                    Value::Array(vec![Value::String(field.clone())]),
+                );
+
+                let mut link = Map::new();
+                link.insert("if".to_string(), Value::Object(condition));
+                link.insert("then".to_string(), variant);
+                link.insert("else".to_string(), otherwise);
+                otherwise = Value::Object(link);
+            }
+            // The outermost link of the chain is the union itself.
+            union.insert("type".to_string(), Value::String("object".to_string()));
+            if let Value::Object(chain) = otherwise {
+                union.extend(chain);
+            }
+        }
+    }
+    Value::Object(union)
+}
+
+/// Returns whether the schema `property` only accepts the string `value`, with a `const`
+/// or an `enum` of one.
+fn names_value(property: &Value, value: &str) -> bool {
+    match (property.get("const"), property.get("enum")) {
+        (Some(constant), _) => constant == value,
+        (None, Some(Value::Array(values))) => values.len() == 1 && values[0] == value,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SchemaGenerator, SchemaOptions};
+    use serde_json::json;
+
+    fn generate(records: &[Value], settings: Discriminators) -> Value {
+        let mut generator = SchemaGenerator::new(SchemaOptions {
+            discriminators: Some(settings),
+            ..Default::default()
+        });
+        for record in records {
+            generator.observe(record).unwrap();
+        }
+        generator.schema()
+    }
+
+    /// Clicks and purchases, sharing an `id` and half of the clicks with a `target`.
+    fn events(count: usize) -> Vec<Value> {
+        (0..count)
+            .map(|i| match i % 4 {
+                0 => json!({"type": "click", "id": i, "x": 1, "y": 2, "target": "button"}),
+                1 | 2 => json!({"type": "click", "id": i, "x": 1, "y": 2}),
+                _ => json!({"type": "purchase", "id": i, "amount": 9.99}),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_two_clean_variants() {
+        let schema = generate(&events(8), Discriminators::default());
+        assert_eq!(
+            schema,
+            json!({
+                "$schema": "http://json-schema.org/draft-07/schema#",
+                "oneOf": [
+                    {
+                        "type": "object",
+                        "properties": {
+                            "id": {"type": "integer"},
+                            "target": {"type": "string"},
+                            "type": {"type": "string", "const": "click"},
+                            "x": {"type": "integer"},
+                            "y": {"type": "integer"}
+                        },
+                        "required": ["id", "type", "x", "y"]
+                    },
+                    {
+                        "type": "object",
+                        "properties": {
+                            "amount": {"type": "number"},
+                            "id": {"type": "integer"},
+                            "type": {"type": "string", "const": "purchase"}
+                        },
+                        "required": ["amount", "id", "type"]
+                    }
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn test_noisy_variant_falls_back_to_merging() {
+        let mut records = events(8);
+        records.push(json!({"type": "click", "id": 8, "x": 1, "y": 2, "amount": 1}));
+        let schema = generate(&records, Discriminators::default());
+
+        let mut generator = SchemaGenerator::new(SchemaOptions::default());
+        for record in &records {
+            generator.observe(record).unwrap();
+        }
+        assert_eq!(schema, generator.schema());
+        assert!(schema.get("oneOf").is_none());
+    }
+
+    #[test]
+    fn test_if_then_else() {
+        let settings = Discriminators {
+            style: DiscriminatorStyle::IfThenElse,
+            ..Default::default()
+        };
+        let schema = generate(&events(8), settings);
+        assert_eq!(schema["type"], "object");
+        assert_eq!(
+            schema["if"],
+            json!({"properties": {"type": {"const": "click"}}, "required": ["type"]})
+        );
+        assert_eq!(schema["then"]["required"], json!(["id", "type", "x", "y"]));
+        assert_eq!(
+            schema["else"]["if"]["properties"]["type"]["const"],
+            "purchase"
+        );
+        assert_eq!(schema["else"]["else"], false);
+    }
+
+    #[test]
+    fn test_openapi() {
+        let settings = Discriminators {
+            style: DiscriminatorStyle::OpenApi,
+            ..Default::default()
+        };
+        let schema = generate(&events(8), settings);
+        assert_eq!(schema["discriminator"]["propertyName"], "type");
+        let mapping = schema["discriminator"]["mapping"].as_object().unwrap();
+        assert_eq!(mapping.keys().collect::<Vec<_>>(), ["click", "purchase"]);
+        assert_eq!(
+            schema["oneOf"],
+            json!([{"$ref": "#/definitions/Click"}, {"$ref": "#/definitions/Purchase"}])
+        );
+        for (value, reference) in mapping {
+            let pointer = reference.as_str().unwrap().strip_prefix('#').unwrap();
+            let variant = schema.pointer(pointer).unwrap();
+            assert_eq!(variant["properties"]["type"]["const"], *value);
+        }
+    }
+
+    #[test]
+    fn test_openapi_names_are_unique() {
+        let settings = Discriminators {
+            style: DiscriminatorStyle::OpenApi,
+            ..Default::default()
+        };
+        let records: Vec<Value> = events(8)
+            .into_iter()
+            .map(|event| json!({"first": event, "last": event}))
+            .collect();
+        let schema = generate(&records, settings);
+        let definitions = schema["definitions"].as_object().unwrap();
+        assert_eq!(
+            definitions.keys().collect::<Vec<_>>(),
+            ["Click", "Click2", "Purchase", "Purchase2"]
+        );
+        for property in ["first", "last"] {
+            let union = &schema["properties"][property];
+            for reference in union["discriminator"]["mapping"]
+                .as_object()
+                .unwrap()
+                .values()
+            {
+                let pointer = reference.as_str().unwrap().strip_prefix('#').unwrap();
+                assert!(schema.pointer(pointer).is_some(), "{}", reference);
+            }
+        }
+    }
+
+    #[test]
+    fn test_names_value() {
+        assert!(names_value(&json!({"const": "a"}), "a"));
+        assert!(names_value(&json!({"enum": ["a"]}), "a"));
+        assert!(!names_value(&json!({"enum": ["a", "b"]}), "a"));
+        assert!(!names_value(&json!({"type": "string"}), "a"));
+    }
+
+    #[test]
+    fn test_thresholds() {
+        // The only purchase is too rare to make a variant.
+        let records = events(4);
+        assert!(generate(&records, Discriminators::default())
+            .get("oneOf")
+            .is_none());
+        let single = Discriminators {
+            min_samples: 1,
+            ..Default::default()
+        };
+        assert!(generate(&records, single).get("oneOf").is_some());
+
+        let few = Discriminators {
+            max_variants: 1,
+            ..Default::default()
+        };
+        assert!(generate(&events(8), few).get("oneOf").is_none());
+    }
+
+    #[test]
+    fn test_other_fields_and_nesting() {
+        let records: Vec<Value> = events(8)
+            .into_iter()
+            .map(|mut event| {
+                let kind = event.as_object_mut().unwrap().remove("type").unwrap();
+                event["kind"] = kind;
+                json!({"events": [event]})
+            })
+            .collect();
+        let schema = generate(&records, Discriminators::default());
+        let items = &schema["properties"]["events"]["items"];
+        assert_eq!(
+            items["oneOf"][1]["properties"]["kind"],
+            json!({"type": "string", "const": "purchase"})
+        );
+        assert!(items.get("$schema").is_none());
+        assert!(schema.get("oneOf").is_none());
+    }
+
+    #[test]
+    fn test_identical_shapes_stay_merged() {
+        let records = [
+            json!({"type": "a", "value": 1}),
+            json!({"type": "a", "value": 2}),
+            json!({"type": "b", "value": 3}),
+            json!({"type": "b", "value": 4}),
+        ];
+        assert!(generate(&records, Discriminators::default())
+            .get("oneOf")
+            .is_none());
+    }
+
+    #[test]
+    fn test_missing_discriminator() {
+        let mut records = events(8);
+        records.push(json!({"id": 8, "amount": 1.5}));
+        assert!(generate(&records, Discriminators::default())
+            .get("oneOf")
+            .is_none());
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_closed_unions() {
+        use crate::validation::compile_schema_with_draft;
+        use crate::{ClosedWith, Draft};
+
+        let extra = json!({"type": "purchase", "id": 1, "amount": 1.5, "x": 1});
+        for style in [
+            DiscriminatorStyle::OneOf,
+            DiscriminatorStyle::IfThenElse,
+            DiscriminatorStyle::OpenApi,
+        ] {
+            let mut generator = SchemaGenerator::new(SchemaOptions {
+                discriminators: Some(Discriminators {
+                    style,
+                    ..Default::default()
+                }),
+                closed_objects: Some(ClosedWith::UnevaluatedProperties),
+                ..Default::default()
+            });
+            for record in events(8) {
+                generator.observe(&record).unwrap();
+            }
+            let mut schema = generator.schema();
+            assert_eq!(schema["unevaluatedProperties"], false, "{:?}", style);
+
+            let compiled = compile_schema_with_draft(&schema, Draft::Draft202012).unwrap();
+            assert!(events(8).iter().all(|event| compiled.is_valid(event)));
+            assert!(!compiled.is_valid(&extra), "{:?}", style);
+
+            // additionalProperties in its place doesn't see the properties of the
+            // variants, rejecting every event.
+            let obj = schema.as_object_mut().unwrap();
+            obj.remove("unevaluatedProperties");
+            obj.insert("additionalProperties".to_string(), Value::Bool(false));
+            let compiled = compile_schema_with_draft(&schema, Draft::Draft202012).unwrap();
+            assert!(!compiled.is_valid(&events(1)[0]), "{:?}", style);
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_samples_validate() {
+        for style in [
+            DiscriminatorStyle::OneOf,
+            DiscriminatorStyle::IfThenElse,
+            DiscriminatorStyle::OpenApi,
+        ] {
+            let settings = Discriminators {
+                style,
+                ..Default::default()
+            };
+            let schema = generate(&events(8), settings);
+            for record in events(8) {
+                assert!(crate::validation::validate_instance(&schema, &record).is_ok());
+            }
+            let mixed = json!({"type": "purchase", "id": 1, "x": 1, "y": 2});
+            assert!(crate::validation::validate_instance(&schema, &mixed).is_err());
+            let unknown = json!({"type": "refund", "id": 1});
+            assert!(crate::validation::validate_instance(&schema, &unknown).is_err());
+        }
+    }
+}
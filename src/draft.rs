@@ -0,0 +1,245 @@
+//! The JSON Schema drafts this crate knows, identified by their `$schema` URIs.
+
+use serde_json::Value;
+use std::fmt;
+use std::str::FromStr;
+
+/// A JSON Schema draft.
+///
+/// With the `validation` feature this converts to and from `jsonschema::Draft`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Draft {
+    /// Draft-04.
+    Draft4,
+    /// Draft-06.
+    Draft6,
+    /// Draft-07, the dialect this crate generates.
+    #[default]
+    Draft7,
+    /// Draft 2019-09.
+    Draft201909,
+    /// Draft 2020-12.
+    Draft202012,
+}
+
+impl Draft {
+    /// Every draft, oldest first.
+    pub const ALL: [Draft; 5] = [
+        Draft::Draft4,
+        Draft::Draft6,
+        Draft::Draft7,
+        Draft::Draft201909,
+        Draft::Draft202012,
+    ];
+
+    /// Returns the canonical meta-schema URI of the draft, as written in `$schema`.
+    pub fn uri(self) -> &'static str {
+        match self {
+            Draft::Draft4 => "http://json-schema.org/draft-04/schema#",
+            Draft::Draft6 => "http://json-schema.org/draft-06/schema#",
+            Draft::Draft7 => "http://json-schema.org/draft-07/schema#",
+            Draft::Draft201909 => "https://json-schema.org/draft/2019-09/schema",
+            Draft::Draft202012 => "https://json-schema.org/draft/2020-12/schema",
+        }
+    }
+
+    /// Returns the keyword holding definitions in the draft: `definitions` up to draft-07,
+    /// and `$defs` from 2019-09.
+    pub fn definitions_keyword(self) -> &'static str {
+        match self {
+            Draft::Draft4 | Draft::Draft6 | Draft::Draft7 => "definitions",
+            Draft::Draft201909 | Draft::Draft202012 => "$defs",
+        }
+    }
+
+    /// Returns the draft whose meta-schema is `uri`.
+    ///
+    /// Both `http` and `https` are accepted, with or without an empty trailing fragment.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use json_schema_generator::Draft;
+    ///
+    /// assert_eq!(
+    ///     Draft::from_schema_uri("http://json-schema.org/draft-07/schema"),
+    ///     Some(Draft::Draft7)
+    /// );
+    /// assert_eq!(Draft::from_schema_uri("https://example.com/schema"), None);
+    /// ```
+    pub fn from_schema_uri(uri: &str) -> Option<Draft> {
+        let uri = uri.strip_suffix('#').unwrap_or(uri);
+        let path = uri
+            .strip_prefix("http://json-schema.org/")
+            .or_else(|| uri.strip_prefix("https://json-schema.org/"))?;
+        match path {
+            "draft-04/schema" => Some(Draft::Draft4),
+            "draft-06/schema" => Some(Draft::Draft6),
+            "draft-07/schema" => Some(Draft::Draft7),
+            "draft/2019-09/schema" => Some(Draft::Draft201909),
+            "draft/2020-12/schema" => Some(Draft::Draft202012),
+            _ => None,
+        }
+    }
+
+    /// Returns the draft named by the `$schema` keyword of `schema`, or `None` if it has
+    /// none or names an unknown meta-schema.
+    pub fn from_schema(schema: &Value) -> Option<Draft> {
+        schema
+            .get("$schema")
+            .and_then(Value::as_str)
+            .and_then(Draft::from_schema_uri)
+    }
+}
+
+/// The draft's short name, such as `draft-07` or `2020-12`.
+impl fmt::Display for Draft {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Draft::Draft4 => "draft-04",
+            Draft::Draft6 => "draft-06",
+            Draft::Draft7 => "draft-07",
+            Draft::Draft201909 => "2019-09",
+            Draft::Draft202012 => "2020-12",
+        })
+    }
+}
+
+/// Parses a short name as written by `Display`, a bare draft number such as `7`, or a
+/// meta-schema URI.
+impl FromStr for Draft {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "4" | "04" | "draft-04" => Ok(Draft::Draft4),
+            "6" | "06" | "draft-06" => Ok(Draft::Draft6),
+            "7" | "07" | "draft-07" => Ok(Draft::Draft7),
+            "2019-09" => Ok(Draft::Draft201909),
+            "2020-12" => Ok(Draft::Draft202012),
+            _ => Draft::from_schema_uri(s).ok_or_else(|| {
+                format!(
+                    "unknown draft '{}', expected one of draft-04, draft-06, draft-07, 2019-09 or 2020-12",
+                    s
+                )
+            }),
+        }
+    }
+}
+
+#[cfg(any(feature = "validation", all(test, not(target_arch = "wasm32"))))]
+impl From<Draft> for jsonschema::Draft {
+    fn from(draft: Draft) -> Self {
+        match draft {
+            Draft::Draft4 => jsonschema::Draft::Draft4,
+            Draft::Draft6 => jsonschema::Draft::Draft6,
+            Draft::Draft7 => jsonschema::Draft::Draft7,
+            Draft::Draft201909 => jsonschema::Draft::Draft201909,
+            Draft::Draft202012 => jsonschema::Draft::Draft202012,
+        }
+    }
+}
+
+/// Fails with the original value for drafts this crate doesn't know.
+#[cfg(any(feature = "validation", all(test, not(target_arch = "wasm32"))))]
+impl TryFrom<jsonschema::Draft> for Draft {
+    type Error = jsonschema::Draft;
+
+    fn try_from(draft: jsonschema::Draft) -> Result<Self, Self::Error> {
+        match draft {
+            jsonschema::Draft::Draft4 => Ok(Draft::Draft4),
+            jsonschema::Draft::Draft6 => Ok(Draft::Draft6),
+            jsonschema::Draft::Draft7 => Ok(Draft::Draft7),
+            jsonschema::Draft::Draft201909 => Ok(Draft::Draft201909),
+            jsonschema::Draft::Draft202012 => Ok(Draft::Draft202012),
+            other => Err(other),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_schema_uris() {
+        let uris = [
+            ("http://json-schema.org/draft-04/schema#", Draft::Draft4),
+            ("http://json-schema.org/draft-04/schema", Draft::Draft4),
+            ("https://json-schema.org/draft-04/schema#", Draft::Draft4),
+            ("http://json-schema.org/draft-06/schema#", Draft::Draft6),
+            ("http://json-schema.org/draft-06/schema", Draft::Draft6),
+            ("http://json-schema.org/draft-07/schema#", Draft::Draft7),
+            ("http://json-schema.org/draft-07/schema", Draft::Draft7),
+            ("https://json-schema.org/draft-07/schema", Draft::Draft7),
+            (
+                "https://json-schema.org/draft/2019-09/schema",
+                Draft::Draft201909,
+            ),
+            (
+                "https://json-schema.org/draft/2019-09/schema#",
+                Draft::Draft201909,
+            ),
+            (
+                "http://json-schema.org/draft/2019-09/schema",
+                Draft::Draft201909,
+            ),
+            (
+                "https://json-schema.org/draft/2020-12/schema",
+                Draft::Draft202012,
+            ),
+            (
+                "https://json-schema.org/draft/2020-12/schema#",
+                Draft::Draft202012,
+            ),
+        ];
+        for (uri, draft) in uris {
+            assert_eq!(Draft::from_schema_uri(uri), Some(draft), "uri: {}", uri);
+        }
+        for draft in Draft::ALL {
+            assert_eq!(Draft::from_schema_uri(draft.uri()), Some(draft));
+        }
+
+        for uri in [
+            "http://json-schema.org/draft-05/schema#",
+            "http://json-schema.org/schema#",
+            "http://json-schema.org/draft-07/schema##",
+            "http://example.com/draft-07/schema",
+        ] {
+            assert_eq!(Draft::from_schema_uri(uri), None, "uri: {}", uri);
+        }
+    }
+
+    #[test]
+    fn test_from_schema() {
+        let schema = json!({"$schema": "https://json-schema.org/draft/2020-12/schema"});
+        assert_eq!(Draft::from_schema(&schema), Some(Draft::Draft202012));
+        assert_eq!(Draft::from_schema(&json!({"type": "string"})), None);
+        assert_eq!(Draft::from_schema(&json!({"$schema": 7})), None);
+    }
+
+    #[test]
+    fn test_names_round_trip() {
+        for draft in Draft::ALL {
+            assert_eq!(draft.to_string().parse(), Ok(draft));
+        }
+        assert_eq!("7".parse(), Ok(Draft::Draft7));
+        assert_eq!("07".parse(), Ok(Draft::Draft7));
+        assert_eq!(Draft::Draft4.uri().parse(), Ok(Draft::Draft4));
+        assert!("draft-05".parse::<Draft>().is_err());
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_jsonschema_conversions() {
+        for draft in Draft::ALL {
+            let converted = jsonschema::Draft::from(draft);
+            assert_eq!(Draft::try_from(converted), Ok(draft));
+            assert_eq!(
+                jsonschema::Draft::default().detect(&json!({"$schema": draft.uri()})),
+                converted
+            );
+        }
+    }
+}
@@ -0,0 +1,178 @@
+//! The string-in, string-out generator shared by the WebAssembly and C bindings and the
+//! HTTP server.
+
+use crate::{GeneratorError, SchemaGenerator, SchemaOptions, Strictness, Warning};
+use serde::Deserialize;
+use serde_json::Value;
+use std::fmt;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+/// The options accepted by the bindings, given as a JSON object.
+#[derive(Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct EmbedOptions {
+    /// Fail if a warning with one of these codes is raised, like the CLI's `--deny-lints`.
+    pub(crate) deny_lints: Vec<String>,
+    /// Check that the input validates against the generated schema, like `--verify`.
+    #[cfg(feature = "validation")]
+    pub(crate) verify: bool,
+    /// Fail on merges that would widen the schema, like `--strict`.
+    pub(crate) strict: bool,
+}
+
+#[cfg(any(feature = "wasm", feature = "ffi"))]
+impl EmbedOptions {
+    /// Parses options given as a JSON object, or the defaults if `options_json` is empty.
+    pub(crate) fn parse(options_json: &str) -> Result<Self, EmbedError> {
+        if options_json.trim().is_empty() {
+            Ok(EmbedOptions::default())
+        } else {
+            serde_json::from_str(options_json).map_err(EmbedError::InvalidOptions)
+        }
+    }
+}
+
+/// Why generation failed.
+#[derive(Debug)]
+// The server parses its own input, so only the bindings construct the input errors.
+#[cfg_attr(not(any(feature = "wasm", feature = "ffi")), allow(dead_code))]
+pub(crate) enum EmbedError {
+    InvalidInput(serde_json::Error),
+    InvalidOptions(serde_json::Error),
+    Generator(GeneratorError),
+    DeniedWarning(Warning),
+}
+
+impl fmt::Display for EmbedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EmbedError::InvalidInput(e) => write!(f, "invalid input: {}", e),
+            EmbedError::InvalidOptions(e) => write!(f, "invalid options: {}", e),
+            EmbedError::Generator(e) => e.fmt(f),
+            EmbedError::DeniedWarning(warning) => write!(f, "denied warning: {}", warning),
+        }
+    }
+}
+
+/// Generates the schema of the JSON document `input_json`, returning it serialized.
+///
+/// `options_json` is a JSON object holding [`EmbedOptions`], or empty for the defaults.
+#[cfg(any(feature = "wasm", feature = "ffi"))]
+pub(crate) fn generate(input_json: &str, options_json: &str) -> Result<String, EmbedError> {
+    let instance: Value = serde_json::from_str(input_json).map_err(EmbedError::InvalidInput)?;
+    let options = EmbedOptions::parse(options_json)?;
+    let schema = generate_records(std::slice::from_ref(&instance), &options, None)?;
+    Ok(schema.to_string())
+}
+
+/// Generates one schema from `records`, stopping with [`GeneratorError::Cancelled`] once
+/// `cancel` is set.
+pub(crate) fn generate_records(
+    records: &[Value],
+    options: &EmbedOptions,
+    cancel: Option<Arc<AtomicBool>>,
+) -> Result<Value, EmbedError> {
+    let mut generator = SchemaGenerator::new(SchemaOptions {
+        cancel,
+        #[cfg(feature = "validation")]
+        verify: options.verify,
+        strictness: if options.strict {
+            Strictness::Strict
+        } else {
+            Strictness::Permissive
+        },
+        ..Default::default()
+    });
+    for record in records {
+        generator.observe(record).map_err(EmbedError::Generator)?;
+    }
+    if let Some(denied) = generator
+        .warnings()
+        .into_iter()
+        .find(|warning| options.deny_lints.contains(&warning.code))
+    {
+        return Err(EmbedError::DeniedWarning(denied));
+    }
+    generator.finish().map_err(EmbedError::Generator)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[cfg(any(feature = "wasm", feature = "ffi"))]
+    #[test]
+    fn test_generate_nested_object() {
+        let schema = generate(r#"{"user": {"name": "a", "tags": [1]}}"#, "").unwrap();
+        let schema: Value = serde_json::from_str(&schema).unwrap();
+        assert_eq!(
+            schema["properties"]["user"]["properties"]["tags"],
+            json!({"type": "array", "items": {"type": "integer"}})
+        );
+    }
+
+    #[cfg(any(feature = "wasm", feature = "ffi"))]
+    #[test]
+    fn test_options() {
+        let options: EmbedOptions =
+            serde_json::from_str(r#"{"deny_lints": ["always-null"]}"#).unwrap();
+        assert_eq!(options.deny_lints, ["always-null"]);
+
+        let input = r#"{"deleted_at": null}"#;
+        assert!(generate(input, "{}").is_ok());
+        let error = generate(input, r#"{"deny_lints": ["always-null"]}"#).unwrap_err();
+        assert!(matches!(error, EmbedError::DeniedWarning(_)));
+        assert!(error
+            .to_string()
+            .starts_with("denied warning: /properties/deleted_at [always-null]"));
+    }
+
+    #[cfg(any(feature = "wasm", feature = "ffi"))]
+    #[test]
+    fn test_errors() {
+        assert!(generate("{", "")
+            .unwrap_err()
+            .to_string()
+            .starts_with("invalid input"));
+        assert!(generate("1", r#"{"colour": 1}"#)
+            .unwrap_err()
+            .to_string()
+            .contains("unknown field `colour`"));
+    }
+
+    #[cfg(feature = "validation")]
+    #[test]
+    fn test_verify_option() {
+        let options: EmbedOptions = serde_json::from_str(r#"{"verify": true}"#).unwrap();
+        assert!(options.verify);
+        let records = [json!({"a": 1}), json!({"a": [true]})];
+        assert!(generate_records(&records, &options, None).is_ok());
+    }
+
+    #[test]
+    fn test_strict_option() {
+        let records = [json!({"a": 1}), json!({"a": "x"})];
+        assert!(generate_records(&records, &EmbedOptions::default(), None).is_ok());
+        let strict = EmbedOptions {
+            strict: true,
+            ..Default::default()
+        };
+        let error = generate_records(&records, &strict, None).unwrap_err();
+        assert!(matches!(
+            error,
+            EmbedError::Generator(GeneratorError::Conflict { ref path, .. }) if path == "/properties/a"
+        ));
+    }
+
+    #[test]
+    fn test_cancelled() {
+        let cancel = Arc::new(AtomicBool::new(true));
+        let error = generate_records(&[json!(1)], &EmbedOptions::default(), Some(cancel));
+        assert!(matches!(
+            error,
+            Err(EmbedError::Generator(GeneratorError::Cancelled))
+        ));
+    }
+}
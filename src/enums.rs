@@ -0,0 +1,302 @@
+//! Inference of the `enum` of strings that take only a few values, such as the status of
+//! an event.
+
+use serde_json::{json, Value};
+use std::collections::BTreeSet;
+
+/// Settings for inferring the `enum` of the strings at a location, see
+/// [`SchemaOptions::enums`](crate::SchemaOptions::enums).
+///
+/// # Examples
+///
+/// ```
+/// use serde_json::json;
+/// use json_schema_generator::{EnumInference, SchemaGenerator, SchemaOptions};
+///
+/// let mut generator = SchemaGenerator::new(SchemaOptions {
+///     enums: Some(EnumInference { max_values: 3, min_samples: 4, ..Default::default() }),
+///     ..Default::default()
+/// });
+/// for status in ["active", "pending", "closed", "active"] {
+///     generator.observe(&json!({"status": status})).unwrap();
+/// }
+/// assert_eq!(
+///     generator.schema()["properties"]["status"],
+///     json!({"type": "string", "enum": ["active", "closed", "pending"]})
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnumInference {
+    /// The most distinct values the strings at a location may take for their schema to
+    /// list them as an `enum`.
+    pub max_values: usize,
+    /// How many strings must be seen at a location before their values are listed.
+    pub min_samples: usize,
+    /// Write strings that all have the same value as `{"const": value}`, without their
+    /// `type`, rather than as an `enum` of one value.
+    pub consts: bool,
+}
+
+impl Default for EnumInference {
+    fn default() -> Self {
+        EnumInference {
+            max_values: 10,
+            min_samples: 10,
+            consts: true,
+        }
+    }
+}
+
+/// The distinct values of the strings seen at one location, until there are too many.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Values {
+    samples: usize,
+    values: BTreeSet<String>,
+    /// Set once more than [`EnumInference::max_values`] were seen, forgetting them.
+    overflowed: bool,
+}
+
+impl Values {
+    /// Counts the string `value`.
+    pub(crate) fn record(&mut self, value: &str, max_values: usize) {
+        self.samples += 1;
+        if self.overflowed || self.values.contains(value) {
+            return;
+        }
+        self.values.insert(value.to_string());
+        if self.values.len() > max_values {
+            self.overflowed = true;
+            self.values.clear();
+        }
+    }
+
+    /// Lists the values in the `enum` of the string schema `schema`, sorted, if enough
+    /// strings were seen, or writes the one value as its `const`. A schema of strings or
+    /// null also lists null.
+    pub(crate) fn apply(&self, schema: &mut Value, settings: &EnumInference) {
+        if self.overflowed || self.samples < settings.min_samples {
+            return;
+        }
+        let single = match (settings.consts, self.values.first()) {
+            (true, Some(value)) if self.values.len() == 1 => Some(value),
+            _ => None,
+        };
+        if let (Some(value), Some(obj)) = (single, schema.as_object_mut()) {
+            if obj.get("type") == Some(&json!("string")) {
+                // The value implies the type.
+                obj.remove("type");
+                obj.insert("const".to_string(), json!(value));
+                return;
+            }
+        }
+        let mut listed: Vec<Value> = self.values.iter().map(|value| json!(value)).collect();
+        match schema.get("type") {
+            Some(Value::String(kind)) if kind == "string" => {}
+            Some(Value::Array(types)) if types.contains(&json!("string")) => {
+                if types.contains(&json!("null")) {
+                    listed.push(Value::Null);
+                }
+            }
+            _ => return,
+        }
+        if let Some(obj) = schema.as_object_mut() {
+            obj.insert("enum".to_string(), Value::Array(listed));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SchemaGenerator, SchemaOptions};
+
+    fn generate(records: &[Value], max_values: usize, min_samples: usize) -> Value {
+        let mut generator = SchemaGenerator::new(SchemaOptions {
+            enums: Some(EnumInference {
+                max_values,
+                min_samples,
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+        for record in records {
+            generator.observe(record).unwrap();
+        }
+        generator.schema()
+    }
+
+    fn events() -> Vec<Value> {
+        (0..500)
+            .map(|i| {
+                let status = ["active", "pending", "closed"][i % 3];
+                json!({"id": i, "status": status, "note": format!("note {}", i)})
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_low_cardinality() {
+        let schema = generate(&events(), 10, 10);
+        assert_eq!(
+            schema["properties"]["status"],
+            json!({"type": "string", "enum": ["active", "closed", "pending"]})
+        );
+        // Strings of many values degrade to plain strings.
+        assert_eq!(schema["properties"]["note"], json!({"type": "string"}));
+        assert_eq!(schema["properties"]["id"], json!({"type": "integer"}));
+
+        let options = SchemaOptions {
+            enums: Some(EnumInference::default()),
+            ..Default::default()
+        };
+        let raw = serde_json::value::to_raw_value(&json!(events())).unwrap();
+        assert_eq!(
+            crate::generate_from_raw(&raw, &options).unwrap()["items"],
+            schema
+        );
+    }
+
+    #[test]
+    fn test_threshold_boundary() {
+        let statuses = |values: &[&str]| -> Vec<Value> {
+            values.iter().map(|s| json!({ "status": s })).collect()
+        };
+        let status = |schema: Value| schema["properties"]["status"].clone();
+
+        let three = statuses(&["a", "b", "c", "a"]);
+        assert_eq!(
+            status(generate(&three, 3, 4)),
+            json!({"type": "string", "enum": ["a", "b", "c"]})
+        );
+        assert_eq!(status(generate(&three, 2, 4)), json!({"type": "string"}));
+        // Too few samples.
+        assert_eq!(status(generate(&three, 3, 5)), json!({"type": "string"}));
+    }
+
+    #[test]
+    fn test_unions_and_arrays() {
+        let records = [
+            json!({"tags": ["x", "y"], "state": "on", "code": "a"}),
+            json!({"tags": ["y"], "state": null, "code": 1}),
+            json!({"tags": ["x"], "state": "off", "code": "b"}),
+        ];
+        let schema = generate(&records, 5, 2);
+        let properties = &schema["properties"];
+        assert_eq!(
+            properties["tags"]["items"],
+            json!({"type": "string", "enum": ["x", "y"]})
+        );
+        assert_eq!(
+            properties["state"],
+            json!({"type": ["string", "null"], "enum": ["off", "on", null]})
+        );
+        assert_eq!(
+            properties["code"],
+            json!({"oneOf": [{"type": "string", "enum": ["a", "b"]}, {"type": "integer"}]})
+        );
+    }
+
+    #[test]
+    fn test_consts() {
+        let records: Vec<Value> = (0..500)
+            .map(|i| {
+                let kind = ["a", "b"][i % 2];
+                json!({"version": "1.0", "kind": kind, "id": i})
+            })
+            .collect();
+        let schema = generate(&records, 10, 10);
+        let properties = &schema["properties"];
+        assert_eq!(properties["version"], json!({"const": "1.0"}));
+        assert_eq!(
+            properties["kind"],
+            json!({"type": "string", "enum": ["a", "b"]})
+        );
+
+        let options = SchemaOptions {
+            enums: Some(EnumInference {
+                consts: false,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let schema = crate::try_generate_schema_from_samples(&records, &options).unwrap();
+        assert_eq!(
+            schema["properties"]["version"],
+            json!({"type": "string", "enum": ["1.0"]})
+        );
+
+        // A value seen once, or sometimes null, is no const.
+        let schema = generate(&records[..1], 10, 10);
+        assert_eq!(schema["properties"]["version"], json!({"type": "string"}));
+        let nullable = [json!({"v": "1"}), json!({"v": null}), json!({"v": "1"})];
+        assert_eq!(
+            generate(&nullable, 10, 2)["properties"]["v"],
+            json!({"type": ["string", "null"], "enum": ["1", null]})
+        );
+    }
+
+    #[test]
+    fn test_across_samples() {
+        let users: Vec<Value> = ["admin", "editor", "viewer", "editor"]
+            .iter()
+            .cycle()
+            .take(12)
+            .enumerate()
+            .map(|(i, role)| json!({"name": format!("user {}", i), "role": role}))
+            .collect();
+        let options = SchemaOptions {
+            enums: Some(EnumInference::default()),
+            ..Default::default()
+        };
+        let schema = crate::try_generate_schema_from_samples(&users, &options).unwrap();
+        assert_eq!(
+            schema["properties"]["role"],
+            json!({"type": "string", "enum": ["admin", "editor", "viewer"]})
+        );
+        assert_eq!(schema["properties"]["name"], json!({"type": "string"}));
+        assert_eq!(
+            schema,
+            crate::try_generate_schema_from_samples(&users, &options).unwrap()
+        );
+        // Without the option the values aren't tracked.
+        let plain = crate::generate_schema_from_instances(&users);
+        assert_eq!(plain["properties"]["role"], json!({"type": "string"}));
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_samples_validate() {
+        let records = events();
+        let schema = generate(&records, 10, 10);
+        for record in &records {
+            assert!(crate::validation::validate_instance(&schema, record).is_ok());
+        }
+        let other = json!({"id": 1, "status": "archived", "note": "n"});
+        assert!(crate::validation::validate_instance(&schema, &other).is_err());
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_merged_consts_validate() {
+        let versions =
+            |version: &str| -> Vec<Value> { (0..10).map(|_| json!({ "v": version })).collect() };
+        let one = generate(&versions("1.0"), 10, 10);
+        let two = generate(&versions("2.0"), 10, 10);
+        let listed = generate(&[versions("2.0"), versions("3.0")].concat(), 10, 10);
+        let plain = crate::generate_json_schema(&json!({"v": "4.0"}));
+        assert_eq!(one["properties"]["v"]["const"], "1.0");
+        assert_eq!(listed["properties"]["v"]["enum"], json!(["2.0", "3.0"]));
+
+        // Merged with a const, an enum or a plain string, the const widens to a string.
+        for (other, version) in [(&two, "2.0"), (&listed, "3.0"), (&plain, "4.0")] {
+            let merged = crate::merge_schemas(&one, other);
+            assert_eq!(merged["properties"]["v"]["type"], "string", "{}", merged);
+            assert!(merged["properties"]["v"].get("oneOf").is_none());
+            for record in [json!({"v": "1.0"}), json!({ "v": version })] {
+                let valid = crate::validation::validate_instance(&merged, &record);
+                assert!(valid.is_ok(), "{} against {}", record, merged);
+            }
+        }
+        assert_eq!(crate::merge_schemas(&one, &one), one);
+    }
+}
@@ -0,0 +1,396 @@
+//! Representative samples embedded under the root `examples` of a schema, see
+//! [`SchemaOptions::root_examples`].
+
+use crate::access::{self, Access};
+use crate::limits::estimate_bytes;
+use crate::SchemaOptions;
+use serde_json::Value;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeSet;
+use std::hash::{Hash, Hasher};
+
+/// How many characters the strings and how many elements the arrays of the examples
+/// keep, tried in turn until the examples fit into
+/// [`SchemaOptions::examples_max_bytes`].
+const TRIMS: [(usize, usize); 2] = [(64, 3), (16, 1)];
+
+/// Replaces the strings of write-only properties.
+const REDACTED: &str = "********";
+
+/// The samples kept as examples.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Samples {
+    kept: Vec<Sample>,
+}
+
+#[derive(Debug, Clone)]
+struct Sample {
+    /// A hash of the paths of the properties the sample has.
+    shape: u64,
+    value: Value,
+}
+
+impl Samples {
+    /// Keeps a copy of the record `instance` while fewer than
+    /// [`SchemaOptions::root_examples`] are kept. Past that, a record with properties no
+    /// kept sample has replaces the latest sample whose shape an earlier one shares.
+    pub(crate) fn offer(&mut self, instance: &Value, options: &SchemaOptions) {
+        if options.root_examples == 0 {
+            return;
+        }
+        let shape = shape(instance);
+        if self.kept.len() >= options.root_examples {
+            if self.kept.iter().any(|sample| sample.shape == shape) {
+                return;
+            }
+            let repeated = (0..self.kept.len()).rev().find(|&index| {
+                let shape = self.kept[index].shape;
+                self.kept[..index]
+                    .iter()
+                    .any(|sample| sample.shape == shape)
+            });
+            match repeated {
+                Some(index) => {
+                    self.kept.remove(index);
+                }
+                None => return,
+            }
+        }
+        self.kept.push(Sample {
+            shape,
+            value: prepare(instance, options),
+        });
+    }
+}
+
+/// Returns a hash of the paths of the properties `instance` has, the elements of an
+/// array sharing one path.
+fn shape(instance: &Value) -> u64 {
+    fn collect(value: &Value, path: &mut String, paths: &mut BTreeSet<String>) {
+        let len = path.len();
+        match value {
+            Value::Object(obj) => {
+                for (name, value) in obj {
+                    path.push('/');
+                    path.push_str(&name.replace('~', "~0").replace('/', "~1"));
+                    paths.insert(path.clone());
+                    collect(value, path, paths);
+                    path.truncate(len);
+                }
+            }
+            Value::Array(items) => {
+                path.push_str("/*");
+                for item in items {
+                    collect(item, path, paths);
+                }
+                path.truncate(len);
+            }
+            _ => {}
+        }
+    }
+    let mut paths = BTreeSet::new();
+    collect(instance, &mut String::new(), &mut paths);
+    let mut hasher = DefaultHasher::new();
+    paths.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Copies `value` with its arrays cut to the longest the examples keep and the values
+/// of write-only properties, by [`SchemaOptions::access_rules`], redacted.
+fn prepare(value: &Value, options: &SchemaOptions) -> Value {
+    match value {
+        Value::Object(obj) => Value::Object(
+            obj.iter()
+                .map(|(name, value)| {
+                    let value = match access::access_of(name, &options.access_rules) {
+                        Some(Access::WriteOnly) => redact(value),
+                        _ => prepare(value, options),
+                    };
+                    (name.clone(), value)
+                })
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .take(TRIMS[0].1)
+                .map(|item| prepare(item, options))
+                .collect(),
+        ),
+        value => value.clone(),
+    }
+}
+
+/// Replaces every string and number of `value` by a placeholder of the same type.
+fn redact(value: &Value) -> Value {
+    match value {
+        Value::String(_) => Value::String(REDACTED.to_string()),
+        Value::Number(n) if n.is_f64() => Value::from(0.0),
+        Value::Number(_) => Value::from(0),
+        Value::Bool(_) => Value::Bool(false),
+        Value::Null => Value::Null,
+        Value::Array(items) => Value::Array(items.iter().take(TRIMS[0].1).map(redact).collect()),
+        Value::Object(obj) => Value::Object(
+            obj.iter()
+                .map(|(name, value)| (name.clone(), redact(value)))
+                .collect(),
+        ),
+    }
+}
+
+/// Writes the kept samples under the root `examples` of `schema`, trimmed to fit into
+/// [`SchemaOptions::examples_max_bytes`] together, and leaving out the last ones while
+/// they don't. Strings whose schema has a `format`, `pattern`, `enum` or `const`, or
+/// isn't known, keep their length, so that the examples stay valid.
+pub(crate) fn embed(schema: &mut Value, samples: &Samples, options: &SchemaOptions) {
+    if samples.kept.is_empty() {
+        return;
+    }
+    let mut examples = Vec::new();
+    let mut sizes = Vec::new();
+    for (chars, items) in TRIMS {
+        examples = samples
+            .kept
+            .iter()
+            .map(|sample| trim(&sample.value, Some(schema), schema, chars, items))
+            .collect();
+        sizes = examples.iter().map(estimate_bytes).collect();
+        if total(&sizes) <= options.examples_max_bytes {
+            break;
+        }
+    }
+    while !sizes.is_empty() && total(&sizes) > options.examples_max_bytes {
+        sizes.pop();
+        examples.pop();
+    }
+    if let (false, Some(obj)) = (examples.is_empty(), schema.as_object_mut()) {
+        obj.insert("examples".to_string(), Value::Array(examples));
+    }
+}
+
+/// The size of an array of values of `sizes`.
+fn total(sizes: &[usize]) -> usize {
+    2 + sizes.len().saturating_sub(1) + sizes.iter().sum::<usize>()
+}
+
+/// Copies `value`, described by `schema` within the schema `root`, keeping at most
+/// `chars` characters of its strings and `items` elements of its arrays.
+fn trim(value: &Value, schema: Option<&Value>, root: &Value, chars: usize, items: usize) -> Value {
+    let schema = schema.and_then(|schema| describing(schema, value, root));
+    match value {
+        Value::String(s) => {
+            let free = schema.is_some_and(|schema| {
+                ["format", "pattern", "enum", "const"]
+                    .iter()
+                    .all(|keyword| schema.get(keyword).is_none())
+            });
+            match s.char_indices().nth(chars) {
+                Some((end, _)) if free => Value::String(s[..end].to_string()),
+                _ => value.clone(),
+            }
+        }
+        Value::Array(elements) => {
+            let schema = schema.and_then(|schema| schema.get("items"));
+            Value::Array(
+                elements
+                    .iter()
+                    .take(items)
+                    .map(|element| trim(element, schema, root, chars, items))
+                    .collect(),
+            )
+        }
+        Value::Object(obj) => {
+            let properties = schema.and_then(|schema| schema.get("properties"));
+            Value::Object(
+                obj.iter()
+                    .map(|(name, value)| {
+                        let schema = properties.and_then(|properties| properties.get(name));
+                        (name.clone(), trim(value, schema, root, chars, items))
+                    })
+                    .collect(),
+            )
+        }
+        value => value.clone(),
+    }
+}
+
+/// Returns the schema describing `value` among `schema`, the local schema it refers to
+/// and the branches of its union, or `None` if no single branch has the type of `value`.
+fn describing<'s>(schema: &'s Value, value: &Value, root: &'s Value) -> Option<&'s Value> {
+    let schema = match schema.get("$ref").and_then(Value::as_str) {
+        Some(reference) => root.pointer(reference.strip_prefix('#')?)?,
+        None => schema,
+    };
+    let Some(Value::Array(branches)) = schema.get("oneOf").or_else(|| schema.get("anyOf")) else {
+        return Some(schema);
+    };
+    let mut matching = branches
+        .iter()
+        .filter_map(|branch| describing(branch, value, root))
+        .filter(|branch| has_type(branch, value));
+    match (matching.next(), matching.next()) {
+        (Some(branch), None) => Some(branch),
+        _ => None,
+    }
+}
+
+/// Returns whether the `type` of `schema` is the type of `value`.
+fn has_type(schema: &Value, value: &Value) -> bool {
+    let expected = match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_f64() => "number",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    };
+    match schema.get("type").and_then(Value::as_str) {
+        Some("number") => expected == "number" || expected == "integer",
+        Some(name) => name == expected,
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{generate_from_raw, try_generate_json_schema, SchemaGenerator};
+    use serde_json::json;
+
+    fn options(root_examples: usize) -> SchemaOptions {
+        SchemaOptions {
+            root_examples,
+            ..Default::default()
+        }
+    }
+
+    fn generate(records: &[Value], options: SchemaOptions) -> Value {
+        let mut generator = SchemaGenerator::new(options);
+        for record in records {
+            generator.observe(record).unwrap();
+        }
+        generator.schema()
+    }
+
+    #[test]
+    fn test_prefers_diverse_samples() {
+        let records = [
+            json!({"id": 1}),
+            json!({"id": 2}),
+            json!({"id": 3, "note": "x"}),
+            json!({"id": 4}),
+            json!({"id": 5, "tags": ["a"]}),
+        ];
+        let schema = generate(&records, options(3));
+        assert_eq!(
+            schema["examples"],
+            json!([{"id": 1}, {"id": 3, "note": "x"}, {"id": 5, "tags": ["a"]}])
+        );
+
+        // With fewer shapes than examples, repeated shapes fill the rest.
+        let schema = generate(&records[..3], options(5));
+        assert_eq!(schema["examples"].as_array().unwrap().len(), 3);
+        assert!(generate(&records, options(0)).get("examples").is_none());
+    }
+
+    #[test]
+    fn test_single_instance() {
+        let instance = json!({"name": "x".repeat(100), "tags": [1, 2, 3, 4, 5]});
+        let schema = try_generate_json_schema(&instance, &options(1)).unwrap();
+        assert_eq!(
+            schema["examples"],
+            json!([{"name": "x".repeat(64), "tags": [1, 2, 3]}])
+        );
+
+        let raw = serde_json::value::to_raw_value(&instance).unwrap();
+        assert_eq!(generate_from_raw(&raw, &options(1)).unwrap(), schema);
+    }
+
+    #[test]
+    fn test_budget() {
+        let records: Vec<Value> = (0..4)
+            .map(|i| json!({format!("key{}", i): "y".repeat(200), "list": [[1, 2], [3], [4]]}))
+            .collect();
+        let schema = generate(
+            &records,
+            SchemaOptions {
+                examples_max_bytes: 100,
+                ..options(4)
+            },
+        );
+        // The examples are trimmed harder, then the last ones left out.
+        assert_eq!(
+            schema["examples"],
+            json!([
+                {"key0": "y".repeat(16), "list": [[1]]},
+                {"key1": "y".repeat(16), "list": [[1]]},
+            ])
+        );
+        assert!(schema["examples"].to_string().len() <= 100);
+
+        let schema = generate(
+            &records,
+            SchemaOptions {
+                examples_max_bytes: 10,
+                ..options(4)
+            },
+        );
+        assert!(schema.get("examples").is_none());
+    }
+
+    #[test]
+    fn test_constrained_strings_keep_their_length() {
+        let url = format!("https://example.com/{}", "a".repeat(80));
+        let mut samples = Samples::default();
+        samples.offer(&json!({"url": url, "name": url}), &options(1));
+        let mut schema = json!({
+            "type": "object",
+            "properties": {
+                "url": {"type": "string", "format": "uri"},
+                "name": {"type": "string"},
+            },
+        });
+        embed(&mut schema, &samples, &options(1));
+        assert_eq!(schema["examples"][0]["url"], url);
+        assert_eq!(schema["examples"][0]["name"], url[..64]);
+    }
+
+    #[test]
+    fn test_redacts_write_only_properties() {
+        let instance = json!({
+            "user": "ada",
+            "password": "hunter2",
+            "api_secret": {"key": "abc", "rotations": 3, "ttl": 1.5},
+        });
+        let schema = try_generate_json_schema(&instance, &options(1)).unwrap();
+        assert_eq!(
+            schema["examples"],
+            json!([{
+                "user": "ada",
+                "password": "********",
+                "api_secret": {"key": "********", "rotations": 0, "ttl": 0.0},
+            }])
+        );
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_examples_validate() {
+        let records = [
+            json!({"id": 1, "name": "n".repeat(90), "tags": ["a", "b", "c", "d"], "password": "p"}),
+            json!({"id": 2, "when": "2024-05-01T12:00:00Z", "items": [{"a": 1}, {"a": 2, "b": "x"}]}),
+            json!({"id": "3", "value": null, "nested": {"deep": ["z".repeat(70)]}}),
+        ];
+        let schema = generate(&records, options(3));
+        let examples = schema["examples"].as_array().unwrap();
+        assert_eq!(examples.len(), 3);
+        for example in examples {
+            assert!(
+                crate::validation::validate_instance(&schema, example).is_ok(),
+                "{}",
+                example
+            );
+        }
+    }
+}
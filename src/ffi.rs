@@ -0,0 +1,158 @@
+//! A C ABI for embedding the generator, see `include/json_schema_generator.h`.
+//!
+//! All strings crossing the boundary are NUL-terminated UTF-8. Panics are caught before
+//! they reach the caller and reported as [`JSG_ERR_PANIC`].
+
+use crate::embed::{self, EmbedError};
+use crate::GeneratorError;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+/// The schema was generated.
+pub const JSG_OK: i32 = 0;
+/// A required pointer was null or a string was not valid UTF-8.
+pub const JSG_ERR_INVALID_ARGUMENT: i32 = 1;
+/// The input is not valid JSON.
+pub const JSG_ERR_INVALID_INPUT: i32 = 2;
+/// The options are not a valid options object.
+pub const JSG_ERR_INVALID_OPTIONS: i32 = 3;
+/// Generation was cancelled, see `GeneratorError::Cancelled`.
+pub const JSG_ERR_CANCELLED: i32 = 4;
+/// The input does not validate against the generated schema, see
+/// `GeneratorError::Verification`.
+pub const JSG_ERR_VERIFICATION: i32 = 5;
+/// A warning listed in the `deny_lints` option was raised.
+pub const JSG_ERR_DENIED_WARNING: i32 = 6;
+/// The generator panicked.
+pub const JSG_ERR_PANIC: i32 = 7;
+/// With the `strict` option, the samples only merge into a wider schema, see
+/// `GeneratorError::Conflict`.
+pub const JSG_ERR_CONFLICT: i32 = 8;
+/// An input exceeded a size limit of the generator, see `GeneratorError::LimitExceeded`.
+pub const JSG_ERR_LIMIT_EXCEEDED: i32 = 9;
+/// A post-processing hook rejected the schema, see `GeneratorError::Transform`.
+pub const JSG_ERR_TRANSFORM: i32 = 10;
+
+/// Generates the schema of the JSON document `json`.
+///
+/// `options_json` is a JSON object such as `{"deny_lints": ["always-null"]}`, or null
+/// for the default options. Both strings are borrowed for the duration of the call.
+///
+/// Returns [`JSG_OK`] and stores the serialized schema in `*out_schema`, or returns
+/// one of the `JSG_ERR_*` codes and stores a description in `*out_error`. The pointer
+/// not written to is set to null. Each string stored is owned by the caller and must be
+/// released with [`jsg_free_string`]. `out_schema` and `out_error` may be null, in which
+/// case the corresponding string is discarded.
+///
+/// # Safety
+///
+/// `json` and `options_json` must be null or point to NUL-terminated strings, and
+/// `out_schema` and `out_error` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn jsg_generate(
+    json: *const c_char,
+    options_json: *const c_char,
+    out_schema: *mut *mut c_char,
+    out_error: *mut *mut c_char,
+) -> i32 {
+    // SAFETY: the caller guarantees the out pointers are null or valid for writes.
+    unsafe {
+        store(out_schema, None);
+        store(out_error, None);
+    }
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        // SAFETY: the caller guarantees the pointers are null or NUL-terminated.
+        let json = unsafe { read(json) }?.ok_or((
+            JSG_ERR_INVALID_ARGUMENT,
+            "json must not be null".to_string(),
+        ))?;
+        let options_json = unsafe { read(options_json) }?.unwrap_or("");
+        embed::generate(json, options_json).map_err(|error| (code(&error), error.to_string()))
+    }));
+
+    let (status, schema, error) = match result {
+        Ok(Ok(schema)) => (JSG_OK, Some(schema), None),
+        Ok(Err((status, message))) => (status, None, Some(message)),
+        Err(payload) => {
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|message| message.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".to_string());
+            (JSG_ERR_PANIC, None, Some(format!("panic: {}", message)))
+        }
+    };
+    // SAFETY: as above.
+    unsafe {
+        store(out_schema, schema);
+        store(out_error, error);
+    }
+    status
+}
+
+/// Releases a string returned by [`jsg_generate`]. Passing null does nothing.
+///
+/// # Safety
+///
+/// `s` must be null or a string returned by this library that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn jsg_free_string(s: *mut c_char) {
+    if !s.is_null() {
+        // SAFETY: the string was allocated by `CString::into_raw` in `store`.
+        drop(unsafe { CString::from_raw(s) });
+    }
+}
+
+fn code(error: &EmbedError) -> i32 {
+    match error {
+        EmbedError::InvalidInput(_)
+        | EmbedError::Generator(GeneratorError::Json(_) | GeneratorError::InvalidInput(_)) => {
+            JSG_ERR_INVALID_INPUT
+        }
+        EmbedError::InvalidOptions(_) => JSG_ERR_INVALID_OPTIONS,
+        EmbedError::Generator(GeneratorError::Cancelled) => JSG_ERR_CANCELLED,
+        #[cfg(feature = "validation")]
+        EmbedError::Generator(GeneratorError::Verification(_)) => JSG_ERR_VERIFICATION,
+        EmbedError::Generator(GeneratorError::Conflict { .. }) => JSG_ERR_CONFLICT,
+        EmbedError::Generator(GeneratorError::LimitExceeded(_)) => JSG_ERR_LIMIT_EXCEEDED,
+        EmbedError::Generator(GeneratorError::Transform(_)) => JSG_ERR_TRANSFORM,
+        EmbedError::DeniedWarning(_) => JSG_ERR_DENIED_WARNING,
+    }
+}
+
+/// Borrows the string at `s`, if it isn't null.
+///
+/// # Safety
+///
+/// `s` must be null or point to a NUL-terminated string outliving the returned one.
+unsafe fn read<'a>(s: *const c_char) -> Result<Option<&'a str>, (i32, String)> {
+    if s.is_null() {
+        return Ok(None);
+    }
+    // SAFETY: guaranteed by the caller.
+    let s = unsafe { CStr::from_ptr(s) };
+    s.to_str()
+        .map(Some)
+        .map_err(|e| (JSG_ERR_INVALID_ARGUMENT, format!("invalid UTF-8: {}", e)))
+}
+
+/// Stores `s` as a newly allocated C string in `*out`, or null if `s` is `None`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writes.
+unsafe fn store(out: *mut *mut c_char, s: Option<String>) {
+    if out.is_null() {
+        return;
+    }
+    let s = s.map_or(ptr::null_mut(), |s| {
+        // Serialized JSON escapes NUL, but messages may quote raw input.
+        CString::new(s.replace('\0', "\\0"))
+            .expect("NUL bytes were replaced")
+            .into_raw()
+    });
+    // SAFETY: guaranteed by the caller.
+    unsafe { *out = s };
+}
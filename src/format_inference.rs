@@ -0,0 +1,294 @@
+//! The `format` of a string, inferred from its content, see
+//! [`SchemaOptions::infer_formats`](crate::SchemaOptions::infer_formats).
+
+use crate::Draft;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+/// Returns the draft-07 `format` the string `s` has: `uuid`, `date-time`, `date`, `time`,
+/// `email`, `ipv4`, `ipv6`, `uri` or `hostname`, checked in this order, or `None`.
+///
+/// The checks follow the RFCs the formats name, so that validators accept the strings
+/// they match, but leave out the rarely seen forms: a `uri` needs an authority, such as
+/// `https://example.com`, and a `hostname` at least two labels and a top-level label of
+/// letters, which still takes file names such as `notes.txt` for hostnames.
+///
+/// # Examples
+///
+/// ```
+/// use json_schema_generator::infer_format;
+///
+/// assert_eq!(infer_format("2024-01-15T10:30:00Z"), Some("date-time"));
+/// assert_eq!(infer_format("user@example.com"), Some("email"));
+/// assert_eq!(infer_format("hello"), None);
+/// ```
+pub fn infer_format(s: &str) -> Option<&'static str> {
+    type Check = fn(&str) -> bool;
+    let checks: [(&str, Check); 9] = [
+        ("uuid", is_uuid),
+        ("date-time", is_date_time),
+        ("date", is_date),
+        ("time", is_time),
+        ("email", is_email),
+        ("ipv4", |s| s.parse::<Ipv4Addr>().is_ok()),
+        ("ipv6", |s| s.parse::<Ipv6Addr>().is_ok()),
+        ("uri", is_uri),
+        ("hostname", is_hostname),
+    ];
+    checks
+        .into_iter()
+        .find(|(_, check)| check(s))
+        .map(|(format, _)| format)
+}
+
+/// Returns whether `draft` defines the format `format`, one of those [`infer_format`]
+/// returns: `date` and `time` came with draft-07 and `uuid` with 2019-09.
+pub(crate) fn defined_in(format: &str, draft: Draft) -> bool {
+    match format {
+        "date" | "time" => draft >= Draft::Draft7,
+        "uuid" => draft >= Draft::Draft201909,
+        _ => true,
+    }
+}
+
+/// Returns whether `s` consists only of ASCII digits, and has `len` of them.
+fn is_digits(s: &str, len: usize) -> bool {
+    s.len() == len && s.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Returns the number `s` writes if it is `len` ASCII digits.
+fn number(s: &str, len: usize) -> Option<u32> {
+    is_digits(s, len).then(|| s.parse().ok()).flatten()
+}
+
+/// `8-4-4-4-12` hexadecimal digits, as RFC 4122 writes a UUID.
+fn is_uuid(s: &str) -> bool {
+    let groups: Vec<&str> = s.split('-').collect();
+    groups.len() == 5
+        && groups
+            .iter()
+            .zip([8, 4, 4, 4, 12])
+            .all(|(group, len)| group.len() == len && group.bytes().all(|b| b.is_ascii_hexdigit()))
+}
+
+/// An RFC 3339 `full-date`, such as `2024-01-15`, of a day that exists.
+fn is_date(s: &str) -> bool {
+    let mut parts = s.split('-');
+    let (Some(year), Some(month), Some(day), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return false;
+    };
+    let (Some(year), Some(month), Some(day)) = (number(year, 4), number(month, 2), number(day, 2))
+    else {
+        return false;
+    };
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if leap => 29,
+        2 => 28,
+        _ => return false,
+    };
+    (1..=days).contains(&day)
+}
+
+/// An RFC 3339 `full-time` with its offset, such as `10:30:00Z` or
+/// `10:30:00.5+01:00`.
+fn is_time(s: &str) -> bool {
+    let (time, offset) = match s.find(['Z', 'z', '+', '-']) {
+        Some(at) => s.split_at(at),
+        None => return false,
+    };
+    let offset_ok = match offset {
+        "Z" | "z" => true,
+        offset => is_hh_mm(&offset[1..], 23),
+    };
+    let (time, fraction) = match time.split_once('.') {
+        Some((time, fraction)) => (time, Some(fraction)),
+        None => (time, None),
+    };
+    let fraction_ok = fraction.is_none_or(|f| !f.is_empty() && is_digits(f, f.len()));
+    let mut parts = time.split(':');
+    let (Some(hour), Some(minute), Some(second), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return false;
+    };
+    // A leap second is 60.
+    offset_ok
+        && fraction_ok
+        && number(hour, 2).is_some_and(|h| h <= 23)
+        && number(minute, 2).is_some_and(|m| m <= 59)
+        && number(second, 2).is_some_and(|s| s <= 60)
+}
+
+/// `hh:mm` with the hours up to `max_hour`.
+fn is_hh_mm(s: &str, max_hour: u32) -> bool {
+    match s.split_once(':') {
+        Some((hour, minute)) => {
+            number(hour, 2).is_some_and(|h| h <= max_hour)
+                && number(minute, 2).is_some_and(|m| m <= 59)
+        }
+        None => false,
+    }
+}
+
+/// An RFC 3339 `date-time`, such as `2024-01-15T10:30:00Z`.
+fn is_date_time(s: &str) -> bool {
+    match s.find(['T', 't']) {
+        Some(at) => is_date(&s[..at]) && is_time(&s[at + 1..]),
+        None => false,
+    }
+}
+
+/// An address such as `user@example.com`: a dot-atom of RFC 5322 before the `@` and a
+/// [hostname](is_hostname) after it.
+fn is_email(s: &str) -> bool {
+    let Some((local, domain)) = s.rsplit_once('@') else {
+        return false;
+    };
+    let atom = |c: char| c.is_ascii_alphanumeric() || "!#$%&'*+-/=?^_`{|}~".contains(c);
+    !local.is_empty()
+        && local.len() <= 64
+        && local
+            .split('.')
+            .all(|part| !part.is_empty() && part.chars().all(atom))
+        && is_hostname(domain)
+}
+
+/// An absolute URI with an authority, such as `https://example.com/a?b#c`, made of the
+/// characters RFC 3986 allows.
+fn is_uri(s: &str) -> bool {
+    let Some((scheme, rest)) = s.split_once("://") else {
+        return false;
+    };
+    let scheme_ok = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let allowed = |c: char| c.is_ascii_alphanumeric() || "-._~:/?#[]@!$&'()*+,;=%".contains(c);
+    let escapes_ok = rest.split('%').skip(1).all(|escape| {
+        escape.len() >= 2 && escape.as_bytes()[..2].iter().all(u8::is_ascii_hexdigit)
+    });
+    scheme_ok && !authority.is_empty() && rest.chars().all(allowed) && escapes_ok
+}
+
+/// An RFC 1123 hostname of at least two labels, such as `api.example.com`, whose last
+/// label is made of letters.
+fn is_hostname(s: &str) -> bool {
+    let labels: Vec<&str> = s.split('.').collect();
+    let label_ok = |label: &&str| {
+        (1..=63).contains(&label.len())
+            && label
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b == b'-')
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+    };
+    let top_ok = labels
+        .last()
+        .is_some_and(|top| top.len() >= 2 && top.bytes().all(|b| b.is_ascii_alphabetic()));
+    s.len() <= 253 && labels.len() >= 2 && labels.iter().all(label_ok) && top_ok
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_each_format() {
+        let cases = [
+            ("550e8400-e29b-41d4-a716-446655440000", "uuid"),
+            ("550E8400-E29B-41D4-A716-446655440000", "uuid"),
+            ("2024-01-15T10:30:00Z", "date-time"),
+            ("2024-02-29t23:59:60.123+05:30", "date-time"),
+            ("2024-01-15", "date"),
+            ("10:30:00Z", "time"),
+            ("10:30:00.25-08:00", "time"),
+            ("user@example.com", "email"),
+            ("first.last+tag@mail.example.org", "email"),
+            ("192.168.0.1", "ipv4"),
+            ("::1", "ipv6"),
+            ("2001:db8::8a2e:370:7334", "ipv6"),
+            ("https://example.com/a/b?c=d#e", "uri"),
+            ("ftp://user@host:21/%20file", "uri"),
+            ("api.example.com", "hostname"),
+            ("xn--bcher-kva.example", "hostname"),
+        ];
+        for (s, format) in cases {
+            assert_eq!(infer_format(s), Some(format), "{}", s);
+        }
+    }
+
+    #[test]
+    fn test_near_misses() {
+        for s in [
+            "",
+            "hello",
+            "hello world",
+            "2023-02-29",
+            "2024-13-01",
+            "2024-1-15",
+            "2024-01-15T25:00:00Z",
+            "2024-01-15 10:30:00Z",
+            "10:30:00",
+            "10:30",
+            "user@",
+            "not-an-email@",
+            "@example.com",
+            " user@example.com",
+            "user@example.com ",
+            "a..b@example.com",
+            "user@localhost",
+            "256.1.1.1",
+            "01.1.1.1",
+            "1.5",
+            "v1.2.3",
+            "example.com/path",
+            "https://",
+            "https://exa mple.com",
+            "https://example.com/%zz",
+            "note:hello",
+            "-a.example.com",
+            "550e8400-e29b-41d4-a716-44665544000g",
+        ] {
+            assert_eq!(infer_format(s), None, "{}", s);
+        }
+    }
+
+    #[test]
+    fn test_defined_in() {
+        assert!(defined_in("email", Draft::Draft4));
+        assert!(!defined_in("date", Draft::Draft6));
+        assert!(defined_in("date", Draft::Draft7));
+        assert!(!defined_in("uuid", Draft::Draft7));
+        assert!(defined_in("uuid", Draft::Draft202012));
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_validators_agree() {
+        use serde_json::json;
+        for s in [
+            "550e8400-e29b-41d4-a716-446655440000",
+            "2024-02-29T23:59:59.5+05:30",
+            "2024-01-15",
+            "10:30:00Z",
+            "first.last+tag@mail.example.org",
+            "192.168.0.1",
+            "2001:db8::8a2e:370:7334",
+            "ftp://user@host:21/%20file",
+            "api.example.com",
+        ] {
+            let format = infer_format(s).unwrap();
+            let schema = json!({"type": "string", "format": format});
+            let validator = jsonschema::options()
+                .should_validate_formats(true)
+                .build(&schema)
+                .unwrap();
+            assert!(validator.is_valid(&json!(s)), "{} as {}", s, format);
+        }
+    }
+}
@@ -0,0 +1,219 @@
+//! Bounds for geographic coordinates, recognized as pairs of latitude and longitude
+//! properties such as `lat` and `lng`.
+
+use serde_json::{Map, Value};
+
+/// The property names recognized as coordinates, see
+/// [`SchemaOptions::coordinates`](crate::SchemaOptions::coordinates).
+///
+/// A number gets `"minimum": -90, "maximum": 90` as a latitude, or -180 and 180 as a
+/// longitude, when it is in range and the object it belongs to also has a property of the
+/// other kind. The bounds survive merging until a value out of range, or one without its
+/// pair, is seen.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Coordinates {
+    /// The names of latitude properties, compared exactly.
+    pub latitude: Vec<String>,
+    /// The names of longitude properties, compared exactly.
+    pub longitude: Vec<String>,
+}
+
+impl Default for Coordinates {
+    fn default() -> Self {
+        Coordinates {
+            latitude: ["lat", "latitude"].map(String::from).to_vec(),
+            longitude: ["lng", "lon", "long", "longitude"]
+                .map(String::from)
+                .to_vec(),
+        }
+    }
+}
+
+impl Coordinates {
+    /// The bounds of the property `name`, if it is a coordinate.
+    fn range(&self, name: &str) -> Option<f64> {
+        if self.latitude.iter().any(|latitude| latitude == name) {
+            Some(90.0)
+        } else if self.longitude.iter().any(|longitude| longitude == name) {
+            Some(180.0)
+        } else {
+            None
+        }
+    }
+
+    /// Bounds the schema `schema` of the number `value` of the property `name`, if it is
+    /// a coordinate in range.
+    pub(crate) fn bound(&self, name: &str, value: f64, schema: &mut Value) {
+        let (Some(range), Some(obj)) = (self.range(name), schema.as_object_mut()) else {
+            return;
+        };
+        if (-range..=range).contains(&value) {
+            obj.insert("minimum".to_string(), Value::from(-range as i64));
+            obj.insert("maximum".to_string(), Value::from(range as i64));
+        }
+    }
+
+    /// Removes the bounds from the coordinates among `properties` unless they hold both a
+    /// latitude and a longitude.
+    pub(crate) fn pair(&self, properties: &mut Map<String, Value>) {
+        let has = |names: &[String]| names.iter().any(|name| properties.contains_key(name));
+        if has(&self.latitude) && has(&self.longitude) {
+            return;
+        }
+        for name in self.latitude.iter().chain(&self.longitude) {
+            if let Some(Value::Object(schema)) = properties.get_mut(name) {
+                schema.remove("minimum");
+                schema.remove("maximum");
+            }
+        }
+    }
+}
+
+/// Returns the bounds two merged schemas share, which the merge keeps as every value of
+/// either is within them.
+pub(crate) fn carried(target: &Value, schema: &Value) -> Option<(Value, Value)> {
+    fn bounds(schema: &Value) -> Option<(&Value, &Value)> {
+        Some((schema.get("minimum")?, schema.get("maximum")?))
+    }
+    match (bounds(target), bounds(schema)) {
+        (Some(left), Some(right)) if left == right => Some((left.0.clone(), left.1.clone())),
+        _ => None,
+    }
+}
+
+/// Adds the bounds `carried` returned to the merged schema `schema`, if it is numeric.
+pub(crate) fn restore(schema: &mut Value, carried: Option<(Value, Value)>) {
+    let Some((minimum, maximum)) = carried else {
+        return;
+    };
+    let numeric = matches!(
+        schema.get("type").and_then(Value::as_str),
+        Some("integer" | "number")
+    );
+    if let (true, Some(obj)) = (numeric, schema.as_object_mut()) {
+        obj.insert("minimum".to_string(), minimum);
+        obj.insert("maximum".to_string(), maximum);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{generate_from_raw, SchemaGenerator, SchemaOptions};
+    use serde_json::json;
+
+    fn generate(records: &[Value], coordinates: Coordinates) -> Value {
+        let mut generator = SchemaGenerator::new(SchemaOptions {
+            coordinates: Some(coordinates),
+            ..Default::default()
+        });
+        for record in records {
+            generator.observe(record).unwrap();
+        }
+        generator.schema()
+    }
+
+    fn latitude() -> Value {
+        json!({"type": "number", "minimum": -90, "maximum": 90})
+    }
+
+    fn longitude() -> Value {
+        json!({"type": "number", "minimum": -180, "maximum": 180})
+    }
+
+    #[test]
+    fn test_pair_in_range() {
+        let records = [
+            json!({"lat": 52.52, "lng": 13.405, "name": "Berlin"}),
+            json!({"lat": -33.9, "lng": 151, "name": "Sydney"}),
+        ];
+        let schema = generate(&records, Coordinates::default());
+        assert_eq!(schema["properties"]["lat"], latitude());
+        // An integer and a number merge into a number within the same bounds.
+        assert_eq!(schema["properties"]["lng"], longitude());
+        assert_eq!(schema["properties"]["name"], json!({"type": "string"}));
+
+        let raw = serde_json::value::to_raw_value(&records[0]).unwrap();
+        let options = SchemaOptions {
+            coordinates: Some(Coordinates::default()),
+            ..Default::default()
+        };
+        let schema = generate_from_raw(&raw, &options).unwrap();
+        assert_eq!(schema["properties"]["lat"], latitude());
+    }
+
+    #[test]
+    fn test_out_of_range_suppresses_bounds() {
+        let records = [
+            json!({"latitude": 10.5, "longitude": 20.5}),
+            json!({"latitude": 95.0, "longitude": -200.5}),
+        ];
+        let schema = generate(&records, Coordinates::default());
+        assert_eq!(schema["properties"]["latitude"], json!({"type": "number"}));
+        assert_eq!(schema["properties"]["longitude"], json!({"type": "number"}));
+
+        // Each coordinate is checked on its own.
+        let schema = generate(
+            &[json!({"lat": 45.0, "lon": 181.0})],
+            Coordinates::default(),
+        );
+        assert_eq!(schema["properties"]["lat"], latitude());
+        assert_eq!(schema["properties"]["lon"], json!({"type": "number"}));
+    }
+
+    #[test]
+    fn test_lone_field_is_not_a_coordinate() {
+        let schema = generate(&[json!({"lat": 12.5, "lot": 3})], Coordinates::default());
+        assert_eq!(schema["properties"]["lat"], json!({"type": "number"}));
+
+        // Nor are objects where only some samples have the pair.
+        let records = [json!({"lat": 1.5, "lng": 2.5}), json!({"lat": 3.5})];
+        let schema = generate(&records, Coordinates::default());
+        assert_eq!(schema["properties"]["lat"], json!({"type": "number"}));
+        assert_eq!(schema["properties"]["lng"], longitude());
+    }
+
+    #[test]
+    fn test_nested_and_custom_names() {
+        let coordinates = Coordinates {
+            latitude: vec!["y".to_string()],
+            longitude: vec!["x".to_string()],
+        };
+        let schema = generate(
+            &[
+                json!({"stops": [{"y": 1.5, "x": 2.5}, {"y": -3.25, "x": 4.75}], "lat": 1.5, "lng": 1.5}),
+            ],
+            coordinates,
+        );
+        let stop = &schema["properties"]["stops"]["items"]["properties"];
+        assert_eq!(stop["y"], latitude());
+        assert_eq!(stop["x"], longitude());
+        assert_eq!(schema["properties"]["lat"], json!({"type": "number"}));
+    }
+
+    #[test]
+    fn test_off_by_default() {
+        let mut generator = SchemaGenerator::new(SchemaOptions::default());
+        generator.observe(&json!({"lat": 1.5, "lng": 2.5})).unwrap();
+        assert_eq!(
+            generator.schema()["properties"]["lat"],
+            json!({"type": "number"})
+        );
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_samples_validate() {
+        let records = [
+            json!({"lat": 90, "lng": -180}),
+            json!({"lat": -90.0, "lng": 180.0}),
+        ];
+        let schema = generate(&records, Coordinates::default());
+        assert_eq!(schema["properties"]["lat"], latitude());
+        for record in &records {
+            assert!(crate::validation::validate_instance(&schema, record).is_ok());
+        }
+        let outside = json!({"lat": 90.5, "lng": 0});
+        assert!(crate::validation::validate_instance(&schema, &outside).is_err());
+    }
+}
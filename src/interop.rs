@@ -0,0 +1,359 @@
+//! Conversions between `serde_json` schemas and the [`schemars`](https://docs.rs/schemars)
+//! schema model.
+//!
+//! schemars models draft-07, so the conversion covers the draft-07 keywords. Keywords
+//! schemars doesn't know are kept as extensions and survive a round trip, except for the
+//! keywords of later drafts, whose meaning a draft-07 consumer would silently drop; those
+//! are reported by [`to_schemars`].
+
+use serde_json::Value;
+use std::borrow::Cow;
+
+/// A schema in one of the representations accepted by the normalization functions, such
+/// as [`to_canonical_string`](crate::to_canonical_string).
+///
+/// Implemented for `serde_json::Value` and, with the `schemars` feature, for schemars'
+/// `RootSchema`, `Schema` and `SchemaObject`.
+pub trait AsSchemaValue {
+    /// Returns the schema as JSON, borrowing it if it already is.
+    fn as_schema_value(&self) -> Cow<'_, Value>;
+}
+
+impl AsSchemaValue for Value {
+    fn as_schema_value(&self) -> Cow<'_, Value> {
+        Cow::Borrowed(self)
+    }
+}
+
+impl<T: AsSchemaValue + ?Sized> AsSchemaValue for &T {
+    fn as_schema_value(&self) -> Cow<'_, Value> {
+        (**self).as_schema_value()
+    }
+}
+
+#[cfg(feature = "schemars")]
+pub use convert::{from_schemars, to_schemars, ConvertError, Unconvertible};
+
+#[cfg(feature = "schemars")]
+mod convert {
+    use super::AsSchemaValue;
+    use schemars::schema::{RootSchema, Schema, SchemaObject};
+    use serde::Serialize;
+    use serde_json::{Map, Value};
+    use std::borrow::Cow;
+    use std::error::Error;
+    use std::fmt;
+
+    /// Keywords introduced after draft-07, which schemars would keep as inert extensions.
+    const LATER_DRAFT_KEYWORDS: [&str; 13] = [
+        "$defs",
+        "$anchor",
+        "$dynamicRef",
+        "$dynamicAnchor",
+        "$recursiveRef",
+        "$recursiveAnchor",
+        "dependentRequired",
+        "dependentSchemas",
+        "prefixItems",
+        "unevaluatedItems",
+        "unevaluatedProperties",
+        "minContains",
+        "maxContains",
+    ];
+
+    /// Keywords whose value is a single subschema.
+    const SCHEMA_KEYWORDS: [&str; 9] = [
+        "items",
+        "additionalItems",
+        "additionalProperties",
+        "contains",
+        "propertyNames",
+        "not",
+        "if",
+        "then",
+        "else",
+    ];
+
+    /// A keyword [`to_schemars`] cannot carry over.
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+    pub struct Unconvertible {
+        /// The JSON Pointer of the schema node holding the keyword.
+        pub pointer: String,
+        /// The keyword, such as `dependentRequired`.
+        pub keyword: String,
+        /// What would be lost.
+        pub message: String,
+    }
+
+    impl fmt::Display for Unconvertible {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let at = if self.pointer.is_empty() {
+                "/"
+            } else {
+                &self.pointer
+            };
+            write!(f, "{} [{}]: {}", at, self.keyword, self.message)
+        }
+    }
+
+    /// Errors returned by [`to_schemars`].
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum ConvertError {
+        /// The schema uses keywords schemars cannot represent.
+        Unsupported(Vec<Unconvertible>),
+        /// The schema is not an object, or a keyword doesn't have the draft-07 shape,
+        /// such as a boolean `exclusiveMinimum`.
+        Invalid(String),
+    }
+
+    impl fmt::Display for ConvertError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                ConvertError::Unsupported(keywords) => write!(
+                    f,
+                    "{} keyword(s) cannot be represented in schemars",
+                    keywords.len()
+                ),
+                ConvertError::Invalid(message) => write!(f, "invalid schema: {}", message),
+            }
+        }
+    }
+
+    impl Error for ConvertError {}
+
+    /// Converts `schema` into a schemars `RootSchema`.
+    ///
+    /// Keywords of drafts after draft-07, such as `dependentRequired` or `$defs`, are
+    /// reported as [`ConvertError::Unsupported`]. Other unknown keywords are kept in the
+    /// `extensions` of their schema object. schemars stores numeric bounds as floats, so
+    /// `"minimum": 1` converts back as `"minimum": 1.0`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use serde_json::json;
+    /// use json_schema_generator::{from_schemars, generate_json_schema, to_schemars};
+    ///
+    /// let schema = generate_json_schema(&json!({"name": "John", "tags": ["a"]}));
+    /// let root = to_schemars(&schema).unwrap();
+    /// assert!(root.schema.object.as_ref().unwrap().properties.contains_key("tags"));
+    /// assert_eq!(from_schemars(&root), schema);
+    /// ```
+    pub fn to_schemars(schema: &Value) -> Result<RootSchema, ConvertError> {
+        if !schema.is_object() {
+            return Err(ConvertError::Invalid(
+                "the root schema must be an object".to_string(),
+            ));
+        }
+        let mut unconvertible = Vec::new();
+        check(schema, "", &mut unconvertible);
+        if !unconvertible.is_empty() {
+            return Err(ConvertError::Unsupported(unconvertible));
+        }
+        serde_json::from_value(schema.clone()).map_err(|e| ConvertError::Invalid(e.to_string()))
+    }
+
+    /// Converts a schemars `RootSchema` into a JSON schema.
+    pub fn from_schemars(schema: &RootSchema) -> Value {
+        to_value(schema)
+    }
+
+    fn to_value(schema: &impl Serialize) -> Value {
+        serde_json::to_value(schema).expect("schemars schemas serialize to JSON")
+    }
+
+    /// Collects the later-draft keywords of `schema` and its subschemas.
+    fn check(schema: &Value, pointer: &str, unconvertible: &mut Vec<Unconvertible>) {
+        let Some(obj) = schema.as_object() else {
+            return;
+        };
+        for (keyword, value) in obj {
+            let child = format!("{}/{}", pointer, escape(keyword));
+            if LATER_DRAFT_KEYWORDS.contains(&keyword.as_str()) {
+                unconvertible.push(Unconvertible {
+                    pointer: pointer.to_string(),
+                    keyword: keyword.clone(),
+                    message: format!(
+                        "`{}` is not part of draft-07, so schemars would not apply it",
+                        keyword
+                    ),
+                });
+            } else if SCHEMA_KEYWORDS.contains(&keyword.as_str()) {
+                match value {
+                    Value::Array(items) => {
+                        check_all(items.iter().enumerate(), &child, unconvertible)
+                    }
+                    _ => check(value, &child, unconvertible),
+                }
+            } else if matches!(keyword.as_str(), "allOf" | "anyOf" | "oneOf") {
+                if let Some(branches) = value.as_array() {
+                    check_all(branches.iter().enumerate(), &child, unconvertible);
+                }
+            } else if matches!(
+                keyword.as_str(),
+                "properties" | "patternProperties" | "definitions" | "dependencies"
+            ) {
+                if let Some(schemas) = value.as_object() {
+                    check_map(schemas, &child, unconvertible);
+                }
+            }
+        }
+    }
+
+    fn check_all<'a>(
+        schemas: impl Iterator<Item = (usize, &'a Value)>,
+        pointer: &str,
+        unconvertible: &mut Vec<Unconvertible>,
+    ) {
+        for (index, schema) in schemas {
+            check(schema, &format!("{}/{}", pointer, index), unconvertible);
+        }
+    }
+
+    fn check_map(
+        schemas: &Map<String, Value>,
+        pointer: &str,
+        unconvertible: &mut Vec<Unconvertible>,
+    ) {
+        for (name, schema) in schemas {
+            check(
+                schema,
+                &format!("{}/{}", pointer, escape(name)),
+                unconvertible,
+            );
+        }
+    }
+
+    fn escape(segment: &str) -> String {
+        segment.replace('~', "~0").replace('/', "~1")
+    }
+
+    impl AsSchemaValue for RootSchema {
+        fn as_schema_value(&self) -> Cow<'_, Value> {
+            Cow::Owned(to_value(self))
+        }
+    }
+
+    impl AsSchemaValue for Schema {
+        fn as_schema_value(&self) -> Cow<'_, Value> {
+            Cow::Owned(to_value(self))
+        }
+    }
+
+    impl AsSchemaValue for SchemaObject {
+        fn as_schema_value(&self) -> Cow<'_, Value> {
+            Cow::Owned(to_value(self))
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::{generate_json_schema, to_canonical_string};
+        use schemars::{schema_for, JsonSchema};
+        use serde_json::json;
+
+        /// An account, as a service would derive its schema.
+        #[allow(dead_code)]
+        #[derive(JsonSchema)]
+        struct Account {
+            id: u64,
+            /// The display name.
+            name: String,
+            email: Option<String>,
+            roles: Vec<Role>,
+            address: Address,
+        }
+
+        #[allow(dead_code)]
+        #[derive(JsonSchema)]
+        enum Role {
+            Admin,
+            User,
+        }
+
+        #[allow(dead_code)]
+        #[derive(JsonSchema)]
+        struct Address {
+            city: String,
+            lines: Vec<String>,
+        }
+
+        #[test]
+        fn test_inferred_schema_round_trip() {
+            let inputs = [
+                json!({"name": "John", "age": 30, "tags": ["a", 1, null]}),
+                json!([{"a": {"b": [true]}}, {"a": {"c": 2.5}}]),
+                json!({"$ref": "#/definitions/x", "y": []}),
+            ];
+            for input in inputs {
+                let schema = generate_json_schema(&input);
+                let root = to_schemars(&schema).unwrap();
+                assert_eq!(from_schemars(&root), schema, "input: {}", input);
+            }
+        }
+
+        #[test]
+        fn test_derived_schema_round_trip() {
+            let root = schema_for!(Account);
+            let schema = from_schemars(&root);
+            assert_eq!(
+                schema["definitions"]["Role"]["enum"],
+                json!(["Admin", "User"])
+            );
+            assert_eq!(to_schemars(&schema).unwrap(), root);
+        }
+
+        #[test]
+        fn test_unknown_keywords_are_extensions() {
+            let schema = json!({"type": "string", "$comment": "kept", "x-origin": {"a": 1}});
+            let root = to_schemars(&schema).unwrap();
+            assert_eq!(root.schema.extensions["$comment"], json!("kept"));
+            assert_eq!(from_schemars(&root), schema);
+        }
+
+        #[test]
+        fn test_later_draft_keywords_are_reported() {
+            let schema = json!({
+                "type": "object",
+                "properties": {
+                    "a/b": {"type": "object", "dependentRequired": {"x": ["y"]}},
+                    "c": {"items": [{"unevaluatedProperties": false}]}
+                },
+                "$defs": {"d": {}}
+            });
+            let Err(ConvertError::Unsupported(keywords)) = to_schemars(&schema) else {
+                panic!("expected unsupported keywords");
+            };
+            let reported: Vec<String> = keywords.iter().map(|k| k.to_string()).collect();
+            assert_eq!(reported.len(), 3);
+            assert!(reported[0].starts_with("/ [$defs]"));
+            assert!(reported[1].starts_with("/properties/a~1b [dependentRequired]"));
+            assert!(reported[2].starts_with("/properties/c/items/0 [unevaluatedProperties]"));
+        }
+
+        #[test]
+        fn test_invalid_schemas() {
+            assert!(matches!(
+                to_schemars(&json!(true)),
+                Err(ConvertError::Invalid(_))
+            ));
+            assert!(matches!(
+                to_schemars(&json!({"minimum": 0, "exclusiveMinimum": true})),
+                Err(ConvertError::Invalid(_))
+            ));
+        }
+
+        #[test]
+        fn test_normalize_either_representation() {
+            let root = schema_for!(Account);
+            let schema = from_schemars(&root);
+            assert_eq!(to_canonical_string(&root), to_canonical_string(&schema));
+            #[cfg(feature = "hash")]
+            assert_eq!(
+                crate::canonical_hash(&root.schema),
+                crate::canonical_hash(&to_value(&root.schema))
+            );
+        }
+    }
+}
@@ -1,267 +1,4246 @@
 //! This module provides functionality to generate JSON schemas from JSON instances.
 //! It supports various JSON types including objects, arrays, strings, numbers, booleans, and null values.
 
-use serde_json::{json, Map, Value};
-
-/// Generates a JSON schema for a given JSON instance.
-///
-/// # Arguments
+/// Emits a `tracing` event when the `tracing` feature is enabled.
 ///
-/// * `instance` - A reference to a `serde_json::Value` representing the JSON instance.
+/// With the feature disabled the macro expands to nothing, so its arguments are never
+/// evaluated and the generator carries no logging overhead.
+macro_rules! trace {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::debug!($($arg)*);
+    };
+}
+
+mod access;
+mod anchors;
+mod annotations;
+mod bounds;
+mod bundle;
+mod canonical;
+mod casing;
+mod codegen;
+mod compat;
+mod coverage;
+mod csv;
+mod defaults;
+mod dependencies;
+mod discriminator;
+mod draft;
+#[cfg(any(feature = "wasm", feature = "ffi", feature = "server"))]
+mod embed;
+mod enums;
+mod examples;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod format_inference;
+mod geo;
+mod interop;
+mod limits;
+mod lint;
+mod patch;
+#[cfg(feature = "patterns")]
+mod patterns;
+mod provenance;
+// The validation test suites run natively only, as their dev-dependencies need an OS
+// random source.
+#[cfg(any(feature = "validation", all(test, not(target_arch = "wasm32"))))]
+mod meta;
+mod ndjson;
+mod raw;
+mod required;
+#[cfg(feature = "server")]
+pub mod server;
+mod shared;
+mod stats;
+#[cfg(feature = "toml")]
+mod toml;
+mod tuples;
+mod unique;
+#[cfg(any(feature = "validation", all(test, not(target_arch = "wasm32"))))]
+mod validation;
+#[cfg(feature = "wasm")]
+mod wasm;
+#[cfg(feature = "yaml")]
+mod yaml;
+
+pub use access::{Access, AccessRule};
+pub use anchors::Anchors;
+pub use bounds::StringLengths;
+#[cfg(not(target_arch = "wasm32"))]
+pub use bundle::FileResolver;
+pub use bundle::{bundle_schema, BundleError, BundleMode, RefResolver};
+#[cfg(feature = "hash")]
+pub use canonical::canonical_hash;
+pub use canonical::to_canonical_string;
+pub use codegen::{generate_rust_types, generate_typescript, CodegenOptions};
+pub use compat::{
+    check_compatibility, is_backward_compatible, is_forward_compatible, CompatMode, CompatReport,
+    CompatRule, CompatViolation,
+};
+pub use coverage::{
+    coverage_report, CoverageEntry, CoverageReport, CoverageStatus, KeywordCoverage,
+};
+pub use csv::{csv_records, generate_schema_from_csv, CsvOptions, CsvRecords, EmptyCells};
+pub use defaults::{DefaultConflict, TypeDefaults};
+pub use dependencies::DependentRequired;
+pub use discriminator::{DiscriminatorStyle, Discriminators};
+pub use draft::Draft;
+pub use enums::EnumInference;
+pub use format_inference::infer_format;
+pub use geo::Coordinates;
+pub use interop::AsSchemaValue;
+#[cfg(feature = "schemars")]
+pub use interop::{from_schemars, to_schemars, ConvertError, Unconvertible};
+pub use limits::LimitPolicy;
+pub use lint::{
+    lint_schema, LintWarning, DEEP_REQUIRED_DEPTH, DEEP_REQUIRED_THRESHOLD, MAP_PROPERTY_THRESHOLD,
+    MAX_UNION_BRANCHES,
+};
+#[cfg(feature = "validation")]
+pub use meta::check_schema;
+pub use ndjson::{generate_schema_from_ndjson, try_generate_schema_from_ndjson};
+pub use patch::{normalize_schema, schema_patch, PatchOp};
+#[cfg(feature = "patterns")]
+pub use patterns::{PatternError, Patterns, StringPattern, BUILTIN_PATTERNS};
+pub use provenance::ProvenanceMode;
+pub use raw::{generate_from_raw, generate_json_schema_from_reader, generate_json_schema_from_str};
+pub use shared::{deduplicate_defs, SharedDefinitions};
+pub use stats::{schema_stats, SchemaStats};
+#[cfg(feature = "toml")]
+pub use toml::{generate_json_schema_from_toml, toml_to_json, try_generate_json_schema_from_toml};
+pub use tuples::Tuples;
+#[cfg(feature = "validation")]
+pub use validation::{
+    compile_schema, compile_schema_with_draft, generate_and_verify, validate_instance,
+    CompiledSchema, GenerationOutcome, ValidationIssue, VerificationFailure,
+};
+#[cfg(feature = "wasm")]
+pub use wasm::generate_schema;
+#[cfg(feature = "yaml")]
+pub use yaml::schema_to_yaml_string;
+
+use defaults::Observed;
+use serde::Serialize;
+use serde_json::value::RawValue;
+use serde_json::{json, Map, Number, Value};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// How many array elements are processed between two checks of the cancellation flag.
+const CANCEL_CHECK_INTERVAL: usize = 1024;
+
+/// The default [`SchemaOptions::max_depth`]. It is well below the 128 levels serde_json
+/// parses, so that [`generate_from_raw`] reaches it before the parser gives up.
+pub const DEFAULT_MAX_DEPTH: usize = 64;
+
+/// The default [`SchemaOptions::max_properties`].
+pub const DEFAULT_MAX_PROPERTIES: usize = 10_000;
+
+/// The default [`SchemaOptions::examples_max_bytes`].
+pub const DEFAULT_EXAMPLES_MAX_BYTES: usize = 4096;
+
+/// A segment of a [`SchemaPath`].
+#[derive(Clone, Copy)]
+enum Segment<'a> {
+    Property(&'a str),
+    Items,
+}
+
+/// The location of a schema node within the generated schema.
 ///
-/// # Returns
+/// Paths are borrowed chains living on the stack of the recursive generator, so tracking
+/// them is free until one is rendered as a JSON Pointer through its `Display` impl.
+#[derive(Clone, Copy)]
+struct SchemaPath<'a> {
+    parent: Option<&'a SchemaPath<'a>>,
+    segment: Option<Segment<'a>>,
+    /// The number of segments, which is how deep the instance value is nested.
+    depth: usize,
+}
+
+impl<'a> SchemaPath<'a> {
+    fn root() -> Self {
+        SchemaPath {
+            parent: None,
+            segment: None,
+            depth: 0,
+        }
+    }
+
+    fn property(&'a self, key: &'a str) -> Self {
+        SchemaPath {
+            parent: Some(self),
+            segment: Some(Segment::Property(key)),
+            depth: self.depth + 1,
+        }
+    }
+
+    fn items(&'a self) -> Self {
+        SchemaPath {
+            parent: Some(self),
+            segment: Some(Segment::Items),
+            depth: self.depth + 1,
+        }
+    }
+}
+
+impl fmt::Display for SchemaPath<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(parent) = self.parent {
+            parent.fmt(f)?;
+        }
+        match self.segment {
+            Some(Segment::Property(key)) => {
+                write!(
+                    f,
+                    "/properties/{}",
+                    key.replace('~', "~0").replace('/', "~1")
+                )
+            }
+            Some(Segment::Items) => f.write_str("/items"),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A callback receiving [`ProgressEvent`]s, see [`SchemaOptions::progress`].
+pub type ProgressCallback = Arc<dyn Fn(&ProgressEvent) + Send + Sync>;
+
+/// A function annotating each schema of the finished schema, given its JSON Pointer, see
+/// [`SchemaOptions::annotator`].
+pub type AnnotateHook = Arc<dyn Fn(&str, &mut Map<String, Value>) + Send + Sync>;
+
+/// A function rewriting the finished schema, see [`SchemaOptions::post_transform`].
+pub type TransformHook = Arc<dyn Fn(Value) -> Result<Value, String> + Send + Sync>;
+
+/// How the schemas of empty objects and arrays are written, see
+/// [`SchemaOptions::empty_containers`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EmptyContainers {
+    /// Write `"properties": {}` and `"required": []` for empty objects, and `"items": {}`
+    /// for arrays whose items are unconstrained, as [`generate_json_schema`] does.
+    #[default]
+    Keep,
+    /// Leave out empty `properties` and `required`, and unconstrained `items`.
+    Omit,
+    /// Write `"additionalProperties": true` instead of empty `properties` and
+    /// `required`, and `"items": true` instead of unconstrained `items`.
+    Explicit,
+}
+
+/// The keyword closing object schemas to properties they don't list, see
+/// [`SchemaOptions::closed_objects`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClosedWith {
+    /// `"additionalProperties": false` on every object schema, including the branches
+    /// of unions. It only sees the properties listed next to it, so it is placed on the
+    /// innermost object schemas.
+    AdditionalProperties,
+    /// `"unevaluatedProperties": false`, which sees the properties evaluated by every
+    /// subschema, on the outermost schema at each location: the union rather than its
+    /// branches. It needs draft 2019-09 or later.
+    UnevaluatedProperties,
+}
+
+impl ClosedWith {
+    /// Returns the keyword suited to `draft`: `unevaluatedProperties` from 2019-09 on.
+    pub fn for_draft(draft: Draft) -> Self {
+        match draft {
+            Draft::Draft4 | Draft::Draft6 | Draft::Draft7 => ClosedWith::AdditionalProperties,
+            Draft::Draft201909 | Draft::Draft202012 => ClosedWith::UnevaluatedProperties,
+        }
+    }
+}
+
+/// Options controlling schema generation. The defaults are those of
+/// [`generate_json_schema`].
 ///
-/// A `serde_json::Value` representing the JSON schema for the given instance.
+/// Every field is public, and the `with_*` methods set the common ones in a chain:
 ///
-/// # Examples
+/// ```
+/// use json_schema_generator::{MergePolicy, SchemaOptions};
 ///
+/// let options = SchemaOptions::default()
+///     .with_max_depth(32)
+///     .with_merge_policy(MergePolicy::Annotated);
+/// assert_eq!(options.max_depth, 32);
 /// ```
-/// use serde_json::json;
-/// use json_schema_generator::generate_json_schema;
+#[derive(Clone)]
+pub struct SchemaOptions {
+    /// A flag polled while generating. Once it is set, generation stops and returns
+    /// [`GeneratorError::Cancelled`]. It is checked for every object, every
+    /// 1024 array elements and every observed record.
+    pub cancel: Option<Arc<AtomicBool>>,
+
+    /// A callback reporting how far generation has got.
+    pub progress: Option<ProgressCallback>,
+
+    /// The minimum time between two progress callbacks. The last event of a run is
+    /// always reported regardless of this interval.
+    pub progress_interval: Duration,
+
+    /// How deeply objects and arrays may be nested. Deeper ones are described by the
+    /// schema `{}`, which accepts anything, and a `depth-limit` warning, so that
+    /// adversarial input can't overflow the stack. Defaults to [`DEFAULT_MAX_DEPTH`].
+    pub max_depth: usize,
+
+    /// How many properties an object schema tracks, counting those merged in from other
+    /// array items and records. Further properties are left out with a `property-limit`
+    /// warning; the schema still accepts them, as it allows additional properties.
+    /// Defaults to [`DEFAULT_MAX_PROPERTIES`].
+    pub max_properties: usize,
+
+    /// About how many bytes the serialized schema may take before any annotations are
+    /// added. Past it, the smallest subschema whose removal brings the schema back within
+    /// the limit is replaced by `{}`, with a `size-limit` warning. The size is estimated
+    /// from the schema nodes rather than by serializing, and a [`SchemaGenerator`] only
+    /// measures the whole schema once the records it merged could have exceeded the
+    /// limit. Unset by default.
+    pub max_schema_bytes: Option<usize>,
+
+    /// What happens once an input exceeds [`SchemaOptions::max_depth`],
+    /// [`SchemaOptions::max_properties`] or [`SchemaOptions::max_schema_bytes`]: the
+    /// schema degrades with a warning, or generation fails.
+    pub on_limit: LimitPolicy,
+
+    /// How the schemas of empty objects and arrays are written in the finished schema.
+    /// Merging treats all of the forms alike.
+    pub empty_containers: EmptyContainers,
+
+    /// Infer which optional properties always appear together, writing them as
+    /// `dependentRequired` (or `dependencies`, depending on the draft). Unset by default.
+    pub dependent_required: Option<DependentRequired>,
+
+    /// Infer unions of objects told apart by the value of a string property, such as a
+    /// `type`, when every other property is seen with a single value of it or with all of
+    /// them. Objects without a clean partition are merged as usual. Unset by default.
+    pub discriminators: Option<Discriminators>,
+
+    /// Name each definition hoisted into the root definitions, such as the variants of an
+    /// OpenAPI discriminated union, by an anchor, and optionally refer to it by that.
+    /// Unset by default.
+    pub anchors: Option<Anchors>,
+
+    /// Hoist object schemas that appear identical several times, such as an address
+    /// under both `billing` and `shipping`, into the root definitions, named after the
+    /// property they appear under most, and refer to them by `$ref`. Unset by default.
+    pub shared_definitions: Option<SharedDefinitions>,
+
+    /// Warn with `case-collision` about property names of the objects at one location
+    /// that differ only in casing, such as `userId` and `user_id`, with how often each
+    /// was seen. Off by default.
+    pub casing_report: bool,
+
+    /// Fold property names that differ only in casing into the one seen most often,
+    /// merging their schemas. The folded names are no longer accepted where objects are
+    /// closed, so this is off by default.
+    pub merge_case_variants: bool,
+
+    /// Keywords added to every schema of a type, such as `"minLength": 1` on strings,
+    /// unless a sample contradicts them. Empty by default.
+    pub type_defaults: TypeDefaults,
+
+    /// Write the `format` of strings whose content has one, such as `date-time` or
+    /// `email`, as [`infer_format`] finds it. A merge keeps it only where every value
+    /// has it. Off by default.
+    pub infer_formats: bool,
+
+    /// List the values of the strings at a location as an `enum` when they take only a
+    /// few, such as the status of an event. Unset by default.
+    pub enums: Option<EnumInference>,
+
+    /// Write short arrays of differently typed elements, such as
+    /// `["GET", "/api/users", 200]`, as tuples with a schema for each position. Unset by
+    /// default.
+    pub tuples: Option<Tuples>,
+
+    /// Write the lowest and highest of the numbers seen at a location as its `minimum`
+    /// and `maximum`, widening them as samples are merged. Off by default, as the
+    /// samples seldom reach the limits of a field.
+    pub numeric_bounds: bool,
+
+    /// With [`numeric_bounds`](Self::numeric_bounds), write `"minimum": 0` in place of
+    /// the lowest number seen at a location where the integers seen were never negative,
+    /// rather than tie counts and ids to the smallest one sampled. Off by default.
+    pub non_negative_minimum: bool,
+
+    /// Write the shortest and longest lengths of the strings seen at a location, in
+    /// Unicode characters, as its `minLength` and `maxLength`, widening them as samples
+    /// are merged. Unset by default.
+    pub string_lengths: Option<StringLengths>,
+
+    /// Write the fewest and most elements of the arrays seen at a location as its
+    /// `minItems` and `maxItems`, widening them as samples are merged. Off by default.
+    pub array_lengths: bool,
+
+    /// Write `"uniqueItems": true` for the arrays at a location whose elements were all
+    /// distinct, as JSON Schema compares them, in every array seen there, where at least
+    /// one of them had two elements. Off by default.
+    /// [`generate_from_raw`] then builds the elements of arrays to compare them.
+    pub unique_items: bool,
+
+    /// Bound numbers named like coordinates, such as `lat` and `lng`, to the ranges of
+    /// latitudes and longitudes when both appear in an object and are in range. Unset by
+    /// default.
+    pub coordinates: Option<Coordinates>,
+
+    /// Reject properties the samples didn't have, closing object schemas with the
+    /// keyword given, which [`ClosedWith::for_draft`] picks for a draft. Objects that had
+    /// properties left out by [`SchemaOptions::max_properties`] stay open. Unset by
+    /// default, leaving objects open.
+    pub closed_objects: Option<ClosedWith>,
+
+    /// Mark properties as `readOnly` or `writeOnly` by their names, following
+    /// [`SchemaOptions::access_rules`]. Off by default.
+    pub annotate_access: bool,
+
+    /// The rules applied with [`SchemaOptions::annotate_access`], at any depth. Defaults
+    /// to [`AccessRule::defaults`].
+    pub access_rules: Vec<AccessRule>,
+
+    /// Record at the root of the schema which version of this tool generated it, from
+    /// how many samples, with which options and when. Off by default.
+    pub provenance: ProvenanceMode,
+
+    /// Leave the timestamp out of the provenance, so that reruns produce identical
+    /// schemas.
+    pub reproducible: bool,
+
+    /// How many of the samples to embed under the root `examples` of the schema,
+    /// preferring samples with properties the others lack. Their long strings and arrays
+    /// are cut short and the values of properties [`SchemaOptions::access_rules`] marks
+    /// write-only are redacted, whether or not [`SchemaOptions::annotate_access`] is set.
+    /// None by default.
+    pub root_examples: usize,
+
+    /// About how many bytes the [`SchemaOptions::root_examples`] may take together. They
+    /// are trimmed harder, then left out from the last, until they fit. Defaults to
+    /// [`DEFAULT_EXAMPLES_MAX_BYTES`].
+    pub examples_max_bytes: usize,
+
+    /// Whether merging may widen the schema. [`Strictness::Strict`] fails with
+    /// [`GeneratorError::Conflict`] instead, for generating contracts.
+    pub strictness: Strictness,
+
+    /// How merging treats keywords the merged schemas disagree on: dropping them, keeping
+    /// the schemas apart or dropping them while recording why.
+    /// [`MergePolicy::Permissive`] by default.
+    pub merge_policy: MergePolicy,
+
+    /// The draft to write the schema for: its meta-schema URI as `$schema`, the hoisted
+    /// definitions under `definitions` up to draft-07 and `$defs` from 2019-09 on, the
+    /// keywords of [`DependentRequired`] as well as the anchors for that draft, whatever
+    /// their own `draft`, and only the [inferred formats](SchemaOptions::infer_formats)
+    /// it defines. Unset by default, which writes the draft-07 URI and `definitions`.
+    pub draft: Option<Draft>,
+
+    /// Write `$schema` on the object schemas nested in arrays and unions as well as at
+    /// the root, as this crate always has. On by default.
+    pub nested_dialect: bool,
+
+    /// Leave `required` out of every object schema, so that any property may be
+    /// missing. Off by default.
+    pub all_optional: bool,
+
+    /// The share of the objects at a location, between 0 and 1, that must have a
+    /// property for it to be required. Below 1, the schema rejects the samples lacking a
+    /// property most others have. Defaults to 1, requiring the properties every object
+    /// has.
+    pub required_threshold: f64,
+
+    /// The keyword of the unions formed where samples have different types.
+    /// [`UnionKeyword::OneOf`] by default.
+    pub union_keyword: UnionKeyword,
+
+    /// Write the union of a string, integer, number or boolean schema with null as the
+    /// one schema of a type array, such as `{"type": ["string", "null"]}`, rather than
+    /// a `oneOf` of both. On by default; some tools only read a single `type`.
+    pub nullable_type_arrays: bool,
+
+    /// Write a `pattern` for strings of a recurring shape, such as semantic versions,
+    /// when every value of a property has it. Unset by default.
+    #[cfg(feature = "patterns")]
+    pub patterns: Option<Patterns>,
+
+    /// A function applying house rules to the finished schema, such as a copyright
+    /// `$comment`, once definitions are extracted and objects closed. The schema it
+    /// returns is what the fallible entry points return, still serialized in key order;
+    /// an error fails them with [`GeneratorError::Transform`].
+    /// [`SchemaGenerator::schema`] doesn't apply it. Unset by default.
+    pub post_transform: Option<TransformHook>,
+
+    /// A function called with each schema object of the finished schema and its location
+    /// in it as a JSON Pointer, such as `/properties/user/properties/email` or
+    /// `/items/oneOf/1`, to add a `title`, a `description` or an extension. It runs once
+    /// samples are merged, outside to inside, before shared definitions are hoisted.
+    /// Unset by default.
+    pub annotator: Option<AnnotateHook>,
+
+    /// Validate the source samples against the finished schema, failing with
+    /// [`GeneratorError::Verification`] if any is rejected. A [`SchemaGenerator`] keeps
+    /// a copy of every observed record to verify them in [`SchemaGenerator::finish`].
+    #[cfg(feature = "validation")]
+    pub verify: bool,
+
+    /// Compute the [`SchemaStats`] of the schema [`generate_and_verify`] returns, as its
+    /// [`GenerationOutcome::stats`]. Off by default.
+    #[cfg(feature = "validation")]
+    pub stats: bool,
+}
+
+impl Default for SchemaOptions {
+    fn default() -> Self {
+        SchemaOptions {
+            cancel: None,
+            progress: None,
+            progress_interval: Duration::from_millis(100),
+            max_depth: DEFAULT_MAX_DEPTH,
+            max_properties: DEFAULT_MAX_PROPERTIES,
+            max_schema_bytes: None,
+            on_limit: LimitPolicy::Degrade,
+            empty_containers: EmptyContainers::default(),
+            dependent_required: None,
+            discriminators: None,
+            anchors: None,
+            shared_definitions: None,
+            casing_report: false,
+            merge_case_variants: false,
+            type_defaults: TypeDefaults::default(),
+            infer_formats: false,
+            enums: None,
+            tuples: None,
+            numeric_bounds: false,
+            non_negative_minimum: false,
+            string_lengths: None,
+            array_lengths: false,
+            unique_items: false,
+            coordinates: None,
+            closed_objects: None,
+            annotate_access: false,
+            access_rules: AccessRule::defaults(),
+            provenance: ProvenanceMode::Off,
+            reproducible: false,
+            root_examples: 0,
+            examples_max_bytes: DEFAULT_EXAMPLES_MAX_BYTES,
+            strictness: Strictness::Permissive,
+            merge_policy: MergePolicy::Permissive,
+            draft: None,
+            nested_dialect: true,
+            all_optional: false,
+            required_threshold: 1.0,
+            union_keyword: UnionKeyword::OneOf,
+            nullable_type_arrays: true,
+            #[cfg(feature = "patterns")]
+            patterns: None,
+            post_transform: None,
+            annotator: None,
+            #[cfg(feature = "validation")]
+            verify: false,
+            #[cfg(feature = "validation")]
+            stats: false,
+        }
+    }
+}
+
+impl SchemaOptions {
+    /// Returns the meta-schema URI written as `$schema`.
+    fn dialect(&self) -> &'static str {
+        self.draft.unwrap_or(Draft::Draft7).uri()
+    }
+
+    /// Sets [`SchemaOptions::max_depth`].
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Sets [`SchemaOptions::max_properties`].
+    pub fn with_max_properties(mut self, max_properties: usize) -> Self {
+        self.max_properties = max_properties;
+        self
+    }
+
+    /// Sets [`SchemaOptions::empty_containers`].
+    pub fn with_empty_containers(mut self, empty_containers: EmptyContainers) -> Self {
+        self.empty_containers = empty_containers;
+        self
+    }
+
+    /// Closes object schemas with the keyword `closed_with`, see
+    /// [`SchemaOptions::closed_objects`].
+    pub fn with_closed_objects(mut self, closed_with: ClosedWith) -> Self {
+        self.closed_objects = Some(closed_with);
+        self
+    }
+
+    /// Sets [`SchemaOptions::type_defaults`].
+    pub fn with_type_defaults(mut self, type_defaults: TypeDefaults) -> Self {
+        self.type_defaults = type_defaults;
+        self
+    }
+
+    /// Sets [`SchemaOptions::infer_formats`].
+    pub fn with_infer_formats(mut self, infer_formats: bool) -> Self {
+        self.infer_formats = infer_formats;
+        self
+    }
+
+    /// Sets [`SchemaOptions::annotator`] to `annotator`.
+    pub fn with_annotator(
+        mut self,
+        annotator: impl Fn(&str, &mut Map<String, Value>) + Send + Sync + 'static,
+    ) -> Self {
+        self.annotator = Some(Arc::new(annotator));
+        self
+    }
+
+    /// Sets [`SchemaOptions::provenance`].
+    pub fn with_provenance(mut self, provenance: ProvenanceMode) -> Self {
+        self.provenance = provenance;
+        self
+    }
+
+    /// Sets [`SchemaOptions::strictness`].
+    pub fn with_strictness(mut self, strictness: Strictness) -> Self {
+        self.strictness = strictness;
+        self
+    }
+
+    /// Sets [`SchemaOptions::merge_policy`].
+    pub fn with_merge_policy(mut self, merge_policy: MergePolicy) -> Self {
+        self.merge_policy = merge_policy;
+        self
+    }
+
+    /// Sets [`SchemaOptions::draft`].
+    pub fn with_draft(mut self, draft: Draft) -> Self {
+        self.draft = Some(draft);
+        self
+    }
+
+    /// Sets [`SchemaOptions::nested_dialect`].
+    pub fn with_nested_dialect(mut self, nested_dialect: bool) -> Self {
+        self.nested_dialect = nested_dialect;
+        self
+    }
+
+    /// Sets [`SchemaOptions::all_optional`].
+    pub fn with_all_optional(mut self, all_optional: bool) -> Self {
+        self.all_optional = all_optional;
+        self
+    }
+
+    /// Requires the properties the samples have, or with `false` none of them, clearing
+    /// or setting [`SchemaOptions::all_optional`].
+    pub fn with_required(mut self, required: bool) -> Self {
+        self.all_optional = !required;
+        self
+    }
+
+    /// Sets [`SchemaOptions::union_keyword`].
+    pub fn with_union_keyword(mut self, union_keyword: UnionKeyword) -> Self {
+        self.union_keyword = union_keyword;
+        self
+    }
+
+    /// Sets [`SchemaOptions::nullable_type_arrays`].
+    pub fn with_nullable_type_arrays(mut self, nullable_type_arrays: bool) -> Self {
+        self.nullable_type_arrays = nullable_type_arrays;
+        self
+    }
+}
+
+/// Another name for [`SchemaOptions`], as [`generate_json_schema_with_options`] takes
+/// them. [`generate_json_schema`] uses the defaults.
+pub type GeneratorOptions = SchemaOptions;
+
+impl fmt::Debug for SchemaOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("SchemaOptions");
+        debug
+            .field("cancel", &self.cancel)
+            .field("progress", &self.progress.as_ref().map(|_| "<callback>"))
+            .field("progress_interval", &self.progress_interval)
+            .field("max_depth", &self.max_depth)
+            .field("max_properties", &self.max_properties)
+            .field("max_schema_bytes", &self.max_schema_bytes)
+            .field("on_limit", &self.on_limit)
+            .field("empty_containers", &self.empty_containers)
+            .field("dependent_required", &self.dependent_required)
+            .field("discriminators", &self.discriminators)
+            .field("anchors", &self.anchors)
+            .field("shared_definitions", &self.shared_definitions)
+            .field("casing_report", &self.casing_report)
+            .field("merge_case_variants", &self.merge_case_variants)
+            .field("type_defaults", &self.type_defaults)
+            .field("infer_formats", &self.infer_formats)
+            .field("enums", &self.enums)
+            .field("tuples", &self.tuples)
+            .field("numeric_bounds", &self.numeric_bounds)
+            .field("non_negative_minimum", &self.non_negative_minimum)
+            .field("string_lengths", &self.string_lengths)
+            .field("array_lengths", &self.array_lengths)
+            .field("unique_items", &self.unique_items)
+            .field("coordinates", &self.coordinates)
+            .field("closed_objects", &self.closed_objects)
+            .field("annotate_access", &self.annotate_access)
+            .field("access_rules", &self.access_rules)
+            .field("provenance", &self.provenance)
+            .field("reproducible", &self.reproducible)
+            .field("root_examples", &self.root_examples)
+            .field("examples_max_bytes", &self.examples_max_bytes)
+            .field("strictness", &self.strictness)
+            .field("merge_policy", &self.merge_policy)
+            .field("draft", &self.draft)
+            .field("nested_dialect", &self.nested_dialect)
+            .field("all_optional", &self.all_optional)
+            .field("required_threshold", &self.required_threshold)
+            .field("union_keyword", &self.union_keyword)
+            .field("nullable_type_arrays", &self.nullable_type_arrays);
+        #[cfg(feature = "patterns")]
+        debug.field("patterns", &self.patterns);
+        debug.field(
+            "post_transform",
+            &self.post_transform.as_ref().map(|_| "<callback>"),
+        );
+        debug.field("annotator", &self.annotator.as_ref().map(|_| "<callback>"));
+        #[cfg(feature = "validation")]
+        debug.field("verify", &self.verify);
+        #[cfg(feature = "validation")]
+        debug.field("stats", &self.stats);
+        debug.finish()
+    }
+}
+
+/// A snapshot of generation progress passed to [`SchemaOptions::progress`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProgressEvent {
+    /// The number of records fully observed so far.
+    pub records: usize,
+    /// The number of JSON values processed so far, across all records.
+    pub nodes: u64,
+    /// The JSON Pointer of the array being processed, if any.
+    pub pointer: Option<String>,
+    /// The number of elements processed so far in the array at `pointer`.
+    pub array_elements: usize,
+    /// The number of input bytes consumed, for entry points reading from a byte source.
+    pub bytes: Option<u64>,
+}
+
+/// Something noteworthy that happened during generation, such as input the generated
+/// schema may not describe well.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Warning {
+    /// A stable identifier for the kind of warning.
+    pub code: String,
+    /// The JSON Pointer of the schema node the warning concerns.
+    pub pointer: String,
+    /// A human-readable description.
+    pub message: String,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let at = if self.pointer.is_empty() {
+            "/"
+        } else {
+            &self.pointer
+        };
+        write!(f, "{} [{}]: {}", at, self.code, self.message)
+    }
+}
+
+/// Whether merging may widen schemas, see [`SchemaOptions::strictness`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Strictness {
+    /// Widen schemas as the samples need: into unions, optional properties and fewer
+    /// formats.
+    #[default]
+    Permissive,
+    /// Raise a [`Conflict`] wherever a merge widens a schema that way, failing with
+    /// [`GeneratorError::Conflict`].
+    Strict,
+}
+
+/// How merging treats the keywords beside the type, properties and items of the merged
+/// schemas, such as a `pattern` or the bounds of coordinates, see
+/// [`SchemaOptions::merge_policy`].
 ///
-/// let instance = json!({"name": "John", "age": 30});
-/// let schema = generate_json_schema(&instance);
+/// A keyword both schemas share with the same value is kept whatever the policy, as are
+/// the `readOnly` and `writeOnly` flags of either.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MergePolicy {
+    /// Drop the keywords the schemas disagree on, widening the merged schema.
+    #[default]
+    Permissive,
+    /// Keep schemas of the same type that disagree on a keyword apart, as branches of an
+    /// `anyOf` of that type, so that no constraint is dropped. Integers and numbers count
+    /// as the same type. Differing properties still merge, no longer being required.
+    Conservative,
+    /// Merge as [`MergePolicy::Permissive`] does, recording a [`Widening`] for each
+    /// keyword dropped, union formed and property no longer required. Each is also
+    /// raised as a `widening` [`Warning`].
+    Annotated,
+}
+
+impl fmt::Display for MergePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            MergePolicy::Permissive => "permissive",
+            MergePolicy::Conservative => "conservative",
+            MergePolicy::Annotated => "annotated",
+        })
+    }
+}
+
+impl std::str::FromStr for MergePolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "permissive" => Ok(MergePolicy::Permissive),
+            "conservative" => Ok(MergePolicy::Conservative),
+            "annotated" => Ok(MergePolicy::Annotated),
+            _ => Err(format!(
+                "unknown merge policy '{}', expected one of permissive, conservative or annotated",
+                s
+            )),
+        }
+    }
+}
+
+/// The keyword of the unions of differently typed schemas, see
+/// [`SchemaOptions::union_keyword`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UnionKeyword {
+    /// `oneOf`. Its branches never overlap, which the merge ensures.
+    #[default]
+    OneOf,
+    /// `anyOf`, which some consumers handle better and validators check faster.
+    AnyOf,
+}
+
+impl fmt::Display for UnionKeyword {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            UnionKeyword::OneOf => "one-of",
+            UnionKeyword::AnyOf => "any-of",
+        })
+    }
+}
+
+impl std::str::FromStr for UnionKeyword {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "one-of" => Ok(UnionKeyword::OneOf),
+            "any-of" => Ok(UnionKeyword::AnyOf),
+            _ => Err(format!(
+                "unknown union keyword '{}', expected one-of or any-of",
+                s
+            )),
+        }
+    }
+}
+
+/// Why the schema got wider, recorded with [`MergePolicy::Annotated`] for the first
+/// record making each change.
 ///
-/// assert_eq!(schema, json!({
-/// "$schema": "http://json-schema.org/draft-07/schema#",
-///     "type": "object",
-///     "properties": {
-///         "name": {"type": "string"},
-///         "age": {"type": "integer"}
-///     },
-///     "required": ["age", "name"]
-/// }));
-/// ```
-pub fn generate_json_schema(instance: &Value) -> Value {
-    match instance {
-        Value::Object(_) => generate_object_schema(instance),
-        Value::Array(arr) => generate_array_schema(arr),
-        Value::String(_) => json!({"type": "string"}),
-        Value::Number(n) => {
-            if n.is_i64() {
-                json!({"type": "integer"})
-            } else {
-                json!({"type": "number"})
-            }
+/// The code is `type-union` where a type had to become one branch of a `oneOf`,
+/// `required-demotion` where properties are missing from some samples, `format-drop`
+/// and `pattern-drop` where a `format` or `pattern` was dropped, and `keyword-drop` where
+/// another keyword was.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Widening {
+    /// A stable identifier for the kind of widening.
+    pub code: String,
+    /// The JSON Pointer of the schema node that got wider.
+    pub pointer: String,
+    /// What no longer holds: the dropped keywords with their values, both values in an
+    /// array where the schemas disagreed, `{"required": [...]}` with the properties no
+    /// longer required, or `{"type": ...}` with the types the union was formed from.
+    pub dropped: Value,
+    /// The number of the record whose merge widened the schema, counting from 1.
+    pub record: usize,
+    /// A human-readable description.
+    pub message: String,
+}
+
+impl fmt::Display for Widening {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let at = if self.pointer.is_empty() {
+            "/"
+        } else {
+            &self.pointer
+        };
+        write!(
+            f,
+            "{} [{}]: record {}: {}",
+            at, self.code, self.record, self.message
+        )
+    }
+}
+
+/// A merge that had to widen the schema, raised with [`Strictness::Strict`].
+///
+/// The code is `type-union` where two types could only be merged into a `oneOf`,
+/// `required-demotion` where a property is missing from some samples, and `format-drop`
+/// or `pattern-drop` where a `format` or `pattern` holds for only some of them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Conflict {
+    /// A stable identifier for the kind of conflict.
+    pub code: String,
+    /// The JSON Pointer of the schema node the conflict concerns.
+    pub pointer: String,
+    /// A human-readable description.
+    pub message: String,
+    /// The schema merged so far.
+    pub left: Value,
+    /// The schema that could not be merged into it without widening.
+    pub right: Value,
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let at = if self.pointer.is_empty() {
+            "/"
+        } else {
+            &self.pointer
+        };
+        write!(f, "{} [{}]: {}", at, self.code, self.message)
+    }
+}
+
+impl From<Conflict> for GeneratorError {
+    fn from(conflict: Conflict) -> Self {
+        GeneratorError::Conflict {
+            path: conflict.pointer,
+            left: conflict.left,
+            right: conflict.right,
         }
-        Value::Bool(_) => json!({"type": "boolean"}),
-        Value::Null => json!({"type": "null"}),
     }
 }
 
-fn generate_object_schema(instance: &Value) -> Value {
-    let mut schema = json!({
-        "type": "object",
-        "properties": {},
-        "required": []
-    });
+/// Errors returned by the fallible generation entry points.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GeneratorError {
+    /// Generation was stopped through [`SchemaOptions::cancel`].
+    Cancelled,
+    /// The JSON input could not be read or isn't JSON, as `serde_json` reported it with
+    /// its line and column.
+    Json(JsonError),
+    /// The input in another format, such as CSV or NDJSON, could not be read.
+    InvalidInput(String),
+    /// With [`Strictness::Strict`], the first merge that had to widen the schema: at the
+    /// JSON Pointer `path`, `right` could not be merged into `left`. A
+    /// [`SchemaGenerator`] lists all of them in [`SchemaGenerator::conflicts`].
+    Conflict {
+        path: String,
+        left: Value,
+        right: Value,
+    },
+    /// With [`LimitPolicy::Fail`], the warning raised by the first limit the input
+    /// exceeded.
+    LimitExceeded(Warning),
+    /// [`SchemaOptions::post_transform`] failed with this message.
+    Transform(String),
+    /// Some source samples are rejected by the generated schema, see
+    /// [`SchemaOptions::verify`].
+    #[cfg(feature = "validation")]
+    Verification(Vec<VerificationFailure>),
+}
 
-    if let Value::Object(obj) = instance {
-        for (key, value) in obj {
-            if key == "$ref" {
-                schema["$ref"] = value.clone();
-            } else {
-                let mut sub_schema = generate_json_schema(value);
-                if let Some(obj) = sub_schema.as_object_mut() {
-                    obj.remove("$schema"); // Remove $schema from nested objects
-                }
-                schema["properties"][key] = sub_schema;
-                schema["required"]
-                    .as_array_mut()
-                    .unwrap()
-                    .push(Value::String(key.clone()));
-            }
+impl fmt::Display for GeneratorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GeneratorError::Cancelled => f.write_str("schema generation was cancelled"),
+            GeneratorError::Json(error) => write!(f, "invalid JSON input: {}", error),
+            GeneratorError::InvalidInput(message) => write!(f, "invalid JSON input: {}", message),
+            GeneratorError::Conflict { path, left, right } => write!(
+                f,
+                "conflicting schemas at {}: {} and {} can't be merged without widening",
+                if path.is_empty() { "/" } else { path },
+                left,
+                right
+            ),
+            GeneratorError::LimitExceeded(warning) => write!(f, "limit exceeded: {}", warning),
+            GeneratorError::Transform(message) => write!(f, "post-processing failed: {}", message),
+            #[cfg(feature = "validation")]
+            GeneratorError::Verification(failures) => write!(
+                f,
+                "{} sample(s) do not validate against the generated schema",
+                failures.len()
+            ),
         }
     }
+}
 
-    // Sort the "required" array
-    if let Some(required) = schema["required"].as_array_mut() {
-        required.sort_by(|a, b| a.as_str().unwrap().cmp(b.as_str().unwrap()));
+impl Error for GeneratorError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            GeneratorError::Json(error) => Some(&**error),
+            _ => None,
+        }
     }
+}
 
-    // Add $schema only to the top-level object
-    schema["$schema"] = json!("http://json-schema.org/draft-07/schema#");
+/// The error of `serde_json` for JSON input that could not be read, shared so that a
+/// [`GeneratorError`] stays cloneable. It dereferences to the [`serde_json::Error`], for
+/// its [`line`](serde_json::Error::line) and [`column`](serde_json::Error::column). Two
+/// errors are equal when they have the same category, position and message.
+#[derive(Debug, Clone)]
+pub struct JsonError(Arc<serde_json::Error>);
 
-    schema
+impl From<serde_json::Error> for JsonError {
+    fn from(error: serde_json::Error) -> Self {
+        JsonError(Arc::new(error))
+    }
 }
 
-fn generate_array_schema(arr: &Vec<Value>) -> Value {
-    if arr.is_empty() {
-        return json!({
-            "type": "array",
-            "items": {}
+impl std::ops::Deref for JsonError {
+    type Target = serde_json::Error;
+
+    fn deref(&self) -> &serde_json::Error {
+        &self.0
+    }
+}
+
+impl PartialEq for JsonError {
+    fn eq(&self, other: &Self) -> bool {
+        self.classify() == other.classify()
+            && (self.line(), self.column()) == (other.line(), other.column())
+            && self.to_string() == other.to_string()
+    }
+}
+
+impl Eq for JsonError {}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl From<serde_json::Error> for GeneratorError {
+    fn from(error: serde_json::Error) -> Self {
+        GeneratorError::Json(error.into())
+    }
+}
+
+/// State that outlives a single generation run, so that a [`SchemaGenerator`] reports
+/// totals and warnings across all of its records.
+#[derive(Debug, Clone, Default)]
+struct State {
+    records: usize,
+    nodes: u64,
+    bytes: Option<u64>,
+    last_report: Option<Instant>,
+    warnings: Vec<Warning>,
+    /// The codes and pointers of the limit warnings raised so far, each raised only once.
+    limits: HashSet<(&'static str, String)>,
+    /// The limit warnings raised with [`LimitPolicy::Fail`].
+    exceeded: Vec<Warning>,
+    /// An upper bound on the estimated size of the accumulated schema, tracked with
+    /// [`SchemaOptions::max_schema_bytes`].
+    schema_bytes: usize,
+    /// The presence of properties in the objects at each JSON Pointer, tracked with
+    /// [`SchemaOptions::dependent_required`].
+    presence: HashMap<String, dependencies::Presence>,
+    /// The values of each candidate discriminator in the objects at each JSON Pointer,
+    /// tracked with [`SchemaOptions::discriminators`].
+    candidates: HashMap<String, Vec<discriminator::Candidate>>,
+    /// The property names of the objects at each JSON Pointer, tracked with
+    /// [`SchemaOptions::casing_report`] or [`SchemaOptions::merge_case_variants`].
+    names: HashMap<String, casing::Names>,
+    /// How often the properties of the objects at each JSON Pointer appear, tracked with
+    /// a [`SchemaOptions::required_threshold`] below 1.
+    occurrences: HashMap<String, required::Occurrences>,
+    /// The values of the strings at each JSON Pointer, tracked with
+    /// [`SchemaOptions::enums`].
+    strings: HashMap<String, enums::Values>,
+    /// How many strings were seen at each JSON Pointer, tracked with
+    /// [`SchemaOptions::string_lengths`].
+    lengths: HashMap<String, usize>,
+    /// How many arrays of at least two elements were seen at each JSON Pointer, tracked
+    /// with [`SchemaOptions::unique_items`].
+    arrays: HashMap<String, usize>,
+    /// The records kept for [`SchemaOptions::root_examples`].
+    samples: examples::Samples,
+    /// The conflicts raised with [`Strictness::Strict`], at most one of each code at each
+    /// pointer.
+    conflicts: Vec<Conflict>,
+    /// The widenings recorded with [`MergePolicy::Annotated`].
+    widenings: Vec<Widening>,
+}
+
+/// Mutable state threaded through a single generation run.
+struct Context<'a> {
+    options: &'a SchemaOptions,
+    state: &'a mut State,
+    elements: usize,
+}
+
+impl<'a> Context<'a> {
+    fn new(options: &'a SchemaOptions, state: &'a mut State) -> Self {
+        Context {
+            options,
+            state,
+            elements: 0,
+        }
+    }
+
+    fn check_cancelled(&self) -> Result<(), GeneratorError> {
+        match &self.options.cancel {
+            Some(flag) if flag.load(Ordering::Relaxed) => Err(GeneratorError::Cancelled),
+            _ => Ok(()),
+        }
+    }
+
+    fn warn(&mut self, code: &str, path: &SchemaPath, message: String) {
+        trace!(pointer = %path, code, "{}", message);
+        self.state.warnings.push(Warning {
+            code: code.to_string(),
+            pointer: path.to_string(),
+            message,
         });
     }
 
-    let item_schemas: Vec<Value> = arr.iter().map(generate_json_schema).collect();
-    let common_schema = find_common_schema(&item_schemas);
+    /// Warns that the value at `path` is nested deeper than [`SchemaOptions::max_depth`].
+    fn limit_depth(&mut self, path: &SchemaPath) {
+        let message = format!(
+            "left values nested more than {} levels deep unconstrained",
+            self.options.max_depth
+        );
+        self.limit("depth-limit", path.to_string(), message);
+    }
+
+    /// Warns that the object schema at `path` has [`SchemaOptions::max_properties`]
+    /// properties and leaves out the rest.
+    fn limit_properties(&mut self, path: &SchemaPath) {
+        let message = format!(
+            "left out the properties beyond the first {}",
+            self.options.max_properties
+        );
+        self.limit("property-limit", path.to_string(), message);
+    }
+
+    /// Degrades `schema` to within [`SchemaOptions::max_schema_bytes`], or records that
+    /// it is too large with [`LimitPolicy::Fail`], and returns its estimated size, or 0
+    /// without a limit.
+    fn limit_size(&mut self, schema: &mut Value) -> usize {
+        let Some(limit) = self.options.max_schema_bytes else {
+            return 0;
+        };
+        let size = limits::estimate_bytes(schema);
+        if size <= limit {
+            return size;
+        }
+        match self.options.on_limit {
+            LimitPolicy::Degrade => {
+                let pointer = limits::collapse(schema, size, limit);
+                let message = limits::size_message(size, self.options, true);
+                self.limit("size-limit", pointer, message);
+                limits::estimate_bytes(schema)
+            }
+            LimitPolicy::Fail => {
+                let message = limits::size_message(size, self.options, false);
+                self.limit("size-limit", String::new(), message);
+                size
+            }
+        }
+    }
+
+    /// Raises a limit warning unless one with the same code was raised at `pointer`
+    /// before, as limits are typically hit by every item of an array.
+    fn limit(&mut self, code: &'static str, pointer: String, message: String) {
+        if !self.state.limits.insert((code, pointer.clone())) {
+            return;
+        }
+        trace!(pointer = %pointer, code, "{}", message);
+        let warning = Warning {
+            code: code.to_string(),
+            pointer,
+            message,
+        };
+        if self.options.on_limit == LimitPolicy::Fail {
+            self.state.exceeded.push(warning.clone());
+        }
+        self.state.warnings.push(warning);
+    }
+
+    /// Fails with the first limit exceeded after the first `seen`, with
+    /// [`LimitPolicy::Fail`].
+    fn check_limits(&self, seen: usize) -> Result<(), GeneratorError> {
+        match self.state.exceeded.get(seen) {
+            Some(warning) => Err(GeneratorError::LimitExceeded(warning.clone())),
+            None => Ok(()),
+        }
+    }
+
+    /// Records which properties the object at `path` has, for inferring dependencies and
+    /// discriminators and comparing casings. `value_of` returns the string value of a
+    /// property.
+    fn record_object<'v>(
+        &mut self,
+        path: &SchemaPath,
+        properties: &Map<String, Value>,
+        value_of: impl Fn(&str) -> Option<&'v str>,
+    ) {
+        if let Some(settings) = &self.options.dependent_required {
+            self.state
+                .presence
+                .entry(path.to_string())
+                .or_default()
+                .record(properties, settings.max_properties);
+        }
+        if let Some(settings) = &self.options.discriminators {
+            let candidates = self
+                .state
+                .candidates
+                .entry(path.to_string())
+                .or_insert_with(|| vec![Default::default(); settings.fields.len()]);
+            for (field, candidate) in settings.fields.iter().zip(candidates) {
+                let value = properties
+                    .contains_key(field)
+                    .then(|| value_of(field))
+                    .flatten();
+                candidate.record(value, properties, settings.max_variants);
+            }
+        }
+        if self.options.required_threshold < 1.0 {
+            self.state
+                .occurrences
+                .entry(path.to_string())
+                .or_default()
+                .record(properties);
+        }
+        if self.options.casing_report || self.options.merge_case_variants {
+            self.state
+                .names
+                .entry(path.to_string())
+                .or_default()
+                .record(properties);
+        }
+    }
+
+    fn strict(&self) -> bool {
+        self.options.strictness == Strictness::Strict
+    }
+
+    /// Whether merges watch for widening, to raise conflicts or record widenings.
+    fn watches_widening(&self) -> bool {
+        self.strict() || self.options.merge_policy == MergePolicy::Annotated
+    }
+
+    /// Raises that merging `right` into `left` at `path` widens the schema: as a conflict
+    /// with [`Strictness::Strict`], and with [`MergePolicy::Annotated`] as a widening
+    /// dropping `dropped`, unless it is `None` as `left` held none of it.
+    fn widen(
+        &mut self,
+        code: &str,
+        path: &SchemaPath,
+        (left, right): (&Value, &Value),
+        dropped: Option<Value>,
+        message: String,
+    ) {
+        let annotated = self.options.merge_policy == MergePolicy::Annotated;
+        let pointer = path.to_string();
+        let seen = |widening: &Widening| {
+            widening.code == code
+                && widening.pointer == pointer
+                && Some(&widening.dropped) == dropped.as_ref()
+        };
+        if annotated && self.state.widenings.iter().any(seen) {
+            return;
+        }
+        if let (true, Some(dropped)) = (annotated, dropped) {
+            let widening = Widening {
+                code: code.to_string(),
+                pointer,
+                dropped,
+                record: self.state.records + 1,
+                message: message.clone(),
+            };
+            self.warn(
+                "widening",
+                path,
+                format!("record {}: {}", widening.record, message),
+            );
+            self.state.widenings.push(widening);
+        }
+        if self.strict() && code != "keyword-drop" {
+            self.conflict(code, path, left, right, message);
+        }
+    }
+
+    /// Records that `right` could not be merged into `left` at `path` without widening,
+    /// unless a conflict with the same code was raised there before.
+    fn conflict(
+        &mut self,
+        code: &str,
+        path: &SchemaPath,
+        left: &Value,
+        right: &Value,
+        message: String,
+    ) {
+        let pointer = path.to_string();
+        let conflicts = &mut self.state.conflicts;
+        if conflicts
+            .iter()
+            .any(|conflict| conflict.code == code && conflict.pointer == pointer)
+        {
+            return;
+        }
+        trace!(pointer = %path, code, "{}", message);
+        conflicts.push(Conflict {
+            code: code.to_string(),
+            pointer,
+            message,
+            left: left.clone(),
+            right: right.clone(),
+        });
+    }
+
+    /// Fails with the first conflict raised after the first `seen`.
+    fn check_conflicts(&self, seen: usize) -> Result<(), GeneratorError> {
+        match self.state.conflicts.get(seen) {
+            Some(conflict) => Err(conflict.clone().into()),
+            None => Ok(()),
+        }
+    }
+
+    /// Marks the schema of the property `name` as [`SchemaOptions::access_rules`] say.
+    fn annotate_access(&self, name: &str, schema: &mut Value) {
+        if self.options.annotate_access {
+            access::annotate(name, schema, &self.options.access_rules);
+        }
+    }
+
+    fn count_node(&mut self) {
+        self.state.nodes += 1;
+    }
+
+    /// Bounds the schema `schema` of the number `value` at `path` if it is a coordinate,
+    /// see [`SchemaOptions::coordinates`].
+    fn number_schema(&self, mut schema: Value, n: &Number, path: &SchemaPath) -> Value {
+        let value = n.as_f64().unwrap_or(f64::NAN);
+        if let (Some(coordinates), Some(Segment::Property(name))) =
+            (&self.options.coordinates, path.segment)
+        {
+            coordinates.bound(name, value, &mut schema);
+        }
+        if self.options.numeric_bounds {
+            let lower = match self.options.non_negative_minimum && n.as_u64().is_some() {
+                true => Number::from(0),
+                false => n.clone(),
+            };
+            bounds::NUMBERS.record(&mut schema, lower, n.clone());
+        }
+        self.type_defaults(&mut schema, Observed::Number(value));
+        schema
+    }
+
+    /// Bounds the array schema `schema` by the number of its elements, `len`, see
+    /// [`SchemaOptions::array_lengths`].
+    fn count_items(&self, schema: &mut Value, len: usize) {
+        if self.options.array_lengths {
+            bounds::ITEMS.record(schema, Number::from(len), Number::from(len));
+        }
+    }
+
+    /// Marks the array schema `schema` at `path` unique if its elements, `distinct`, were,
+    /// see [`SchemaOptions::unique_items`].
+    fn unique_items(&mut self, schema: &mut Value, distinct: unique::Distinct, path: &SchemaPath) {
+        if !self.options.unique_items {
+            return;
+        }
+        let corroborating = self.state.arrays.entry(path.to_string()).or_default();
+        if distinct.corroborates() {
+            *corroborating += 1;
+        }
+        distinct.write(schema);
+    }
+
+    /// Adds the [`SchemaOptions::type_defaults`] of the type of `schema`, generated from
+    /// `observed`.
+    fn type_defaults(&self, schema: &mut Value, observed: Observed) {
+        self.options.type_defaults.apply(schema, observed);
+    }
+
+    /// Removes the bounds of coordinates that lack their pair from the properties of an
+    /// object.
+    fn pair_coordinates(&self, properties: &mut Map<String, Value>) {
+        if let Some(coordinates) = &self.options.coordinates {
+            coordinates.pair(properties);
+        }
+    }
+
+    /// Returns the schema of the string `value` at `path`.
+    fn string_schema(&mut self, value: &str, path: &SchemaPath) -> Value {
+        if let Some(settings) = &self.options.enums {
+            self.state
+                .strings
+                .entry(path.to_string())
+                .or_default()
+                .record(value, settings.max_values);
+        }
+        if self.options.string_lengths.is_some() {
+            *self.state.lengths.entry(path.to_string()).or_default() += 1;
+        }
+        #[cfg(feature = "patterns")]
+        let mut schema = match &self.options.patterns {
+            Some(patterns) => patterns.string_schema(value),
+            None => json!({"type": "string"}),
+        };
+        #[cfg(not(feature = "patterns"))]
+        let mut schema = json!({"type": "string"});
+        let format = self.options.infer_formats.then(|| infer_format(value));
+        // Formats the draft doesn't define would go unchecked.
+        if let Some(format) = format.flatten().filter(|format| {
+            let draft = self.options.draft;
+            draft.is_none_or(|draft| format_inference::defined_in(format, draft))
+        }) {
+            schema["format"] = json!(format);
+        }
+        if self.options.string_lengths.is_some() {
+            let length = value.chars().count();
+            bounds::LENGTHS.record(&mut schema, Number::from(length), Number::from(length));
+        }
+        self.type_defaults(&mut schema, Observed::String(value));
+        schema
+    }
+
+    /// Returns the schema of a boolean.
+    fn boolean_schema(&self) -> Value {
+        let mut schema = json!({"type": "boolean"});
+        self.type_defaults(&mut schema, Observed::Other);
+        schema
+    }
+
+    /// Counts the element at `index` of the array at `path`, checking the cancellation
+    /// flag and reporting progress every [`CANCEL_CHECK_INTERVAL`] elements.
+    fn count_element(&mut self, path: &SchemaPath, index: usize) -> Result<(), GeneratorError> {
+        self.elements += 1;
+        if !self.elements.is_multiple_of(CANCEL_CHECK_INTERVAL) {
+            return Ok(());
+        }
+        self.check_cancelled()?;
+        if self.options.progress.is_some() {
+            self.report(Some(path), index, false);
+        }
+        Ok(())
+    }
+
+    /// Calls the progress callback, unless `force` is unset and the previous call
+    /// happened less than [`SchemaOptions::progress_interval`] ago.
+    fn report(&mut self, path: Option<&SchemaPath>, array_elements: usize, force: bool) {
+        let Some(callback) = &self.options.progress else {
+            return;
+        };
+        let now = Instant::now();
+        let due = match self.state.last_report {
+            Some(last) => now.duration_since(last) >= self.options.progress_interval,
+            None => true,
+        };
+        if !due && !force {
+            return;
+        }
+        self.state.last_report = Some(now);
+        callback(&ProgressEvent {
+            records: self.state.records,
+            nodes: self.state.nodes,
+            pointer: path.map(|path| path.to_string()),
+            array_elements,
+            bytes: self.state.bytes,
+        });
+    }
+}
+
+/// Builds a single schema from a sequence of JSON records.
+///
+/// Each observed record is merged into the schema accumulated so far, the same way the
+/// items of an array are merged.
+///
+/// # Examples
+///
+/// ```
+/// use serde_json::json;
+/// use json_schema_generator::{SchemaGenerator, SchemaOptions};
+///
+/// let mut generator = SchemaGenerator::new(SchemaOptions::default());
+/// generator.observe(&json!("a")).unwrap();
+/// generator.observe(&json!("b")).unwrap();
+///
+/// assert_eq!(generator.schema(), json!({"type": "string"}));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SchemaGenerator {
+    options: SchemaOptions,
+    schema: Option<Value>,
+    /// Whether the schemas of the records had a `$schema`, which is moved to the root.
+    dialect: bool,
+    state: State,
+    #[cfg(feature = "validation")]
+    records: Vec<Value>,
+}
+
+impl SchemaGenerator {
+    /// Creates a generator that has not observed any records yet.
+    pub fn new(options: SchemaOptions) -> Self {
+        SchemaGenerator {
+            options,
+            ..Default::default()
+        }
+    }
+
+    /// Merges the schema of `instance` into the accumulated schema.
+    ///
+    /// Progress is reported after each record, subject to
+    /// [`SchemaOptions::progress_interval`]; call [`SchemaGenerator::report_progress`]
+    /// once the last record is observed to report the totals.
+    ///
+    /// With [`Strictness::Strict`] the record is still merged if the merge has to widen
+    /// the schema, but the first [`Conflict`] it raised is returned.
+    pub fn observe(&mut self, instance: &Value) -> Result<(), GeneratorError> {
+        let (seen, exceeded) = (self.state.conflicts.len(), self.state.exceeded.len());
+        let mut ctx = Context::new(&self.options, &mut self.state);
+        ctx.check_cancelled()?;
+
+        let schema = generate_schema_at(instance, &mut ctx, &SchemaPath::root())?;
+        #[cfg(feature = "validation")]
+        if self.options.verify {
+            self.records.push(instance.clone());
+        }
+        self.state.samples.offer(instance, &self.options);
+        self.absorb(schema);
+        let ctx = Context::new(&self.options, &mut self.state);
+        ctx.check_limits(exceeded)?;
+        ctx.check_conflicts(seen)
+    }
+
+    /// Merges the schema of the serialized JSON document `raw` into the accumulated
+    /// schema, without parsing it into a `Value`, see [`generate_from_raw`].
+    ///
+    /// With [`SchemaOptions::verify`] or [`SchemaOptions::root_examples`] set the document
+    /// is still parsed, to be kept for verification or as an example.
+    pub fn observe_raw(&mut self, raw: &RawValue) -> Result<(), GeneratorError> {
+        let (seen, exceeded) = (self.state.conflicts.len(), self.state.exceeded.len());
+        let mut ctx = Context::new(&self.options, &mut self.state);
+        ctx.check_cancelled()?;
+
+        let schema = raw::generate_raw_at(raw, &mut ctx)?;
+        #[cfg(feature = "validation")]
+        if self.options.verify {
+            self.records.push(raw::parse(raw)?);
+        }
+        if self.options.root_examples > 0 {
+            self.state.samples.offer(&raw::parse(raw)?, &self.options);
+        }
+        self.absorb(schema);
+        let ctx = Context::new(&self.options, &mut self.state);
+        ctx.check_limits(exceeded)?;
+        ctx.check_conflicts(seen)
+    }
+
+    /// Merges the schema of one record into the accumulated schema.
+    fn absorb(&mut self, mut schema: Value) {
+        if let Some(obj) = schema.as_object_mut() {
+            self.dialect |= obj.remove("$schema").is_some();
+        }
+        let record_bytes = match self.options.max_schema_bytes {
+            Some(_) => limits::estimate_bytes(&schema),
+            None => 0,
+        };
+        let mut ctx = Context::new(&self.options, &mut self.state);
+        let common = match &mut self.schema {
+            Some(common) => {
+                merge_into(common, &schema, &mut ctx, &SchemaPath::root());
+                common
+            }
+            None => self.schema.insert(schema),
+        };
+        // The merged schema is at most as large as its parts, so it is only measured once
+        // their sizes add up to more than the limit.
+        if let Some(limit) = self.options.max_schema_bytes {
+            let bound = ctx.state.schema_bytes + record_bytes;
+            ctx.state.schema_bytes = if bound > limit {
+                ctx.limit_size(common)
+            } else {
+                bound
+            };
+        }
+        self.state.records += 1;
+        Context::new(&self.options, &mut self.state).report(None, 0, false);
+    }
+
+    /// Calls the progress callback with the current totals, regardless of
+    /// [`SchemaOptions::progress_interval`].
+    pub fn report_progress(&mut self) {
+        Context::new(&self.options, &mut self.state).report(None, 0, true);
+    }
+
+    /// Returns the warnings raised while observing records so far, followed by the
+    /// `case-collision` warnings of [`SchemaOptions::casing_report`] and the
+    /// [`lint_schema`] warnings for the schema generated so far.
+    pub fn warnings(&self) -> Vec<Warning> {
+        let mut warnings = self.state.warnings.clone();
+        if self.options.casing_report {
+            warnings.extend(casing::warnings(&self.state.names, &self.options));
+        }
+        warnings.extend(lint_schema(&self.schema()).into_iter().map(Warning::from));
+        warnings
+    }
+
+    /// Returns the conflicts raised with [`Strictness::Strict`] so far, in the order
+    /// they were raised.
+    pub fn conflicts(&self) -> &[Conflict] {
+        &self.state.conflicts
+    }
+
+    /// Returns the widenings recorded with [`MergePolicy::Annotated`] so far, in the
+    /// order the merges made them.
+    pub fn widenings(&self) -> &[Widening] {
+        &self.state.widenings
+    }
+
+    /// Returns the number of records observed so far.
+    pub fn records(&self) -> usize {
+        self.state.records
+    }
+
+    /// Reports the final progress and returns the schema of all observed records,
+    /// verifying them against it if [`SchemaOptions::verify`] is set. Fails with the
+    /// first limit exceeded with [`LimitPolicy::Fail`] or the first [`Conflict`] raised,
+    /// if any.
+    pub fn finish(mut self) -> Result<Value, GeneratorError> {
+        self.report_progress();
+        let ctx = Context::new(&self.options, &mut self.state);
+        ctx.check_limits(0)?;
+        ctx.check_conflicts(0)?;
+        let schema = transform(self.schema(), &self.options)?;
+        #[cfg(feature = "validation")]
+        if self.options.verify {
+            validation::verify_samples(&schema, &self.records)?;
+        }
+        Ok(schema)
+    }
+
+    /// Returns the schema of all records observed so far, or `{}` if there were none.
+    pub fn schema(&self) -> Value {
+        let mut schema = self.schema.clone().unwrap_or_else(|| json!({}));
+        finish(&mut schema, &self.state, &self.options);
+        if let (Some(obj), true) = (schema.as_object_mut(), self.dialect) {
+            obj.insert("$schema".to_string(), json!(self.options.dialect()));
+        }
+        schema
+    }
+}
+
+/// Generates a JSON schema for a given JSON instance using the given options.
+///
+/// Unlike [`generate_json_schema`] this can fail, for example when generation is
+/// cancelled through [`SchemaOptions::cancel`].
+pub fn try_generate_json_schema(
+    instance: &Value,
+    options: &SchemaOptions,
+) -> Result<Value, GeneratorError> {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::debug_span!("generate_json_schema").entered();
+
+    let mut state = State::default();
+    let mut ctx = Context::new(options, &mut state);
+    let mut schema = generate_schema_at(instance, &mut ctx, &SchemaPath::root())?;
+    ctx.limit_size(&mut schema);
+    ctx.check_limits(0)?;
+    ctx.check_conflicts(0)?;
+    ctx.state.records = 1;
+    ctx.state.samples.offer(instance, options);
+    finish(&mut schema, ctx.state, options);
+    let schema = transform(schema, options)?;
+    ctx.report(None, 0, true);
+    #[cfg(feature = "validation")]
+    if options.verify {
+        validation::verify_samples(&schema, [instance])?;
+    }
+    Ok(schema)
+}
+
+/// Generates a JSON schema for a given JSON instance.
+///
+/// # Arguments
+///
+/// * `instance` - A reference to a `serde_json::Value` representing the JSON instance.
+///
+/// # Returns
+///
+/// A `serde_json::Value` representing the JSON schema for the given instance.
+///
+/// # Examples
+///
+/// ```
+/// use serde_json::json;
+/// use json_schema_generator::generate_json_schema;
+///
+/// let instance = json!({"name": "John", "age": 30});
+/// let schema = generate_json_schema(&instance);
+///
+/// assert_eq!(schema, json!({
+/// "$schema": "http://json-schema.org/draft-07/schema#",
+///     "type": "object",
+///     "properties": {
+///         "name": {"type": "string"},
+///         "age": {"type": "integer"}
+///     },
+///     "required": ["age", "name"]
+/// }));
+/// ```
+pub fn generate_json_schema(instance: &Value) -> Value {
+    // Without a cancellation flag generation has no way to fail.
+    generate_json_schema_with_options(instance, &SchemaOptions::default())
+}
+
+/// Generates a JSON schema for a given JSON instance using the given options, for
+/// options that can't make generation fail.
+///
+/// [`generate_json_schema`] is this with the default options.
+///
+/// # Panics
+///
+/// Panics where [`try_generate_json_schema`] would return an error: when generation is
+/// cancelled, a limit is exceeded with [`LimitPolicy::Fail`], the samples conflict with
+/// [`Strictness::Strict`], or the [`SchemaOptions::post_transform`] or verification
+/// fails.
+///
+/// # Examples
+///
+/// ```
+/// use serde_json::json;
+/// use json_schema_generator::{generate_json_schema_with_options, GeneratorOptions};
+///
+/// let options = GeneratorOptions::default().with_max_depth(1);
+/// let schema = generate_json_schema_with_options(&json!({"a": {"b": 1}}), &options);
+/// assert_eq!(schema["properties"]["a"], json!({}));
+/// ```
+pub fn generate_json_schema_with_options(instance: &Value, options: &GeneratorOptions) -> Value {
+    try_generate_json_schema(instance, options)
+        .unwrap_or_else(|error| panic!("schema generation failed: {}", error))
+}
+
+/// Generates a JSON schema accepting every sample of `samples`, as [`SchemaGenerator`]
+/// does with the options of [`generate_json_schema`].
+///
+/// The properties of objects present in every sample at a location are required, and
+/// those missing from some are only listed under `properties`. Samples of different
+/// types become the branches of a `oneOf`. No samples give the schema `{}`, which accepts anything.
+///
+/// # Examples
+///
+/// ```
+/// use serde_json::json;
+/// use json_schema_generator::generate_schema_from_samples;
+///
+/// let schema = generate_schema_from_samples(&[
+///     json!({"id": 1, "email": "a@example.com"}),
+///     json!({"id": 2}),
+/// ]);
+/// assert_eq!(schema["required"], json!(["id"]));
+/// assert_eq!(schema["properties"]["email"], json!({"type": "string"}));
+/// ```
+pub fn generate_schema_from_samples(samples: &[Value]) -> Value {
+    // Without a cancellation flag generation has no way to fail.
+    try_generate_schema_from_samples(samples, &SchemaOptions::default())
+        .expect("generation without a cancellation flag is infallible")
+}
+
+/// Generates a JSON schema accepting every sample of `samples` using the given options,
+/// as a [`SchemaGenerator`] observing each of them does.
+///
+/// Analyses of the values across samples, such as [`SchemaOptions::enums`], need the
+/// options, which [`generate_schema_from_samples`] doesn't take.
+///
+/// # Examples
+///
+/// ```
+/// use serde_json::json;
+/// use json_schema_generator::{try_generate_schema_from_samples, EnumInference, SchemaOptions};
+///
+/// let options = SchemaOptions {
+///     enums: Some(EnumInference { max_values: 10, min_samples: 3, ..Default::default() }),
+///     ..Default::default()
+/// };
+/// let users = [
+///     json!({"role": "admin"}),
+///     json!({"role": "viewer"}),
+///     json!({"role": "admin"}),
+/// ];
+/// let schema = try_generate_schema_from_samples(&users, &options).unwrap();
+/// assert_eq!(schema["properties"]["role"]["enum"], json!(["admin", "viewer"]));
+/// ```
+pub fn try_generate_schema_from_samples(
+    samples: &[Value],
+    options: &SchemaOptions,
+) -> Result<Value, GeneratorError> {
+    let mut generator = SchemaGenerator::new(options.clone());
+    for sample in samples {
+        generator.observe(sample)?;
+    }
+    generator.finish()
+}
+
+/// Generates a JSON schema accepting every instance of `instances`, as
+/// [`generate_schema_from_samples`] does.
+pub fn generate_schema_from_instances(instances: &[Value]) -> Value {
+    generate_schema_from_samples(instances)
+}
+
+/// Merges two generated schemas into one accepting what either accepts, as the schemas
+/// of several samples are merged with the default options.
+///
+/// The properties of merged object schemas are merged one by one and only those both
+/// require stay required; schemas of different types become the branches of a `oneOf`.
+///
+/// # Examples
+///
+/// ```
+/// use serde_json::json;
+/// use json_schema_generator::merge_schemas;
+///
+/// let a = json!({"type": "object", "properties": {"id": {"type": "integer"}, "name": {"type": "string"}}, "required": ["id", "name"]});
+/// let b = json!({"type": "object", "properties": {"id": {"type": "integer"}}, "required": ["id"]});
+/// let merged = merge_schemas(&a, &b);
+/// assert_eq!(merged["required"], json!(["id"]));
+/// assert_eq!(merged["properties"]["name"], json!({"type": "string"}));
+/// ```
+pub fn merge_schemas(a: &Value, b: &Value) -> Value {
+    find_common_schema(&[a.clone(), b.clone()])
+}
+
+/// Merges the generated schemas `schemas` into one accepting what any of them accepts,
+/// as [`merge_schemas`] merges two, keeping the `$schema` they all declare. No schemas
+/// give `{}`, which accepts anything.
+pub fn find_common_schema(schemas: &[Value]) -> Value {
+    let options = SchemaOptions::default();
+    let mut state = State::default();
+    let mut ctx = Context::new(&options, &mut state);
+    let Some((first, rest)) = schemas.split_first() else {
+        return json!({});
+    };
+    let mut common = first.clone();
+    for schema in rest {
+        merge_into(&mut common, schema, &mut ctx, &SchemaPath::root());
+    }
+    // A dialect all of the schemas declare still holds.
+    let dialect = first.get("$schema").filter(|dialect| {
+        rest.iter()
+            .all(|schema| schema.get("$schema") == Some(dialect))
+    });
+    if let (Some(obj), Some(dialect)) = (common.as_object_mut(), dialect) {
+        obj.insert("$schema".to_string(), dialect.clone());
+    }
+    common
+}
+
+fn generate_schema_at(
+    instance: &Value,
+    ctx: &mut Context,
+    path: &SchemaPath,
+) -> Result<Value, GeneratorError> {
+    ctx.count_node();
+    if path.depth >= ctx.options.max_depth && (instance.is_object() || instance.is_array()) {
+        ctx.limit_depth(path);
+        return Ok(json!({}));
+    }
+    let schema = match instance {
+        Value::Object(obj) => generate_object_schema(obj, ctx, path)?,
+        Value::Array(arr) => generate_array_schema(arr, ctx, path)?,
+        Value::String(s) => ctx.string_schema(s, path),
+        Value::Number(n) => {
+            let schema = if is_integer(n) {
+                json!({"type": "integer"})
+            } else {
+                json!({"type": "number"})
+            };
+            ctx.number_schema(schema, n, path)
+        }
+        Value::Bool(_) => ctx.boolean_schema(),
+        Value::Null => {
+            let mut schema = json!({"type": "null"});
+            ctx.type_defaults(&mut schema, Observed::Other);
+            schema
+        }
+    };
+    Ok(schema)
+}
+
+fn generate_object_schema(
+    obj: &Map<String, Value>,
+    ctx: &mut Context,
+    path: &SchemaPath,
+) -> Result<Value, GeneratorError> {
+    ctx.check_cancelled()?;
+
+    let mut properties = Map::new();
+    let mut reference = None;
+    for (key, value) in obj {
+        if key == "$ref" && value.is_string() {
+            ctx.warn(
+                "ref-passthrough",
+                path,
+                format!(
+                    "copied the reference {} into the schema without resolving it",
+                    value
+                ),
+            );
+            reference = Some(value.clone());
+        } else if properties.len() < ctx.options.max_properties {
+            let mut sub_schema = generate_schema_at(value, ctx, &path.property(key))?;
+            ctx.annotate_access(key, &mut sub_schema);
+            properties.insert(key.clone(), sub_schema);
+        } else {
+            ctx.limit_properties(path);
+        }
+    }
+
+    ctx.pair_coordinates(&mut properties);
+    ctx.record_object(path, &properties, |field| {
+        obj.get(field).and_then(Value::as_str)
+    });
+    let count = properties.len();
+    let mut schema = object_schema(properties, reference);
+    ctx.type_defaults(&mut schema, Observed::Object(count));
+    Ok(schema)
+}
+
+/// Builds the schema of an object from the schemas of its properties, requiring all of
+/// them, and the `$ref` the object held.
+fn object_schema(mut properties: Map<String, Value>, reference: Option<Value>) -> Value {
+    for sub_schema in properties.values_mut() {
+        if let Some(obj) = sub_schema.as_object_mut() {
+            obj.remove("$schema"); // Remove $schema from nested objects
+        }
+    }
+    let mut required: Vec<&String> = properties.keys().collect();
+    required.sort();
+    let required: Vec<Value> = required.into_iter().cloned().map(Value::String).collect();
+
+    // `json!` would serialize interpolated values, copying them, so they are moved in.
+    let mut schema = json!({"type": "object"});
+    schema["properties"] = Value::Object(properties);
+    schema["required"] = Value::Array(required);
+    if let Some(reference) = reference {
+        schema["$ref"] = reference;
+    }
+
+    // Add $schema only to the top-level object
+    schema["$schema"] = json!("http://json-schema.org/draft-07/schema#");
+    schema
+}
+
+fn generate_array_schema(
+    arr: &[Value],
+    ctx: &mut Context,
+    path: &SchemaPath,
+) -> Result<Value, GeneratorError> {
+    if arr.is_empty() {
+        let mut schema = json!({
+            "type": "array",
+            "items": {}
+        });
+        ctx.count_items(&mut schema, 0);
+        ctx.unique_items(&mut schema, unique::Distinct::default(), path);
+        ctx.type_defaults(&mut schema, Observed::Array(0));
+        return Ok(schema);
+    }
+
+    let items_path = path.items();
+    trace!(pointer = %path, elements = arr.len(), "folding array items");
+
+    let mut elements = Elements::default();
+    let mut distinct = unique::Distinct::default();
+    for (index, item) in arr.iter().enumerate() {
+        ctx.count_element(path, index)?;
+        let schema = generate_schema_at(item, ctx, &items_path)?;
+        elements.push(schema, ctx, &items_path);
+        if ctx.options.unique_items {
+            distinct.record(item);
+        }
+    }
+
+    let mut schema = json!({"type": "array"});
+    elements.write(&mut schema, ctx, &items_path);
+    ctx.unique_items(&mut schema, distinct, path);
+    ctx.count_items(&mut schema, arr.len());
+    ctx.type_defaults(&mut schema, Observed::Array(arr.len()));
+    Ok(schema)
+}
+
+/// Returns whether the JSON number `n` is an integer: an `i64` or a `u64`, or, with
+/// serde_json's `arbitrary_precision`, digits beyond them.
+fn is_integer(n: &Number) -> bool {
+    if n.is_i64() || n.is_u64() {
+        return true;
+    }
+    // Without arbitrary_precision, the digits were read as a float and are written with
+    // an exponent or a fraction.
+    let digits = n.to_string();
+    let digits = digits.strip_prefix('-').unwrap_or(&digits);
+    !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// The schemas of the elements of an array as they are generated: one for each position
+/// while the array may still be a [tuple](SchemaOptions::tuples), and otherwise merged
+/// as they come, so that only one schema is held at a time.
+#[derive(Default)]
+struct Elements {
+    positions: Vec<Value>,
+    merged: Option<Value>,
+}
+
+impl Elements {
+    /// Adds the schema of the next element, found at `path`.
+    fn push(&mut self, schema: Value, ctx: &mut Context, path: &SchemaPath) {
+        let max_len = ctx
+            .options
+            .tuples
+            .as_ref()
+            .map_or(0, |tuples| tuples.max_len);
+        if self.merged.is_none() && self.positions.len() < max_len {
+            self.positions.push(schema);
+            return;
+        }
+        for schema in self.positions.drain(..).chain([schema]) {
+            match &mut self.merged {
+                Some(merged) => merge_into(merged, &schema, ctx, path),
+                None => self.merged = Some(schema),
+            }
+        }
+    }
+
+    /// Writes the `items` of the elements into the array schema `schema`.
+    fn write(self, schema: &mut Value, ctx: &mut Context, path: &SchemaPath) {
+        if self.merged.is_none() && tuples::is_tuple(&self.positions) {
+            schema["items"] = Value::Array(self.positions);
+            schema["additionalItems"] = Value::Bool(false);
+            return;
+        }
+        schema["items"] = merge_positions(self.positions, self.merged, ctx, path);
+    }
+}
+
+/// Merges the schemas of the positions of a tuple, found at `path`, into `merged`, or
+/// into one another without it. No schemas at all give `{}`.
+fn merge_positions(
+    positions: Vec<Value>,
+    mut merged: Option<Value>,
+    ctx: &mut Context,
+    path: &SchemaPath,
+) -> Value {
+    for schema in positions {
+        match &mut merged {
+            Some(merged) => merge_into(merged, &schema, ctx, path),
+            None => merged = Some(schema),
+        }
+    }
+    merged.unwrap_or_else(|| json!({}))
+}
+
+/// Merges `schema` into `target`.
+///
+/// The merge happens in place, so that folding many schemas into one costs the size of
+/// each rather than that of the accumulated schema.
+fn merge_into(target: &mut Value, schema: &Value, ctx: &mut Context, path: &SchemaPath) {
+    if !ctx.options.nullable_type_arrays {
+        return merge_unions(target, schema, ctx, path);
+    }
+    // A type array takes part in a merge as the oneOf it stands for.
+    unfold_nullable(target);
+    match schema.get("type").is_some_and(Value::is_array) {
+        true => {
+            let mut schema = schema.clone();
+            unfold_nullable(&mut schema);
+            merge_unions(target, &schema, ctx, path);
+        }
+        false => merge_unions(target, schema, ctx, path),
+    }
+    fold_nullable(target);
+}
+
+/// The types [`SchemaOptions::nullable_type_arrays`] joins with null.
+const NULLABLE_TYPES: [&str; 4] = ["string", "integer", "number", "boolean"];
+
+/// Keywords a null value fails, which keep a branch out of a type array.
+const REJECTING_NULL: [&str; 8] = [
+    "const", "enum", "not", "oneOf", "anyOf", "allOf", "$ref", "if",
+];
+
+/// Turns the oneOf of a primitive schema and `{"type": "null"}` in `schema` into the
+/// primitive schema with the type array `[type, "null"]`.
+fn fold_nullable(schema: &mut Value) {
+    let Some(Value::Array(branches)) = schema.get("oneOf") else {
+        return;
+    };
+    if schema.as_object().map(Map::len) != Some(1) || branches.len() != 2 {
+        return;
+    }
+    let null = json!({"type": "null"});
+    let Some(at) = branches.iter().position(|branch| *branch == null) else {
+        return;
+    };
+    let other = &branches[1 - at];
+    let foldable = other
+        .get("type")
+        .and_then(Value::as_str)
+        .is_some_and(|kind| NULLABLE_TYPES.contains(&kind))
+        && !REJECTING_NULL
+            .iter()
+            .any(|keyword| other.get(*keyword).is_some());
+    if !foldable {
+        return;
+    }
+    let mut folded = other.clone();
+    folded["type"] = json!([folded["type"].take(), "null"]);
+    *schema = folded;
+}
+
+/// Turns a schema `fold_nullable` folded back into the oneOf it stands for.
+fn unfold_nullable(schema: &mut Value) {
+    let Some(Value::Array(types)) = schema.get("type") else {
+        return;
+    };
+    let (Some(first), Some(second), 2) = (
+        types.first().and_then(Value::as_str),
+        types.get(1).and_then(Value::as_str),
+        types.len(),
+    ) else {
+        return;
+    };
+    let branch = |kind: &str| match kind {
+        "null" => json!({"type": "null"}),
+        kind => {
+            let mut branch = schema.clone();
+            branch["type"] = json!(kind);
+            branch
+        }
+    };
+    if first != "null" && second != "null" {
+        return;
+    }
+    let branches = vec![branch(first), branch(second)];
+    *schema = json!({ "oneOf": branches });
+}
+
+/// Merges `schema` into `target`, joining them in a oneOf where they are incompatible.
+fn merge_unions(target: &mut Value, schema: &Value, ctx: &mut Context, path: &SchemaPath) {
+    if merge_compatible(target, schema, ctx, path) {
+        return;
+    }
+
+    // Fold each alternative of the second schema into a compatible branch of the
+    // first, so that no value can match two branches of the resulting oneOf.
+    for alternative in alternatives(schema) {
+        if merge_into_branch(target, alternative, ctx, path) {
+            continue;
+        }
+        let conservative = ctx.options.merge_policy == MergePolicy::Conservative;
+        if conservative && merge_into_any_of(target, alternative) {
+            continue;
+        }
+        if ctx.watches_widening() {
+            let message = format!(
+                "{} and {} can only be merged into a oneOf",
+                describe_types(target),
+                describe_types(alternative)
+            );
+            let mut types = Vec::new();
+            for kind in alternatives(target)
+                .into_iter()
+                .filter_map(|b| b.get("type"))
+            {
+                if !types.contains(kind) {
+                    types.push(kind.clone());
+                }
+            }
+            let dropped = json!({ "type": types });
+            ctx.widen(
+                "type-union",
+                path,
+                (target, alternative),
+                Some(dropped),
+                message,
+            );
+        }
+        trace!(pointer = %path, "merged incompatible schemas into oneOf");
+        // A further alternative joins the branches rather than nesting the oneOf.
+        if let Some(branches) = target.get_mut("oneOf").and_then(Value::as_array_mut) {
+            branches.push(alternative.clone());
+            continue;
+        }
+        let branches = vec![target.take(), alternative.clone()];
+        *target = json!({});
+        target["oneOf"] = Value::Array(branches);
+    }
+}
+
+/// Merges two schemas that describe the same kind of value into `target`, returning
+/// `false` and leaving it untouched if they have to be kept apart as branches of a union.
+fn merge_compatible(
+    target: &mut Value,
+    schema: &Value,
+    ctx: &mut Context,
+    path: &SchemaPath,
+) -> bool {
+    if target == schema {
+        return true;
+    }
+    let conservative = ctx.options.merge_policy == MergePolicy::Conservative;
+    let mut disputed = match conservative || ctx.watches_widening() {
+        true => disputed_keywords(target, schema),
+        false => Vec::new(),
+    };
+    // Bounds that widen aren't dropped.
+    let widened = [
+        (ctx.options.numeric_bounds, &bounds::NUMBERS),
+        (ctx.options.string_lengths.is_some(), &bounds::LENGTHS),
+        (ctx.options.array_lengths, &bounds::ITEMS),
+    ]
+    .map(|(inferred, kind)| match inferred {
+        true => kind.widened(target, schema).map(|widened| (kind, widened)),
+        false => None,
+    });
+    for (kind, _) in widened.iter().flatten() {
+        disputed.retain(|keyword| !kind.contains(keyword));
+    }
+    if conservative && !disputed.is_empty() && same_kind(target, schema) {
+        return false;
+    }
+    let carried = access::carried(target, schema);
+    let coordinates = geo::carried(target, schema);
+    let defaults = ctx.options.type_defaults.carried(target, schema);
+    let left = (ctx.watches_widening() && !disputed.is_empty()).then(|| target.clone());
+    if !merge_shapes(target, schema, ctx, path) {
+        return false;
+    }
+    if !is_unconstrained(target) {
+        access::restore(target, &carried);
+        ctx.options.type_defaults.restore(target, defaults);
+        for (kind, widened) in widened.into_iter().flatten() {
+            kind.restore(target, Some(widened));
+        }
+        geo::restore(target, coordinates);
+    }
+    let Some(left) = left else {
+        return true;
+    };
+    for keyword in &disputed {
+        if target.get(keyword).is_some() {
+            continue;
+        }
+        let code = match keyword.as_str() {
+            "format" => "format-drop",
+            "pattern" => "pattern-drop",
+            _ => "keyword-drop",
+        };
+        let (message, value) = match (left.get(keyword), schema.get(keyword)) {
+            (Some(a), Some(b)) => (
+                format!("the {}s {} and {} can't both be kept", keyword, a, b),
+                json!([a, b]),
+            ),
+            (Some(value), None) | (None, Some(value)) => (
+                format!("the {} {} holds for only some samples", keyword, value),
+                value.clone(),
+            ),
+            (None, None) => unreachable!("one of the schemas has the keyword"),
+        };
+        // A keyword only the new schema has leaves the merged schema as wide as it was.
+        let dropped = left.get(keyword).map(|_| {
+            let mut dropped = Map::new();
+            dropped.insert(keyword.clone(), value);
+            Value::Object(dropped)
+        });
+        ctx.widen(code, path, (&left, schema), dropped, message);
+    }
+    true
+}
+
+/// The keywords a merge merges rather than keeps only where the schemas agree.
+const MERGED_KEYWORDS: [&str; 7] = [
+    "type",
+    "properties",
+    "items",
+    "required",
+    "$schema",
+    "readOnly",
+    "writeOnly",
+];
+
+/// Returns the keywords beside the [`MERGED_KEYWORDS`] that `a` and `b` disagree on,
+/// which their merge drops.
+fn disputed_keywords(a: &Value, b: &Value) -> Vec<String> {
+    let (Some(a), Some(b)) = (a.as_object(), b.as_object()) else {
+        return Vec::new();
+    };
+    let mut disputed: Vec<String> = a
+        .keys()
+        .chain(b.keys().filter(|keyword| !a.contains_key(*keyword)))
+        .filter(|keyword| {
+            !MERGED_KEYWORDS.contains(&keyword.as_str()) && a.get(*keyword) != b.get(*keyword)
+        })
+        .cloned()
+        .collect();
+    disputed.sort_unstable();
+    disputed
+}
+
+/// Returns whether `a` and `b` have the same type, counting integers as numbers.
+fn same_kind(a: &Value, b: &Value) -> bool {
+    fn kind(schema: &Value) -> Option<&str> {
+        match implied_type(schema) {
+            Some("integer") => Some("number"),
+            kind => kind,
+        }
+    }
+    kind(a).is_some() && kind(a) == kind(b)
+}
+
+/// Returns the single type of `schema`: its `type`, or without one the type of the
+/// values its `const` or `enum` lists, which imply it.
+fn implied_type(schema: &Value) -> Option<&str> {
+    if let Some(kind) = schema.get("type") {
+        return kind.as_str();
+    }
+    let values = match (schema.get("const"), schema.get("enum")) {
+        (Some(value), _) => std::slice::from_ref(value),
+        (None, Some(Value::Array(values))) => values.as_slice(),
+        _ => return None,
+    };
+    let (first, rest) = values.split_first()?;
+    let kind = value_type(first);
+    rest.iter()
+        .all(|value| value_type(value) == kind)
+        .then_some(kind)
+}
+
+/// Returns the JSON Schema type of `value`.
+fn value_type(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_f64() => "number",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Adds `schema` to the `anyOf` of the branch of `target` sharing its type with it, or
+/// turns that branch into one, as [`MergePolicy::Conservative`] keeps schemas of the same
+/// type that disagree on a keyword apart. Returns `false` if no branch shares the type.
+fn merge_into_any_of(target: &mut Value, schema: &Value) -> bool {
+    if let Some(branches) = target.get_mut("oneOf").and_then(Value::as_array_mut) {
+        return branches
+            .iter_mut()
+            .any(|branch| merge_into_any_of(branch, schema));
+    }
+    if let Some(branches) = target.get_mut("anyOf").and_then(Value::as_array_mut) {
+        if !branches.iter().any(|branch| same_kind(branch, schema)) {
+            return false;
+        }
+        branches.push(schema.clone());
+        return true;
+    }
+    if !same_kind(target, schema) {
+        return false;
+    }
+    let branches = vec![target.take(), schema.clone()];
+    *target = json!({});
+    target["anyOf"] = Value::Array(branches);
+    true
+}
+
+/// Merges the types, properties and items of `schema` into `target`, as
+/// [`merge_compatible`] does, dropping every other keyword.
+fn merge_shapes(target: &mut Value, schema: &Value, ctx: &mut Context, path: &SchemaPath) -> bool {
+    if is_unconstrained(target) || is_unconstrained(schema) {
+        *target = json!({});
+        return true;
+    }
+
+    let numeric = |t: &str| t == "integer" || t == "number";
+    let kind = match (implied_type(target), implied_type(schema)) {
+        (Some(type1), Some(type2)) if type1 == type2 => type1.to_string(),
+        (Some(type1), Some(type2)) if numeric(type1) && numeric(type2) => {
+            // Every integer is also a number, so the two can't be oneOf branches.
+            *target = json!({"type": "number"});
+            return true;
+        }
+        _ => return false,
+    };
+
+    if ctx.watches_widening() {
+        let names = |schema: &Value| -> BTreeSet<String> {
+            schema
+                .get("properties")
+                .and_then(Value::as_object)
+                .map(|properties| properties.keys().cloned().collect())
+                .unwrap_or_default()
+        };
+        let (left, right) = (names(target), names(schema));
+        let missing: Vec<String> = left
+            .symmetric_difference(&right)
+            .map(|name| Value::String(name.clone()).to_string())
+            .collect();
+        if !missing.is_empty() {
+            let message = match missing.len() {
+                1 => format!(
+                    "the property {} is missing from some samples, so it would no longer be required",
+                    missing[0]
+                ),
+                _ => format!(
+                    "the properties {} are missing from some samples, so they would no longer be required",
+                    missing.join(", ")
+                ),
+            };
+            let dropped =
+                json!({ "required": left.symmetric_difference(&right).collect::<Vec<_>>() });
+            ctx.widen(
+                "required-demotion",
+                path,
+                (target, schema),
+                Some(dropped),
+                message,
+            );
+        }
+    }
+
+    // Only the type, the properties, the items, the properties both schemas require and
+    // the closing keywords and uniqueness both have survive a merge.
+    let Value::Object(merged) = target else {
+        return false;
+    };
+    let properties = merged.remove("properties");
+    let items = merged.remove("items");
+    let required = merged.remove("required");
+    let closed: Vec<(String, Value)> = [
+        ("additionalProperties", false),
+        ("unevaluatedProperties", false),
+        ("uniqueItems", true),
+    ]
+    .into_iter()
+    .filter(|(keyword, value)| {
+        let kept = Some(&Value::Bool(*value));
+        merged.get(*keyword) == kept && schema.get(*keyword) == kept
+    })
+    .map(|(keyword, value)| (keyword.to_string(), Value::Bool(value)))
+    .collect();
+    merged.retain(|key, _| key == "type");
+    // A const or enum without a type widens to the type of its values.
+    merged.insert("type".to_string(), Value::String(kind));
+    merged.extend(closed);
+    if let (Some(Value::Array(mut required)), Some(Value::Array(other))) =
+        (required, schema.get("required"))
+    {
+        required.retain(|name| other.contains(name));
+        if !required.is_empty() {
+            merged.insert("required".to_string(), Value::Array(required));
+        }
+    }
+
+    // Left out properties are the same as empty ones.
+    let other = schema.get("properties").and_then(Value::as_object);
+    if properties.is_some() || other.is_some() {
+        let mut properties = match properties {
+            Some(Value::Object(properties)) => properties,
+            _ => Map::new(),
+        };
+        for (key, value) in other.into_iter().flatten() {
+            let tracked = properties.len() < ctx.options.max_properties;
+            match properties.get_mut(key) {
+                Some(existing) => merge_into(existing, value, ctx, &path.property(key)),
+                None if tracked => {
+                    properties.insert(key.clone(), value.clone());
+                }
+                None => ctx.limit_properties(path),
+            }
+        }
+        merged.insert("properties".to_string(), Value::Object(properties));
+    }
+
+    // Left out items are unconstrained, so they absorb the others. Tuples of the same
+    // length merge position by position, and otherwise like arrays of one type.
+    match (items, schema.get("items")) {
+        (Some(Value::Array(mut positions)), Some(Value::Array(other)))
+            if positions.len() == other.len() =>
+        {
+            for (position, other) in positions.iter_mut().zip(other) {
+                merge_into(position, other, ctx, &path.items());
+            }
+            merged.insert("items".to_string(), Value::Array(positions));
+            merged.insert("additionalItems".to_string(), Value::Bool(false));
+        }
+        (Some(items), Some(other)) => {
+            let mut items = match items {
+                Value::Array(positions) => merge_positions(positions, None, ctx, &path.items()),
+                items => items,
+            };
+            match other {
+                Value::Array(positions) => {
+                    let other = merge_positions(positions.clone(), None, ctx, &path.items());
+                    merge_into(&mut items, &other, ctx, &path.items());
+                }
+                other => merge_into(&mut items, other, ctx, &path.items()),
+            }
+            merged.insert("items".to_string(), items);
+        }
+        (None, None) => {}
+        _ => {
+            merged.insert("items".to_string(), json!({}));
+        }
+    }
+
+    true
+}
+
+/// Merges `schema` into the first compatible branch of `target`, looking into nested
+/// oneOf and anyOf branches. Returns `false` if no branch is compatible.
+fn merge_into_branch(
+    target: &mut Value,
+    schema: &Value,
+    ctx: &mut Context,
+    path: &SchemaPath,
+) -> bool {
+    for keyword in ["oneOf", "anyOf"] {
+        if let Some(branches) = target.get_mut(keyword).and_then(Value::as_array_mut) {
+            return branches
+                .iter_mut()
+                .any(|branch| merge_into_branch(branch, schema, ctx, path));
+        }
+    }
+
+    merge_compatible(target, schema, ctx, path)
+}
+
+/// Returns the types of the branches of `schema`, such as `"integer" or "string"`.
+fn describe_types(schema: &Value) -> String {
+    let types: Vec<String> = alternatives(schema)
+        .into_iter()
+        .map(|branch| match branch.get("type") {
+            Some(kind) => kind.to_string(),
+            None => "anything".to_string(),
+        })
+        .collect();
+    types.join(" or ")
+}
+
+/// Returns the branches of a oneOf or anyOf schema, or the schema itself.
+fn alternatives(schema: &Value) -> Vec<&Value> {
+    let union = schema.get("oneOf").or_else(|| schema.get("anyOf"));
+    match union.and_then(Value::as_array) {
+        Some(branches) => branches.iter().flat_map(alternatives).collect(),
+        None => vec![schema],
+    }
+}
+
+/// Returns whether `schema` accepts every value.
+fn is_unconstrained(schema: &Value) -> bool {
+    schema == &Value::Bool(true) || schema.as_object().is_some_and(Map::is_empty)
+}
+
+/// Applies [`SchemaOptions::post_transform`] to the finished `schema`.
+fn transform(schema: Value, options: &SchemaOptions) -> Result<Value, GeneratorError> {
+    match &options.post_transform {
+        Some(hook) => hook(schema).map_err(GeneratorError::Transform),
+        None => Ok(schema),
+    }
+}
+
+/// Applies the options that concern the whole of a finished schema.
+fn finish(schema: &mut Value, state: &State, options: &SchemaOptions) {
+    let folds = options.merge_case_variants && !state.names.is_empty();
+    let tracked = [
+        state.presence.is_empty(),
+        state.candidates.is_empty(),
+        state.occurrences.is_empty(),
+        state.strings.is_empty(),
+        state.lengths.is_empty(),
+        state.arrays.is_empty(),
+    ];
+    if tracked.contains(&false) || folds {
+        let mut definitions = Map::new();
+        annotate(schema, "", state, options, &mut definitions);
+        if !definitions.is_empty() {
+            if let Some(obj) = schema.as_object_mut() {
+                obj.insert("$defs".to_string(), Value::Object(definitions));
+            }
+        }
+    }
+    finalize(schema, options.empty_containers);
+    if let Some(with) = options.closed_objects {
+        // Objects that had properties left out have to stay open to accept them.
+        let mut closing = Closing {
+            with,
+            open: state
+                .limits
+                .iter()
+                .filter(|(code, _)| *code == "property-limit")
+                .map(|(_, pointer)| pointer.as_str())
+                .collect(),
+            references: HashMap::new(),
+        };
+        close(schema, "", false, &mut closing);
+    }
+    shape(schema, options, true);
+    if let Some(annotator) = &options.annotator {
+        annotations::apply(schema, "", annotator);
+    }
+    if let Some(settings) = &options.shared_definitions {
+        shared::extract(schema, settings);
+    }
+    match (&options.anchors, options.draft) {
+        (Some(anchors), draft) => {
+            let anchors = Anchors {
+                draft: draft.unwrap_or(anchors.draft),
+                ..anchors.clone()
+            };
+            anchors::add(schema, &anchors);
+        }
+        // Without a draft the schema declares draft-07.
+        (None, draft) => anchors::relocate(schema, draft.unwrap_or(Draft::Draft7)),
+    }
+    examples::embed(schema, &state.samples, options);
+    provenance::record(schema, state.records, options);
+}
+
+/// Adds what was inferred about the objects at each JSON Pointer to the schemas of
+/// `schema`, whose location is `pointer`: folded case variants, discriminated unions,
+/// dependencies and the enums of strings. Variants hoisted out of the unions are added to `definitions`.
+fn annotate(
+    schema: &mut Value,
+    pointer: &str,
+    state: &State,
+    options: &SchemaOptions,
+    definitions: &mut Map<String, Value>,
+) {
+    if schema.get("type").and_then(Value::as_str) == Some("object") {
+        if let Some(names) = state.names.get(pointer) {
+            if options.merge_case_variants {
+                casing::merge_variants(schema, names, options);
+            }
+        }
+        let split = options.discriminators.as_ref().and_then(|settings| {
+            let candidates = state.candidates.get(pointer)?;
+            discriminator::split(schema, candidates, settings).map(|split| (split, settings))
+        });
+        if let Some(((field, mut variants), settings)) = split {
+            for (_, variant) in &mut variants {
+                annotate_children(variant, pointer, state, options, definitions);
+            }
+            *schema = discriminator::union(schema, field, variants, settings.style, definitions);
+            return;
+        }
+    }
+
+    annotate_children(schema, pointer, state, options, definitions);
+
+    if let (Some(settings), Some(values)) = (&options.enums, state.strings.get(pointer)) {
+        values.apply(schema, settings);
+    }
+    if let (Some(settings), Some(count)) = (&options.string_lengths, state.lengths.get(pointer)) {
+        let string = match schema.get("type") {
+            Some(Value::String(kind)) => kind == "string",
+            Some(Value::Array(types)) => types.contains(&json!("string")),
+            _ => false,
+        };
+        if string && *count < settings.min_samples {
+            bounds::LENGTHS.remove(schema);
+        }
+    }
+    // Arrays of fewer than two elements don't make the others unique.
+    if let (true, Some(0), Some(obj)) = (
+        options.unique_items,
+        state.arrays.get(pointer),
+        schema.as_object_mut(),
+    ) {
+        obj.remove("uniqueItems");
+    }
+    if schema.get("type").and_then(Value::as_str) != Some("object") {
+        return;
+    }
+    if let Some(occurrences) = state.occurrences.get(pointer) {
+        occurrences.apply(schema, options.required_threshold);
+    }
+    let Some(obj) = schema.as_object_mut() else {
+        return;
+    };
+    let Some((keyword, dependencies)) = options.dependent_required.as_ref().and_then(|settings| {
+        let dependencies = state
+            .presence
+            .get(pointer)?
+            .dependencies(settings.min_occurrences);
+        (!dependencies.is_empty()).then(|| (settings.keyword(options.draft), dependencies))
+    }) else {
+        return;
+    };
+    obj.insert(keyword.to_string(), Value::Object(dependencies));
+}
+
+fn annotate_children(
+    schema: &mut Value,
+    pointer: &str,
+    state: &State,
+    options: &SchemaOptions,
+    definitions: &mut Map<String, Value>,
+) {
+    let Some(obj) = schema.as_object_mut() else {
+        return;
+    };
+
+    // The branches of a union describe the values at the same location.
+    for keyword in ["oneOf", "anyOf"] {
+        if let Some(Value::Array(branches)) = obj.get_mut(keyword) {
+            for branch in branches {
+                annotate(branch, pointer, state, options, definitions);
+            }
+        }
+    }
+    if let Some(Value::Object(properties)) = obj.get_mut("properties") {
+        for (name, property) in properties.iter_mut() {
+            let child = format!(
+                "{}/properties/{}",
+                pointer,
+                name.replace('~', "~0").replace('/', "~1")
+            );
+            annotate(property, &child, state, options, definitions);
+        }
+    }
+    let child = format!("{}/items", pointer);
+    match obj.get_mut("items") {
+        // The positions of a tuple share the location of the items.
+        Some(Value::Array(positions)) => {
+            for position in positions {
+                annotate(position, &child, state, options, definitions);
+            }
+        }
+        Some(items) => annotate(items, &child, state, options, definitions),
+        None => {}
+    }
+}
+
+/// Rewrites the empty containers of a finished schema as `mode` asks.
+fn finalize(schema: &mut Value, mode: EmptyContainers) {
+    if mode == EmptyContainers::Keep {
+        return;
+    }
+    let Some(obj) = schema.as_object_mut() else {
+        return;
+    };
+
+    for keyword in ["oneOf", "anyOf"] {
+        if let Some(Value::Array(branches)) = obj.get_mut(keyword) {
+            for branch in branches {
+                finalize(branch, mode);
+            }
+        }
+    }
+    for keyword in ["then", "else"] {
+        if let Some(branch) = obj.get_mut(keyword) {
+            finalize(branch, mode);
+        }
+    }
+    if let Some(Value::Object(definitions)) = obj.get_mut("$defs") {
+        for definition in definitions.values_mut() {
+            finalize(definition, mode);
+        }
+    }
+
+    if obj
+        .get("properties")
+        .is_some_and(|p| p.as_object().is_some_and(Map::is_empty))
+    {
+        obj.remove("properties");
+        if obj
+            .get("required")
+            .is_some_and(|r| r.as_array().is_some_and(Vec::is_empty))
+        {
+            obj.remove("required");
+        }
+        if mode == EmptyContainers::Explicit {
+            obj.insert("additionalProperties".to_string(), Value::Bool(true));
+        }
+    } else if let Some(Value::Object(properties)) = obj.get_mut("properties") {
+        for property in properties.values_mut() {
+            finalize(property, mode);
+        }
+    }
+
+    match obj.get_mut("items") {
+        Some(items) if is_unconstrained(items) => match mode {
+            EmptyContainers::Explicit => *items = Value::Bool(true),
+            _ => {
+                obj.remove("items");
+            }
+        },
+        Some(Value::Array(positions)) => {
+            for position in positions {
+                finalize(position, mode);
+            }
+        }
+        Some(items) => finalize(items, mode),
+        None => {}
+    }
+}
+
+/// Writes the `$schema`, `required` and union keywords of a finished schema as `options`
+/// ask. `root` is set for the root schema, which keeps its `$schema` regardless of
+/// [`SchemaOptions::nested_dialect`].
+fn shape(schema: &mut Value, options: &SchemaOptions, root: bool) {
+    let unchanged = options.draft.is_none()
+        && options.nested_dialect
+        && !options.all_optional
+        && options.union_keyword == UnionKeyword::OneOf;
+    if unchanged {
+        return;
+    }
+    let Some(obj) = schema.as_object_mut() else {
+        return;
+    };
+
+    if root || options.nested_dialect {
+        if let Some(dialect) = obj.get_mut("$schema") {
+            *dialect = json!(options.dialect());
+        }
+    } else {
+        obj.remove("$schema");
+    }
+    if options.all_optional && obj.get("required").is_some_and(Value::is_array) {
+        obj.remove("required");
+    }
+    if options.union_keyword == UnionKeyword::AnyOf && !obj.contains_key("anyOf") {
+        if let Some(branches) = obj.remove("oneOf") {
+            obj.insert("anyOf".to_string(), branches);
+        }
+    }
+    if options.draft >= Some(Draft::Draft202012) {
+        tuples::to_prefix_items(obj);
+    }
+
+    for keyword in ["oneOf", "anyOf", "items", "prefixItems"] {
+        if let Some(Value::Array(branches)) = obj.get_mut(keyword) {
+            for branch in branches {
+                shape(branch, options, false);
+            }
+        }
+    }
+    for keyword in ["then", "else", "items"] {
+        if let Some(branch) = obj.get_mut(keyword) {
+            shape(branch, options, false);
+        }
+    }
+    for keyword in ["$defs", "properties"] {
+        if let Some(Value::Object(schemas)) = obj.get_mut(keyword) {
+            for schema in schemas.values_mut() {
+                shape(schema, options, false);
+            }
+        }
+    }
+}
+
+/// How the object schemas of a finished schema are closed, see [`close`].
+struct Closing<'a> {
+    with: ClosedWith,
+    /// The JSON Pointers of the objects that are left open, as their schemas don't list
+    /// every property.
+    open: HashSet<&'a str>,
+    /// The JSON Pointers of the locations the definitions in `$defs` are referenced from,
+    /// by name.
+    references: HashMap<String, String>,
+}
+
+/// Closes the object schemas of a finished schema, whose location is `pointer`.
+/// `composed` is set for the branches of a union, which `unevaluatedProperties` closes
+/// from outside.
+fn close(schema: &mut Value, pointer: &str, composed: bool, closing: &mut Closing) {
+    let is_object = |schema: &Value| schema.get("type").and_then(Value::as_str) == Some("object");
+    // Hoisted variants are only referenced from unions.
+    let may_be_object = alternatives(schema)
+        .into_iter()
+        .any(|branch| is_object(branch) || branch.get("$ref").is_some());
+    let object = is_object(schema);
+    let Some(obj) = schema.as_object_mut() else {
+        return;
+    };
+    if let Some(name) = obj
+        .get("$ref")
+        .and_then(Value::as_str)
+        .and_then(|reference| reference.strip_prefix("#/$defs/"))
+    {
+        closing
+            .references
+            .insert(name.to_string(), pointer.to_string());
+    }
+    let composition = ["oneOf", "anyOf", "then"]
+        .iter()
+        .any(|keyword| obj.contains_key(*keyword));
+
+    // The branches of a union describe the values at the same location.
+    for keyword in ["oneOf", "anyOf"] {
+        if let Some(Value::Array(branches)) = obj.get_mut(keyword) {
+            for branch in branches {
+                close(branch, pointer, true, closing);
+            }
+        }
+    }
+    for keyword in ["then", "else"] {
+        if let Some(branch) = obj.get_mut(keyword) {
+            close(branch, pointer, true, closing);
+        }
+    }
+    if let Some(Value::Object(properties)) = obj.get_mut("properties") {
+        for (name, property) in properties.iter_mut() {
+            let child = format!(
+                "{}/properties/{}",
+                pointer,
+                name.replace('~', "~0").replace('/', "~1")
+            );
+            close(property, &child, false, closing);
+        }
+    }
+    match obj.get_mut("items") {
+        Some(Value::Array(positions)) => {
+            let items = format!("{}/items", pointer);
+            for position in positions {
+                close(position, &items, false, closing);
+            }
+        }
+        Some(items) => close(items, &format!("{}/items", pointer), false, closing),
+        None => {}
+    }
+    // After the schemas referencing the definitions, whose locations they take.
+    if let Some(Value::Object(definitions)) = obj.get_mut("$defs") {
+        for (name, definition) in definitions.iter_mut() {
+            let location = closing.references.get(name).cloned().unwrap_or_default();
+            close(definition, &location, true, closing);
+        }
+    }
+
+    if closing.open.contains(pointer) {
+        return;
+    }
+    // An explicitly open empty object would evaluate every property.
+    if obj.get("additionalProperties") == Some(&Value::Bool(true)) {
+        obj.remove("additionalProperties");
+    }
+    match closing.with {
+        ClosedWith::AdditionalProperties if object && !composition => {
+            obj.insert("additionalProperties".to_string(), Value::Bool(false));
+        }
+        ClosedWith::UnevaluatedProperties if !composed && (object || may_be_object) => {
+            obj.insert("unevaluatedProperties".to_string(), Value::Bool(false));
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_generate_json_schema_string() {
+        let input = json!("test");
+        let expected = json!({"type": "string"});
+        assert_eq!(generate_json_schema(&input), expected);
+    }
+
+    #[test]
+    fn test_generate_json_schema_integer() {
+        let input = json!(42);
+        let expected = json!({"type": "integer"});
+        assert_eq!(generate_json_schema(&input), expected);
+    }
+
+    #[test]
+    fn test_integers_beyond_i64() {
+        for n in [json!(u64::MAX), json!(i64::MIN), json!(i64::MAX as u64 + 1)] {
+            assert_eq!(
+                generate_json_schema(&n),
+                json!({"type": "integer"}),
+                "{}",
+                n
+            );
+        }
+        // A float of the same magnitude.
+        assert_eq!(
+            generate_json_schema(&json!(1.8446744073709552e19)),
+            json!({"type": "number"})
+        );
+        assert_eq!(
+            merge(&[
+                generate_json_schema(&json!(u64::MAX)),
+                generate_json_schema(&json!(-1))
+            ]),
+            json!({"type": "integer"})
+        );
+        let records = [json!({"id": u64::MAX}), json!({"id": i64::MIN})];
+        assert_eq!(
+            generate_schema_from_samples(&records)["properties"]["id"],
+            json!({"type": "integer"})
+        );
+        let raw = serde_json::value::to_raw_value(&records).unwrap();
+        let schema = generate_from_raw(&raw, &SchemaOptions::default()).unwrap();
+        assert_eq!(
+            schema["items"]["properties"]["id"],
+            json!({"type": "integer"})
+        );
+    }
+
+    #[test]
+    fn test_generate_json_schema_number() {
+        let input = json!(2.5);
+        let expected = json!({"type": "number"});
+        assert_eq!(generate_json_schema(&input), expected);
+    }
+
+    #[test]
+    fn test_generate_json_schema_boolean() {
+        let input = json!(true);
+        let expected = json!({"type": "boolean"});
+        assert_eq!(generate_json_schema(&input), expected);
+    }
+
+    #[test]
+    fn test_generate_json_schema_null() {
+        let input = json!(null);
+        let expected = json!({"type": "null"});
+        assert_eq!(generate_json_schema(&input), expected);
+    }
+
+    #[test]
+    fn test_generate_object_schema() {
+        let input = json!({
+            "name": "John Doe",
+            "age": 30,
+            "is_student": false
+        });
+        let expected = json!({
+            "$schema": "http://json-schema.org/draft-07/schema#",
+            "type": "object",
+            "properties": {
+                "name": {"type": "string"},
+                "age": {"type": "integer"},
+                "is_student": {"type": "boolean"}
+            },
+            "required": ["age", "is_student", "name"]
+        });
+        assert_eq!(generate_json_schema(&input), expected);
+    }
+
+    #[test]
+    fn test_generate_array_schema() {
+        let input = json!([1, 2, 3]);
+        let expected = json!({
+            "type": "array",
+            "items": {"type": "integer"}
+        });
+        assert_eq!(generate_json_schema(&input), expected);
+    }
+
+    #[test]
+    fn test_generate_array_schema_mixed_types() {
+        let input = json!([1, "two", 3.0, true, null]);
+        // 1 is a number too, so it is folded into the number branch rather than
+        // matching two branches of the oneOf.
+        let expected = json!({
+            "type": "array",
+            "items": {
+                "oneOf": [
+                    {"type": "number"},
+                    {"type": "string"},
+                    {"type": "boolean"},
+                    {"type": "null"}
+                ]
+            }
+        });
+        assert_eq!(generate_json_schema(&input), expected);
+    }
+
+    #[test]
+    fn test_generate_array_schema_merges_into_union_branch() {
+        let input = json!([1, "two", 3]);
+        let expected = json!({
+            "type": "array",
+            "items": {
+                "oneOf": [
+                    {"type": "integer"},
+                    {"type": "string"}
+                ]
+            }
+        });
+        assert_eq!(generate_json_schema(&input), expected);
+    }
+
+    #[test]
+    fn test_merge_schemas_shared_property() {
+        let input = json!([{"a": 1, "b": [1]}, {"a": "x", "b": ["y"]}]);
+        let expected = json!({
+            "type": "object",
+            "properties": {
+                "a": {"oneOf": [{"type": "integer"}, {"type": "string"}]},
+                "b": {
+                    "type": "array",
+                    "items": {"oneOf": [{"type": "integer"}, {"type": "string"}]}
+                }
+            },
+            "required": ["a", "b"]
+        });
+        assert_eq!(generate_json_schema(&input)["items"], expected);
+    }
+
+    #[test]
+    fn test_schema_from_samples() {
+        let users = [
+            json!({"id": 1, "name": "Ann", "email": "ann@example.com", "address": {"city": "A", "zip": "1"}}),
+            json!({"id": 2, "name": "Bo", "address": {"city": "B"}, "roles": [{"name": "admin"}]}),
+            json!({"id": 3, "name": "Cy", "email": "cy@example.com", "address": {"city": "C", "zip": "3"}, "roles": [{"name": "dev", "scope": "x"}, {"name": "ops"}]}),
+        ];
+        let schema = generate_schema_from_samples(&users);
+        assert_eq!(schema["required"], json!(["address", "id", "name"]));
+        assert_eq!(schema["properties"]["email"], json!({"type": "string"}));
+        let properties = &schema["properties"];
+        assert_eq!(properties["address"]["required"], json!(["city"]));
+        assert!(properties["address"]["properties"].get("zip").is_some());
+        assert_eq!(properties["roles"]["items"]["required"], json!(["name"]));
+
+        // Properties no sample shares are all optional.
+        let schema = generate_schema_from_samples(&[json!({"a": 1}), json!({"b": 2})]);
+        assert!(schema.get("required").is_none());
+        let schema = generate_schema_from_samples(&[json!({"a": 1}), json!("x")]);
+        assert_eq!(schema["oneOf"][0]["required"], json!(["a"]));
+
+        assert_eq!(generate_schema_from_samples(&[]), json!({}));
+        assert_eq!(generate_schema_from_instances(&[]), json!({}));
+        assert_eq!(
+            generate_schema_from_instances(&users),
+            generate_schema_from_samples(&users)
+        );
+        assert_eq!(
+            generate_schema_from_samples(&users[..1]),
+            generate_json_schema(&users[0])
+        );
+    }
+
+    #[test]
+    fn test_merge_schemas_intersects_nested_required() {
+        // Objects under properties and in the items of arrays keep what both require.
+        let schema1 = json!({
+            "type": "object",
+            "properties": {
+                "o": {
+                    "type": "object",
+                    "properties": {"x": {"type": "integer"}, "y": {"type": "integer"}},
+                    "required": ["x", "y"]
+                },
+                "l": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {"k": {"type": "string"}, "v": {"type": "integer"}},
+                        "required": ["k", "v"]
+                    }
+                }
+            },
+            "required": ["l", "o"]
+        });
+        let schema2 = json!({
+            "type": "object",
+            "properties": {
+                "o": {
+                    "type": "object",
+                    "properties": {"y": {"type": "integer"}, "z": {"type": "integer"}},
+                    "required": ["y", "z"]
+                },
+                "l": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {"k": {"type": "string"}},
+                        "required": ["k"]
+                    }
+                }
+            },
+            "required": ["o"]
+        });
+        let merged = merge(&[schema1, schema2]);
+        assert_eq!(merged["required"], json!(["o"]));
+        let properties = &merged["properties"];
+        assert_eq!(properties["o"]["required"], json!(["y"]));
+        assert_eq!(properties["o"]["properties"].as_object().unwrap().len(), 3);
+        assert_eq!(properties["l"]["items"]["required"], json!(["k"]));
+        assert!(properties["l"]["items"]["properties"].get("v").is_some());
+    }
+
+    #[test]
+    fn test_merge_schemas_unconstrained() {
+        let input = json!([[], [1], ["a"]]);
+        let expected = json!({
+            "type": "array",
+            "items": {
+                "type": "array",
+                "items": {}
+            }
+        });
+        assert_eq!(generate_json_schema(&input), expected);
+    }
+
+    #[test]
+    fn test_generate_array_schema_empty() {
+        let input = json!([]);
+        let expected = json!({
+            "type": "array",
+            "items": {}
+        });
+        assert_eq!(generate_json_schema(&input), expected);
+    }
+
+    fn generate_with(input: &Value, empty_containers: EmptyContainers) -> Value {
+        let options = SchemaOptions {
+            empty_containers,
+            ..Default::default()
+        };
+        try_generate_json_schema(input, &options).unwrap()
+    }
+
+    #[test]
+    fn test_empty_containers_omitted() {
+        let input = json!({"a": {}, "b": [], "c": [[{}]], "d": {"e": {"f": []}}});
+        let expected = json!({
+            "$schema": "http://json-schema.org/draft-07/schema#",
+            "type": "object",
+            "properties": {
+                "a": {"type": "object"},
+                "b": {"type": "array"},
+                "c": {
+                    "type": "array",
+                    "items": {
+                        "type": "array",
+                        "items": {
+                            "$schema": "http://json-schema.org/draft-07/schema#",
+                            "type": "object"
+                        }
+                    }
+                },
+                "d": {
+                    "type": "object",
+                    "properties": {
+                        "e": {
+                            "type": "object",
+                            "properties": {"f": {"type": "array"}},
+                            "required": ["f"]
+                        }
+                    },
+                    "required": ["e"]
+                }
+            },
+            "required": ["a", "b", "c", "d"]
+        });
+        assert_eq!(generate_with(&input, EmptyContainers::Omit), expected);
+
+        let root = json!({"$schema": "http://json-schema.org/draft-07/schema#", "type": "object"});
+        assert_eq!(generate_with(&json!({}), EmptyContainers::Omit), root);
+        assert_eq!(
+            generate_with(&json!([]), EmptyContainers::Omit),
+            json!({"type": "array"})
+        );
+    }
+
+    #[test]
+    fn test_empty_containers_explicit() {
+        let input = json!([{"a": [[]]}, {"a": [[]], "b": {}}, "x"]);
+        let expected = json!({
+            "type": "array",
+            "items": {
+                "oneOf": [
+                    {
+                        "type": "object",
+                        "properties": {
+                            "a": {"type": "array", "items": {"type": "array", "items": true}},
+                            "b": {"type": "object", "additionalProperties": true}
+                        },
+                        "required": ["a"]
+                    },
+                    {"type": "string"}
+                ]
+            }
+        });
+        assert_eq!(generate_with(&input, EmptyContainers::Explicit), expected);
+    }
+
+    #[test]
+    fn test_closed_objects() {
+        let input = json!([{"a": [{"c": 1}]}, {"a": [{"c": 2}], "b": {}}, "x"]);
+        let generate = |with| {
+            let options = SchemaOptions {
+                empty_containers: EmptyContainers::Explicit,
+                closed_objects: Some(with),
+                ..Default::default()
+            };
+            try_generate_json_schema(&input, &options).unwrap()
+        };
+
+        let schema = generate(ClosedWith::AdditionalProperties);
+        let object = &schema["items"]["oneOf"][0];
+        assert_eq!(object["additionalProperties"], false);
+        assert_eq!(
+            object["properties"]["a"]["items"]["additionalProperties"],
+            false
+        );
+        assert_eq!(object["properties"]["b"]["additionalProperties"], false);
+        assert!(schema["items"].get("additionalProperties").is_none());
+
+        // The union is closed rather than its object branch.
+        let schema = generate(ClosedWith::UnevaluatedProperties);
+        let object = &schema["items"]["oneOf"][0];
+        assert_eq!(schema["items"]["unevaluatedProperties"], false);
+        assert!(object.get("unevaluatedProperties").is_none());
+        assert_eq!(
+            object["properties"]["a"]["items"]["unevaluatedProperties"],
+            false
+        );
+        assert_eq!(
+            object["properties"]["b"],
+            json!({"type": "object", "unevaluatedProperties": false})
+        );
+        assert!(schema.get("unevaluatedProperties").is_none());
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_closed_objects_validate() {
+        let records = [json!({"a": 1, "b": {"c": "x"}}), json!({"a": 2, "b": "y"})];
+        for (with, draft) in [
+            (ClosedWith::AdditionalProperties, Draft::Draft7),
+            (ClosedWith::UnevaluatedProperties, Draft::Draft202012),
+        ] {
+            assert_eq!(ClosedWith::for_draft(draft), with);
+            let mut generator = SchemaGenerator::new(SchemaOptions {
+                closed_objects: Some(with),
+                ..Default::default()
+            });
+            for record in &records {
+                generator.observe(record).unwrap();
+            }
+            let schema = generator.schema();
+            let compiled = validation::compile_schema_with_draft(&schema, draft).unwrap();
+            assert!(records.iter().all(|record| compiled.is_valid(record)));
+            assert!(!compiled.is_valid(&json!({"a": 1, "b": "x", "d": 0})));
+            assert!(!compiled.is_valid(&json!({"a": 1, "b": {"c": "x", "d": 0}})));
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_closed_objects_keep_limited_objects_open() {
+        let input = json!({
+            "wide": {"a": 1, "b": 2, "c": 3, "d": 4, "e": 5},
+            "narrow": [{"x": 1}, {"y": 2}]
+        });
+        for (with, draft) in [
+            (ClosedWith::AdditionalProperties, Draft::Draft7),
+            (ClosedWith::UnevaluatedProperties, Draft::Draft202012),
+        ] {
+            let options = SchemaOptions {
+                max_properties: 2,
+                closed_objects: Some(with),
+                ..Default::default()
+            };
+            let schema = try_generate_json_schema(&input, &options).unwrap();
+            let compiled = validation::compile_schema_with_draft(&schema, draft).unwrap();
+            assert!(compiled.is_valid(&input));
+            // Only the objects that had properties left out stay open.
+            let wide = json!({"wide": {"a": 1, "b": 2, "f": 6}, "narrow": []});
+            assert!(compiled.is_valid(&wide));
+            let narrow = json!({"wide": {}, "narrow": [{"z": 3}]});
+            assert!(!compiled.is_valid(&narrow));
+            let root = json!({"wide": {}, "narrow": [], "other": 0});
+            assert!(!compiled.is_valid(&root));
+        }
+    }
+
+    #[test]
+    fn test_empty_containers_kept_by_default() {
+        let input = json!({"a": {}, "b": []});
+        let schema = generate_json_schema(&input);
+        assert_eq!(schema["properties"]["a"]["properties"], json!({}));
+        assert_eq!(schema["properties"]["a"]["required"], json!([]));
+        assert_eq!(schema["properties"]["b"]["items"], json!({}));
+        assert_eq!(generate_with(&input, EmptyContainers::Keep), schema);
+        let options = SchemaOptions::default();
+        assert_eq!(try_generate_json_schema(&input, &options).unwrap(), schema);
+    }
+
+    #[test]
+    fn test_legacy_output_unchanged() {
+        // Written by generate_json_schema before it took options, byte for byte.
+        let cases = [
+            (
+                json!({"name": "John", "age": 30, "tags": [], "address": {}}),
+                r##"{"$schema":"http://json-schema.org/draft-07/schema#","properties":{"address":{"properties":{},"required":[],"type":"object"},"age":{"type":"integer"},"name":{"type":"string"},"tags":{"items":{},"type":"array"}},"required":["address","age","name","tags"],"type":"object"}"##,
+            ),
+            (
+                json!({"id": 1, "ratio": 0.5, "ok": true, "none": null, "$ref": "#/a"}),
+                r##"{"$ref":"#/a","$schema":"http://json-schema.org/draft-07/schema#","properties":{"id":{"type":"integer"},"none":{"type":"null"},"ok":{"type":"boolean"},"ratio":{"type":"number"}},"required":["id","none","ok","ratio"],"type":"object"}"##,
+            ),
+            (
+                json!([{"a": 1, "b": ["x", "y"]}, {"a": 1, "b": ["x", "y"]}]),
+                r##"{"items":{"$schema":"http://json-schema.org/draft-07/schema#","properties":{"a":{"type":"integer"},"b":{"items":{"type":"string"},"type":"array"}},"required":["a","b"],"type":"object"},"type":"array"}"##,
+            ),
+            (
+                json!({"user": {"name": "a", "roles": [{"id": 1}]}}),
+                r##"{"$schema":"http://json-schema.org/draft-07/schema#","properties":{"user":{"properties":{"name":{"type":"string"},"roles":{"items":{"$schema":"http://json-schema.org/draft-07/schema#","properties":{"id":{"type":"integer"}},"required":["id"],"type":"object"},"type":"array"}},"required":["name","roles"],"type":"object"}},"required":["user"],"type":"object"}"##,
+            ),
+            (
+                json!([1, 2, 3]),
+                r#"{"items":{"type":"integer"},"type":"array"}"#,
+            ),
+            (json!(null), r#"{"type":"null"}"#),
+        ];
+        for (input, expected) in &cases {
+            let schema = generate_json_schema_with_options(input, &GeneratorOptions::default());
+            assert_eq!(serde_json::to_string(&schema).unwrap(), *expected);
+            assert_eq!(
+                serde_json::to_string(&generate_json_schema(input)).unwrap(),
+                *expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_with_required() {
+        let input = json!({"a": 1, "b": {"c": "x"}});
+        let options = GeneratorOptions::default().with_required(false);
+        assert!(options.all_optional);
+        let schema = generate_json_schema_with_options(&input, &options);
+        assert!(schema.get("required").is_none());
+        assert!(schema["properties"]["b"].get("required").is_none());
+        let options = options.with_required(true);
+        assert_eq!(
+            generate_json_schema_with_options(&input, &options),
+            generate_json_schema(&input)
+        );
+    }
+
+    #[test]
+    fn test_draft_and_nested_dialect() {
+        let input = json!({"a": [{"b": 1}]});
+        let options = SchemaOptions::default().with_draft(Draft::Draft202012);
+        let schema = try_generate_json_schema(&input, &options).unwrap();
+        let uri = Draft::Draft202012.uri();
+        assert_eq!(schema["$schema"], uri);
+        assert_eq!(schema["properties"]["a"]["items"]["$schema"], uri);
+
+        let mut generator = SchemaGenerator::new(options.clone());
+        generator.observe(&input).unwrap();
+        assert_eq!(generator.schema(), schema);
+
+        let options = options.with_nested_dialect(false);
+        let schema = try_generate_json_schema(&input, &options).unwrap();
+        assert_eq!(schema["$schema"], uri);
+        assert_eq!(
+            schema["properties"]["a"]["items"],
+            json!({"type": "object", "properties": {"b": {"type": "integer"}}, "required": ["b"]})
+        );
+    }
+
+    #[test]
+    fn test_draft_structure() {
+        let events: Vec<Value> = (0..10)
+            .map(|i| match i % 4 {
+                0 => json!({"type": "view", "id": i, "meta": {"path": "/", "ref": "x"}}),
+                2 => json!({"type": "view", "id": i, "meta": {}}),
+                _ => json!({"type": "order", "id": i, "total": 9.5}),
+            })
+            .collect();
+        let generate = |draft: Option<Draft>| {
+            let mut generator = SchemaGenerator::new(SchemaOptions {
+                draft,
+                discriminators: Some(Discriminators {
+                    style: DiscriminatorStyle::OpenApi,
+                    ..Default::default()
+                }),
+                dependent_required: Some(DependentRequired {
+                    min_occurrences: 1,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            });
+            for event in &events {
+                generator.observe(event).unwrap();
+            }
+            generator.schema()
+        };
+
+        let unset = generate(None);
+        assert_eq!(unset["$schema"], Draft::Draft7.uri());
+
+        let earlier = generate(Some(Draft::Draft7));
+        assert_eq!(unset, earlier);
+        assert_eq!(earlier["$schema"], Draft::Draft7.uri());
+        assert!(earlier.get("$defs").is_none());
+        assert_eq!(earlier["oneOf"][1], json!({"$ref": "#/definitions/View"}));
+        assert_eq!(
+            earlier["definitions"]["View"]["properties"]["meta"]["dependencies"],
+            json!({"path": ["ref"], "ref": ["path"]})
+        );
+
+        let later = generate(Some(Draft::Draft202012));
+        assert_eq!(later["$schema"], Draft::Draft202012.uri());
+        assert_eq!(later["oneOf"][1], json!({"$ref": "#/$defs/View"}));
+        assert!(later
+            .pointer("/$defs/View/properties/meta/dependentRequired")
+            .is_some());
+
+        #[cfg(not(target_arch = "wasm32"))]
+        for schema in [&unset, &earlier, &later] {
+            for event in &events {
+                assert!(validation::validate_instance(schema, event).is_ok());
+            }
+        }
+    }
+
+    #[test]
+    fn test_each_draft() {
+        let events: Vec<Value> = (0..4)
+            .map(|i| match i % 2 {
+                0 => json!({"type": "view", "day": "2024-01-15", "id": "550e8400-e29b-41d4-a716-446655440000"}),
+                _ => json!({"type": "order", "total": 9.5, "email": "a@example.com"}),
+            })
+            .collect();
+        let generate = |draft| {
+            let mut generator = SchemaGenerator::new(
+                SchemaOptions {
+                    discriminators: Some(Discriminators {
+                        style: DiscriminatorStyle::OpenApi,
+                        ..Default::default()
+                    }),
+                    anchors: Some(Anchors::default()),
+                    ..Default::default()
+                }
+                .with_draft(draft)
+                .with_infer_formats(true),
+            );
+            for event in &events {
+                generator.observe(event).unwrap();
+            }
+            generator.schema()
+        };
+        let cases = [
+            (Draft::Draft4, "definitions", "id", "#View", None, None),
+            (
+                Draft::Draft7,
+                "definitions",
+                "$id",
+                "#View",
+                Some("date"),
+                None,
+            ),
+            (
+                Draft::Draft201909,
+                "$defs",
+                "$anchor",
+                "View",
+                Some("date"),
+                Some("uuid"),
+            ),
+            (
+                Draft::Draft202012,
+                "$defs",
+                "$anchor",
+                "View",
+                Some("date"),
+                Some("uuid"),
+            ),
+        ];
+        for (draft, container, keyword, anchor, date, uuid) in cases {
+            let schema = generate(draft);
+            assert_eq!(schema["$schema"], draft.uri());
+            let view = &schema[container]["View"];
+            assert_eq!(view[keyword], anchor, "{:?}", draft);
+            assert_eq!(
+                view["properties"]["day"].get("format"),
+                date.map(|d| json!(d)).as_ref()
+            );
+            assert_eq!(
+                view["properties"]["id"].get("format"),
+                uuid.map(|u| json!(u)).as_ref()
+            );
+            assert_eq!(
+                schema[container]["Order"]["properties"]["email"]["format"],
+                "email"
+            );
+            let other = if container == "$defs" {
+                "definitions"
+            } else {
+                "$defs"
+            };
+            assert!(schema.get(other).is_none());
+            #[cfg(not(target_arch = "wasm32"))]
+            for event in &events {
+                assert!(
+                    validation::validate_instance(&schema, event).is_ok(),
+                    "{}",
+                    schema
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_infer_formats() {
+        let records = [
+            json!({"id": "550e8400-e29b-41d4-a716-446655440000", "at": "2024-01-15T10:30:00Z", "contact": "a@example.com", "note": "hi"}),
+            json!({"id": "6ba7b810-9dad-11d1-80b4-00c04fd430c8", "at": "2024-01-16T08:00:00+01:00", "contact": "+1 555 0100", "note": "2024-01-15"}),
+        ];
+        let options = SchemaOptions::default().with_infer_formats(true);
+        let schema = try_generate_json_schema(&records[0], &options).unwrap();
+        assert_eq!(schema["properties"]["contact"]["format"], "email");
+        assert!(schema["properties"]["note"].get("format").is_none());
+        let raw = serde_json::value::to_raw_value(&records[0]).unwrap();
+        assert_eq!(generate_from_raw(&raw, &options).unwrap(), schema);
+
+        let mut generator = SchemaGenerator::new(options);
+        for record in &records {
+            generator.observe(record).unwrap();
+        }
+        let schema = generator.schema();
+        let properties = &schema["properties"];
+        assert_eq!(
+            properties["id"],
+            json!({"type": "string", "format": "uuid"})
+        );
+        assert_eq!(properties["at"]["format"], "date-time");
+        // A format only some values have is dropped.
+        assert_eq!(properties["contact"], json!({"type": "string"}));
+        assert_eq!(properties["note"], json!({"type": "string"}));
+
+        let off = try_generate_json_schema(&records[0], &SchemaOptions::default()).unwrap();
+        assert_eq!(off["properties"]["id"], json!({"type": "string"}));
+    }
+
+    #[test]
+    fn test_all_optional() {
+        let input = json!({"required": {"x": 1}, "list": [{"y": "a"}, {"y": "b", "z": 2}]});
+        let options = SchemaOptions::default().with_all_optional(true);
+        let schema = try_generate_json_schema(&input, &options).unwrap();
+        assert!(schema.get("required").is_none());
+        // A property named `required` is kept, though not required.
+        let properties = &schema["properties"];
+        assert_eq!(
+            properties["required"],
+            json!({"type": "object", "properties": {"x": {"type": "integer"}}})
+        );
+        assert!(properties["list"]["items"].get("required").is_none());
+    }
+
+    #[test]
+    fn test_union_keyword() {
+        let input = json!([1, "a", [true, {"b": null}]]);
+        let any_of = SchemaOptions::default().with_union_keyword(UnionKeyword::AnyOf);
+        let schema = generate_json_schema_with_options(&input, &any_of);
+        let one_of = generate_json_schema(&input);
+        assert!(one_of["items"].get("oneOf").is_some());
+        assert_eq!(
+            serde_json::to_string(&schema).unwrap(),
+            serde_json::to_string(&one_of)
+                .unwrap()
+                .replace("oneOf", "anyOf")
+        );
+        assert_eq!("any-of".parse(), Ok(UnionKeyword::AnyOf));
+        assert_eq!(UnionKeyword::OneOf.to_string(), "one-of");
+    }
+
+    #[test]
+    fn test_nullable_type_arrays() {
+        let none = json!({"type": "null"});
+        for (kind, sample) in [
+            ("string", json!("a")),
+            ("integer", json!(1)),
+            ("number", json!(1.5)),
+            ("boolean", json!(true)),
+        ] {
+            let primitive = json!({ "type": kind });
+            let folded = json!({"type": [kind, "null"]});
+            assert_eq!(merge(&[primitive.clone(), none.clone()]), folded);
+            assert_eq!(merge(&[none.clone(), primitive.clone()]), folded);
+            assert_eq!(merge(&[folded.clone(), primitive.clone()]), folded);
+            assert_eq!(
+                merge(&[folded.clone(), none.clone(), folded.clone()]),
+                folded
+            );
+            assert_eq!(
+                generate_json_schema(&json!([{"a": sample}, {"a": null}]))["items"]["properties"]
+                    ["a"],
+                folded
+            );
+
+            let mut generator =
+                SchemaGenerator::new(SchemaOptions::default().with_nullable_type_arrays(false));
+            generator.observe(&sample).unwrap();
+            generator.observe(&json!(null)).unwrap();
+            assert_eq!(generator.schema(), json!({"oneOf": [primitive, none]}));
+        }
+
+        // An integer joins the number of a type array, and keywords carry over.
+        let dated = json!({"type": "string", "format": "date"});
+        assert_eq!(
+            merge(&[json!(1), json!(null), json!(2.5)].map(|v| generate_json_schema(&v))),
+            json!({"type": ["number", "null"]})
+        );
+        assert_eq!(
+            merge(&[dated.clone(), none.clone()]),
+            json!({"type": ["string", "null"], "format": "date"})
+        );
+        // A third type, or a branch that isn't a primitive, stays a oneOf.
+        assert_eq!(
+            merge(&[
+                json!({"type": ["string", "null"]}),
+                json!({"type": "boolean"})
+            ]),
+            json!({"oneOf": [{"type": "string"}, {"type": "null"}, {"type": "boolean"}]})
+        );
+        let array = json!({"type": "array", "items": {"type": "integer"}});
+        assert_eq!(
+            merge(&[array.clone(), none.clone()]),
+            json!({"oneOf": [array, none]})
+        );
+        let choice = json!({"type": "string", "enum": ["a"]});
+        assert_eq!(
+            merge(&[choice.clone(), none.clone()]),
+            json!({"oneOf": [choice, none]})
+        );
+    }
 
-    json!({
-        "type": "array",
-        "items": common_schema
-    })
-}
+    #[test]
+    fn test_nullable_type_arrays_nest() {
+        let schema = generate_schema_from_samples(&[
+            json!({"user": {"name": "a", "age": 1}, "scores": [1.5, null]}),
+            json!({"user": {"name": null, "age": null}, "scores": [null, 2]}),
+            json!({"user": {"name": "b", "age": null}, "scores": [null]}),
+            json!(null),
+        ]);
+        let record = &schema["oneOf"][0];
+        assert_eq!(schema["oneOf"][1], json!({"type": "null"}));
+        assert_eq!(
+            record["properties"]["user"]["properties"],
+            json!({"name": {"type": ["string", "null"]}, "age": {"type": ["integer", "null"]}})
+        );
+        assert_eq!(
+            record["properties"]["scores"]["items"],
+            json!({"type": ["number", "null"]})
+        );
 
-fn find_common_schema(schemas: &[Value]) -> Value {
-    if schemas.is_empty() {
-        return json!({});
+        // Merging further nulls leaves the type array as it is.
+        let folded = json!({"type": ["boolean", "null"]});
+        let mut merged = folded.clone();
+        for _ in 0..3 {
+            merged = merge(&[merged, json!({"type": "null"}), folded.clone()]);
+        }
+        assert_eq!(merged, folded);
     }
 
-    let mut common = schemas[0].clone();
-    for schema in schemas.iter().skip(1) {
-        common = merge_schemas(&common, schema);
+    #[test]
+    fn test_public_merge() {
+        let a = generate_json_schema(&json!({"id": 1, "name": "a", "meta": {"x": 1, "y": 2}}));
+        let b = generate_json_schema(&json!({"id": 2, "meta": {"x": 3}, "extra": true}));
+        let merged = super::merge_schemas(&a, &b);
+        assert_eq!(merged["required"], json!(["id", "meta"]));
+        assert_eq!(merged["properties"]["meta"]["required"], json!(["x"]));
+        assert_eq!(merged["properties"]["extra"], json!({"type": "boolean"}));
+        assert_eq!(
+            merged,
+            generate_schema_from_samples(&[
+                json!({"id": 1, "name": "a", "meta": {"x": 1, "y": 2}}),
+                json!({"id": 2, "meta": {"x": 3}, "extra": true}),
+            ])
+        );
+
+        let c = generate_json_schema(&json!({"id": 3, "name": "c"}));
+        let common = find_common_schema(&[a.clone(), b, c.clone()]);
+        assert_eq!(common["required"], json!(["id"]));
+        assert_eq!(
+            find_common_schema(&[a.clone(), c])["required"],
+            json!(["id", "name"])
+        );
+        assert_eq!(find_common_schema(&[]), json!({}));
+        assert_eq!(find_common_schema(std::slice::from_ref(&a)), a);
     }
 
-    common
-}
+    #[test]
+    fn test_merge_keeps_closed_objects() {
+        let options =
+            SchemaOptions::default().with_closed_objects(ClosedWith::AdditionalProperties);
+        let a = json!({"id": 1, "address": {"city": "Oslo"}});
+        let b = json!({"id": 2, "address": {"zip": "0150"}, "tags": [{"k": 1}]});
+        let closed = |instance| try_generate_json_schema(instance, &options).unwrap();
+        let merged = super::merge_schemas(&closed(&a), &closed(&b));
+        assert_eq!(merged["additionalProperties"], false);
+        assert_eq!(
+            merged["properties"]["address"],
+            json!({
+                "type": "object",
+                "properties": {"city": {"type": "string"}, "zip": {"type": "string"}},
+                "additionalProperties": false
+            })
+        );
+        assert_eq!(
+            merged["properties"]["tags"]["items"]["additionalProperties"],
+            false
+        );
+        // An open schema opens the merge.
+        let open = super::merge_schemas(&closed(&a), &generate_json_schema(&b));
+        assert!(open.get("additionalProperties").is_none());
 
-fn merge_schemas(schema1: &Value, schema2: &Value) -> Value {
-    if schema1 == schema2 {
-        return schema1.clone();
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let validator = jsonschema::validator_for(&merged).unwrap();
+            assert!(validator.is_valid(&a) && validator.is_valid(&b));
+            assert!(!validator.is_valid(&json!({"id": 3, "adress": {}})));
+        }
     }
 
-    let mut merged = json!({
-        "oneOf": [schema1, schema2]
-    });
+    #[test]
+    fn test_merge_enum_with_plain_string() {
+        let listed = json!({"type": "string", "enum": ["active", "closed"]});
+        let plain = json!({"type": "string"});
+        assert_eq!(merge(&[listed.clone(), plain.clone()]), plain);
+        assert_eq!(merge(&[plain.clone(), listed.clone()]), plain);
+        assert_eq!(merge(&[listed.clone(), listed.clone()]), listed);
+        // The values a merged schema lists are those of every sample, or none.
+        let other = json!({"type": "string", "enum": ["pending"]});
+        assert_eq!(merge(&[listed, other]), plain);
+    }
 
-    if let (Value::Object(obj1), Value::Object(obj2)) = (schema1, schema2) {
-        if obj1.get("type") == obj2.get("type") {
-            merged = json!({
-                "type": obj1["type"].clone()
-            });
+    #[test]
+    #[should_panic(expected = "schema generation failed")]
+    fn test_with_options_panics_on_failure() {
+        let options = SchemaOptions::default().with_strictness(Strictness::Strict);
+        generate_json_schema_with_options(&json!([1, "a"]), &options);
+    }
 
-            if obj1.contains_key("properties") && obj2.contains_key("properties") {
-                let mut properties = Map::new();
-                let props1 = obj1["properties"].as_object().unwrap();
-                let props2 = obj2["properties"].as_object().unwrap();
+    #[test]
+    fn test_merge_treats_empty_forms_alike() {
+        let nested = json!({"type": "object", "properties": {"b": {"type": "integer"}}});
+        for empty in [
+            json!({"type": "object"}),
+            json!({"type": "object", "properties": {}, "required": []}),
+        ] {
+            assert_eq!(merge(&[empty.clone(), nested.clone()]), nested);
+            assert_eq!(merge(&[nested.clone(), empty]), nested);
+        }
 
-                for (key, value) in props1.iter().chain(props2.iter()) {
-                    properties.insert(key.clone(), value.clone());
-                }
+        let items = json!({"type": "array", "items": {"type": "integer"}});
+        for empty in [
+            json!({"type": "array"}),
+            json!({"type": "array", "items": {}}),
+            json!({"type": "array", "items": true}),
+        ] {
+            let expected = json!({"type": "array", "items": {}});
+            assert_eq!(merge(&[items.clone(), empty.clone()]), expected);
+            assert_eq!(merge(&[empty, items.clone()]), expected);
+        }
+    }
 
-                merged["properties"] = Value::Object(properties);
-            }
+    /// Merges `schemas` in order, as the items of an array are.
+    fn merge(schemas: &[Value]) -> Value {
+        let options = SchemaOptions::default();
+        let mut state = State::default();
+        let mut ctx = Context::new(&options, &mut state);
+        let mut merged = schemas[0].clone();
+        for schema in &schemas[1..] {
+            merge_into(&mut merged, schema, &mut ctx, &SchemaPath::root());
         }
+        merged
     }
 
-    merged
-}
+    fn strict() -> SchemaOptions {
+        SchemaOptions {
+            strictness: Strictness::Strict,
+            ..Default::default()
+        }
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use serde_json::json;
+    #[test]
+    fn test_strict_type_union() {
+        assert_eq!(
+            try_generate_json_schema(&json!([1, 2.5, "a"]), &strict()),
+            Err(GeneratorError::Conflict {
+                path: "/items".to_string(),
+                left: json!({"type": "number"}),
+                right: json!({"type": "string"}),
+            })
+        );
+        // Integers widen to numbers without a union.
+        assert!(try_generate_json_schema(&json!([1, 2.5]), &strict()).is_ok());
+        assert!(try_generate_json_schema(&json!([1, "a"]), &SchemaOptions::default()).is_ok());
+    }
 
     #[test]
-    fn test_generate_json_schema_string() {
-        let input = json!("test");
-        let expected = json!({"type": "string"});
-        assert_eq!(generate_json_schema(&input), expected);
+    fn test_strict_required_demotion() {
+        let mut generator = SchemaGenerator::new(strict());
+        generator.observe(&json!({"a": 1, "b": 2})).unwrap();
+        generator.observe(&json!({"a": 3, "b": 4})).unwrap();
+        let error = generator.observe(&json!({"a": 5})).unwrap_err();
+        assert!(matches!(error, GeneratorError::Conflict { ref path, .. } if path.is_empty()));
+        let conflict = &generator.conflicts()[0];
+        assert_eq!(conflict.code, "required-demotion");
+        assert_eq!(
+            conflict.to_string(),
+            r#"/ [required-demotion]: the property "b" is missing from some samples, so it would no longer be required"#
+        );
+        assert_eq!(
+            conflict.right["properties"],
+            json!({"a": {"type": "integer"}})
+        );
     }
 
     #[test]
-    fn test_generate_json_schema_integer() {
-        let input = json!(42);
-        let expected = json!({"type": "integer"});
-        assert_eq!(generate_json_schema(&input), expected);
+    fn test_strict_format_drop() {
+        let options = strict();
+        let mut state = State::default();
+        let mut ctx = Context::new(&options, &mut state);
+        let mut target = json!({"type": "string", "format": "date"});
+        merge_into(
+            &mut target,
+            &json!({"type": "string"}),
+            &mut ctx,
+            &SchemaPath::root(),
+        );
+        let mut other = json!({"type": "string", "format": "email"});
+        let root = SchemaPath::root();
+        let path = root.property("b");
+        merge_into(
+            &mut other,
+            &json!({"type": "string", "format": "uri"}),
+            &mut ctx,
+            &path,
+        );
+        merge_into(&mut other, &json!({"type": "string"}), &mut ctx, &path);
+
+        let messages: Vec<String> = state.conflicts.iter().map(|c| c.to_string()).collect();
+        assert_eq!(
+            messages,
+            [
+                r#"/ [format-drop]: the format "date" holds for only some samples"#,
+                r#"/properties/b [format-drop]: the formats "email" and "uri" can't both be kept"#,
+            ]
+        );
+        assert_eq!(
+            state.conflicts[0].left,
+            json!({"type": "string", "format": "date"})
+        );
     }
 
     #[test]
-    fn test_generate_json_schema_number() {
-        let input = json!(3.14);
-        let expected = json!({"type": "number"});
-        assert_eq!(generate_json_schema(&input), expected);
+    fn test_strict_reports_every_conflict() {
+        let records = [
+            json!({"id": 1, "tags": ["a"], "owner": {"name": "x"}}),
+            json!({"id": "2", "tags": ["b", 3], "owner": {}}),
+            json!({"id": 3.5, "tags": [], "owner": {"name": "y"}}),
+        ];
+        let mut generator = SchemaGenerator::new(strict());
+        let mut permissive = SchemaGenerator::new(SchemaOptions::default());
+        for record in &records {
+            let _ = generator.observe(record);
+            permissive.observe(record).unwrap();
+        }
+
+        let conflicts: Vec<(&str, &str)> = generator
+            .conflicts()
+            .iter()
+            .map(|c| (c.code.as_str(), c.pointer.as_str()))
+            .collect();
+        assert_eq!(
+            conflicts,
+            [
+                ("type-union", "/properties/tags/items"),
+                ("type-union", "/properties/id"),
+                ("required-demotion", "/properties/owner"),
+            ]
+        );
+        assert!(permissive.conflicts().is_empty());
+        assert_eq!(generator.schema(), permissive.schema());
+        assert!(matches!(
+            generator.finish(),
+            Err(GeneratorError::Conflict { path, .. }) if path == "/properties/tags/items"
+        ));
+    }
+
+    /// Generates the schema of `records` with `merge_policy`, and returns the generator.
+    fn generate_with_policy(records: &[Value], merge_policy: MergePolicy) -> SchemaGenerator {
+        let mut generator = SchemaGenerator::new(SchemaOptions {
+            coordinates: Some(Coordinates::default()),
+            merge_policy,
+            ..Default::default()
+        });
+        for record in records {
+            generator.observe(record).unwrap();
+        }
+        generator
+    }
+
+    /// Records where a coordinate goes out of range, a type changes and a property goes
+    /// missing.
+    fn widening_records() -> [Value; 3] {
+        [
+            json!({"lat": 45.5, "lng": 10.5, "id": 1}),
+            json!({"lat": 95.5, "lng": 10.5, "id": "x"}),
+            json!({"lat": 50.5, "lng": 10.5}),
+        ]
     }
 
     #[test]
-    fn test_generate_json_schema_boolean() {
-        let input = json!(true);
-        let expected = json!({"type": "boolean"});
-        assert_eq!(generate_json_schema(&input), expected);
+    fn test_merge_policies() {
+        let records = widening_records();
+        let bounded = json!({"type": "number", "minimum": -90, "maximum": 90});
+        let id = json!({"oneOf": [{"type": "integer"}, {"type": "string"}]});
+
+        let permissive = generate_with_policy(&records, MergePolicy::Permissive).schema();
+        assert_eq!(permissive["properties"]["lat"], json!({"type": "number"}));
+        assert_eq!(permissive["properties"]["id"], id);
+
+        let conservative = generate_with_policy(&records, MergePolicy::Conservative).schema();
+        assert_eq!(
+            conservative["properties"]["lat"],
+            json!({"anyOf": [bounded, {"type": "number"}]})
+        );
+        assert_eq!(conservative["properties"]["id"], id);
+
+        let annotated = generate_with_policy(&records, MergePolicy::Annotated);
+        assert_eq!(annotated.schema(), permissive);
+        let warnings = annotated.warnings();
+        assert!(warnings.iter().any(|warning| warning.code == "widening"
+            && warning.message == "record 3: the property \"id\" is missing from some samples, so it would no longer be required"));
+        assert!(generate_with_policy(&records, MergePolicy::Permissive)
+            .widenings()
+            .is_empty());
     }
 
     #[test]
-    fn test_generate_json_schema_null() {
-        let input = json!(null);
-        let expected = json!({"type": "null"});
-        assert_eq!(generate_json_schema(&input), expected);
+    fn test_annotated_widenings() {
+        let generator = generate_with_policy(&widening_records(), MergePolicy::Annotated);
+        let notes: Vec<(&str, &str, &Value, usize)> = generator
+            .widenings()
+            .iter()
+            .map(|w| (w.code.as_str(), w.pointer.as_str(), &w.dropped, w.record))
+            .collect();
+        assert_eq!(
+            notes,
+            [
+                (
+                    "type-union",
+                    "/properties/id",
+                    &json!({"type": ["integer"]}),
+                    2
+                ),
+                (
+                    "keyword-drop",
+                    "/properties/lat",
+                    &json!({"maximum": 90}),
+                    2
+                ),
+                (
+                    "keyword-drop",
+                    "/properties/lat",
+                    &json!({"minimum": -90}),
+                    2
+                ),
+                ("required-demotion", "", &json!({"required": ["id"]}), 3),
+            ]
+        );
+        assert_eq!(
+            generator.widenings()[1].to_string(),
+            "/properties/lat [keyword-drop]: record 2: the maximum 90 holds for only some samples"
+        );
+        // Each widening is recorded once.
+        let mut records = widening_records().to_vec();
+        records.push(json!({"lat": 1.5, "lng": 1.5}));
+        let generator = generate_with_policy(&records, MergePolicy::Annotated);
+        assert_eq!(generator.widenings().len(), notes.len());
+
+        // Disagreeing values are both listed.
+        let options = SchemaOptions {
+            merge_policy: MergePolicy::Annotated,
+            ..Default::default()
+        };
+        let mut state = State::default();
+        let mut ctx = Context::new(&options, &mut state);
+        let mut target = json!({"type": "string", "format": "email"});
+        let other = json!({"type": "string", "format": "uri"});
+        merge_into(&mut target, &other, &mut ctx, &SchemaPath::root());
+        assert_eq!(target, json!({"type": "string"}));
+        assert_eq!(state.widenings[0].code, "format-drop");
+        assert_eq!(
+            state.widenings[0].dropped,
+            json!({"format": ["email", "uri"]})
+        );
+        assert!(state.conflicts.is_empty());
     }
 
     #[test]
-    fn test_generate_object_schema() {
-        let input = json!({
-            "name": "John Doe",
-            "age": 30,
-            "is_student": false
-        });
-        let expected = json!({
-            "$schema": "http://json-schema.org/draft-07/schema#",
-            "type": "object",
-            "properties": {
-                "name": {"type": "string"},
-                "age": {"type": "integer"},
-                "is_student": {"type": "boolean"}
-            },
-            "required": ["age", "is_student", "name"]
-        });
-        assert_eq!(generate_json_schema(&input), expected);
+    fn test_conservative_unions_nest() {
+        let records = [
+            json!({"lat": 45.5, "lng": 1.5}),
+            json!({"lat": 95.5, "lng": 1.5}),
+            json!({"lat": "n/a", "lng": 1.5}),
+            json!({"lat": 100.5, "lng": 1.5}),
+            json!({"lat": -10, "lng": 1.5}),
+        ];
+        let schema = generate_with_policy(&records, MergePolicy::Conservative).schema();
+        assert_eq!(
+            schema["properties"]["lat"],
+            json!({"oneOf": [
+                {"anyOf": [
+                    {"type": "number", "minimum": -90, "maximum": 90},
+                    {"type": "number"},
+                ]},
+                {"type": "string"},
+            ]})
+        );
     }
 
+    #[cfg(not(target_arch = "wasm32"))]
     #[test]
-    fn test_generate_array_schema() {
-        let input = json!([1, 2, 3]);
-        let expected = json!({
-            "type": "array",
-            "items": {"type": "integer"}
-        });
-        assert_eq!(generate_json_schema(&input), expected);
+    fn test_conservative_samples_validate() {
+        let records = widening_records();
+        let schema = generate_with_policy(&records, MergePolicy::Conservative).schema();
+        for record in &records {
+            assert!(crate::validation::validate_instance(&schema, record).is_ok());
+        }
     }
 
     #[test]
-    fn test_generate_array_schema_mixed_types() {
-        let input = json!([1, "two", 3.0]);
-        let expected = json!({
-            "type": "array",
-            "items": {
-                "oneOf": [
-                    {"oneOf": [
-                        {"type": "integer"},
-                        {"type": "string"}
-                    ]},
-                    {"type": "number"}
-                ]
-            }
+    fn test_post_transform_sees_the_finished_schema() {
+        let hook: TransformHook = Arc::new(|mut schema: Value| {
+            let click = schema
+                .pointer_mut("/definitions/Click")
+                .and_then(Value::as_object_mut)
+                .ok_or("no definitions")?;
+            click.insert("additionalProperties".to_string(), json!(false));
+            schema["$comment"] = json!("Copyright Example Corp");
+            Ok(schema)
         });
-        assert_eq!(generate_json_schema(&input), expected);
+        let options = SchemaOptions {
+            discriminators: Some(Discriminators {
+                style: DiscriminatorStyle::OpenApi,
+                ..Default::default()
+            }),
+            post_transform: Some(hook),
+            ..Default::default()
+        };
+        let mut generator = SchemaGenerator::new(options);
+        for i in 0..4 {
+            let record = match i % 2 {
+                0 => json!({"type": "click", "x": i}),
+                _ => json!({"type": "purchase", "amount": 1.5}),
+            };
+            generator.observe(&record).unwrap();
+        }
+        let schema = generator.finish().unwrap();
+        assert_eq!(
+            schema["definitions"]["Click"]["additionalProperties"],
+            false
+        );
+        assert_eq!(schema["$comment"], "Copyright Example Corp");
+        // Keys the hook added are still written in order.
+        assert_eq!(
+            serde_json::to_string(&schema).unwrap(),
+            to_canonical_string(&schema)
+        );
+        let keys: Vec<&String> = schema.as_object().unwrap().keys().collect();
+        assert_eq!(
+            keys,
+            [
+                "$comment",
+                "$schema",
+                "definitions",
+                "discriminator",
+                "oneOf"
+            ]
+        );
     }
 
     #[test]
-    fn test_generate_array_schema_empty() {
-        let input = json!([]);
-        let expected = json!({
-            "type": "array",
-            "items": {}
-        });
-        assert_eq!(generate_json_schema(&input), expected);
+    fn test_post_transform_errors() {
+        let options = SchemaOptions {
+            post_transform: Some(Arc::new(|schema: Value| match schema.get("$defs") {
+                Some(_) => Ok(schema),
+                None => Err("no definitions".to_string()),
+            })),
+            ..Default::default()
+        };
+        let error = try_generate_json_schema(&json!({"a": 1}), &options).unwrap_err();
+        assert_eq!(
+            error,
+            GeneratorError::Transform("no definitions".to_string())
+        );
+        assert_eq!(error.to_string(), "post-processing failed: no definitions");
+
+        let raw = serde_json::value::to_raw_value(&json!(1)).unwrap();
+        assert!(matches!(
+            generate_from_raw(&raw, &options),
+            Err(GeneratorError::Transform(_))
+        ));
+        // Only the fallible entry points apply the hook.
+        let mut generator = SchemaGenerator::new(options);
+        generator.observe(&json!(1)).unwrap();
+        assert_eq!(generator.schema(), json!({"type": "integer"}));
+        assert!(generator.finish().is_err());
     }
 
     #[test]
-    fn test_find_common_schema() {
+    fn test_merge_schemas_flattens_unions() {
         let schemas = vec![
             json!({"type": "integer"}),
             json!({"type": "string"}),
             json!({"type": "boolean"}),
+            json!({"oneOf": [{"type": "null"}, {"type": "array"}]}),
         ];
         let expected = json!({
             "oneOf": [
-                {"oneOf": [
-                    {"type": "integer"},
-                    {"type": "string"}
-                ]},
-                {"type": "boolean"}
+                {"type": "integer"},
+                {"type": "string"},
+                {"type": "boolean"},
+                {"type": "null"},
+                {"type": "array"}
             ]
         });
-        assert_eq!(find_common_schema(&schemas), expected);
+        assert_eq!(merge(&schemas), expected);
+    }
+
+    #[test]
+    fn test_merge_schemas_unions_on_both_sides() {
+        let left = json!({"oneOf": [{"type": "integer"}, {"type": "string"}]});
+        let right =
+            json!({"oneOf": [{"type": "string"}, {"type": "boolean"}, {"type": "integer"}]});
+        let expected = json!({
+            "oneOf": [{"type": "integer"}, {"type": "string"}, {"type": "boolean"}]
+        });
+        assert_eq!(merge(&[left.clone(), right.clone()]), expected);
+        // Repeated alternatives join their branch rather than duplicating it.
+        let schemas = [json!({"type": "null"}), left, right.clone(), right];
+        let merged = merge(&schemas);
+        assert_eq!(merged["oneOf"].as_array().unwrap().len(), 4);
+        assert!(merged["oneOf"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .all(|branch| branch.get("oneOf").is_none()));
     }
 
     #[test]
@@ -275,7 +4254,52 @@ mod tests {
                 "b": {"type": "integer"}
             }
         });
-        assert_eq!(merge_schemas(&schema1, &schema2), expected);
+        assert_eq!(merge(&[schema1, schema2]), expected);
+
+        // A schema without required requires nothing of the other's properties.
+        let required = json!({
+            "type": "object",
+            "properties": {"a": {"type": "string"}},
+            "required": ["a"]
+        });
+        let merged = merge(&[required.clone(), expected.clone()]);
+        assert!(merged.get("required").is_none(), "{}", merged);
+        let merged = merge(&[expected, required.clone()]);
+        assert!(merged.get("required").is_none(), "{}", merged);
+
+        // Across several schemas, an intersection that runs out leaves no required.
+        let schemas = [
+            required,
+            json!({"type": "object", "properties": {"a": {"type": "string"}, "b": {"type": "integer"}}, "required": ["a", "b"]}),
+            json!({"type": "object", "properties": {"b": {"type": "integer"}}, "required": ["b"]}),
+        ];
+        assert_eq!(merge(&schemas[..2])["required"], json!(["a"]));
+        let merged = merge(&schemas);
+        assert!(merged.get("required").is_none(), "{}", merged);
+        assert_eq!(merged["properties"].as_object().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_merge_schemas_typeless_const() {
+        let version = json!({"const": "1.0"});
+        let string = json!({"type": "string"});
+        assert_eq!(merge(&[version.clone(), string.clone()]), string);
+        assert_eq!(merge(&[string.clone(), version.clone()]), string);
+        assert_eq!(merge(&[version.clone(), json!({"const": "2.0"})]), string);
+        assert_eq!(
+            merge(&[version.clone(), json!({"enum": ["2.0", "3.0"]})]),
+            string
+        );
+        // Values of another type, or of several, are kept apart.
+        assert_eq!(
+            merge(&[version.clone(), json!({"type": "integer"})]),
+            json!({"oneOf": [version, {"type": "integer"}]})
+        );
+        let mixed = json!({"enum": ["a", 1]});
+        assert_eq!(
+            merge(&[mixed.clone(), string.clone()]),
+            json!({"oneOf": [mixed, string]})
+        );
     }
 
     #[test]
@@ -288,7 +4312,7 @@ mod tests {
                 {"type": "integer"}
             ]
         });
-        assert_eq!(merge_schemas(&schema1, &schema2), expected);
+        assert_eq!(merge(&[schema1, schema2]), expected);
     }
 
     #[test]
@@ -318,4 +4342,504 @@ mod tests {
         });
         assert_eq!(generate_json_schema(&input), expected);
     }
+
+    #[test]
+    fn test_generate_schema_with_non_string_ref() {
+        let input = json!({"$ref": 5});
+        let expected = json!({
+            "$schema": "http://json-schema.org/draft-07/schema#",
+            "type": "object",
+            "properties": {
+                "$ref": {"type": "integer"}
+            },
+            "required": ["$ref"]
+        });
+        assert_eq!(generate_json_schema(&input), expected);
+    }
+
+    #[test]
+    fn test_ref_passthrough_warning() {
+        let mut generator = SchemaGenerator::new(SchemaOptions::default());
+        generator
+            .observe(&json!({"a": {"$ref": "#/definitions/a"}}))
+            .unwrap();
+        assert_eq!(
+            generator.warnings(),
+            &[Warning {
+                code: "ref-passthrough".to_string(),
+                pointer: "/properties/a".to_string(),
+                message: r##"copied the reference "#/definitions/a" into the schema without resolving it"##
+                    .to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_cancel_flag_set_before_generation() {
+        let cancel = Arc::new(AtomicBool::new(true));
+        let options = SchemaOptions {
+            cancel: Some(cancel),
+            ..Default::default()
+        };
+        assert_eq!(
+            try_generate_json_schema(&json!({"a": 1}), &options),
+            Err(GeneratorError::Cancelled)
+        );
+    }
+
+    #[test]
+    fn test_cancel_from_another_thread() {
+        use std::thread;
+        use std::time::{Duration, Instant};
+
+        let input = Value::Array(
+            (0..1_000_000)
+                .map(|i| json!({"id": i, "tags": ["a", "b"]}))
+                .collect(),
+        );
+        let cancel = Arc::new(AtomicBool::new(false));
+        let options = SchemaOptions {
+            cancel: Some(cancel.clone()),
+            ..Default::default()
+        };
+
+        let flipper = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            cancel.store(true, Ordering::Relaxed);
+            Instant::now()
+        });
+        let result = try_generate_json_schema(&input, &options);
+        let stopped = Instant::now();
+        let flipped = flipper.join().unwrap();
+
+        assert_eq!(result, Err(GeneratorError::Cancelled));
+        assert!(stopped.duration_since(flipped) < Duration::from_millis(100));
+    }
+
+    #[test]
+    fn test_schema_generator_observe() {
+        let mut generator = SchemaGenerator::new(SchemaOptions::default());
+        assert_eq!(generator.schema(), json!({}));
+
+        let record = json!({"name": "John", "age": 30});
+        generator.observe(&record).unwrap();
+        assert_eq!(generator.schema(), generate_json_schema(&record));
+
+        generator
+            .observe(&json!({"name": "Jane", "age": 31}))
+            .unwrap();
+        assert_eq!(generator.records(), 2);
+        assert_eq!(
+            generator.schema()["$schema"],
+            json!("http://json-schema.org/draft-07/schema#")
+        );
+    }
+
+    #[test]
+    fn test_schema_generator_observe_cancelled() {
+        let cancel = Arc::new(AtomicBool::new(false));
+        let mut generator = SchemaGenerator::new(SchemaOptions {
+            cancel: Some(cancel.clone()),
+            ..Default::default()
+        });
+        generator.observe(&json!(1)).unwrap();
+        cancel.store(true, Ordering::Relaxed);
+        assert_eq!(generator.observe(&json!(2)), Err(GeneratorError::Cancelled));
+        assert_eq!(generator.records(), 1);
+    }
+
+    fn recording_options() -> (SchemaOptions, Arc<std::sync::Mutex<Vec<ProgressEvent>>>) {
+        let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = events.clone();
+        let options = SchemaOptions {
+            progress: Some(Arc::new(move |event: &ProgressEvent| {
+                sink.lock().unwrap().push(event.clone())
+            })),
+            progress_interval: Duration::ZERO,
+            ..Default::default()
+        };
+        (options, events)
+    }
+
+    #[test]
+    fn test_progress_reports_totals() {
+        let (options, events) = recording_options();
+        let input = Value::Array((0..5000).map(|i| json!({"id": i})).collect());
+
+        try_generate_json_schema(&input, &options).unwrap();
+
+        let events = events.lock().unwrap();
+        assert!(events.len() > 1);
+        for pair in events.windows(2) {
+            assert!(pair[0].nodes <= pair[1].nodes);
+            assert!(pair[0].records <= pair[1].records);
+        }
+        let intermediate = &events[0];
+        assert_eq!(intermediate.pointer.as_deref(), Some(""));
+        assert_eq!(intermediate.array_elements, 1023);
+        // The array itself, plus each element object and its "id" value.
+        let last = events.last().unwrap();
+        assert_eq!(last.nodes, 1 + 2 * 5000);
+        assert_eq!(last.records, 1);
+        assert_eq!(last.bytes, None);
+    }
+
+    #[test]
+    fn test_progress_is_throttled() {
+        let (mut options, events) = recording_options();
+        options.progress_interval = Duration::from_secs(3600);
+        let input = Value::Array((0..5000).map(|i| json!(i)).collect());
+
+        try_generate_json_schema(&input, &options).unwrap();
+
+        // Only the first check and the final totals get through.
+        assert_eq!(events.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_progress_across_records() {
+        let (options, events) = recording_options();
+        let mut generator = SchemaGenerator::new(options);
+        for i in 0..3 {
+            generator.observe(&json!({"id": i})).unwrap();
+        }
+        generator.report_progress();
+
+        let events = events.lock().unwrap();
+        let records: Vec<usize> = events.iter().map(|e| e.records).collect();
+        assert_eq!(records, vec![1, 2, 3, 3]);
+        assert_eq!(events.last().unwrap().nodes, 6);
+    }
+
+    /// Nests `leaf` in `depth` alternating arrays and objects.
+    fn nested(depth: usize, leaf: Value) -> Value {
+        (0..depth).fold(leaf, |inner, level| {
+            // `json!` would serialize `inner`, recursing through it.
+            if level % 2 == 0 {
+                Value::Array(vec![inner])
+            } else {
+                Value::Object(Map::from_iter([("a".to_string(), inner)]))
+            }
+        })
+    }
+
+    /// Drops a value built by [`nested`] without recursing, which would overflow the
+    /// stack for the deepest values.
+    fn drop_nested(mut value: Value) {
+        loop {
+            value = match value {
+                Value::Array(mut items) => items.pop().unwrap_or_default(),
+                Value::Object(mut obj) => obj.remove("a").unwrap_or_default(),
+                _ => return,
+            };
+        }
+    }
+
+    // Regression for fuzz/regressions/generate/deep-nesting: the generator recursed once
+    // per level and overflowed the stack.
+    #[test]
+    fn test_deep_nesting_is_cut_off() {
+        let input = nested(100_000, json!(1));
+        let mut generator = SchemaGenerator::new(SchemaOptions::default());
+        generator.observe(&input).unwrap();
+        drop_nested(input);
+
+        let schema = generator.schema();
+        let mut node = &schema;
+        let mut depth = 0;
+        while let Some(inner) = node.get("items").or_else(|| node.pointer("/properties/a")) {
+            node = inner;
+            depth += 1;
+        }
+        assert_eq!((depth, node), (DEFAULT_MAX_DEPTH, &json!({})));
+        let warning = &generator.warnings()[0];
+        assert_eq!(warning.code, "depth-limit");
+        assert_eq!(warning.pointer.matches('/').count(), 96);
+    }
+
+    #[test]
+    fn test_max_depth() {
+        let options = SchemaOptions {
+            max_depth: 2,
+            ..Default::default()
+        };
+        let input = json!({"a": [1, [2]], "b": [{"c": 3}], "d": 4});
+        let expected = json!({
+            "$schema": "http://json-schema.org/draft-07/schema#",
+            "type": "object",
+            "properties": {
+                "a": {"type": "array", "items": {}},
+                "b": {"type": "array", "items": {}},
+                "d": {"type": "integer"}
+            },
+            "required": ["a", "b", "d"]
+        });
+        let mut generator = SchemaGenerator::new(options);
+        generator.observe(&input).unwrap();
+        assert_eq!(generator.schema(), expected);
+        let warnings: Vec<_> = generator
+            .warnings()
+            .into_iter()
+            .filter(|warning| warning.code == "depth-limit")
+            .map(|warning| (warning.code, warning.pointer))
+            .collect();
+        assert_eq!(
+            warnings,
+            [
+                ("depth-limit".to_string(), "/properties/a/items".to_string()),
+                ("depth-limit".to_string(), "/properties/b/items".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_max_properties() {
+        let options = SchemaOptions {
+            max_properties: 2,
+            ..Default::default()
+        };
+        let schema =
+            try_generate_json_schema(&json!({"d": 1, "c": 2, "b": 3, "$ref": "#/a"}), &options)
+                .unwrap();
+        assert_eq!(
+            schema["properties"],
+            json!({"b": {"type": "integer"}, "c": {"type": "integer"}})
+        );
+        assert_eq!(schema["required"], json!(["b", "c"]));
+        assert_eq!(schema["$ref"], "#/a");
+
+        let mut generator = SchemaGenerator::new(options);
+        for record in [json!({"a": 1}), json!({"b": 2}), json!({"c": 3, "a": 4.5})] {
+            generator.observe(&record).unwrap();
+        }
+        assert_eq!(
+            generator.schema()["properties"],
+            json!({"a": {"type": "number"}, "b": {"type": "integer"}})
+        );
+        assert_eq!(
+            generator.warnings()[0],
+            Warning {
+                code: "property-limit".to_string(),
+                pointer: String::new(),
+                message: "left out the properties beyond the first 2".to_string(),
+            }
+        );
+    }
+
+    // Regression for fuzz/regressions/generate/wide-array and alternating-shapes: merging
+    // copied the accumulated schema for every item, which took quadratic time once the
+    // items had many distinct keys between them.
+    #[test]
+    fn test_wide_arrays_merge_in_place() {
+        let count = DEFAULT_MAX_PROPERTIES + 5000;
+        let input = Value::Array(
+            (0..count)
+                .map(|i| match i % 3 {
+                    0 => json!({ format!("k{:05}", i): i }),
+                    1 => json!([i]),
+                    _ => json!("x"),
+                })
+                .collect(),
+        );
+
+        let mut generator = SchemaGenerator::new(SchemaOptions::default());
+        generator.observe(&input).unwrap();
+        let schema = generator.schema();
+        let branches = schema["items"]["oneOf"].as_array().unwrap();
+        assert_eq!(branches[0]["type"], "object");
+        assert_eq!(
+            branches[0]["properties"].as_object().unwrap().len(),
+            count / 3
+        );
+        assert_eq!(
+            branches[1],
+            json!({"type": "array", "items": {"type": "integer"}})
+        );
+
+        let wide = Value::Array(
+            (0..count)
+                .map(|i| json!({ format!("k{:05}", i): i }))
+                .collect(),
+        );
+        let schema = generate_json_schema(&wide);
+        let properties = schema["items"]["properties"].as_object().unwrap();
+        assert_eq!(properties.len(), DEFAULT_MAX_PROPERTIES);
+        assert!(properties.contains_key("k09999") && !properties.contains_key("k10000"));
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    mod invariants {
+        use super::*;
+        use crate::meta::check_schema;
+        use crate::validation::validate_instance;
+        use proptest::prelude::*;
+
+        /// Arbitrary JSON documents. Keys come from a small alphabet so that merged
+        /// objects share properties; `$ref` keys are left out because they are passed
+        /// through as references rather than described.
+        fn arb_json() -> impl Strategy<Value = Value> {
+            let leaf = prop_oneof![
+                Just(Value::Null),
+                any::<bool>().prop_map(Value::from),
+                any::<i64>().prop_map(Value::from),
+                any::<u64>().prop_map(Value::from),
+                any::<f64>().prop_map(Value::from),
+                "[a-z]{0,3}".prop_map(Value::from),
+            ];
+            leaf.prop_recursive(5, 64, 6, |inner| {
+                prop_oneof![
+                    prop::collection::vec(inner.clone(), 0..6).prop_map(Value::Array),
+                    prop::collection::btree_map("[a-c]", inner, 0..4)
+                        .prop_map(|map| Value::Object(map.into_iter().collect())),
+                ]
+            })
+        }
+
+        /// Arbitrary JSON documents whose keys are schema keywords, such as `$ref`, `type`
+        /// and `required`, with values of any type.
+        fn arb_keyword_json() -> impl Strategy<Value = Value> {
+            let keys = prop::sample::select(vec![
+                "$ref",
+                "type",
+                "required",
+                "properties",
+                "items",
+                "oneOf",
+                "anyOf",
+                "enum",
+                "const",
+                "$defs",
+                "format",
+                "minimum",
+                "additionalProperties",
+                "a",
+            ]);
+            let leaf = prop_oneof![
+                Just(Value::Null),
+                any::<bool>().prop_map(Value::from),
+                any::<i64>().prop_map(Value::from),
+                any::<f64>().prop_map(Value::from),
+                prop::sample::select(vec!["string", "object", "array", "#/$defs/a", "#", ""])
+                    .prop_map(Value::from),
+            ];
+            leaf.prop_recursive(6, 96, 6, move |inner| {
+                prop_oneof![
+                    prop::collection::vec(inner.clone(), 0..5).prop_map(Value::Array),
+                    prop::collection::btree_map(keys.clone(), inner, 0..5).prop_map(|map| {
+                        Value::Object(map.into_iter().map(|(k, v)| (k.to_string(), v)).collect())
+                    }),
+                ]
+            })
+        }
+
+        proptest! {
+            #![proptest_config(ProptestConfig::with_cases(512))]
+
+            #[test]
+            fn generated_schema_accepts_its_instance(instance in arb_json()) {
+                let schema = generate_json_schema(&instance);
+                prop_assert_eq!(check_schema(&schema), Ok(()), "schema: {}", schema);
+                prop_assert_eq!(validate_instance(&schema, &instance), Ok(()), "schema: {}", schema);
+            }
+
+            #[test]
+            fn schema_keywords_as_data_never_panic(records in prop::collection::vec(arb_keyword_json(), 1..5)) {
+                let options = SchemaOptions {
+                    tuples: Some(crate::Tuples::default()),
+                    ..SchemaOptions::default()
+                };
+                let mut generator = SchemaGenerator::new(options);
+                for record in &records {
+                    let _ = try_generate_json_schema(record, &SchemaOptions::default());
+                    let _ = generator.observe(record);
+                }
+                let _ = generator.finish();
+                // Malformed schemas, such as a required that isn't an array, merge too.
+                let _ = find_common_schema(&records);
+                let _ = merge_schemas(&records[0], &generate_json_schema(&records[0]));
+            }
+
+            #[test]
+            fn merged_schema_accepts_every_record(records in prop::collection::vec(arb_json(), 1..5)) {
+                let mut generator = SchemaGenerator::new(SchemaOptions::default());
+                for record in &records {
+                    generator.observe(record).unwrap();
+                }
+                let schema = generator.schema();
+                prop_assert_eq!(check_schema(&schema), Ok(()), "schema: {}", schema);
+                for record in &records {
+                    prop_assert_eq!(validate_instance(&schema, record), Ok(()), "schema: {}", schema);
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    mod tracing_events {
+        use super::*;
+        use std::sync::{Arc, Mutex};
+        use tracing::field::{Field, Visit};
+        use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+
+        #[derive(Default)]
+        struct RecordedEvent {
+            message: String,
+            pointer: String,
+            elements: Option<u64>,
+        }
+
+        impl Visit for RecordedEvent {
+            fn record_u64(&mut self, field: &Field, value: u64) {
+                if field.name() == "elements" {
+                    self.elements = Some(value);
+                }
+            }
+
+            fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+                match field.name() {
+                    "message" => self.message = format!("{:?}", value),
+                    "pointer" => self.pointer = format!("{:?}", value),
+                    _ => {}
+                }
+            }
+        }
+
+        #[derive(Clone, Default)]
+        struct Recorder(Arc<Mutex<Vec<RecordedEvent>>>);
+
+        impl<S: tracing::Subscriber> Layer<S> for Recorder {
+            fn on_event(&self, event: &tracing::Event<'_>, _ctx: Context<'_, S>) {
+                let mut recorded = RecordedEvent::default();
+                event.record(&mut recorded);
+                self.0.lock().unwrap().push(recorded);
+            }
+        }
+
+        #[test]
+        fn test_events_carry_pointers() {
+            let recorder = Recorder::default();
+            let subscriber = tracing_subscriber::registry().with(recorder.clone());
+            let input = json!({
+                "user": {
+                    "tags": ["a", 1, "b"]
+                }
+            });
+
+            tracing::subscriber::with_default(subscriber, || generate_json_schema(&input));
+
+            let events = recorder.0.lock().unwrap();
+            let fold = events
+                .iter()
+                .find(|e| e.message == "folding array items")
+                .expect("array fold event");
+            assert_eq!(fold.pointer, "/properties/user/properties/tags");
+            assert_eq!(fold.elements, Some(3));
+
+            let union = events
+                .iter()
+                .find(|e| e.message == "merged incompatible schemas into oneOf")
+                .expect("union event");
+            assert_eq!(union.pointer, "/properties/user/properties/tags/items");
+        }
+    }
 }
@@ -0,0 +1,319 @@
+//! What happens when an input trips one of the size limits of [`SchemaOptions`], and the
+//! size estimate behind [`SchemaOptions::max_schema_bytes`].
+
+use crate::SchemaOptions;
+use serde_json::{Map, Value};
+use std::fmt;
+use std::str::FromStr;
+
+/// What generation does once an input exceeds [`SchemaOptions::max_depth`],
+/// [`SchemaOptions::max_properties`] or [`SchemaOptions::max_schema_bytes`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LimitPolicy {
+    /// Describe the offending part of the input by a schema accepting anything, or leave
+    /// the properties beyond the limit out, and raise a warning at its pointer.
+    #[default]
+    Degrade,
+    /// Fail with [`GeneratorError::LimitExceeded`](crate::GeneratorError::LimitExceeded),
+    /// holding the warning [`LimitPolicy::Degrade`] would have raised.
+    Fail,
+}
+
+impl fmt::Display for LimitPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LimitPolicy::Degrade => "degrade",
+            LimitPolicy::Fail => "fail",
+        })
+    }
+}
+
+impl FromStr for LimitPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "degrade" => Ok(LimitPolicy::Degrade),
+            "fail" => Ok(LimitPolicy::Fail),
+            _ => Err(format!(
+                "unknown limit policy '{}', expected degrade or fail",
+                s
+            )),
+        }
+    }
+}
+
+/// Returns about how many bytes `value` takes serialized without whitespace, counting
+/// every number as 8 digits, without serializing it.
+pub(crate) fn estimate_bytes(value: &Value) -> usize {
+    match value {
+        Value::Null => 4,
+        Value::Bool(true) => 4,
+        Value::Bool(false) => 5,
+        Value::Number(_) => 8,
+        Value::String(s) => s.len() + 2,
+        Value::Array(items) => {
+            delimiters(items.len()) + items.iter().map(estimate_bytes).sum::<usize>()
+        }
+        // A key, its quotes and the colon, then the value.
+        Value::Object(obj) => {
+            delimiters(obj.len())
+                + obj
+                    .iter()
+                    .map(|(key, value)| key.len() + 3 + estimate_bytes(value))
+                    .sum::<usize>()
+        }
+    }
+}
+
+/// The brackets and commas of an array or object with `len` elements.
+fn delimiters(len: usize) -> usize {
+    2 + len.saturating_sub(1)
+}
+
+/// Replaces the smallest subschema of `schema` whose removal brings it within `limit`
+/// bytes by `{}`, returning its JSON Pointer. Only the schemas of properties, items and
+/// union branches are candidates, besides the root.
+pub(crate) fn collapse(schema: &mut Value, size: usize, limit: usize) -> String {
+    let excess = size.saturating_sub(limit);
+    let mut best = None;
+    find_smallest(schema, &mut Vec::new(), excess, &mut best);
+    let pointer = best.map(|(pointer, _)| pointer).unwrap_or_default();
+    if let Some(target) = schema.pointer_mut(&pointer) {
+        let dialect = match target.as_object_mut() {
+            Some(obj) if pointer.is_empty() => obj.remove("$schema"),
+            _ => None,
+        };
+        let mut unconstrained = Map::new();
+        if let Some(dialect) = dialect {
+            unconstrained.insert("$schema".to_string(), dialect);
+        }
+        *target = Value::Object(unconstrained);
+    }
+    pointer
+}
+
+/// Returns the estimated size of `schema`, at the location `segments`, keeping in `best`
+/// the pointer and size of the smallest subschema saving at least `excess` bytes when
+/// replaced by `{}`.
+fn find_smallest(
+    schema: &Value,
+    segments: &mut Vec<String>,
+    excess: usize,
+    best: &mut Option<(String, usize)>,
+) -> usize {
+    let Some(obj) = schema.as_object() else {
+        return estimate_bytes(schema);
+    };
+    let mut size = delimiters(obj.len());
+    for (key, value) in obj {
+        size += key.len() + 3;
+        segments.push(key.clone());
+        size += match (key.as_str(), value) {
+            ("properties", Value::Object(properties)) => {
+                let mut size = delimiters(properties.len());
+                for (name, property) in properties {
+                    segments.push(name.replace('~', "~0").replace('/', "~1"));
+                    size += name.len() + 3 + find_smallest(property, segments, excess, best);
+                    segments.pop();
+                }
+                size
+            }
+            ("items", items) => find_smallest(items, segments, excess, best),
+            ("oneOf" | "anyOf", Value::Array(branches)) => {
+                let mut size = delimiters(branches.len());
+                for (index, branch) in branches.iter().enumerate() {
+                    segments.push(index.to_string());
+                    size += find_smallest(branch, segments, excess, best);
+                    segments.pop();
+                }
+                size
+            }
+            (_, value) => estimate_bytes(value),
+        };
+        segments.pop();
+    }
+
+    let saves = size.saturating_sub(2) >= excess;
+    if saves && best.as_ref().is_none_or(|(_, smallest)| size < *smallest) {
+        let pointer = segments
+            .iter()
+            .map(|segment| format!("/{}", segment))
+            .collect();
+        *best = Some((pointer, size));
+    }
+    size
+}
+
+/// The message of the `size-limit` warning for a schema of about `size` bytes.
+pub(crate) fn size_message(size: usize, options: &SchemaOptions, collapsed: bool) -> String {
+    let limit = options.max_schema_bytes.unwrap_or_default();
+    if collapsed {
+        format!(
+            "left unconstrained, as the schema would take about {} bytes, more than the limit of {}",
+            size, limit
+        )
+    } else {
+        format!(
+            "the schema would take about {} bytes, more than the limit of {}",
+            size, limit
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        generate_from_raw, try_generate_json_schema, GeneratorError, SchemaGenerator, Warning,
+    };
+    use serde_json::json;
+
+    fn options(max_schema_bytes: usize, on_limit: LimitPolicy) -> SchemaOptions {
+        SchemaOptions {
+            max_schema_bytes: Some(max_schema_bytes),
+            on_limit,
+            ..Default::default()
+        }
+    }
+
+    /// An object with `count` distinct keys holding objects.
+    fn wide(count: usize) -> Value {
+        let properties: Map<String, Value> = (0..count)
+            .map(|i| (format!("key{}", i), json!({"n": i})))
+            .collect();
+        Value::Object(properties)
+    }
+
+    #[test]
+    fn test_estimate_bytes() {
+        for value in [
+            json!({"type": "object", "properties": {"a": {"type": "string"}}, "required": ["a"]}),
+            json!([null, true, "x", {}, []]),
+            json!({}),
+        ] {
+            assert_eq!(estimate_bytes(&value), value.to_string().len(), "{}", value);
+        }
+        assert_eq!(estimate_bytes(&json!(12345678)), 8);
+    }
+
+    #[test]
+    fn test_degrade_collapses_the_offending_subtree() {
+        let instance = json!({"id": 1, "name": "x", "payload": wide(200)});
+        let schema =
+            try_generate_json_schema(&instance, &options(1000, LimitPolicy::Degrade)).unwrap();
+        assert_eq!(schema["properties"]["payload"], json!({}));
+        assert_eq!(schema["properties"]["id"], json!({"type": "integer"}));
+        assert!(schema.to_string().len() <= 1000);
+
+        let raw = serde_json::value::to_raw_value(&instance).unwrap();
+        assert_eq!(
+            generate_from_raw(&raw, &options(1000, LimitPolicy::Degrade)).unwrap(),
+            schema
+        );
+
+        let mut generator = SchemaGenerator::new(options(1000, LimitPolicy::Degrade));
+        generator.observe(&instance).unwrap();
+        let warning = generator
+            .warnings()
+            .into_iter()
+            .find(|warning| warning.code == "size-limit")
+            .unwrap();
+        assert_eq!(warning.pointer, "/properties/payload");
+        assert!(warning.message.contains("more than the limit of 1000"));
+    }
+
+    #[test]
+    fn test_degrade_the_root() {
+        let schema =
+            try_generate_json_schema(&wide(100), &options(200, LimitPolicy::Degrade)).unwrap();
+        assert_eq!(
+            schema,
+            json!({"$schema": "http://json-schema.org/draft-07/schema#"})
+        );
+    }
+
+    #[test]
+    fn test_degrade_across_records() {
+        let mut generator = SchemaGenerator::new(options(600, LimitPolicy::Degrade));
+        for i in 0..50 {
+            let record = json!({"id": i, "tags": {format!("tag{}", i): i}});
+            generator.observe(&record).unwrap();
+        }
+        let schema = generator.schema();
+        assert_eq!(schema["properties"]["tags"], json!({}));
+        assert_eq!(schema["properties"]["id"], json!({"type": "integer"}));
+        assert!(schema.to_string().len() <= 600);
+        let size_limits = generator
+            .warnings()
+            .into_iter()
+            .filter(|warning| warning.code == "size-limit")
+            .count();
+        assert_eq!(size_limits, 1);
+    }
+
+    #[test]
+    fn test_fail() {
+        let instance = json!({"payload": wide(200)});
+        let error =
+            try_generate_json_schema(&instance, &options(1000, LimitPolicy::Fail)).unwrap_err();
+        let GeneratorError::LimitExceeded(warning) = &error else {
+            panic!("{:?}", error);
+        };
+        assert_eq!(warning.code, "size-limit");
+        assert_eq!(warning.pointer, "");
+        assert!(error
+            .to_string()
+            .starts_with("limit exceeded: / [size-limit]"));
+
+        let mut generator = SchemaGenerator::new(options(1000, LimitPolicy::Fail));
+        generator.observe(&json!({"payload": wide(2)})).unwrap();
+        assert!(matches!(
+            generator.observe(&instance),
+            Err(GeneratorError::LimitExceeded(_))
+        ));
+        assert!(generator.finish().is_err());
+    }
+
+    #[test]
+    fn test_fail_on_depth_and_properties() {
+        let deep = SchemaOptions {
+            max_depth: 2,
+            on_limit: LimitPolicy::Fail,
+            ..Default::default()
+        };
+        assert_eq!(
+            try_generate_json_schema(&json!({"a": {"b": {"c": 1}}}), &deep),
+            Err(GeneratorError::LimitExceeded(Warning {
+                code: "depth-limit".to_string(),
+                pointer: "/properties/a/properties/b".to_string(),
+                message: "left values nested more than 2 levels deep unconstrained".to_string(),
+            }))
+        );
+
+        let narrow = SchemaOptions {
+            max_properties: 2,
+            on_limit: LimitPolicy::Fail,
+            ..Default::default()
+        };
+        let mut generator = SchemaGenerator::new(narrow.clone());
+        generator.observe(&json!({"a": 1, "b": 2})).unwrap();
+        let error = generator.observe(&json!({"c": 3})).unwrap_err();
+        assert!(
+            matches!(&error, GeneratorError::LimitExceeded(warning) if warning.code == "property-limit"),
+            "{:?}",
+            error
+        );
+        let raw = serde_json::value::to_raw_value(&wide(3)).unwrap();
+        assert!(generate_from_raw(&raw, &narrow).is_err());
+        assert!(try_generate_json_schema(&wide(2), &narrow).is_ok());
+    }
+
+    #[test]
+    fn test_parse_policy() {
+        for policy in [LimitPolicy::Degrade, LimitPolicy::Fail] {
+            assert_eq!(policy.to_string().parse(), Ok(policy));
+        }
+        assert!("ignore".parse::<LimitPolicy>().is_err());
+    }
+}
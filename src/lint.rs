@@ -0,0 +1,318 @@
+//! Lints flagging generated constructs that usually point at unrepresentative input.
+
+use crate::Warning;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use std::fmt;
+
+/// Unions with more branches than this are reported as `wide-union`.
+pub const MAX_UNION_BRANCHES: usize = 4;
+
+/// Objects with at least this many properties are reported as `many-properties`.
+pub const MAP_PROPERTY_THRESHOLD: usize = 300;
+
+/// Objects nested at least this deep are checked for `deep-required`.
+pub const DEEP_REQUIRED_DEPTH: usize = 4;
+
+/// Nested objects requiring more than this many properties are reported as
+/// `deep-required`.
+pub const DEEP_REQUIRED_THRESHOLD: usize = 10;
+
+/// A suspicious construct found by [`lint_schema`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LintWarning {
+    /// A stable identifier for the lint.
+    pub code: String,
+    /// The JSON Pointer of the schema node concerned.
+    pub pointer: String,
+    /// What was found.
+    pub message: String,
+    /// How the input or options could be changed to get a better schema.
+    pub suggestion: String,
+}
+
+impl fmt::Display for LintWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let at = if self.pointer.is_empty() {
+            "/"
+        } else {
+            &self.pointer
+        };
+        write!(
+            f,
+            "{} [{}]: {}; {}",
+            at, self.code, self.message, self.suggestion
+        )
+    }
+}
+
+impl From<LintWarning> for Warning {
+    fn from(lint: LintWarning) -> Self {
+        Warning {
+            code: lint.code,
+            pointer: lint.pointer,
+            message: format!("{}; {}", lint.message, lint.suggestion),
+        }
+    }
+}
+
+/// Checks a generated schema for constructs that almost always indicate bad input.
+///
+/// The lints are:
+///
+/// - `empty-items`: an array schema whose items are unconstrained, written as `"items": {}`,
+///   `"items": true` or left out, because only empty arrays were seen.
+/// - `wide-union`: a `oneOf` with more than [`MAX_UNION_BRANCHES`] branches.
+/// - `many-properties`: an object with at least [`MAP_PROPERTY_THRESHOLD`] properties,
+///   which is probably a map keyed by data.
+/// - `always-null`: a property that was only ever `null`.
+/// - `deep-required`: an object nested [`DEEP_REQUIRED_DEPTH`] or more levels deep that
+///   requires more than [`DEEP_REQUIRED_THRESHOLD`] properties.
+///
+/// # Examples
+///
+/// ```
+/// use serde_json::json;
+/// use json_schema_generator::{generate_json_schema, lint_schema};
+///
+/// let schema = generate_json_schema(&json!({"tags": [], "deleted_at": null}));
+/// let codes: Vec<_> = lint_schema(&schema).into_iter().map(|lint| lint.code).collect();
+/// assert_eq!(codes, ["always-null", "empty-items"]);
+/// ```
+pub fn lint_schema(schema: &Value) -> Vec<LintWarning> {
+    let mut lints = Vec::new();
+    lint_node(schema, "", 0, &mut lints);
+    lints
+}
+
+fn push(
+    lints: &mut Vec<LintWarning>,
+    code: &str,
+    pointer: &str,
+    message: String,
+    suggestion: &str,
+) {
+    lints.push(LintWarning {
+        code: code.to_string(),
+        pointer: pointer.to_string(),
+        message,
+        suggestion: suggestion.to_string(),
+    });
+}
+
+fn lint_node(schema: &Value, pointer: &str, depth: usize, lints: &mut Vec<LintWarning>) {
+    let Some(schema) = schema.as_object() else {
+        return;
+    };
+
+    if let Some(branches) = schema.get("oneOf").and_then(Value::as_array) {
+        // Schemas generated by earlier versions nest unions pairwise, so count the
+        // branches of nested unions too.
+        let mut leaves = Vec::new();
+        collect_branches(branches, pointer, &mut leaves);
+        if leaves.len() > MAX_UNION_BRANCHES {
+            push(
+                lints,
+                "wide-union",
+                pointer,
+                format!("the union has {} branches", leaves.len()),
+                "check the input for values of inconsistent shape at this location",
+            );
+        }
+        for (branch, branch_pointer) in leaves {
+            lint_node(branch, &branch_pointer, depth, lints);
+        }
+    }
+
+    if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+        lint_object(schema, properties, pointer, depth, lints);
+    }
+
+    let unconstrained = |items: &Value| items == &Value::Bool(true) || items == &json!({});
+    let array = schema.get("type").and_then(Value::as_str) == Some("array");
+    if array && schema.get("items").is_none_or(unconstrained) {
+        push(
+            lints,
+            "empty-items",
+            pointer,
+            "only empty arrays were seen, so the item type is unknown".to_string(),
+            "provide samples where this array has elements",
+        );
+    }
+    if let Some(items) = schema.get("items") {
+        lint_node(items, &format!("{}/items", pointer), depth, lints);
+    }
+}
+
+/// Collects the branches of a union, descending into branches that are unions themselves.
+fn collect_branches<'a>(
+    branches: &'a [Value],
+    pointer: &str,
+    leaves: &mut Vec<(&'a Value, String)>,
+) {
+    for (index, branch) in branches.iter().enumerate() {
+        let branch_pointer = format!("{}/oneOf/{}", pointer, index);
+        match branch.get("oneOf").and_then(Value::as_array) {
+            Some(nested) => collect_branches(nested, &branch_pointer, leaves),
+            None => leaves.push((branch, branch_pointer)),
+        }
+    }
+}
+
+fn lint_object(
+    schema: &Map<String, Value>,
+    properties: &Map<String, Value>,
+    pointer: &str,
+    depth: usize,
+    lints: &mut Vec<LintWarning>,
+) {
+    if properties.len() >= MAP_PROPERTY_THRESHOLD {
+        push(
+            lints,
+            "many-properties",
+            pointer,
+            format!("the object has {} properties", properties.len()),
+            "this is probably a map keyed by data; describe it with additionalProperties instead",
+        );
+    }
+
+    let required = schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map_or(0, Vec::len);
+    if depth >= DEEP_REQUIRED_DEPTH && required > DEEP_REQUIRED_THRESHOLD {
+        push(
+            lints,
+            "deep-required",
+            pointer,
+            format!(
+                "the object is nested {} levels deep and requires {} properties",
+                depth, required
+            ),
+            "the samples may be too uniform; provide samples where optional fields are missing",
+        );
+    }
+
+    for (name, property) in properties {
+        let child = format!(
+            "{}/properties/{}",
+            pointer,
+            name.replace('~', "~0").replace('/', "~1")
+        );
+        if property.get("type") == Some(&Value::from("null")) {
+            push(
+                lints,
+                "always-null",
+                &child,
+                format!("the property `{}` was only ever null", name),
+                "provide samples where this property has a value",
+            );
+        }
+        lint_node(property, &child, depth + 1, lints);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate_json_schema;
+    use serde_json::json;
+
+    fn codes(schema: &Value) -> Vec<(String, String)> {
+        lint_schema(schema)
+            .into_iter()
+            .map(|lint| (lint.code, lint.pointer))
+            .collect()
+    }
+
+    fn lint(code: &str, pointer: &str) -> (String, String) {
+        (code.to_string(), pointer.to_string())
+    }
+
+    #[test]
+    fn test_clean_schema() {
+        let schema = generate_json_schema(&json!({"a": 1, "b": ["x"], "c": {"d": true}}));
+        assert!(lint_schema(&schema).is_empty());
+    }
+
+    #[test]
+    fn test_empty_items() {
+        let schema = generate_json_schema(&json!({"tags": [], "matrix": [[]]}));
+        assert_eq!(
+            codes(&schema),
+            vec![
+                lint("empty-items", "/properties/matrix/items"),
+                lint("empty-items", "/properties/tags"),
+            ]
+        );
+
+        let omitted = json!({"type": "array", "items": {"type": "array"}});
+        let explicit = json!({"type": "array", "items": {"type": "array", "items": true}});
+        for schema in [omitted, explicit] {
+            assert_eq!(codes(&schema), vec![lint("empty-items", "/items")]);
+        }
+    }
+
+    #[test]
+    fn test_wide_union() {
+        let schema = generate_json_schema(&json!([1, "a", true, null, {"b": 1}]));
+        assert_eq!(codes(&schema), vec![lint("wide-union", "/items")]);
+
+        let narrow = generate_json_schema(&json!([1, "a"]));
+        assert!(lint_schema(&narrow).is_empty());
+    }
+
+    #[test]
+    fn test_many_properties() {
+        let map: Map<String, Value> = (0..MAP_PROPERTY_THRESHOLD)
+            .map(|i| (format!("id{}", i), json!(i)))
+            .collect();
+        let schema = generate_json_schema(&json!({ "by_id": map }));
+        assert_eq!(
+            codes(&schema),
+            vec![lint("many-properties", "/properties/by_id")]
+        );
+    }
+
+    #[test]
+    fn test_always_null() {
+        let schema = generate_json_schema(&json!({"a/b": {"deleted_at": null}}));
+        let lints = lint_schema(&schema);
+        assert_eq!(lints.len(), 1);
+        assert_eq!(lints[0].code, "always-null");
+        assert_eq!(lints[0].pointer, "/properties/a~1b/properties/deleted_at");
+        assert_eq!(
+            lints[0].to_string(),
+            "/properties/a~1b/properties/deleted_at [always-null]: the property `deleted_at` \
+             was only ever null; provide samples where this property has a value"
+        );
+    }
+
+    #[test]
+    fn test_deep_required() {
+        let wide: Map<String, Value> = (0..=DEEP_REQUIRED_THRESHOLD)
+            .map(|i| (format!("f{}", i), json!(i)))
+            .collect();
+        let deep = generate_json_schema(&json!({"a": {"b": {"c": {"d": wide.clone()}}}}));
+        assert_eq!(
+            codes(&deep),
+            vec![lint(
+                "deep-required",
+                "/properties/a/properties/b/properties/c/properties/d"
+            )]
+        );
+
+        let shallow = generate_json_schema(&json!({"a": {"b": wide}}));
+        assert!(lint_schema(&shallow).is_empty());
+    }
+
+    #[test]
+    fn test_into_warning() {
+        let warning = Warning::from(lint_schema(&json!({"type": "array", "items": {}})).remove(0));
+        assert_eq!(warning.code, "empty-items");
+        assert_eq!(warning.pointer, "");
+        assert!(warning
+            .message
+            .ends_with("; provide samples where this array has elements"));
+    }
+}
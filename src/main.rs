@@ -1,64 +1,1308 @@
-use clap::Parser;
-use json_schema_generator::generate_json_schema;
-use serde_json::Value;
+use clap::{Parser, Subcommand, ValueEnum};
+use json_schema_generator::{
+    BundleMode, ClosedWith, CsvOptions, EmptyCells, GeneratorError, LimitPolicy, MergePolicy,
+    ProvenanceMode, SchemaGenerator, SchemaOptions, Strictness, UnionKeyword, Warning,
+};
+use serde::Serialize;
+use serde_json::ser::PrettyFormatter;
+use serde_json::{json, Map, Value};
+use std::ffi::OsString;
+use std::fmt::Display;
 use std::fs;
 use std::io::{self, Read};
-use std::path::Path;
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
 
 #[derive(Parser)]
 #[clap(author, version, about, long_about = None)]
 struct Cli {
-    /// Input file name
-    input: Option<String>,
+    #[clap(subcommand)]
+    command: Option<Command>,
 
-    /// Output file name
+    /// Input file names, stdin if none is given or it is -. Patterns such as
+    /// fixtures/*.json are expanded when the shell leaves them, and each of several inputs
+    /// gets a schema of its own
+    #[clap(value_name = "INPUT")]
+    inputs: Vec<String>,
+
+    /// Output file name, - for stdout. Without it the schema is written next to the
+    /// input, as <INPUT_STEM>.jsonschema
     #[clap(short, long)]
     output: Option<String>,
 
     /// Output to stdout
     #[clap(short, long)]
     stdout: bool,
+
+    /// Before replacing the output file, rename it to its name followed by SUFFIX, .bak
+    /// if none is given. Nothing is written when the schema is unchanged
+    #[clap(
+        long,
+        value_name = "SUFFIX",
+        min_values = 0,
+        require_equals = true,
+        default_missing_value = ".bak"
+    )]
+    backup: Option<String>,
+
+    /// Write the schema of each input into this directory, named after its stem, instead
+    /// of next to it
+    #[clap(long, value_name = "DIR", conflicts_with = "output")]
+    output_dir: Option<String>,
+
+    /// Write the schema on one line, without a newline at the end
+    #[clap(long, conflicts_with = "indent")]
+    compact: bool,
+
+    /// Indent the schema by N spaces, or by one tab per level with tab
+    #[clap(long, value_name = "N|tab", default_value = "2")]
+    indent: Indent,
+
+    /// Write the schema as json or, with the yaml feature, yaml, in a .schema.yaml file
+    /// next to the input by default
+    #[clap(long, value_enum, value_name = "FORMAT", default_value = "json")]
+    schema_format: SchemaFormat,
+
+    /// Write the schema, or the types of the input as rust structs or typescript
+    /// interfaces, in a .rs or .ts file next to it by default
+    #[clap(long, value_enum, value_name = "OUTPUT", default_value = "schema")]
+    emit: Emit,
+
+    /// Replace an output file holding a different schema, which is otherwise an error
+    /// unless --backup keeps it
+    #[clap(long)]
+    force: bool,
+
+    /// With a directory as input, write an index schema referring to the schema of each
+    /// file to this file
+    #[clap(long, value_name = "FILE")]
+    index: Option<String>,
+
+    /// How the --index refers to the schemas: one-of (a oneOf of them) or defs (a $defs
+    /// entry named after each file)
+    #[clap(long, value_enum, default_value = "one-of", requires = "index")]
+    index_style: IndexStyle,
+
+    /// How many --backup files are kept: the latest named <output><SUFFIX>, the earlier
+    /// ones <output><SUFFIX>.1, <output><SUFFIX>.2 and so on
+    #[clap(long, value_name = "N", default_value = "1", requires = "backup")]
+    backup_keep: usize,
+
+    /// How warnings and validation errors are printed to stderr
+    #[clap(long, value_enum, default_value = "human")]
+    error_format: ErrorFormat,
+
+    /// Fail if a warning with one of these comma-separated codes is raised
+    #[clap(long, value_name = "CODES", use_value_delimiter = true)]
+    deny_lints: Vec<String>,
+
+    /// Print statistics about the generated schema as JSON to stderr
+    #[clap(long)]
+    stats: bool,
+
+    /// Record how the schema was generated at its root: off, comment (a $comment) or
+    /// extension (an x-generated-by object)
+    #[clap(long, value_name = "MODE", default_value = "off")]
+    provenance: ProvenanceMode,
+
+    /// Leave the timestamp out of the provenance, so that reruns produce identical output
+    #[clap(long)]
+    reproducible: bool,
+
+    /// Fail instead of widening the schema when samples disagree: a type that only merges
+    /// into a oneOf, a required property missing from some samples, or a dropped format.
+    /// Merging several inputs, also stop at the first that can't be read
+    #[clap(long)]
+    strict: bool,
+
+    /// The format of the inputs: json, ndjson, csv, yaml with the yaml feature, or toml
+    /// with the toml feature. By default files named .ndjson or .jsonl are NDJSON, .csv
+    /// CSV, .yaml or .yml YAML, .toml TOML, and the others JSON
+    #[clap(long, alias = "format", value_enum, value_name = "FORMAT")]
+    input_format: Option<InputFormat>,
+
+    /// The character between the cells of a CSV row
+    #[clap(long, value_name = "CHAR", default_value = ",")]
+    csv_delimiter: char,
+
+    /// Name the columns of CSV inputs column1, column2 and so on rather than by their
+    /// first row
+    #[clap(long)]
+    csv_no_header: bool,
+
+    /// What an empty unquoted CSV cell stands for: null, omit (the row lacks the
+    /// property) or empty-string
+    #[clap(long, value_enum, value_name = "MEANING", default_value = "null")]
+    csv_empty: CsvEmpty,
+
+    /// Read the inputs as newline-delimited JSON, as --input-format ndjson does
+    #[clap(long)]
+    ndjson: bool,
+
+    /// Merge several inputs into one schema, written as the schema of the first would be.
+    /// Implied by --output and --stdout
+    #[clap(long)]
+    merge: bool,
+
+    /// How merging treats keywords the samples disagree on: permissive (drop them),
+    /// conservative (keep the schemas apart in an anyOf) or annotated (drop them with a
+    /// widening warning saying why)
+    #[clap(long, value_name = "POLICY", default_value = "permissive")]
+    merge_policy: MergePolicy,
+
+    /// The draft to write the schema for: draft-04, draft-06, draft-07, 2019-09 or
+    /// 2020-12
+    #[clap(long, value_name = "DRAFT")]
+    draft: Option<json_schema_generator::Draft>,
+
+    /// Write $schema only at the root, not on the object schemas nested in arrays
+    #[clap(long)]
+    root_dialect_only: bool,
+
+    /// Leave required out of every object schema
+    #[clap(long, alias = "no-required")]
+    all_optional: bool,
+
+    /// Write "additionalProperties": false on every object schema, rejecting properties
+    /// the samples didn't have
+    #[clap(long, alias = "closed")]
+    no_additional_properties: bool,
+
+    /// The share of the objects at a location, between 0 and 1, that must have a
+    /// property for it to be required
+    #[clap(long, value_name = "RATE", default_value = "1")]
+    required_threshold: f64,
+
+    /// Write the format of strings such as dates, email addresses and UUIDs
+    #[clap(long)]
+    infer_formats: bool,
+
+    /// List the values of strings that take at most 10 of them, in at least 10 samples,
+    /// as an enum, or a const for a single value
+    #[clap(long)]
+    infer_enums: bool,
+
+    /// Write arrays of up to 8 elements of different types as tuples, with a schema for
+    /// each position
+    #[clap(long)]
+    tuples: bool,
+
+    /// Write the lowest and highest numbers seen at each location as its minimum and
+    /// maximum
+    #[clap(long)]
+    numeric_bounds: bool,
+
+    /// With --numeric-bounds or --constraints, write a minimum of 0 where the integers
+    /// seen were never negative
+    #[clap(long)]
+    non_negative_minimum: bool,
+
+    /// Write the shortest and longest lengths of the strings seen at each location, where
+    /// at least two were seen, as its minLength and maxLength
+    #[clap(long)]
+    string_lengths: bool,
+
+    /// Write the fewest and most elements of the arrays seen at each location as its
+    /// minItems and maxItems
+    #[clap(long)]
+    array_lengths: bool,
+
+    /// Write uniqueItems for the arrays at each location whose elements were all distinct
+    #[clap(long)]
+    unique_items: bool,
+
+    /// Infer all of the constraints above: --numeric-bounds, --string-lengths,
+    /// --array-lengths and --unique-items
+    #[clap(long)]
+    constraints: bool,
+
+    /// The keyword of the unions of differently typed samples: one-of or any-of
+    #[clap(long, value_name = "KEYWORD", default_value = "one-of")]
+    union_keyword: UnionKeyword,
+
+    /// Write a string, number or boolean that is sometimes null as a oneOf with null
+    /// rather than a type array
+    #[clap(long)]
+    nullable_one_of: bool,
+
+    /// How many of the conflicts found with --strict are printed
+    #[clap(long, value_name = "N", default_value = "10", requires = "strict")]
+    max_conflicts: usize,
+
+    /// About how many bytes the schema may take; past it, the smallest subschema
+    /// bringing it back within the limit is replaced by {}
+    #[clap(long, value_name = "BYTES")]
+    max_schema_bytes: Option<usize>,
+
+    /// What happens once the input exceeds a limit: degrade (the schema is loosened,
+    /// with a warning) or fail
+    #[clap(long, value_name = "POLICY", default_value = "degrade")]
+    on_limit: LimitPolicy,
+
+    /// Embed up to N of the input records under the root examples of the schema
+    #[clap(long, value_name = "N", default_value = "0")]
+    root_examples: usize,
+
+    /// About how many bytes the --root-examples may take together
+    #[clap(long, value_name = "BYTES", default_value = "4096")]
+    examples_max_bytes: usize,
+
+    /// Write a pattern for strings of these comma-separated shapes: semver, hex-color,
+    /// country-code, currency-code, mac-address or language-tag
+    #[cfg(feature = "patterns")]
+    #[clap(long, value_name = "NAMES", use_value_delimiter = true)]
+    patterns: Vec<String>,
+
+    /// Also write the name of each --patterns pattern as x-pattern-name
+    #[cfg(feature = "patterns")]
+    #[clap(long, requires = "patterns")]
+    pattern_names: bool,
+
+    /// Validate the input against this schema file instead of generating a schema
+    #[cfg(feature = "validation")]
+    #[clap(long, value_name = "SCHEMA")]
+    validate: Option<String>,
+
+    /// The draft to validate as when the --validate schema has no $schema: draft-04,
+    /// draft-06, draft-07, 2019-09 or 2020-12
+    #[cfg(feature = "validation")]
+    #[clap(long, value_name = "DRAFT", requires = "validate")]
+    validation_draft: Option<json_schema_generator::Draft>,
+
+    /// Check that the input validates against the generated schema
+    #[cfg(feature = "validation")]
+    #[clap(long)]
+    verify: bool,
+
+    /// Check the generated schema against the meta-schema of its draft
+    #[cfg(feature = "validation")]
+    #[clap(long)]
+    self_check: bool,
+
+    /// Serve schemas over HTTP at this address, such as 127.0.0.1:8080, instead of
+    /// reading an input
+    #[cfg(feature = "server")]
+    #[clap(long, value_name = "ADDR")]
+    serve: Option<String>,
+
+    /// The largest request body the server accepts, in bytes
+    #[cfg(feature = "server")]
+    #[clap(
+        long,
+        value_name = "BYTES",
+        default_value = "10485760",
+        requires = "serve"
+    )]
+    max_body_bytes: usize,
+
+    /// How many seconds the server may spend generating a schema for one request
+    #[cfg(feature = "server")]
+    #[clap(long, value_name = "SECONDS", default_value = "30", requires = "serve")]
+    request_timeout: u64,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Report how much of a hand-written reference schema the schema generated from the
+    /// input reproduces, printing the report as JSON
+    Coverage {
+        /// Reference schema file name
+        reference: String,
+
+        /// Input file name
+        input: Option<String>,
+    },
+    /// Print a copy of a schema needing no other document, with the files its $refs
+    /// point to, relative to the schema file, inlined or collected under its $defs
+    Bundle {
+        /// Schema file name
+        schema: String,
+
+        /// How references are bundled: inline (each replaced by its target, cycles kept
+        /// as references into $defs) or defs (targets in other files collected under
+        /// $defs)
+        #[clap(long, value_name = "MODE", default_value = "inline")]
+        bundle_mode: BundleMode,
+    },
+    /// Print the changes turning one schema into another
+    Diff {
+        /// The earlier schema file name
+        old: String,
+
+        /// The later schema file name
+        new: String,
+
+        /// How the changes are printed: human (one line per change) or json-patch (an
+        /// RFC 6902 JSON Patch applying to the normalized earlier schema)
+        #[clap(long, value_enum, default_value = "human")]
+        diff_format: DiffFormat,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum DiffFormat {
+    /// One line per change
+    Human,
+    /// An RFC 6902 JSON Patch
+    JsonPatch,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Emit {
+    /// The JSON schema
+    Schema,
+    /// Rust structs deriving serde's Serialize and Deserialize
+    Rust,
+    /// TypeScript interfaces
+    Typescript,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum SchemaFormat {
+    /// JSON
+    Json,
+    /// YAML, with the yaml feature
+    Yaml,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum InputFormat {
+    /// JSON
+    Json,
+    /// Newline-delimited JSON: each line that isn't blank or a # comment is a record,
+    /// and their schemas are merged into one
+    Ndjson,
+    /// CSV, an array of an object per row
+    Csv,
+    /// YAML, with the yaml feature
+    Yaml,
+    /// TOML, with the toml feature
+    Toml,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum CsvEmpty {
+    /// null, making the column nullable
+    Null,
+    /// Nothing, making the column optional
+    Omit,
+    /// ""
+    EmptyString,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum IndexStyle {
+    /// {"oneOf": [{"$ref": "a.jsonschema"}, ...]}
+    OneOf,
+    /// {"$defs": {"a": {"$ref": "a.jsonschema"}, ...}}
+    Defs,
+}
+
+/// The indentation of each level of the schema written, see --indent.
+struct Indent(String);
+
+impl FromStr for Indent {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "tab" => Ok(Indent("\t".to_string())),
+            _ => s
+                .parse()
+                .map(|spaces| Indent(" ".repeat(spaces)))
+                .map_err(|_| format!("expected a number of spaces or tab, not '{}'", s)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ErrorFormat {
+    /// One line of text per diagnostic
+    Human,
+    /// One JSON object per line
+    Json,
 }
 
 fn main() -> io::Result<()> {
-    let cli = Cli::parse();
+    let mut cli = Cli::parse();
+
+    #[cfg(feature = "cli-tracing")]
+    tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .with_writer(io::stderr)
+        .init();
+    #[cfg(feature = "tracing")]
+    let _span = tracing::info_span!("file", input = cli.input().unwrap_or("<stdin>")).entered();
+
+    if let Some(Command::Coverage { reference, input }) = &cli.command {
+        return coverage(&cli, reference, input);
+    }
+    if let Some(Command::Bundle {
+        schema,
+        bundle_mode,
+    }) = &cli.command
+    {
+        return bundle(schema, *bundle_mode);
+    }
+    if let Some(Command::Diff {
+        old,
+        new,
+        diff_format,
+    }) = &cli.command
+    {
+        return diff(old, new, *diff_format);
+    }
+
+    #[cfg(feature = "server")]
+    if let Some(addr) = &cli.serve {
+        return serve(&cli, addr);
+    }
+
+    cli.inputs = expand_patterns(&cli.inputs)?;
+    if cli.emit != Emit::Schema {
+        if cli.inputs.len() > 1 {
+            return Err(io::Error::other("--emit takes one input"));
+        }
+        let instance = read_instance(&cli, &cli.inputs.first().cloned())?;
+        let options = json_schema_generator::CodegenOptions::default();
+        let code = match cli.emit {
+            Emit::Typescript => json_schema_generator::generate_typescript(&instance, &options),
+            _ => json_schema_generator::generate_rust_types(&instance, &options),
+        };
+        return write_text(&cli, &code);
+    }
+    if cli.inputs.len() > 1 {
+        if cli.merge || cli.output.is_some() || cli.stdout {
+            return merge_files(&cli, &cli.inputs);
+        }
+        return generate_files(&cli, &cli.inputs);
+    }
+    if let Some(dir) = cli.input().map(Path::new).filter(|input| input.is_dir()) {
+        return generate_directory(&cli, dir);
+    }
+
+    if input_format(&cli, cli.input()) == InputFormat::Ndjson {
+        let schema = generate_ndjson(&cli, cli.input())?;
+        return write_schema(&cli, &schema);
+    }
+
+    // A TOML document's dates and times keep their formats, unless it is checked
+    // against a schema as JSON.
+    #[cfg(all(feature = "toml", feature = "validation"))]
+    let checked = cli.verify || cli.validate.is_some();
+    #[cfg(all(feature = "toml", not(feature = "validation")))]
+    let checked = false;
+    #[cfg(feature = "toml")]
+    if input_format(&cli, cli.input()) == InputFormat::Toml && !checked {
+        let text = read_text(&cli.inputs.first().cloned())?;
+        let schema = json_schema_generator::try_generate_json_schema_from_toml(
+            &text,
+            &schema_options(&cli)?,
+        )
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        return write_schema(&cli, &schema);
+    }
+
+    let json_value = read_instance(&cli, &cli.inputs.first().cloned())?;
+
+    #[cfg(feature = "validation")]
+    if let Some(schema_file) = &cli.validate {
+        return validate(&cli, schema_file, &json_value);
+    }
+
+    let options = schema_options(&cli)?;
+
+    #[cfg(feature = "validation")]
+    let schema = if cli.verify {
+        verify(&cli, json_value, &options)?
+    } else {
+        generate(&cli, &json_value, &options)?
+    };
+    #[cfg(not(feature = "validation"))]
+    let schema = generate(&cli, &json_value, &options)?;
+
+    write_schema(&cli, &schema)
+}
+
+/// Prints the --stats of `schema` and runs the --self-check before writing it out.
+fn write_schema(cli: &Cli, schema: &Value) -> io::Result<()> {
+    if cli.stats {
+        let stats = json_schema_generator::schema_stats(schema);
+        eprintln!("{}", serde_json::to_string(&stats)?);
+    }
+
+    #[cfg(feature = "validation")]
+    if cli.self_check {
+        if let Err(issues) = json_schema_generator::check_schema(schema) {
+            for issue in &issues {
+                print_diagnostic(cli, issue);
+            }
+            std::process::exit(1);
+        }
+    }
+    write_output(cli, schema)
+}
+
+/// Generates the schema of the NDJSON file `input`, or stdin, reading it one line at a
+/// time.
+fn generate_ndjson(cli: &Cli, input: Option<&str>) -> io::Result<Value> {
+    let mut generator = SchemaGenerator::new(schema_options(cli)?);
+    observe_ndjson(cli, &mut generator, input)?;
+    report_warnings(cli, &generator.warnings());
+    generator.finish().map_err(io::Error::other)
+}
+
+/// Merges each record of the NDJSON file `input`, or stdin, into `generator`, exiting
+/// as [`observe`] does.
+fn observe_ndjson(
+    cli: &Cli,
+    generator: &mut SchemaGenerator,
+    input: Option<&str>,
+) -> io::Result<()> {
+    let result = match input.filter(|input| *input != "-") {
+        Some(filename) => generator.observe_ndjson(io::BufReader::new(fs::File::open(filename)?)),
+        None => generator.observe_ndjson(io::stdin().lock()),
+    };
+    observed(cli, generator, result.map(|_| ()))
+}
+
+/// Returns the options the command line asks for.
+fn schema_options(cli: &Cli) -> io::Result<SchemaOptions> {
+    #[cfg(feature = "patterns")]
+    let patterns = if cli.patterns.is_empty() {
+        None
+    } else {
+        let patterns =
+            json_schema_generator::Patterns::select(&cli.patterns).map_err(io::Error::other)?;
+        Some(json_schema_generator::Patterns {
+            annotate_names: cli.pattern_names,
+            ..patterns
+        })
+    };
+    Ok(SchemaOptions {
+        provenance: cli.provenance,
+        reproducible: cli.reproducible,
+        max_schema_bytes: cli.max_schema_bytes,
+        on_limit: cli.on_limit,
+        root_examples: cli.root_examples,
+        examples_max_bytes: cli.examples_max_bytes,
+        #[cfg(feature = "patterns")]
+        patterns,
+        merge_policy: cli.merge_policy,
+        draft: cli.draft,
+        nested_dialect: !cli.root_dialect_only,
+        all_optional: cli.all_optional,
+        closed_objects: cli
+            .no_additional_properties
+            .then_some(ClosedWith::AdditionalProperties),
+        required_threshold: cli.required_threshold,
+        infer_formats: cli.infer_formats,
+        tuples: cli.tuples.then(json_schema_generator::Tuples::default),
+        numeric_bounds: cli.numeric_bounds || cli.constraints,
+        non_negative_minimum: cli.non_negative_minimum,
+        array_lengths: cli.array_lengths || cli.constraints,
+        unique_items: cli.unique_items || cli.constraints,
+        string_lengths: (cli.string_lengths || cli.constraints)
+            .then(json_schema_generator::StringLengths::default),
+        enums: cli
+            .infer_enums
+            .then(json_schema_generator::EnumInference::default),
+        union_keyword: cli.union_keyword,
+        nullable_type_arrays: !cli.nullable_one_of,
+        strictness: if cli.strict {
+            Strictness::Strict
+        } else {
+            Strictness::Permissive
+        },
+        ..Default::default()
+    })
+}
+
+fn generate(cli: &Cli, instance: &Value, options: &SchemaOptions) -> io::Result<Value> {
+    let mut generator = SchemaGenerator::new(options.clone());
+    observe(cli, &mut generator, instance)?;
+    report_warnings(cli, &generator.warnings());
+    generator.finish().map_err(io::Error::other)
+}
+
+/// Merges `instance` into `generator`, exiting with status 1 on the conflicts `--strict`
+/// finds or on an exceeded limit.
+fn observe(cli: &Cli, generator: &mut SchemaGenerator, instance: &Value) -> io::Result<()> {
+    let result = generator.observe(instance);
+    observed(cli, generator, result)
+}
+
+/// Returns the `result` of observing records with `generator`, exiting as [`observe`]
+/// does.
+fn observed(
+    cli: &Cli,
+    generator: &SchemaGenerator,
+    result: Result<(), GeneratorError>,
+) -> io::Result<()> {
+    match result {
+        Err(GeneratorError::Conflict { .. }) => report_conflicts(cli, generator),
+        Err(GeneratorError::LimitExceeded(warning)) => {
+            match cli.error_format {
+                ErrorFormat::Human => eprintln!("error: {}", warning),
+                ErrorFormat::Json => print_diagnostic(cli, &warning),
+            }
+            std::process::exit(1);
+        }
+        result => result.map_err(io::Error::other),
+    }
+}
+
+/// Prints the first `--max-conflicts` of the conflicts `--strict` found to stderr and
+/// exits with status 1.
+fn report_conflicts(cli: &Cli, generator: &SchemaGenerator) -> ! {
+    let conflicts = generator.conflicts();
+    for conflict in conflicts.iter().take(cli.max_conflicts) {
+        match cli.error_format {
+            ErrorFormat::Human => eprintln!("error: {}", conflict),
+            ErrorFormat::Json => print_diagnostic(cli, conflict),
+        }
+    }
+    match conflicts.len().checked_sub(cli.max_conflicts) {
+        Some(more @ 1..) => eprintln!(
+            "error: {} conflict(s) found, {} more not shown",
+            conflicts.len(),
+            more
+        ),
+        _ => eprintln!("error: {} conflict(s) found", conflicts.len()),
+    }
+    std::process::exit(1);
+}
 
-    let json_value = read_input(&cli.input)?;
-    let schema = generate_json_schema(&json_value);
-    write_output(&cli, &schema)?;
+/// Prints `warnings` to stderr, exiting with status 1 if any of them is denied through
+/// `--deny-lints`.
+fn report_warnings(cli: &Cli, warnings: &[Warning]) {
+    for warning in warnings {
+        match cli.error_format {
+            ErrorFormat::Human => eprintln!("warning: {}", warning),
+            ErrorFormat::Json => print_diagnostic(cli, warning),
+        }
+    }
+
+    let denied = warnings
+        .iter()
+        .filter(|warning| cli.deny_lints.contains(&warning.code))
+        .count();
+    if denied > 0 {
+        eprintln!("error: {} denied warning(s) raised", denied);
+        std::process::exit(1);
+    }
+}
+
+fn print_diagnostic(cli: &Cli, diagnostic: &(impl Display + Serialize)) {
+    match cli.error_format {
+        ErrorFormat::Human => eprintln!("{}", diagnostic),
+        ErrorFormat::Json => eprintln!(
+            "{}",
+            serde_json::to_string(diagnostic).expect("diagnostics serialize to JSON")
+        ),
+    }
+}
+
+/// Generates the schema and checks that the input validates against it, printing a
+/// summary to stderr.
+#[cfg(feature = "validation")]
+fn verify(cli: &Cli, instance: Value, options: &SchemaOptions) -> io::Result<Value> {
+    let outcome = json_schema_generator::generate_and_verify(&[instance], options)
+        .map_err(io::Error::other)?;
+    report_warnings(cli, &outcome.warnings);
+    for issue in outcome.failures.iter().flat_map(|failure| &failure.issues) {
+        print_diagnostic(cli, issue);
+    }
+    if cli.error_format == ErrorFormat::Human {
+        eprintln!("{}", outcome);
+    }
+
+    if !outcome.all_valid() {
+        std::process::exit(1);
+    }
+    Ok(outcome.schema)
+}
+
+/// Prints the coverage report of the schema generated from `input` against `reference`
+/// to stdout, followed by a summary on stderr.
+fn coverage(cli: &Cli, reference: &str, input: &Option<String>) -> io::Result<()> {
+    let reference = read_input(&Some(reference.to_string()))?;
+    let instance = read_instance(cli, input)?;
+    let inferred = generate(cli, &instance, &SchemaOptions::default())?;
+
+    let report = json_schema_generator::coverage_report(&reference, &inferred);
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    eprintln!("{}", report);
+    Ok(())
+}
 
+/// Prints the bundled schema file `schema`, or the reference that couldn't be resolved
+/// to stderr.
+fn bundle(schema: &str, mode: BundleMode) -> io::Result<()> {
+    let root = read_input(&Some(schema.to_string()))?;
+    let base = Path::new(schema).parent().unwrap_or(Path::new(""));
+    let resolver = json_schema_generator::FileResolver {
+        base: base.to_path_buf(),
+    };
+    match json_schema_generator::bundle_schema(&root, &resolver, mode) {
+        Ok(bundled) => println!("{}", serde_json::to_string_pretty(&bundled)?),
+        Err(error) => {
+            eprintln!("error: {}", error);
+            std::process::exit(1);
+        }
+    }
+    Ok(())
+}
+
+fn diff(old: &str, new: &str, format: DiffFormat) -> io::Result<()> {
+    let old = read_input(&Some(old.to_string()))?;
+    let new = read_input(&Some(new.to_string()))?;
+    let patch = json_schema_generator::schema_patch(&old, &new);
+    match format {
+        DiffFormat::Human => patch.iter().for_each(|op| println!("{}", op)),
+        DiffFormat::JsonPatch => println!("{}", serde_json::to_string_pretty(&patch)?),
+    }
+    Ok(())
+}
+
+/// Serves schemas until SIGTERM or SIGINT, then finishes the requests in flight.
+#[cfg(feature = "server")]
+fn serve(cli: &Cli, addr: &str) -> io::Result<()> {
+    use json_schema_generator::server::{Server, ServerConfig};
+
+    let server = Server::bind(
+        addr,
+        ServerConfig {
+            max_body_bytes: cli.max_body_bytes,
+            timeout: std::time::Duration::from_secs(cli.request_timeout),
+        },
+    )?;
+    #[cfg(unix)]
+    {
+        use signal_hook::consts::{SIGINT, SIGTERM};
+
+        let mut signals = signal_hook::iterator::Signals::new([SIGTERM, SIGINT])?;
+        let handle = server.shutdown_handle();
+        std::thread::spawn(move || {
+            if signals.forever().next().is_some() {
+                handle.shutdown();
+            }
+        });
+    }
+    eprintln!("listening on http://{}", server.local_addr());
+    server.run();
     Ok(())
 }
 
 fn read_input(input: &Option<String>) -> io::Result<Value> {
-    let json_str = match input {
-        Some(filename) => fs::read_to_string(filename)?,
-        None => {
+    let json_str = read_text(input)?;
+    serde_json::from_str(&json_str).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Reads the file `input`, or stdin.
+fn read_text(input: &Option<String>) -> io::Result<String> {
+    match input {
+        Some(filename) if filename != "-" => fs::read_to_string(filename),
+        _ => {
             let mut buffer = String::new();
             io::stdin().read_to_string(&mut buffer)?;
-            buffer
+            Ok(buffer)
         }
+    }
+}
+
+/// Returns the --input-format of the file `input`, or else the format its extension
+/// names.
+fn input_format(cli: &Cli, input: Option<&str>) -> InputFormat {
+    let extension = input
+        .and_then(|input| Path::new(input).extension())
+        .and_then(|extension| extension.to_str());
+    let format = if cli.ndjson {
+        Some(InputFormat::Ndjson)
+    } else {
+        cli.input_format
     };
+    format.unwrap_or(match extension {
+        Some("ndjson" | "jsonl") => InputFormat::Ndjson,
+        Some("csv") => InputFormat::Csv,
+        Some("yaml" | "yml") => InputFormat::Yaml,
+        Some("toml") => InputFormat::Toml,
+        _ => InputFormat::Json,
+    })
+}
 
-    serde_json::from_str(&json_str).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+/// Reads the document to describe from the file `input`, or stdin, in its
+/// [`input_format`].
+fn read_instance(cli: &Cli, input: &Option<String>) -> io::Result<Value> {
+    match input_format(cli, input.as_deref()) {
+        InputFormat::Json => read_input(input),
+        // Its records are merged as they are read, by [`observe_ndjson`].
+        InputFormat::Ndjson => Err(io::Error::other(
+            "NDJSON is not one document but a record per line",
+        )),
+        InputFormat::Csv => {
+            let options = CsvOptions {
+                delimiter: cli.csv_delimiter,
+                header: !cli.csv_no_header,
+                empty_cells: match cli.csv_empty {
+                    CsvEmpty::Null => EmptyCells::Null,
+                    CsvEmpty::Omit => EmptyCells::Omit,
+                    CsvEmpty::EmptyString => EmptyCells::EmptyString,
+                },
+            };
+            let text = read_text(input)?;
+            let rows = json_schema_generator::csv_records(text.as_bytes(), &options);
+            rows.collect::<Result<_, _>>()
+                .map(Value::Array)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+        }
+        #[cfg(feature = "yaml")]
+        InputFormat::Yaml => {
+            // Anchors and aliases are resolved as the document is deserialized.
+            serde_yaml::from_str(&read_text(input)?)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+        }
+        #[cfg(not(feature = "yaml"))]
+        InputFormat::Yaml => Err(io::Error::other(
+            "reading YAML needs the yaml feature of json_schema_generator",
+        )),
+        #[cfg(feature = "toml")]
+        InputFormat::Toml => json_schema_generator::toml_to_json(&read_text(input)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+        #[cfg(not(feature = "toml"))]
+        InputFormat::Toml => Err(io::Error::other(
+            "reading TOML needs the toml feature of json_schema_generator",
+        )),
+    }
+}
+
+#[cfg(feature = "validation")]
+fn validate(cli: &Cli, schema_file: &str, instance: &Value) -> io::Result<()> {
+    let schema = read_input(&Some(schema_file.to_string()))?;
+
+    let compiled = match cli.validation_draft {
+        Some(draft) if schema.get("$schema").is_none() => {
+            json_schema_generator::compile_schema_with_draft(&schema, draft)
+        }
+        _ => json_schema_generator::compile_schema(&schema),
+    };
+    let compiled = compiled.unwrap_or_else(|issue| {
+        print_diagnostic(cli, &issue);
+        std::process::exit(1);
+    });
+    if let Err(issues) = compiled.validate(instance) {
+        for issue in &issues {
+            print_diagnostic(cli, issue);
+        }
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
+
+/// Writes `schema` in the --schema-format, as YAML or else as --compact or --indent ask,
+/// followed by a newline unless compact.
+fn format_schema(cli: &Cli, schema: &Value) -> io::Result<String> {
+    if cli.schema_format == SchemaFormat::Yaml {
+        #[cfg(feature = "yaml")]
+        return Ok(json_schema_generator::schema_to_yaml_string(schema));
+        #[cfg(not(feature = "yaml"))]
+        return Err(io::Error::other(
+            "writing YAML needs the yaml feature of json_schema_generator",
+        ));
+    }
+    if cli.compact {
+        return Ok(serde_json::to_string(schema)?);
+    }
+    let mut written = Vec::new();
+    let formatter = PrettyFormatter::with_indent(cli.indent.0.as_bytes());
+    schema.serialize(&mut serde_json::Serializer::with_formatter(
+        &mut written,
+        formatter,
+    ))?;
+    written.push(b'\n');
+    Ok(String::from_utf8(written).expect("serde_json writes UTF-8"))
 }
 
 fn write_output(cli: &Cli, schema: &Value) -> io::Result<()> {
-    let schema_str = serde_json::to_string_pretty(schema)?;
+    write_text(cli, &format_schema(cli, schema)?)
+}
 
-    if cli.stdout {
-        println!("{}", schema_str);
+/// Writes `schema_str` to the --output, stdout, or the file named after the input.
+fn write_text(cli: &Cli, schema_str: &str) -> io::Result<()> {
+    if cli.stdout || cli.output.as_deref() == Some("-") {
+        print!("{}", schema_str);
     } else if let Some(output_file) = &cli.output {
-        fs::write(output_file, &schema_str)?;
-    } else if let Some(input_file) = &cli.input {
-        let output_file = format!(
-            "{}.jsonschema",
-            Path::new(input_file).file_stem().unwrap().to_str().unwrap()
-        );
-        fs::write(output_file, &schema_str)?;
+        write_file(cli, Path::new(output_file), schema_str)?;
+    } else if let Some(input_file) = cli.input().filter(|input| *input != "-") {
+        if let Some(dir) = &cli.output_dir {
+            fs::create_dir_all(dir)?;
+        }
+        write_file(cli, &output_path(cli, Path::new(input_file)), schema_str)?;
     } else {
-        println!("{}", schema_str);
+        print!("{}", schema_str);
+    }
+
+    Ok(())
+}
+
+/// Replaces the file `path` by one holding `contents`, written to a temporary file first
+/// so that `path` is never left half written, and backs up the file replaced as --backup
+/// asks. A file already holding `contents` is left alone, and one holding anything else
+/// is only replaced with --force or --backup.
+fn write_file(cli: &Cli, path: &Path, contents: &str) -> io::Result<()> {
+    if fs::read(path).is_ok_and(|old| old == contents.as_bytes()) {
+        return Ok(());
+    }
+    if path.exists() && !cli.force && cli.backup.is_none() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!(
+                "{} already exists, pass --force to replace it",
+                path.display()
+            ),
+        ));
+    }
+    let temporary = with_suffix(path, ".tmp");
+    fs::write(&temporary, contents)?;
+    if let (Some(suffix), true) = (&cli.backup, path.exists()) {
+        if let Err(e) = rotate_backups(path, suffix, cli.backup_keep) {
+            let _ = fs::remove_file(&temporary);
+            return Err(e);
+        }
+    }
+    fs::rename(&temporary, path)
+}
+
+/// Renames the file `path` to the latest of `keep` backups, shifting each earlier one to
+/// the next number and removing the oldest.
+fn rotate_backups(path: &Path, suffix: &str, keep: usize) -> io::Result<()> {
+    if keep == 0 {
+        return Ok(());
+    }
+    let backup = |n: usize| match n {
+        0 => with_suffix(path, suffix),
+        n => with_suffix(path, &format!("{}.{}", suffix, n)),
+    };
+    match fs::remove_file(backup(keep - 1)) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+        _ => {}
+    }
+    for n in (1..keep).rev() {
+        if backup(n - 1).exists() {
+            fs::rename(backup(n - 1), backup(n))?;
+        }
+    }
+    fs::rename(path, backup(0))
+}
+
+/// Returns `path` with `suffix` appended to its file name.
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = OsString::from(path);
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
+impl Cli {
+    /// The input file name, if one is given.
+    fn input(&self) -> Option<&str> {
+        self.inputs.first().map(String::as_str)
+    }
+}
+
+/// Where the schema of the file `input` is written: `<stem>.jsonschema` in --output-dir,
+/// or next to `input`.
+fn output_path(cli: &Cli, input: &Path) -> PathBuf {
+    match &cli.output_dir {
+        Some(dir) => {
+            let stem = input.file_stem().unwrap_or_default().to_string_lossy();
+            Path::new(dir).join(format!("{}.{}", stem, schema_extension(cli)))
+        }
+        None => input.with_extension(schema_extension(cli)),
     }
+}
 
+/// Returns the extension of the files written for the --emit, schemas in the
+/// --schema-format.
+fn schema_extension(cli: &Cli) -> &'static str {
+    match (cli.emit, cli.schema_format) {
+        (Emit::Rust, _) => "rs",
+        (Emit::Typescript, _) => "ts",
+        (Emit::Schema, SchemaFormat::Json) => "jsonschema",
+        (Emit::Schema, SchemaFormat::Yaml) => "schema.yaml",
+    }
+}
+
+/// Replaces each of `inputs` whose file name holds a `*` or `?` by the files it matches,
+/// sorted, for the shells that don't expand them. A pattern matching nothing is an error.
+fn expand_patterns(inputs: &[String]) -> io::Result<Vec<String>> {
+    let mut expanded = Vec::new();
+    for input in inputs {
+        let path = Path::new(input);
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        if !name.contains(['*', '?']) {
+            expanded.push(input.clone());
+            continue;
+        }
+        let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty());
+        let pattern: Vec<char> = name.chars().collect();
+        let mut matches = Vec::new();
+        for entry in fs::read_dir(dir.unwrap_or(Path::new(".")))? {
+            let entry = entry?;
+            let file_name = entry.file_name().to_string_lossy().into_owned();
+            let chars: Vec<char> = file_name.chars().collect();
+            if wildcard_match(&pattern, &chars) && entry.path().is_file() {
+                matches.push(match dir {
+                    Some(dir) => dir.join(&file_name).to_string_lossy().into_owned(),
+                    None => file_name,
+                });
+            }
+        }
+        if matches.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no file matches {}", input),
+            ));
+        }
+        matches.sort();
+        expanded.extend(matches);
+    }
+    Ok(expanded)
+}
+
+/// Whether `name` matches `pattern`, in which `*` stands for any run of characters and
+/// `?` for any one character. Names starting with a dot only match a pattern that does.
+fn wildcard_match(pattern: &[char], name: &[char]) -> bool {
+    if name.first() == Some(&'.') && pattern.first() != Some(&'.') {
+        return false;
+    }
+    let (mut p, mut n) = (0, 0);
+    // Where the last `*` is in the pattern, and the position in the name it resumes at.
+    let mut star = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((star_p, star_n)) => {
+                    p = star_p + 1;
+                    n = star_n + 1;
+                    star = Some((star_p, n));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Generates the schema of each of `inputs` as [`write_output`] does for one input,
+/// carrying on past the files that fail, then prints what became of each to stderr. Any
+/// failure makes the exit status non-zero.
+fn generate_files(cli: &Cli, inputs: &[String]) -> io::Result<()> {
+    let options = schema_options(cli)?;
+    if let Some(dir) = &cli.output_dir {
+        fs::create_dir_all(dir)?;
+    }
+    let mut failed = 0;
+    for input in inputs {
+        let output = output_path(cli, Path::new(input));
+        let schema = if input_format(cli, Some(input)) == InputFormat::Ndjson {
+            generate_ndjson(cli, Some(input))
+        } else {
+            read_instance(cli, &Some(input.clone()))
+                .and_then(|instance| generate(cli, &instance, &options))
+        };
+        let written =
+            schema.and_then(|schema| write_file(cli, &output, &format_schema(cli, &schema)?));
+        match written {
+            Ok(()) => eprintln!("{}: wrote {}", input, output.display()),
+            Err(e) => {
+                eprintln!("{}: error: {}", input, e);
+                failed += 1;
+            }
+        }
+    }
+    eprintln!(
+        "{} of {} inputs succeeded, {} failed",
+        inputs.len() - failed,
+        inputs.len(),
+        failed
+    );
+    if failed > 0 {
+        std::process::exit(1);
+    }
     Ok(())
 }
+
+/// Merges `inputs` into one schema, written by [`write_output`]. A file that can't be
+/// read is reported on stderr and skipped, making the exit status non-zero once the
+/// schema of the others is written, or with --strict stops everything.
+fn merge_files(cli: &Cli, inputs: &[String]) -> io::Result<()> {
+    let mut generator = SchemaGenerator::new(schema_options(cli)?);
+    let mut failed = 0;
+    for input in inputs {
+        let read = if input_format(cli, Some(input)) == InputFormat::Ndjson {
+            // The records before a line that isn't JSON are merged.
+            observe_ndjson(cli, &mut generator, Some(input)).map(|()| None)
+        } else {
+            read_instance(cli, &Some(input.clone())).map(Some)
+        };
+        match read {
+            Ok(Some(instance)) => observe(cli, &mut generator, &instance)?,
+            Ok(None) => {}
+            Err(e) if cli.strict => {
+                return Err(io::Error::new(e.kind(), format!("{}: {}", input, e)))
+            }
+            Err(e) => {
+                eprintln!("{}: error: {}", input, e);
+                failed += 1;
+            }
+        }
+    }
+    if failed == inputs.len() {
+        return Err(io::Error::other("none of the inputs could be read"));
+    }
+    report_warnings(cli, &generator.warnings());
+    let schema = generator.finish().map_err(io::Error::other)?;
+    write_output(cli, &schema)?;
+    if failed > 0 {
+        eprintln!(
+            "{} of {} inputs merged, {} failed",
+            inputs.len() - failed,
+            inputs.len(),
+            failed
+        );
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Generates the schema of each `.json` file in the directory `dir` into a
+/// `.jsonschema` file of the same name in `--output-dir`, or in `dir`, followed by the
+/// `--index` referring to them.
+///
+/// The generated schemas and their backups are never read as inputs, and neither is the
+/// index. Files are processed in the order of their names. A file that fails is reported
+/// on stderr and left out of the index, making the exit status non-zero once the others
+/// are written.
+fn generate_directory(cli: &Cli, dir: &Path) -> io::Result<()> {
+    if cli.stdout {
+        return Err(io::Error::other(
+            "--stdout can't print the schemas of a directory",
+        ));
+    }
+    if cli.output.is_some() {
+        return Err(io::Error::other(
+            "--output names one file; write the schemas of a directory with --output-dir",
+        ));
+    }
+    let options = schema_options(cli)?;
+    let index = cli.index.as_deref().map(Path::new);
+    let index_path = index.and_then(|index| fs::canonicalize(index).ok());
+    let mut inputs = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let generated = name.contains(".jsonschema");
+        let is_index = index_path.is_some() && fs::canonicalize(&path).ok() == index_path;
+        if path.is_file() && name.ends_with(".json") && !generated && !is_index {
+            inputs.push(path);
+        }
+    }
+    inputs.sort();
+
+    let output_dir = cli.output_dir.as_deref().map(Path::new).unwrap_or(dir);
+    fs::create_dir_all(output_dir)?;
+    let mut outputs = Vec::new();
+    let mut failed = 0;
+    for input in &inputs {
+        let stem = input.file_stem().unwrap_or_default().to_string_lossy();
+        let output = output_dir.join(format!("{}.{}", stem, schema_extension(cli)));
+        let written = fs::read_to_string(input)
+            .and_then(|json_str| {
+                serde_json::from_str(&json_str)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+            })
+            .and_then(|instance: Value| generate(cli, &instance, &options))
+            .and_then(|schema| write_file(cli, &output, &format_schema(cli, &schema)?));
+        match written {
+            Ok(()) => outputs.push((stem.into_owned(), output)),
+            Err(e) => {
+                eprintln!("{}: error: {}", input.display(), e);
+                failed += 1;
+            }
+        }
+    }
+
+    // The index refers to the schemas that were written.
+    if let Some(index) = index {
+        let index_dir = index.parent().filter(|dir| !dir.as_os_str().is_empty());
+        let index_dir = index_dir.unwrap_or(Path::new("."));
+        fs::create_dir_all(index_dir)?;
+        let index_dir = fs::canonicalize(index_dir)?;
+        let mut refs = Vec::new();
+        for (stem, output) in &outputs {
+            let reference = relative_reference(&index_dir, &fs::canonicalize(output)?);
+            refs.push((stem, json!({ "$ref": reference })));
+        }
+        let draft = options.draft.unwrap_or_default();
+        let mut document = json!({"$schema": draft.uri()});
+        match cli.index_style {
+            IndexStyle::OneOf => {
+                document["oneOf"] = refs.into_iter().map(|(_, reference)| reference).collect();
+            }
+            IndexStyle::Defs => {
+                let defs: Map<String, Value> = refs
+                    .into_iter()
+                    .map(|(stem, reference)| (stem.clone(), reference))
+                    .collect();
+                document[draft.definitions_keyword()] = Value::Object(defs);
+            }
+        }
+        write_file(cli, index, &format_schema(cli, &document)?)?;
+    }
+    if failed > 0 {
+        eprintln!(
+            "{} of {} inputs succeeded, {} failed",
+            inputs.len() - failed,
+            inputs.len(),
+            failed
+        );
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Returns the URI reference to the file `target` relative to the directory `base`, both
+/// absolute, with each segment percent-encoded.
+fn relative_reference(base: &Path, target: &Path) -> String {
+    let base: Vec<Component> = base.components().collect();
+    let target: Vec<Component> = target.components().collect();
+    let common = base.iter().zip(&target).take_while(|(a, b)| a == b).count();
+    let ups = std::iter::repeat_n("..".to_string(), base.len() - common);
+    let downs = target[common..]
+        .iter()
+        .map(|component| percent_encode(&component.as_os_str().to_string_lossy()));
+    ups.chain(downs).collect::<Vec<_>>().join("/")
+}
+
+/// Percent-encodes everything in the path segment `segment` but the characters RFC 3986
+/// leaves unreserved.
+fn percent_encode(segment: &str) -> String {
+    let mut encoded = String::new();
+    for byte in segment.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
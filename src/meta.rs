@@ -0,0 +1,227 @@
+//! Checks generated schemas against the JSON Schema meta-schema of their draft.
+//!
+//! The meta-schemas are vendored under `meta-schemas/`, so checking never touches the
+//! network.
+
+use crate::validation::ValidationIssue;
+use crate::Draft;
+use serde_json::Value;
+use std::sync::OnceLock;
+
+/// A vendored meta-schema document: its URI and contents.
+type Document = (&'static str, &'static str);
+
+macro_rules! document {
+    ($uri:literal, $path:literal) => {
+        (
+            $uri,
+            include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/meta-schemas/", $path)),
+        )
+    };
+}
+
+const DRAFT_04: &[Document] = &[document!(
+    "http://json-schema.org/draft-04/schema",
+    "draft4.json"
+)];
+
+const DRAFT_06: &[Document] = &[document!(
+    "http://json-schema.org/draft-06/schema",
+    "draft6.json"
+)];
+
+const DRAFT_07: &[Document] = &[document!(
+    "http://json-schema.org/draft-07/schema",
+    "draft7.json"
+)];
+
+const DRAFT_2019_09: &[Document] = &[
+    document!(
+        "https://json-schema.org/draft/2019-09/schema",
+        "draft2019-09/schema.json"
+    ),
+    document!(
+        "https://json-schema.org/draft/2019-09/meta/core",
+        "draft2019-09/meta/core.json"
+    ),
+    document!(
+        "https://json-schema.org/draft/2019-09/meta/applicator",
+        "draft2019-09/meta/applicator.json"
+    ),
+    document!(
+        "https://json-schema.org/draft/2019-09/meta/validation",
+        "draft2019-09/meta/validation.json"
+    ),
+    document!(
+        "https://json-schema.org/draft/2019-09/meta/meta-data",
+        "draft2019-09/meta/meta-data.json"
+    ),
+    document!(
+        "https://json-schema.org/draft/2019-09/meta/format",
+        "draft2019-09/meta/format.json"
+    ),
+    document!(
+        "https://json-schema.org/draft/2019-09/meta/content",
+        "draft2019-09/meta/content.json"
+    ),
+];
+
+const DRAFT_2020_12: &[Document] = &[
+    document!(
+        "https://json-schema.org/draft/2020-12/schema",
+        "draft2020-12/schema.json"
+    ),
+    document!(
+        "https://json-schema.org/draft/2020-12/meta/core",
+        "draft2020-12/meta/core.json"
+    ),
+    document!(
+        "https://json-schema.org/draft/2020-12/meta/applicator",
+        "draft2020-12/meta/applicator.json"
+    ),
+    document!(
+        "https://json-schema.org/draft/2020-12/meta/unevaluated",
+        "draft2020-12/meta/unevaluated.json"
+    ),
+    document!(
+        "https://json-schema.org/draft/2020-12/meta/validation",
+        "draft2020-12/meta/validation.json"
+    ),
+    document!(
+        "https://json-schema.org/draft/2020-12/meta/meta-data",
+        "draft2020-12/meta/meta-data.json"
+    ),
+    document!(
+        "https://json-schema.org/draft/2020-12/meta/format-annotation",
+        "draft2020-12/meta/format-annotation.json"
+    ),
+    document!(
+        "https://json-schema.org/draft/2020-12/meta/content",
+        "draft2020-12/meta/content.json"
+    ),
+];
+
+/// Returns the vendored documents of the meta-schema of `draft`, the meta-schema itself
+/// first.
+fn documents(draft: Draft) -> &'static [Document] {
+    match draft {
+        Draft::Draft4 => DRAFT_04,
+        Draft::Draft6 => DRAFT_06,
+        Draft::Draft7 => DRAFT_07,
+        Draft::Draft201909 => DRAFT_2019_09,
+        Draft::Draft202012 => DRAFT_2020_12,
+    }
+}
+
+/// Compiles a validator for the meta-schema described by `documents`.
+fn compile(documents: &'static [Document]) -> jsonschema::Validator {
+    let parse = |(_, contents): &Document| -> Value {
+        serde_json::from_str(contents).expect("vendored meta-schemas are valid JSON")
+    };
+    let registry = documents
+        .iter()
+        .skip(1)
+        .try_fold(jsonschema::Registry::new(), |registry, document| {
+            registry.add(document.0, parse(document))
+        })
+        .and_then(|registry| registry.prepare())
+        .expect("vendored meta-schemas form a valid registry");
+    jsonschema::options()
+        .with_registry(&registry)
+        .build(&parse(&documents[0]))
+        .expect("vendored meta-schemas compile")
+}
+
+/// Validates `schema` against the meta-schema of the draft named by its `$schema`
+/// keyword, or draft-07 if it has none.
+///
+/// Each issue's `instance_path` is the location of the offending keyword in `schema`.
+/// Schemas naming an unsupported draft are reported as a single issue at `/$schema`.
+pub fn check_schema(schema: &Value) -> Result<(), Vec<ValidationIssue>> {
+    static VALIDATORS: [OnceLock<jsonschema::Validator>; Draft::ALL.len()] = [
+        OnceLock::new(),
+        OnceLock::new(),
+        OnceLock::new(),
+        OnceLock::new(),
+        OnceLock::new(),
+    ];
+
+    let draft = match schema.get("$schema") {
+        None => Some(Draft::default()),
+        Some(uri) => uri.as_str().and_then(Draft::from_schema_uri),
+    };
+    let Some(draft) = draft else {
+        return Err(vec![ValidationIssue {
+            instance_path: "/$schema".to_string(),
+            schema_path: String::new(),
+            message: format!("unsupported meta-schema {}", schema["$schema"]),
+        }]);
+    };
+    let validator = VALIDATORS[draft as usize].get_or_init(|| compile(documents(draft)));
+
+    let issues: Vec<ValidationIssue> = validator
+        .iter_errors(schema)
+        .map(ValidationIssue::from)
+        .collect();
+    if issues.is_empty() {
+        Ok(())
+    } else {
+        Err(issues)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_generated_schema_is_valid() {
+        let schema = crate::generate_json_schema(&json!({"a": [1, "x"], "b": {"c": null}}));
+        assert_eq!(check_schema(&schema), Ok(()));
+    }
+
+    #[test]
+    fn test_every_draft() {
+        for uri in [
+            "http://json-schema.org/draft-04/schema#",
+            "http://json-schema.org/draft-06/schema#",
+            "http://json-schema.org/draft-07/schema#",
+            "https://json-schema.org/draft/2019-09/schema",
+            "https://json-schema.org/draft/2020-12/schema",
+        ] {
+            let valid = json!({"$schema": uri, "type": "object", "required": ["a"]});
+            assert_eq!(check_schema(&valid), Ok(()), "{}", uri);
+
+            let invalid = json!({"$schema": uri, "properties": {"a": {"required": {"b": 1}}}});
+            let issues = check_schema(&invalid).unwrap_err();
+            assert_eq!(issues[0].instance_path, "/properties/a/required", "{}", uri);
+        }
+    }
+
+    #[test]
+    fn test_draft_specific_keywords() {
+        // exclusiveMinimum is a boolean modifier in draft-04 but a number afterwards.
+        let draft4 = json!({
+            "$schema": "http://json-schema.org/draft-04/schema#",
+            "minimum": 1,
+            "exclusiveMinimum": 1
+        });
+        assert_eq!(
+            check_schema(&draft4).unwrap_err()[0].instance_path,
+            "/exclusiveMinimum"
+        );
+
+        let draft7 = json!({"exclusiveMinimum": true});
+        assert_eq!(
+            check_schema(&draft7).unwrap_err()[0].instance_path,
+            "/exclusiveMinimum"
+        );
+    }
+
+    #[test]
+    fn test_unsupported_draft() {
+        let issues = check_schema(&json!({"$schema": "http://example.com/schema"})).unwrap_err();
+        assert_eq!(issues[0].instance_path, "/$schema");
+    }
+}
@@ -0,0 +1,125 @@
+//! Schema generation from newline-delimited JSON (JSON Lines), such as the records
+//! exported by a logging pipeline, read one line at a time.
+
+use crate::{GeneratorError, SchemaGenerator, SchemaOptions};
+use serde_json::Value;
+use std::io::BufRead;
+
+impl SchemaGenerator {
+    /// Observes each line of `reader` as a record, as [`SchemaGenerator::observe`] does,
+    /// skipping blank lines and comments starting with `#`, and returns how many records
+    /// there were.
+    ///
+    /// Only one line is held in memory at a time. Fails with
+    /// [`GeneratorError::InvalidInput`] naming the line, counted from 1, that isn't JSON,
+    /// once the records before it are observed.
+    pub fn observe_ndjson<R: BufRead>(&mut self, mut reader: R) -> Result<usize, GeneratorError> {
+        let mut line = String::new();
+        let (mut number, mut records) = (0, 0);
+        loop {
+            line.clear();
+            let read = reader
+                .read_line(&mut line)
+                .map_err(|e| GeneratorError::InvalidInput(format!("line {}: {}", number + 1, e)))?;
+            if read == 0 {
+                return Ok(records);
+            }
+            number += 1;
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            let record: Value = serde_json::from_str(&line)
+                .map_err(|e| GeneratorError::InvalidInput(format!("line {}: {}", number, e)))?;
+            self.observe(&record)?;
+            records += 1;
+        }
+    }
+}
+
+/// Generates a JSON schema accepting the record on each line of `reader` that isn't blank
+/// or a comment, as
+/// [`generate_schema_from_samples`](crate::generate_schema_from_samples) does for the
+/// parsed records, without holding more than one line in memory.
+///
+/// # Examples
+///
+/// ```
+/// use serde_json::json;
+/// use json_schema_generator::generate_schema_from_ndjson;
+///
+/// let lines = "# exported 2024-01-15\n{\"id\": 1, \"user\": \"a\"}\n\n{\"id\": 2}\n";
+/// let schema = generate_schema_from_ndjson(lines.as_bytes()).unwrap();
+/// assert_eq!(schema["required"], json!(["id"]));
+/// assert!(generate_schema_from_ndjson("{\"id\": 1}\n{\"id\"\n".as_bytes()).is_err());
+/// ```
+pub fn generate_schema_from_ndjson<R: BufRead>(reader: R) -> Result<Value, GeneratorError> {
+    try_generate_schema_from_ndjson(reader, &SchemaOptions::default())
+}
+
+/// Generates a JSON schema accepting the record on each line of `reader` that isn't blank
+/// or a comment using the given options, as [`SchemaGenerator::observe_ndjson`] does.
+pub fn try_generate_schema_from_ndjson<R: BufRead>(
+    reader: R,
+    options: &SchemaOptions,
+) -> Result<Value, GeneratorError> {
+    let mut generator = SchemaGenerator::new(options.clone());
+    generator.observe_ndjson(reader)?;
+    generator.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const EVENTS: &str = r#"{"type": "click", "at": "2024-01-15T10:30:00Z", "target": {"id": "buy"}}
+{"type": "view", "at": "2024-01-15T10:31:00Z", "page": "/home", "duration": 1.5}
+
+{"type": "click", "at": "2024-01-15T10:32:00Z", "target": {"id": "cart", "x": 10}}
+
+{"type": "error", "at": "2024-01-15T10:33:00Z", "message": "timeout", "code": 504}
+"#;
+
+    #[test]
+    fn test_events() {
+        let schema = generate_schema_from_ndjson(EVENTS.as_bytes()).unwrap();
+        assert_eq!(schema["type"], "object");
+        assert_eq!(schema["required"], json!(["at", "type"]));
+        let properties = schema["properties"].as_object().unwrap();
+        let names: Vec<&str> = properties.keys().map(String::as_str).collect();
+        assert_eq!(
+            names,
+            ["at", "code", "duration", "message", "page", "target", "type"]
+        );
+        assert_eq!(properties["target"]["required"], json!(["id"]));
+
+        let records: Vec<Value> = EVENTS
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(schema, crate::generate_schema_from_samples(&records));
+
+        let mut generator = SchemaGenerator::new(SchemaOptions::default());
+        assert_eq!(generator.observe_ndjson(EVENTS.as_bytes()).unwrap(), 4);
+    }
+
+    #[test]
+    fn test_malformed_line() {
+        let lines = "{\"a\": 1}\r\n  # a comment\n{\"a\": }\n{\"a\": 2}\n";
+        match generate_schema_from_ndjson(lines.as_bytes()) {
+            Err(GeneratorError::InvalidInput(message)) => {
+                assert!(message.starts_with("line 3: "), "{}", message)
+            }
+            other => panic!("{:?}", other),
+        }
+        // Windows line endings and a last line without one.
+        let schema = generate_schema_from_ndjson("{\"a\": 1}\r\n{\"a\": 2}".as_bytes()).unwrap();
+        assert_eq!(schema["properties"]["a"], json!({"type": "integer"}));
+        assert_eq!(
+            generate_schema_from_ndjson("\n \n".as_bytes()).unwrap(),
+            crate::generate_schema_from_samples(&[])
+        );
+    }
+}
@@ -0,0 +1,337 @@
+//! The difference between two versions of a schema as an RFC 6902 JSON Patch, for
+//! registries that store schema changes as patches.
+
+use crate::{provenance, AsSchemaValue};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::fmt;
+
+/// One operation of a JSON Patch, serialized as RFC 6902 writes it, such as
+/// `{"op": "add", "path": "/required/1", "value": "id"}`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum PatchOp {
+    /// Adds `value` at the JSON Pointer `path`, inserting it into an array.
+    Add { path: String, value: Value },
+    /// Removes the value at `path`, shifting the rest of an array.
+    Remove { path: String },
+    /// Replaces the value at `path` by `value`.
+    Replace { path: String, value: Value },
+}
+
+/// One line such as `add /properties/id: {"type":"integer"}`.
+impl fmt::Display for PatchOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let at = |path: &str| if path.is_empty() { "/" } else { path }.to_string();
+        match self {
+            PatchOp::Add { path, value } => write!(f, "add {}: {}", at(path), value),
+            PatchOp::Remove { path } => write!(f, "remove {}", at(path)),
+            PatchOp::Replace { path, value } => write!(f, "replace {}: {}", at(path), value),
+        }
+    }
+}
+
+/// Returns `schema` in the form [`schema_patch`] compares: with each `required` sorted
+/// and without duplicates, and without the timestamp of its provenance.
+///
+/// `schema` can be a `Value` or, with the `schemars` feature, a schemars schema.
+pub fn normalize_schema(schema: &impl AsSchemaValue) -> Value {
+    let schema = schema.as_schema_value();
+    let mut normalized =
+        provenance::without_timestamp(&schema).unwrap_or_else(|| schema.into_owned());
+    sort_required(&mut normalized);
+    normalized
+}
+
+fn sort_required(value: &mut Value) {
+    match value {
+        Value::Object(obj) => {
+            for (keyword, value) in obj.iter_mut() {
+                match (keyword.as_str(), value) {
+                    ("required", Value::Array(names)) if names.iter().all(Value::is_string) => {
+                        names.sort_by(|a, b| a.as_str().cmp(&b.as_str()));
+                        names.dedup();
+                    }
+                    (_, value) => sort_required(value),
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(sort_required),
+        _ => {}
+    }
+}
+
+/// Returns the JSON Patch turning the [normalized](normalize_schema) `old` schema into
+/// the normalized `new` one.
+///
+/// Properties and keywords are added, removed and replaced one by one, and the names of
+/// a `required` list added and removed one by one rather than replacing the list.
+/// Other arrays are compared element by element when their lengths match, and replaced
+/// otherwise.
+///
+/// # Examples
+///
+/// ```
+/// use serde_json::json;
+/// use json_schema_generator::{schema_patch, PatchOp};
+///
+/// let old = json!({"type": "object", "required": ["id", "name"]});
+/// let new = json!({"type": "object", "required": ["email", "id"]});
+///
+/// assert_eq!(
+///     schema_patch(&old, &new),
+///     [
+///         PatchOp::Remove { path: "/required/1".to_string() },
+///         PatchOp::Add { path: "/required/0".to_string(), value: json!("email") },
+///     ]
+/// );
+/// ```
+pub fn schema_patch(old: &Value, new: &Value) -> Vec<PatchOp> {
+    let mut ops = Vec::new();
+    diff(
+        &normalize_schema(old),
+        &normalize_schema(new),
+        &mut String::new(),
+        &mut ops,
+    );
+    ops
+}
+
+/// Adds the operations turning `old` into `new`, found at the JSON Pointer `path`, to
+/// `ops`.
+fn diff(old: &Value, new: &Value, path: &mut String, ops: &mut Vec<PatchOp>) {
+    if old == new {
+        return;
+    }
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) => diff_objects(old, new, path, ops),
+        (Value::Array(old), Value::Array(new)) if old.len() == new.len() => {
+            for (index, (old, new)) in old.iter().zip(new).enumerate() {
+                within(path, &index.to_string(), |path| diff(old, new, path, ops));
+            }
+        }
+        _ => ops.push(PatchOp::Replace {
+            path: path.clone(),
+            value: new.clone(),
+        }),
+    }
+}
+
+fn diff_objects(
+    old: &Map<String, Value>,
+    new: &Map<String, Value>,
+    path: &mut String,
+    ops: &mut Vec<PatchOp>,
+) {
+    for (key, old) in old {
+        within(path, key, |path| match (key.as_str(), old, new.get(key)) {
+            (_, _, None) => ops.push(PatchOp::Remove { path: path.clone() }),
+            ("required", Value::Array(old), Some(Value::Array(new)))
+                if old.iter().chain(new).all(Value::is_string) =>
+            {
+                diff_names(old, new, path, ops)
+            }
+            (_, old, Some(new)) => diff(old, new, path, ops),
+        });
+    }
+    for (key, new) in new {
+        if !old.contains_key(key) {
+            within(path, key, |path| {
+                ops.push(PatchOp::Add {
+                    path: path.clone(),
+                    value: new.clone(),
+                })
+            });
+        }
+    }
+}
+
+/// Adds the operations turning the sorted list of names `old` into the sorted `new`:
+/// the removals from the last, then the additions from the first, so that each index
+/// holds when its operation applies.
+fn diff_names(old: &[Value], new: &[Value], path: &mut String, ops: &mut Vec<PatchOp>) {
+    for (index, name) in old.iter().enumerate().rev() {
+        if !new.contains(name) {
+            within(path, &index.to_string(), |path| {
+                ops.push(PatchOp::Remove { path: path.clone() })
+            });
+        }
+    }
+    for (index, name) in new.iter().enumerate() {
+        if !old.contains(name) {
+            within(path, &index.to_string(), |path| {
+                ops.push(PatchOp::Add {
+                    path: path.clone(),
+                    value: name.clone(),
+                })
+            });
+        }
+    }
+}
+
+/// Runs `f` with `segment`, escaped, appended to the JSON Pointer `path`.
+fn within(path: &mut String, segment: &str, f: impl FnOnce(&mut String)) {
+    let len = path.len();
+    path.push('/');
+    path.push_str(&segment.replace('~', "~0").replace('/', "~1"));
+    f(path);
+    path.truncate(len);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{generate_json_schema, SchemaGenerator, SchemaOptions};
+    use serde_json::json;
+
+    /// Applies `ops` to the normalized `old` as the json-patch crate does.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn apply(old: &Value, ops: &[PatchOp]) -> Value {
+        let patch: json_patch::Patch =
+            serde_json::from_value(serde_json::to_value(ops).unwrap()).unwrap();
+        let mut patched = normalize_schema(old);
+        json_patch::patch(&mut patched, &patch).unwrap();
+        patched
+    }
+
+    #[test]
+    fn test_objects_and_required() {
+        let old = json!({
+            "type": "object",
+            "properties": {"id": {"type": "integer"}, "name": {"type": "string"}},
+            "required": ["name", "id"],
+        });
+        let new = json!({
+            "type": "object",
+            "properties": {
+                "id": {"type": "string"},
+                "email": {"type": "string"},
+            },
+            "required": ["email", "id", "id"],
+        });
+        assert_eq!(
+            schema_patch(&old, &new),
+            [
+                PatchOp::Replace {
+                    path: "/properties/id/type".to_string(),
+                    value: json!("string"),
+                },
+                PatchOp::Remove {
+                    path: "/properties/name".to_string()
+                },
+                PatchOp::Add {
+                    path: "/properties/email".to_string(),
+                    value: json!({"type": "string"}),
+                },
+                PatchOp::Remove {
+                    path: "/required/1".to_string()
+                },
+                PatchOp::Add {
+                    path: "/required/0".to_string(),
+                    value: json!("email"),
+                },
+            ]
+        );
+        assert!(schema_patch(&new, &new).is_empty());
+        // The order of required names doesn't matter.
+        let reordered = json!({"required": ["b", "a"]});
+        assert!(schema_patch(&reordered, &json!({"required": ["a", "b"]})).is_empty());
+    }
+
+    #[test]
+    fn test_escaping() {
+        let old = json!({"properties": {"a/b": {"type": "integer"}, "c~d": {}}});
+        let new = json!({"properties": {"a/b": {"type": "string"}, "~/": {}}});
+        assert_eq!(
+            schema_patch(&old, &new),
+            [
+                PatchOp::Replace {
+                    path: "/properties/a~1b/type".to_string(),
+                    value: json!("string"),
+                },
+                PatchOp::Remove {
+                    path: "/properties/c~0d".to_string()
+                },
+                PatchOp::Add {
+                    path: "/properties/~0~1".to_string(),
+                    value: json!({}),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_arrays_and_root() {
+        let old = json!({"oneOf": [{"type": "integer"}, {"type": "null"}]});
+        let new = json!({"oneOf": [{"type": "number"}, {"type": "null"}]});
+        assert_eq!(
+            schema_patch(&old, &new),
+            [PatchOp::Replace {
+                path: "/oneOf/0/type".to_string(),
+                value: json!("number"),
+            }]
+        );
+        let longer = json!({"oneOf": [{"type": "integer"}, {"type": "null"}, {"type": "string"}]});
+        assert_eq!(
+            schema_patch(&old, &longer)[0].to_string(),
+            r#"replace /oneOf: [{"type":"integer"},{"type":"null"},{"type":"string"}]"#
+        );
+        assert_eq!(
+            schema_patch(&json!(true), &json!({})),
+            [PatchOp::Replace {
+                path: String::new(),
+                value: json!({}),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_serialization() {
+        let ops = [
+            PatchOp::Add {
+                path: "/a".to_string(),
+                value: json!(1),
+            },
+            PatchOp::Remove {
+                path: "/b".to_string(),
+            },
+        ];
+        assert_eq!(
+            serde_json::to_value(ops).unwrap(),
+            json!([{"op": "add", "path": "/a", "value": 1}, {"op": "remove", "path": "/b"}])
+        );
+    }
+
+    #[test]
+    fn test_provenance_timestamp_is_ignored() {
+        let generate = |reproducible| {
+            let mut generator = SchemaGenerator::new(SchemaOptions {
+                provenance: crate::ProvenanceMode::Extension,
+                reproducible,
+                ..Default::default()
+            });
+            generator.observe(&json!({"a": 1})).unwrap();
+            generator.schema()
+        };
+        assert!(schema_patch(&generate(false), &generate(true)).is_empty());
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_patch_applies() {
+        let versions = [
+            generate_json_schema(&json!({"id": 1, "name": "a", "tags": ["x"]})),
+            generate_json_schema(&json!({"id": "1", "email": "e", "tags": [1], "a/b~c": null})),
+            generate_json_schema(&json!([{"id": 1}, {"id": 2, "note": "n"}])),
+            json!({"required": ["z", "a", "m"], "properties": {"z": {"required": ["y", "x"]}}}),
+            json!({"required": ["b", "m"], "properties": {"z": {"required": ["x"]}}}),
+            json!(true),
+        ];
+        for old in &versions {
+            for new in &versions {
+                let patched = apply(old, &schema_patch(old, new));
+                assert_eq!(patched, normalize_schema(new), "{} -> {}", old, new);
+            }
+        }
+    }
+}
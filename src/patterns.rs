@@ -0,0 +1,356 @@
+//! A library of recurring string shapes that have no standard `format`, such as semantic
+//! versions and hex colors, written as a `pattern` when every observed value has one.
+
+use regex::Regex;
+use serde_json::Value;
+use std::error::Error;
+use std::fmt;
+
+/// The built-in patterns, by name. Each is anchored and written in the subset of regular
+/// expressions shared by ECMA-262, which JSON Schema uses, and the `regex` crate.
+pub const BUILTIN_PATTERNS: [(&str, &str); 6] = [
+    (
+        "semver",
+        r"^(0|[1-9][0-9]*)\.(0|[1-9][0-9]*)\.(0|[1-9][0-9]*)(-[0-9A-Za-z-]+(\.[0-9A-Za-z-]+)*)?(\+[0-9A-Za-z-]+(\.[0-9A-Za-z-]+)*)?$",
+    ),
+    ("hex-color", r"^#([0-9A-Fa-f]{3}|[0-9A-Fa-f]{6})$"),
+    ("country-code", r"^[A-Z]{2}$"),
+    ("currency-code", r"^[A-Z]{3}$"),
+    (
+        "mac-address",
+        r"^(([0-9A-Fa-f]{2}:){5}|([0-9A-Fa-f]{2}-){5})[0-9A-Fa-f]{2}$",
+    ),
+    (
+        "language-tag",
+        r"^[a-z]{2,3}(-[A-Z][a-z]{3})?(-([A-Z]{2}|[0-9]{3}))?$",
+    ),
+];
+
+/// A named string shape, see [`Patterns`].
+#[derive(Debug, Clone)]
+pub struct StringPattern {
+    name: String,
+    pattern: String,
+    regex: Regex,
+}
+
+impl StringPattern {
+    /// Compiles the pattern `pattern`, anchoring it at both ends unless it already is.
+    pub fn new(name: impl Into<String>, pattern: &str) -> Result<Self, PatternError> {
+        let name = name.into();
+        let pattern = if pattern.starts_with('^') && pattern.ends_with('$') {
+            pattern.to_string()
+        } else {
+            format!("^(?:{})$", pattern)
+        };
+        match Regex::new(&pattern) {
+            Ok(regex) => Ok(StringPattern {
+                name,
+                pattern,
+                regex,
+            }),
+            Err(error) => Err(PatternError::InvalidRegex {
+                name,
+                message: error.to_string(),
+            }),
+        }
+    }
+
+    /// Returns the built-in pattern called `name`, one of [`BUILTIN_PATTERNS`].
+    pub fn builtin(name: &str) -> Result<Self, PatternError> {
+        let (name, pattern) = BUILTIN_PATTERNS
+            .iter()
+            .find(|(builtin, _)| *builtin == name)
+            .ok_or_else(|| PatternError::Unknown(name.to_string()))?;
+        StringPattern::new(*name, pattern)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The anchored regular expression written as the `pattern`.
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+}
+
+/// The string shapes recognized with [`SchemaOptions::patterns`](crate::SchemaOptions::patterns).
+///
+/// A string property gets the `pattern` of the first entry of `library` its values match,
+/// as long as all of them do; values of different shapes merge into a plain string, the
+/// way differing formats do.
+#[derive(Debug, Clone, Default)]
+pub struct Patterns {
+    pub library: Vec<StringPattern>,
+    /// Also write the name of the pattern as `x-pattern-name`.
+    pub annotate_names: bool,
+}
+
+impl Patterns {
+    /// All of the [`BUILTIN_PATTERNS`].
+    pub fn builtins() -> Self {
+        let library = BUILTIN_PATTERNS
+            .iter()
+            .map(|(name, pattern)| {
+                StringPattern::new(*name, pattern).expect("the built-in patterns compile")
+            })
+            .collect();
+        Patterns {
+            library,
+            annotate_names: false,
+        }
+    }
+
+    /// The built-in patterns called `names`, in that order.
+    pub fn select<S: AsRef<str>>(names: &[S]) -> Result<Self, PatternError> {
+        let library = names
+            .iter()
+            .map(|name| StringPattern::builtin(name.as_ref()))
+            .collect::<Result<_, _>>()?;
+        Ok(Patterns {
+            library,
+            annotate_names: false,
+        })
+    }
+
+    /// Adds the pattern `pattern` called `name` after the others.
+    pub fn with_custom(
+        mut self,
+        name: impl Into<String>,
+        pattern: &str,
+    ) -> Result<Self, PatternError> {
+        self.library.push(StringPattern::new(name, pattern)?);
+        Ok(self)
+    }
+
+    /// Returns the schema of the string `value`.
+    pub(crate) fn string_schema(&self, value: &str) -> Value {
+        let mut schema = serde_json::json!({"type": "string"});
+        if let Some(found) = self
+            .library
+            .iter()
+            .find(|entry| entry.regex.is_match(value))
+        {
+            schema["pattern"] = Value::String(found.pattern.clone());
+            if self.annotate_names {
+                schema["x-pattern-name"] = Value::String(found.name.clone());
+            }
+        }
+        schema
+    }
+}
+
+/// Why a [`StringPattern`] could not be created.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatternError {
+    /// No built-in pattern has this name.
+    Unknown(String),
+    /// The regular expression of the pattern `name` does not compile.
+    InvalidRegex { name: String, message: String },
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PatternError::Unknown(name) => write!(
+                f,
+                "unknown pattern '{}', expected one of {}",
+                name,
+                BUILTIN_PATTERNS.map(|(name, _)| name).join(", ")
+            ),
+            PatternError::InvalidRegex { name, message } => {
+                write!(f, "invalid regular expression for '{}': {}", name, message)
+            }
+        }
+    }
+}
+
+impl Error for PatternError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{generate_from_raw, try_generate_json_schema, SchemaGenerator, SchemaOptions};
+    use serde_json::json;
+
+    fn matches(name: &str, value: &str) -> bool {
+        StringPattern::builtin(name).unwrap().regex.is_match(value)
+    }
+
+    fn options(patterns: Patterns) -> SchemaOptions {
+        SchemaOptions {
+            patterns: Some(patterns),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_semver() {
+        for value in [
+            "1.4.2",
+            "0.0.0",
+            "10.20.30",
+            "1.0.0-alpha.1",
+            "1.0.0+build.5",
+        ] {
+            assert!(matches("semver", value), "{}", value);
+        }
+        for value in ["1.4", "01.2.3", "1.2.3.4", "v1.2.3", "1.2.3-"] {
+            assert!(!matches("semver", value), "{}", value);
+        }
+    }
+
+    #[test]
+    fn test_hex_color() {
+        for value in ["#ff0033", "#FFF", "#a1B2c3"] {
+            assert!(matches("hex-color", value), "{}", value);
+        }
+        for value in ["ff0033", "#ff00", "#gg0033", "#ff00333"] {
+            assert!(!matches("hex-color", value), "{}", value);
+        }
+    }
+
+    #[test]
+    fn test_country_and_currency_codes() {
+        assert!(matches("country-code", "US"));
+        assert!(!matches("country-code", "us"));
+        assert!(!matches("country-code", "USA"));
+        assert!(matches("currency-code", "EUR"));
+        assert!(!matches("currency-code", "EU"));
+        assert!(!matches("currency-code", "Eur"));
+    }
+
+    #[test]
+    fn test_mac_address() {
+        assert!(matches("mac-address", "00:1A:2b:3C:4d:5E"));
+        assert!(matches("mac-address", "00-1A-2B-3C-4D-5E"));
+        assert!(!matches("mac-address", "00:1A-2B:3C:4D:5E"));
+        assert!(!matches("mac-address", "00:1A:2B:3C:4D"));
+        assert!(!matches("mac-address", "00:1A:2B:3C:4D:5G"));
+    }
+
+    #[test]
+    fn test_language_tag() {
+        for value in ["en", "en-US", "zh-Hant-TW", "es-419", "haw"] {
+            assert!(matches("language-tag", value), "{}", value);
+        }
+        for value in ["EN", "en_US", "en-us", "english"] {
+            assert!(!matches("language-tag", value), "{}", value);
+        }
+    }
+
+    #[test]
+    fn test_every_value_must_match() {
+        let semver = StringPattern::builtin("semver").unwrap();
+        let mut generator = SchemaGenerator::new(options(Patterns::builtins()));
+        generator
+            .observe(&json!({"version": "1.4.2", "colors": ["#fff", "#000000"]}))
+            .unwrap();
+        generator
+            .observe(&json!({"version": "2.0.0", "colors": ["#123"]}))
+            .unwrap();
+        let schema = generator.schema();
+        assert_eq!(
+            schema["properties"]["version"],
+            json!({"type": "string", "pattern": semver.pattern()})
+        );
+        assert_eq!(
+            schema["properties"]["colors"]["items"]["pattern"],
+            StringPattern::builtin("hex-color").unwrap().pattern()
+        );
+
+        generator
+            .observe(&json!({"version": "latest", "colors": ["red"]}))
+            .unwrap();
+        let schema = generator.schema();
+        assert_eq!(schema["properties"]["version"], json!({"type": "string"}));
+        assert_eq!(
+            schema["properties"]["colors"]["items"],
+            json!({"type": "string"})
+        );
+    }
+
+    #[test]
+    fn test_strict_pattern_drop() {
+        let mut generator = SchemaGenerator::new(SchemaOptions {
+            strictness: crate::Strictness::Strict,
+            ..options(Patterns::builtins())
+        });
+        generator.observe(&json!({"version": "1.0.0"})).unwrap();
+        generator.observe(&json!({"version": "1.0.1"})).unwrap();
+        assert!(generator.observe(&json!({"version": "latest"})).is_err());
+        let conflict = &generator.conflicts()[0];
+        assert_eq!(conflict.code, "pattern-drop");
+        assert_eq!(conflict.pointer, "/properties/version");
+    }
+
+    #[test]
+    fn test_selection_names_and_custom_patterns() {
+        let patterns = Patterns::select(&["currency-code"])
+            .unwrap()
+            .with_custom("order-id", "ORD-[0-9]{6}")
+            .unwrap();
+        let patterns = Patterns {
+            annotate_names: true,
+            ..patterns
+        };
+        let instance =
+            json!({"currency": "EUR", "country": "DE", "order": "ORD-000042", "note": "ORD-1"});
+        let schema = try_generate_json_schema(&instance, &options(patterns.clone())).unwrap();
+        let properties = &schema["properties"];
+        assert_eq!(properties["currency"]["x-pattern-name"], "currency-code");
+        // Country codes are not selected.
+        assert_eq!(properties["country"], json!({"type": "string"}));
+        assert_eq!(
+            properties["order"],
+            json!({"type": "string", "pattern": "^(?:ORD-[0-9]{6})$", "x-pattern-name": "order-id"})
+        );
+        assert_eq!(properties["note"], json!({"type": "string"}));
+
+        let raw = serde_json::value::to_raw_value(&instance).unwrap();
+        assert_eq!(generate_from_raw(&raw, &options(patterns)).unwrap(), schema);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            Patterns::select(&["semver", "isbn"]).unwrap_err().to_string(),
+            "unknown pattern 'isbn', expected one of semver, hex-color, country-code, currency-code, mac-address, language-tag"
+        );
+        assert!(matches!(
+            StringPattern::new("broken", "(["),
+            Err(PatternError::InvalidRegex { ref name, .. }) if name == "broken"
+        ));
+        assert_eq!(StringPattern::new("a", "^a$").unwrap().pattern(), "^a$");
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_samples_validate() {
+        let samples = [
+            json!({"version": "1.0.0-rc.1", "color": "#abc", "mac": "00:11:22:33:44:55", "lang": "pt-BR"}),
+            json!({"version": "3.2.1", "color": "#abcdef", "mac": "aa-bb-cc-dd-ee-ff", "lang": "de"}),
+        ];
+        let mut generator = SchemaGenerator::new(options(Patterns::builtins()));
+        for sample in &samples {
+            generator.observe(sample).unwrap();
+        }
+        let schema = generator.schema();
+        for name in ["version", "color", "mac", "lang"] {
+            assert!(
+                schema["properties"][name].get("pattern").is_some(),
+                "{}",
+                name
+            );
+        }
+        for sample in &samples {
+            assert!(crate::validation::validate_instance(&schema, sample).is_ok());
+        }
+        assert!(crate::validation::validate_instance(
+            &schema,
+            &json!({"version": "1.0", "color": "#abc", "mac": "00:11:22:33:44:55", "lang": "de"})
+        )
+        .is_err());
+    }
+}
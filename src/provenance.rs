@@ -0,0 +1,344 @@
+//! A record of how a schema was generated, written into the schema for auditing.
+
+use crate::SchemaOptions;
+use serde_json::{Map, Value};
+use std::fmt;
+use std::str::FromStr;
+
+const NAME: &str = env!("CARGO_PKG_NAME");
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// The key of the [`ProvenanceMode::Extension`] object.
+pub(crate) const EXTENSION: &str = "x-generated-by";
+/// Separates the timestamp from the rest of a [`ProvenanceMode::Comment`].
+const COMMENT_TIMESTAMP: &str = ", at ";
+
+/// Whether and how the provenance of a schema is recorded, see
+/// [`SchemaOptions::provenance`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ProvenanceMode {
+    /// Record nothing.
+    #[default]
+    Off,
+    /// A `$comment` at the root such as `Generated by json-schema-generator 0.1.1 from 3
+    /// samples, options 5d2c0fa3b10e7c41, at 2024-05-01T12:00:00Z`.
+    Comment,
+    /// An `x-generated-by` object at the root with the `name` and `version` of the tool,
+    /// the number of `samples`, the `options_hash` and the `generated_at` timestamp.
+    Extension,
+}
+
+impl fmt::Display for ProvenanceMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ProvenanceMode::Off => "off",
+            ProvenanceMode::Comment => "comment",
+            ProvenanceMode::Extension => "extension",
+        })
+    }
+}
+
+impl FromStr for ProvenanceMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "off" => Ok(ProvenanceMode::Off),
+            "comment" => Ok(ProvenanceMode::Comment),
+            "extension" => Ok(ProvenanceMode::Extension),
+            _ => Err(format!(
+                "unknown provenance mode '{}', expected one of off, comment or extension",
+                s
+            )),
+        }
+    }
+}
+
+/// Records the provenance of `schema`, generated from `samples` records, as `options`
+/// ask.
+pub(crate) fn record(schema: &mut Value, samples: usize, options: &SchemaOptions) {
+    let timestamp = if options.reproducible {
+        None
+    } else {
+        timestamp()
+    };
+    let Some(obj) = schema.as_object_mut() else {
+        return;
+    };
+
+    match options.provenance {
+        ProvenanceMode::Off => {}
+        ProvenanceMode::Comment => {
+            let mut comment = format!(
+                "Generated by {} {} from {} sample{}, options {}",
+                NAME,
+                VERSION,
+                samples,
+                if samples == 1 { "" } else { "s" },
+                options_hash(options)
+            );
+            if let Some(timestamp) = timestamp {
+                comment.push_str(COMMENT_TIMESTAMP);
+                comment.push_str(&timestamp);
+            }
+            obj.insert("$comment".to_string(), Value::String(comment));
+        }
+        ProvenanceMode::Extension => {
+            let mut provenance = Map::new();
+            provenance.insert("name".to_string(), Value::String(NAME.to_string()));
+            provenance.insert("version".to_string(), Value::String(VERSION.to_string()));
+            provenance.insert("samples".to_string(), Value::from(samples));
+            provenance.insert(
+                "options_hash".to_string(),
+                Value::String(options_hash(options)),
+            );
+            if let Some(timestamp) = timestamp {
+                provenance.insert("generated_at".to_string(), Value::String(timestamp));
+            }
+            obj.insert(EXTENSION.to_string(), Value::Object(provenance));
+        }
+    }
+}
+
+/// Returns a copy of `schema` without the timestamp of its provenance, or `None` if it
+/// has none, so that the schemas of two runs compare equal.
+pub(crate) fn without_timestamp(schema: &Value) -> Option<Value> {
+    let obj = schema.as_object()?;
+    let comment = obj
+        .get("$comment")
+        .and_then(Value::as_str)
+        .filter(|comment| comment.starts_with(&format!("Generated by {} ", NAME)))
+        .and_then(|comment| comment.rfind(COMMENT_TIMESTAMP).map(|at| &comment[..at]));
+    let extension = obj
+        .get(EXTENSION)
+        .and_then(Value::as_object)
+        .filter(|provenance| provenance.contains_key("generated_at"));
+    if comment.is_none() && extension.is_none() {
+        return None;
+    }
+
+    let mut copy = obj.clone();
+    if let Some(comment) = comment {
+        copy.insert("$comment".to_string(), Value::String(comment.to_string()));
+    }
+    if let Some(Value::Object(provenance)) = copy.get_mut(EXTENSION) {
+        provenance.remove("generated_at");
+    }
+    Some(Value::Object(copy))
+}
+
+/// Returns a hash of the options that change the generated schema, as 16 hex digits.
+/// The hash is FNV-1a, which is stable across platforms and Rust versions.
+fn options_hash(options: &SchemaOptions) -> String {
+    #[allow(unused_mut)]
+    let mut description = format!(
+        "{:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?}",
+        options.max_depth,
+        options.max_properties,
+        options.max_schema_bytes,
+        options.empty_containers,
+        options.dependent_required,
+        options.discriminators,
+        options.anchors,
+        options.shared_definitions,
+        options.merge_case_variants,
+        options.type_defaults,
+        options.infer_formats,
+        options.enums,
+        options.tuples,
+        options.numeric_bounds,
+        options.non_negative_minimum,
+        options.string_lengths,
+        options.array_lengths,
+        options.unique_items,
+        options.coordinates,
+        options.closed_objects,
+        options.annotate_access,
+        options.access_rules,
+        options.root_examples,
+        options.examples_max_bytes,
+        options.merge_policy,
+        options.draft,
+        options.nested_dialect,
+        options.all_optional,
+        options.required_threshold,
+        options.union_keyword,
+        options.nullable_type_arrays,
+    );
+    #[cfg(feature = "patterns")]
+    description.push_str(&format!(" {:?}", options.patterns));
+    let hash = description
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        });
+    format!("{:016x}", hash)
+}
+
+/// Returns the current time in RFC 3339 form, or `None` where the platform has no clock.
+fn timestamp() -> Option<String> {
+    #[cfg(target_arch = "wasm32")]
+    return None;
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let since_epoch = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .ok()?;
+        Some(format_timestamp(since_epoch.as_secs()))
+    }
+}
+
+/// Formats seconds since the Unix epoch as a UTC date and time, such as
+/// `2024-05-01T12:00:00Z`.
+fn format_timestamp(seconds: u64) -> String {
+    let (days, time) = (seconds / 86_400, seconds % 86_400);
+    // Converts days to a civil date, after Howard Hinnant's `civil_from_days`.
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let day_of_era = z % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{to_canonical_string, try_generate_json_schema, SchemaGenerator};
+    use serde_json::json;
+
+    fn generate(mode: ProvenanceMode, reproducible: bool) -> Value {
+        let mut generator = SchemaGenerator::new(SchemaOptions {
+            provenance: mode,
+            reproducible,
+            ..Default::default()
+        });
+        generator.observe(&json!({"a": 1})).unwrap();
+        generator.observe(&json!({"a": 2})).unwrap();
+        generator.schema()
+    }
+
+    #[test]
+    fn test_comment() {
+        let schema = generate(ProvenanceMode::Comment, true);
+        let expected = format!(
+            "Generated by {} {} from 2 samples, options {}",
+            NAME,
+            VERSION,
+            options_hash(&SchemaOptions::default())
+        );
+        assert_eq!(schema["$comment"], expected);
+
+        let schema = generate(ProvenanceMode::Comment, false);
+        let comment = schema["$comment"].as_str().unwrap();
+        #[cfg(not(target_arch = "wasm32"))]
+        assert!(
+            comment.starts_with(&format!("{}, at ", expected)),
+            "{}",
+            comment
+        );
+        assert_eq!(schema["properties"]["a"], json!({"type": "integer"}));
+    }
+
+    #[test]
+    fn test_extension() {
+        let schema = generate(ProvenanceMode::Extension, false);
+        let provenance = &schema[EXTENSION];
+        assert_eq!(provenance["name"], NAME);
+        assert_eq!(provenance["version"], VERSION);
+        assert_eq!(provenance["samples"], 2);
+        assert_eq!(provenance["options_hash"].as_str().unwrap().len(), 16);
+        #[cfg(not(target_arch = "wasm32"))]
+        assert!(provenance["generated_at"].as_str().unwrap().ends_with('Z'));
+
+        let reproducible = generate(ProvenanceMode::Extension, true);
+        assert!(reproducible[EXTENSION].get("generated_at").is_none());
+        assert!(generate(ProvenanceMode::Off, false)
+            .get(EXTENSION)
+            .is_none());
+    }
+
+    #[test]
+    fn test_canonical_form_ignores_timestamp() {
+        for mode in [ProvenanceMode::Comment, ProvenanceMode::Extension] {
+            let mut earlier = generate(mode, false);
+            let later = generate(mode, false);
+            match mode {
+                ProvenanceMode::Comment => {
+                    earlier["$comment"] = json!(format!(
+                        "{}, at 2001-09-09T01:46:40Z",
+                        generate(mode, true)["$comment"].as_str().unwrap()
+                    ))
+                }
+                _ => earlier[EXTENSION]["generated_at"] = json!("2001-09-09T01:46:40Z"),
+            }
+            assert_ne!(earlier, later);
+            assert_eq!(to_canonical_string(&earlier), to_canonical_string(&later));
+            assert_eq!(
+                to_canonical_string(&earlier),
+                to_canonical_string(&generate(mode, true))
+            );
+        }
+
+        // Other comments are left alone.
+        let comment = json!({"$comment": "kept, at noon"});
+        assert_eq!(
+            to_canonical_string(&comment),
+            r#"{"$comment":"kept, at noon"}"#
+        );
+    }
+
+    #[test]
+    fn test_options_change_the_hash() {
+        let options = SchemaOptions {
+            provenance: ProvenanceMode::Extension,
+            reproducible: true,
+            ..Default::default()
+        };
+        let schema = try_generate_json_schema(&json!(1), &options).unwrap();
+        assert_eq!(schema[EXTENSION]["samples"], 1);
+        let closed = SchemaOptions {
+            closed_objects: Some(crate::ClosedWith::AdditionalProperties),
+            ..options.clone()
+        };
+        assert_ne!(options_hash(&options), options_hash(&closed));
+        assert_eq!(options_hash(&options), options_hash(&options.clone()));
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(0), "1970-01-01T00:00:00Z");
+        assert_eq!(format_timestamp(951_782_400), "2000-02-29T00:00:00Z");
+        assert_eq!(format_timestamp(1_000_000_000), "2001-09-09T01:46:40Z");
+        assert_eq!(format_timestamp(4_102_444_799), "2099-12-31T23:59:59Z");
+    }
+
+    #[test]
+    fn test_parse_mode() {
+        for mode in [
+            ProvenanceMode::Off,
+            ProvenanceMode::Comment,
+            ProvenanceMode::Extension,
+        ] {
+            assert_eq!(mode.to_string().parse(), Ok(mode));
+        }
+        assert!("on".parse::<ProvenanceMode>().is_err());
+    }
+}
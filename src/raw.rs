@@ -0,0 +1,665 @@
+//! Schema generation straight from serialized JSON, without building a `Value` first.
+//!
+//! The generator is driven by a [`serde::Deserializer`]: scalars are classified as they
+//! are read, so strings are never copied, and array items are merged into a running
+//! common schema instead of being collected. Only object keys, which become property
+//! names, and the values of candidate discriminators are allocated, as are the elements
+//! of arrays with [`SchemaOptions::unique_items`].
+
+use crate::defaults::Observed;
+use crate::{object_schema, Context, Elements, GeneratorError, SchemaOptions, SchemaPath, State};
+use serde::de::{self, DeserializeSeed, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde_json::de::{IoRead, Read, StrRead};
+use serde_json::value::RawValue;
+use serde_json::{json, Map, Number, Value};
+use std::collections::HashMap;
+use std::{fmt, io};
+
+/// Generates a JSON schema for the serialized JSON document `raw`.
+///
+/// The result is the same as that of [`try_generate_json_schema`](crate::try_generate_json_schema)
+/// on the parsed document, but no intermediate `Value` is built. Warnings and progress
+/// events are raised in document order rather than in key order.
+///
+/// # Examples
+///
+/// ```
+/// use serde_json::value::RawValue;
+/// use json_schema_generator::{generate_from_raw, generate_json_schema, SchemaOptions};
+///
+/// let json = r#"{"name": "John", "tags": ["a", "b"]}"#;
+/// let raw: &RawValue = serde_json::from_str(json).unwrap();
+///
+/// let schema = generate_from_raw(raw, &SchemaOptions::default()).unwrap();
+/// assert_eq!(schema, generate_json_schema(&serde_json::from_str(json).unwrap()));
+/// ```
+pub fn generate_from_raw(raw: &RawValue, options: &SchemaOptions) -> Result<Value, GeneratorError> {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::debug_span!("generate_from_raw").entered();
+
+    generate_document(StrRead::new(raw.get()), Some(raw), options)
+}
+
+/// Generates a JSON schema for the JSON document `json`, as [`generate_json_schema`]
+/// does for the parsed document, failing if it isn't JSON.
+///
+/// [`generate_json_schema`]: crate::generate_json_schema
+///
+/// # Examples
+///
+/// ```
+/// use serde_json::json;
+/// use json_schema_generator::{generate_json_schema, generate_json_schema_from_str};
+///
+/// let schema = generate_json_schema_from_str(r#"{"name": "John"}"#).unwrap();
+/// assert_eq!(schema, generate_json_schema(&json!({"name": "John"})));
+/// assert!(generate_json_schema_from_str(r#"{"name": "#).is_err());
+/// ```
+pub fn generate_json_schema_from_str(json: &str) -> Result<Value, GeneratorError> {
+    generate_document(StrRead::new(json), None, &SchemaOptions::default())
+}
+
+/// Generates a JSON schema for the JSON document read from `reader`, such as a
+/// `BufReader<File>`, as [`generate_json_schema`] does for the parsed document. The
+/// document is read as it is generated rather than held in memory. Fails with
+/// [`GeneratorError::Json`] if it can't be read or isn't JSON.
+///
+/// [`generate_json_schema`]: crate::generate_json_schema
+pub fn generate_json_schema_from_reader<R: io::Read>(reader: R) -> Result<Value, GeneratorError> {
+    generate_document(IoRead::new(reader), None, &SchemaOptions::default())
+}
+
+/// Generates the schema of the JSON document `read`. The options that keep samples,
+/// [`SchemaOptions::root_examples`] and `verify`, need the document as `raw`.
+fn generate_document<'de, R: Read<'de>>(
+    read: R,
+    raw: Option<&RawValue>,
+    options: &SchemaOptions,
+) -> Result<Value, GeneratorError> {
+    let mut state = State::default();
+    let mut ctx = Context::new(options, &mut state);
+    let mut schema = generate_read_at(read, &mut ctx)?;
+    ctx.limit_size(&mut schema);
+    ctx.check_limits(0)?;
+    ctx.check_conflicts(0)?;
+    ctx.state.records = 1;
+    if let (true, Some(raw)) = (options.root_examples > 0, raw) {
+        ctx.state.samples.offer(&parse(raw)?, options);
+    }
+    crate::finish(&mut schema, ctx.state, options);
+    let schema = crate::transform(schema, options)?;
+    ctx.report(None, 0, true);
+    #[cfg(feature = "validation")]
+    if let (true, Some(raw)) = (options.verify, raw) {
+        crate::validation::verify_samples(&schema, [&parse(raw)?])?;
+    }
+    Ok(schema)
+}
+
+/// Generates the schema of `raw` at the root of a run.
+pub(crate) fn generate_raw_at(raw: &RawValue, ctx: &mut Context) -> Result<Value, GeneratorError> {
+    generate_read_at(StrRead::new(raw.get()), ctx)
+}
+
+/// Generates the schema of the JSON document `read` at the root of a run.
+fn generate_read_at<'de, R: Read<'de>>(
+    read: R,
+    ctx: &mut Context,
+) -> Result<Value, GeneratorError> {
+    let mut generation = Generation { ctx, error: None };
+    let mut deserializer = serde_json::Deserializer::new(read);
+    let result = SchemaSeed {
+        generation: &mut generation,
+        path: &SchemaPath::root(),
+    }
+    .deserialize(&mut deserializer)
+    .and_then(|schema| deserializer.end().map(|()| schema));
+
+    match (result, generation.error) {
+        (_, Some(error)) => Err(error),
+        (Ok(schema), None) => Ok(schema),
+        (Err(error), None) => Err(error.into()),
+    }
+}
+
+/// Parses `raw` into a `Value`, for the callers that need to keep the document.
+pub(crate) fn parse(raw: &RawValue) -> Result<Value, GeneratorError> {
+    serde_json::from_str(raw.get()).map_err(GeneratorError::from)
+}
+
+/// A generation run, with a slot for the error that stopped it.
+///
+/// Deserialization can only fail with the deserializer's error type, so a
+/// [`GeneratorError`] is stashed here and replaced by a placeholder error that unwinds
+/// the deserializer.
+struct Generation<'c, 'o> {
+    ctx: &'c mut Context<'o>,
+    error: Option<GeneratorError>,
+}
+
+impl Generation<'_, '_> {
+    fn stop<E: de::Error>(&mut self, error: GeneratorError) -> E {
+        let message = error.to_string();
+        self.error = Some(error);
+        E::custom(message)
+    }
+}
+
+/// Deserializes one JSON value into its schema.
+struct SchemaSeed<'g, 'c, 'o, 'p> {
+    generation: &'g mut Generation<'c, 'o>,
+    path: &'p SchemaPath<'p>,
+}
+
+impl<'de> DeserializeSeed<'de> for SchemaSeed<'_, '_, '_, '_> {
+    type Value = Value;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        self.generation.ctx.count_node();
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for SchemaSeed<'_, '_, '_, '_> {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a JSON value")
+    }
+
+    fn visit_bool<E>(self, _: bool) -> Result<Value, E> {
+        Ok(self.generation.ctx.boolean_schema())
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Value, E> {
+        let schema = json!({"type": "integer"});
+        Ok(self
+            .generation
+            .ctx
+            .number_schema(schema, &Number::from(v), self.path))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Value, E> {
+        let schema = json!({"type": "integer"});
+        Ok(self
+            .generation
+            .ctx
+            .number_schema(schema, &Number::from(v), self.path))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<Value, E> {
+        let schema = json!({"type": "number"});
+        // JSON has no infinite or NaN numbers to read.
+        let n = Number::from_f64(v).unwrap_or_else(|| Number::from(0));
+        Ok(self.generation.ctx.number_schema(schema, &n, self.path))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Value, E> {
+        Ok(self.generation.ctx.string_schema(v, self.path))
+    }
+
+    fn visit_unit<E>(self) -> Result<Value, E> {
+        let mut schema = json!({"type": "null"});
+        self.generation
+            .ctx
+            .type_defaults(&mut schema, Observed::Other);
+        Ok(schema)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        let path = self.path;
+        if path.depth >= self.generation.ctx.options.max_depth {
+            // Skipping is not recursive, unlike deserializing.
+            while seq.next_element::<IgnoredAny>()?.is_some() {}
+            self.generation.ctx.limit_depth(path);
+            return Ok(json!({}));
+        }
+
+        let items_path = path.items();
+        let mut elements = Elements::default();
+        let mut distinct = crate::unique::Distinct::default();
+        let mut index = 0;
+        loop {
+            let item = match self.generation.ctx.options.unique_items {
+                // The elements are kept to be compared.
+                true => match seq.next_element::<Value>()? {
+                    Some(value) => {
+                        distinct.record(&value);
+                        crate::generate_schema_at(&value, self.generation.ctx, &items_path)
+                            .map_err(|error| self.generation.stop(error))?
+                    }
+                    None => break,
+                },
+                false => match seq.next_element_seed(SchemaSeed {
+                    generation: &mut *self.generation,
+                    path: &items_path,
+                })? {
+                    Some(item) => item,
+                    None => break,
+                },
+            };
+            elements.push(item, self.generation.ctx, &items_path);
+            if let Err(error) = self.generation.ctx.count_element(path, index) {
+                return Err(self.generation.stop(error));
+            }
+            index += 1;
+        }
+        if index > 0 {
+            trace!(pointer = %path, elements = index, "folding array items");
+        }
+
+        let mut schema = json!({"type": "array"});
+        elements.write(&mut schema, self.generation.ctx, &items_path);
+        self.generation
+            .ctx
+            .unique_items(&mut schema, distinct, path);
+        self.generation.ctx.count_items(&mut schema, index);
+        self.generation
+            .ctx
+            .type_defaults(&mut schema, Observed::Array(index));
+        Ok(schema)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+        let path = self.path;
+        if let Err(error) = self.generation.ctx.check_cancelled() {
+            return Err(self.generation.stop(error));
+        }
+        if path.depth >= self.generation.ctx.options.max_depth {
+            while map.next_entry::<IgnoredAny, IgnoredAny>()?.is_some() {}
+            self.generation.ctx.limit_depth(path);
+            return Ok(json!({}));
+        }
+
+        // Later duplicates of a key replace earlier ones, as when parsing into a `Value`.
+        let mut properties = Map::new();
+        let mut reference = None;
+        // The string values of the candidate discriminators.
+        let mut strings = HashMap::new();
+        while let Some(key) = map.next_key::<String>()? {
+            let property_path = path.property(&key);
+            if key == "$ref" {
+                let seed = SchemaSeed {
+                    generation: &mut *self.generation,
+                    path: &property_path,
+                };
+                match map.next_value_seed(CaptureSeed(seed))? {
+                    Captured::String(value) => {
+                        let value = Value::String(value);
+                        self.generation.ctx.warn(
+                            "ref-passthrough",
+                            path,
+                            format!(
+                                "copied the reference {} into the schema without resolving it",
+                                value
+                            ),
+                        );
+                        properties.remove(&key);
+                        reference = Some(value);
+                    }
+                    Captured::Other(mut sub_schema) => {
+                        reference = None;
+                        self.generation.ctx.annotate_access(&key, &mut sub_schema);
+                        properties.insert(key, sub_schema);
+                    }
+                }
+                continue;
+            }
+
+            if properties.len() >= self.generation.ctx.options.max_properties
+                && !properties.contains_key(&key)
+            {
+                // Keep the first properties in key order, as the `Value` path does.
+                match properties.keys().next_back() {
+                    Some(last) if *last > key => {
+                        let last = last.clone();
+                        properties.remove(&last);
+                    }
+                    _ => {
+                        map.next_value::<IgnoredAny>()?;
+                        self.generation.ctx.limit_properties(path);
+                        continue;
+                    }
+                }
+                self.generation.ctx.limit_properties(path);
+            }
+            let seed = SchemaSeed {
+                generation: &mut *self.generation,
+                path: &property_path,
+            };
+            let discriminators = &seed.generation.ctx.options.discriminators;
+            let mut sub_schema = if discriminators
+                .as_ref()
+                .is_some_and(|settings| settings.fields.contains(&key))
+            {
+                match map.next_value_seed(CaptureSeed(seed))? {
+                    Captured::String(value) => {
+                        self.generation.ctx.count_node();
+                        let schema = self.generation.ctx.string_schema(&value, &property_path);
+                        strings.insert(key.clone(), value);
+                        schema
+                    }
+                    Captured::Other(sub_schema) => {
+                        strings.remove(&key);
+                        sub_schema
+                    }
+                }
+            } else {
+                map.next_value_seed(seed)?
+            };
+            self.generation.ctx.annotate_access(&key, &mut sub_schema);
+            properties.insert(key, sub_schema);
+        }
+
+        self.generation.ctx.pair_coordinates(&mut properties);
+        self.generation
+            .ctx
+            .record_object(path, &properties, |field| {
+                strings.get(field).map(String::as_str)
+            });
+        let count = properties.len();
+        let mut schema = object_schema(properties, reference);
+        self.generation
+            .ctx
+            .type_defaults(&mut schema, Observed::Object(count));
+        Ok(schema)
+    }
+}
+
+/// The value of a `$ref` key or candidate discriminator: the string it holds, or the
+/// schema of any other value.
+enum Captured {
+    String(String),
+    Other(Value),
+}
+
+/// Deserializes a value, keeping it if it is a string and generating its schema otherwise.
+struct CaptureSeed<'g, 'c, 'o, 'p>(SchemaSeed<'g, 'c, 'o, 'p>);
+
+impl<'de> DeserializeSeed<'de> for CaptureSeed<'_, '_, '_, '_> {
+    type Value = Captured;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Captured, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+/// Forwards everything but strings to [`SchemaSeed`], counting the node as it would.
+impl<'de> Visitor<'de> for CaptureSeed<'_, '_, '_, '_> {
+    type Value = Captured;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.expecting(f)
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Captured, E> {
+        Ok(Captured::String(v.to_string()))
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Captured, E> {
+        self.0.generation.ctx.count_node();
+        self.0.visit_bool(v).map(Captured::Other)
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Captured, E> {
+        self.0.generation.ctx.count_node();
+        self.0.visit_i64(v).map(Captured::Other)
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Captured, E> {
+        self.0.generation.ctx.count_node();
+        self.0.visit_u64(v).map(Captured::Other)
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Captured, E> {
+        self.0.generation.ctx.count_node();
+        self.0.visit_f64(v).map(Captured::Other)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Captured, E> {
+        self.0.generation.ctx.count_node();
+        self.0.visit_unit().map(Captured::Other)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<Captured, A::Error> {
+        self.0.generation.ctx.count_node();
+        self.0.visit_seq(seq).map(Captured::Other)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Captured, A::Error> {
+        self.0.generation.ctx.count_node();
+        self.0.visit_map(map).map(Captured::Other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{try_generate_json_schema, SchemaGenerator};
+    use serde_json::json;
+    use std::error::Error;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Arc;
+
+    fn raw(json: &str) -> Box<RawValue> {
+        RawValue::from_string(json.to_string()).unwrap()
+    }
+
+    fn same_as_value_path(json: &str) {
+        same_as_value_path_with(json, &SchemaOptions::default());
+    }
+
+    fn same_as_value_path_with(json: &str, options: &SchemaOptions) {
+        let expected = try_generate_json_schema(&serde_json::from_str(json).unwrap(), options);
+        assert_eq!(
+            generate_from_raw(&raw(json), options),
+            expected,
+            "input: {}",
+            json
+        );
+    }
+
+    #[test]
+    fn test_from_str_and_reader() {
+        let json = r#"{"users": [{"email": "a@example.com"}, {"email": null, "age": 3}]}"#;
+        let expected = crate::generate_json_schema(&serde_json::from_str(json).unwrap());
+        assert_eq!(generate_json_schema_from_str(json), Ok(expected.clone()));
+        let reader = io::BufReader::new(json.as_bytes());
+        assert_eq!(generate_json_schema_from_reader(reader), Ok(expected));
+
+        for invalid in ["", "{", r#"{"a": 1,}"#, "[1] [2]", "nul"] {
+            assert!(
+                matches!(
+                    generate_json_schema_from_str(invalid),
+                    Err(GeneratorError::Json(_))
+                ),
+                "{:?}",
+                invalid
+            );
+            assert_eq!(
+                generate_json_schema_from_reader(invalid.as_bytes()),
+                generate_json_schema_from_str(invalid)
+            );
+        }
+
+        // The error of serde_json is kept, with the position of the mistake.
+        let error = generate_json_schema_from_str("{\n  \"a\": 1,\n  \"b\": }").unwrap_err();
+        match &error {
+            GeneratorError::Json(json) => {
+                assert_eq!(json.classify(), serde_json::error::Category::Syntax);
+                assert_eq!((json.line(), json.column()), (3, 8));
+            }
+            other => panic!("{:?}", other),
+        }
+        let source = error.source().unwrap().downcast_ref::<serde_json::Error>();
+        assert_eq!(source.map(|e| (e.line(), e.column())), Some((3, 8)));
+
+        struct Failing;
+        impl io::Read for Failing {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::other("disk on fire"))
+            }
+        }
+        match generate_json_schema_from_reader(Failing) {
+            Err(GeneratorError::Json(error)) => {
+                assert!(error.is_io());
+                assert!(error.to_string().contains("disk on fire"));
+            }
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_matches_value_path() {
+        same_as_value_path(r#"{"name": "John", "age": 30, "tags": ["a", 1, 2.5, null]}"#);
+        same_as_value_path(r#"[{"a": 1}, {"a": "x", "b": [true]}, [], 18446744073709551615]"#);
+        same_as_value_path(r##"{"$ref": "#/a", "b": {"$ref": 5}}"##);
+        same_as_value_path(r##"{"a": 1, "a": "x", "$ref": "#/x", "$ref": false}"##);
+        same_as_value_path(r#"[[], [[]], {}]"#);
+        same_as_value_path(r#""just a string""#);
+    }
+
+    #[test]
+    fn test_limits_match_value_path() {
+        let options = SchemaOptions {
+            max_depth: 2,
+            max_properties: 2,
+            ..Default::default()
+        };
+        same_as_value_path_with(r#"{"a": [1, [2]], "b": [{"c": 3}], "d": {}}"#, &options);
+        same_as_value_path_with(r#"{"$ref": [[1]], "b": [[]]}"#, &options);
+        same_as_value_path_with(r#"{"d": 1, "c": 2, "b": 3, "a": 4}"#, &options);
+        same_as_value_path_with(r##"{"b": 1, "a": 2, "b": "x", "$ref": "#/c"}"##, &options);
+        same_as_value_path_with(r#"[{"a": 1}, {"b": 2}, {"c": 3, "a": 4.5}]"#, &options);
+    }
+
+    #[test]
+    fn test_discriminators_match_value_path() {
+        let options = SchemaOptions {
+            discriminators: Some(Default::default()),
+            max_properties: 3,
+            ..Default::default()
+        };
+        let events = r#"[
+            {"type": "click", "x": 1}, {"type": "click", "x": 2},
+            {"type": "buy", "amount": 1.5}, {"type": "buy", "amount": 2, "type": "buy"},
+            {"kind": "a", "type": "click", "x": 3}
+        ]"#;
+        same_as_value_path_with(events, &options);
+        same_as_value_path_with(
+            r#"[{"type": "a", "x": 1}, {"type": 1, "type": "b"}]"#,
+            &options,
+        );
+        same_as_value_path_with(r#"[{"d": 1, "c": 2, "b": 3, "type": "a"}]"#, &options);
+    }
+
+    #[test]
+    fn test_access_matches_value_path() {
+        let options = SchemaOptions {
+            annotate_access: true,
+            ..Default::default()
+        };
+        same_as_value_path_with(
+            r#"[{"id": 1, "a": {"password": "x", "$ref": 1}}, {"id": "x"}]"#,
+            &options,
+        );
+    }
+
+    // Regression for fuzz/regressions/generate/deep-raw: serde_json gives up on documents
+    // nested more than 128 levels deep, although `RawValue` holds them.
+    #[test]
+    fn test_deep_documents() {
+        let deep = |depth| format!("{}1{}", "[".repeat(depth), "]".repeat(depth));
+        let instance = (0..200).fold(json!(1), |inner, _| Value::Array(vec![inner]));
+        assert_eq!(
+            generate_from_raw(&raw(&deep(200)), &SchemaOptions::default()),
+            try_generate_json_schema(&instance, &SchemaOptions::default())
+        );
+
+        let options = SchemaOptions::default();
+        let mut state = State::default();
+        let mut ctx = Context::new(&options, &mut state);
+        let reference = format!(r#"{{"$ref": {}}}"#, deep(100_000));
+        generate_raw_at(&raw(&reference), &mut ctx).unwrap();
+        assert_eq!(state.warnings.len(), 1);
+        assert_eq!(state.warnings[0].code, "depth-limit");
+    }
+
+    #[test]
+    fn test_warnings() {
+        let options = SchemaOptions::default();
+        let mut state = State::default();
+        let mut ctx = Context::new(&options, &mut state);
+        generate_raw_at(&raw(r##"{"a": {"$ref": "#/b"}}"##), &mut ctx).unwrap();
+        assert_eq!(state.warnings.len(), 1);
+        assert_eq!(state.warnings[0].pointer, "/properties/a");
+    }
+
+    #[test]
+    fn test_cancelled() {
+        let options = SchemaOptions {
+            cancel: Some(Arc::new(AtomicBool::new(true))),
+            ..Default::default()
+        };
+        assert_eq!(
+            generate_from_raw(&raw(r#"{"a": 1}"#), &options),
+            Err(GeneratorError::Cancelled)
+        );
+        let large = format!("[{}1]", "1,".repeat(4096));
+        assert_eq!(
+            generate_from_raw(&raw(&large), &options),
+            Err(GeneratorError::Cancelled)
+        );
+    }
+
+    #[test]
+    fn test_observe_raw() {
+        let mut generator = SchemaGenerator::new(SchemaOptions::default());
+        generator.observe_raw(&raw(r#"{"a": 1}"#)).unwrap();
+        generator.observe(&json!({"a": 2.5, "b": "x"})).unwrap();
+        assert_eq!(generator.records(), 2);
+        assert_eq!(
+            generator.schema()["properties"]["a"],
+            json!({"type": "number"})
+        );
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    mod differential {
+        use super::*;
+        use proptest::prelude::*;
+
+        fn arb_json() -> impl Strategy<Value = Value> {
+            let leaf = prop_oneof![
+                Just(Value::Null),
+                any::<bool>().prop_map(Value::Bool),
+                any::<i64>().prop_map(Value::from),
+                any::<u64>().prop_map(Value::from),
+                (-1e6f64..1e6).prop_map(Value::from),
+                "[a-z]{0,3}".prop_map(Value::String),
+                Just(json!("#/definitions/x")),
+            ];
+            leaf.prop_recursive(4, 48, 6, |inner| {
+                prop_oneof![
+                    prop::collection::vec(inner.clone(), 0..6).prop_map(Value::Array),
+                    prop::collection::btree_map("[a-c]|\\$ref", inner, 0..5)
+                        .prop_map(|map| Value::Object(map.into_iter().collect())),
+                ]
+            })
+        }
+
+        proptest! {
+            #![proptest_config(ProptestConfig::with_cases(512))]
+
+            #[test]
+            fn raw_matches_value(instance in arb_json()) {
+                let json = instance.to_string();
+                let options = SchemaOptions::default();
+                prop_assert_eq!(
+                    generate_from_raw(&raw(&json), &options),
+                    try_generate_json_schema(&instance, &options)
+                );
+            }
+        }
+    }
+}
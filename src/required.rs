@@ -0,0 +1,115 @@
+//! How often each property appears in the objects at one location, deciding which
+//! properties are required with
+//! [`SchemaOptions::required_threshold`](crate::SchemaOptions::required_threshold).
+
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+/// How many objects were seen at one location, and how many of them had each property.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Occurrences {
+    objects: usize,
+    counts: HashMap<String, usize>,
+}
+
+impl Occurrences {
+    /// Counts an object with the properties `properties`.
+    pub(crate) fn record(&mut self, properties: &Map<String, Value>) {
+        self.objects += 1;
+        for name in properties.keys() {
+            match self.counts.get_mut(name) {
+                Some(count) => *count += 1,
+                None => {
+                    self.counts.insert(name.clone(), 1);
+                }
+            }
+        }
+    }
+
+    /// Requires the properties of the object schema `schema` that at least `threshold`
+    /// of the objects had, in place of those it required.
+    pub(crate) fn apply(&self, schema: &mut Value, threshold: f64) {
+        let Some(obj) = schema.as_object_mut() else {
+            return;
+        };
+        let required: Vec<Value> = match obj.get("properties").and_then(Value::as_object) {
+            Some(properties) => properties
+                .keys()
+                .filter(|name| {
+                    let count = self.counts.get(*name).copied().unwrap_or_default();
+                    count as f64 >= threshold * self.objects as f64
+                })
+                .cloned()
+                .map(Value::String)
+                .collect(),
+            None => Vec::new(),
+        };
+        if required.is_empty() {
+            obj.remove("required");
+        } else {
+            obj.insert("required".to_string(), Value::Array(required));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{SchemaGenerator, SchemaOptions};
+    use serde_json::{json, Value};
+
+    fn generate(records: &[Value], required_threshold: f64) -> Value {
+        let mut generator = SchemaGenerator::new(SchemaOptions {
+            required_threshold,
+            ..Default::default()
+        });
+        for record in records {
+            generator.observe(record).unwrap();
+        }
+        generator.schema()
+    }
+
+    /// Five users, three with an email, two with a phone and all with an id and tags.
+    fn users() -> Vec<Value> {
+        (0..5)
+            .map(|i| match i {
+                0 | 1 => json!({"id": i, "email": "e", "tags": [{"k": 1, "v": 2}]}),
+                2 => json!({"id": i, "email": "e", "tags": [{"k": 1}]}),
+                _ => json!({"id": i, "phone": "p", "tags": [{"k": 1}]}),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_threshold() {
+        let tags = |schema: &Value| schema["properties"]["tags"]["items"]["required"].clone();
+        let schema = generate(&users(), 0.6);
+        assert_eq!(schema["required"], json!(["email", "id", "tags"]));
+        assert_eq!(tags(&schema), json!(["k"]));
+
+        // Two of the five tags have a value.
+        let schema = generate(&users(), 0.4);
+        assert_eq!(schema["required"], json!(["email", "id", "phone", "tags"]));
+        assert_eq!(tags(&schema), json!(["k", "v"]));
+
+        let schema = generate(&users(), 0.7);
+        assert_eq!(schema["required"], json!(["id", "tags"]));
+    }
+
+    #[test]
+    fn test_default_requires_every_object() {
+        let schema = generate(&users(), 1.0);
+        assert_eq!(
+            schema,
+            generate(&users(), SchemaOptions::default().required_threshold)
+        );
+        assert_eq!(schema["required"], json!(["id", "tags"]));
+        assert_eq!(
+            schema["properties"]["tags"]["items"]["required"],
+            json!(["k"])
+        );
+
+        // Nothing most objects have leaves nothing required.
+        let schema = generate(&[json!({"a": 1}), json!({"b": 1})], 0.75);
+        assert!(schema.get("required").is_none());
+    }
+}
@@ -0,0 +1,394 @@
+//! An HTTP server generating schemas, behind the `server` feature and the CLI's `--serve`.
+//!
+//! - `POST /schema` takes a JSON document and responds with its schema. With a
+//!   `Content-Type` of `application/x-ndjson` the body holds one record per line, and the
+//!   response is the schema of all of them.
+//! - `GET /healthz` responds with `{"status": "ok"}`.
+//!
+//! The options of the WebAssembly and C bindings, `deny_lints`, `strict` and (with the
+//! `validation` feature) `verify`, are given as query parameters such as
+//! `?deny_lints=always-null,empty-items&verify=true`. With `?envelope=true` the body is
+//! instead an object `{"input": ..., "options": {...}}`.
+//!
+//! Errors are responded with a JSON body `{"error": {"code": ..., "message": ...}}`.
+
+use crate::embed::{self, EmbedError, EmbedOptions};
+use crate::GeneratorError;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::io::{self, Read};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+use tiny_http::{Header, Method, Request, Response};
+
+/// Limits applied to every request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerConfig {
+    /// The largest request body accepted, in bytes. Larger bodies are rejected with
+    /// `413 Payload Too Large`.
+    pub max_body_bytes: usize,
+    /// How long generating a schema may take. Slower requests are cancelled and
+    /// rejected with `503 Service Unavailable`.
+    pub timeout: Duration,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            max_body_bytes: 10 * 1024 * 1024,
+            timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+/// A bound server, see the [module documentation](self) for its endpoints.
+pub struct Server {
+    http: Arc<tiny_http::Server>,
+    config: ServerConfig,
+}
+
+/// Stops a [`Server`] from another thread.
+#[derive(Clone)]
+pub struct ShutdownHandle {
+    http: Arc<tiny_http::Server>,
+}
+
+impl ShutdownHandle {
+    /// Stops accepting requests. [`Server::run`] returns once the requests being
+    /// handled have been responded to.
+    pub fn shutdown(&self) {
+        self.http.unblock();
+    }
+}
+
+impl Server {
+    /// Binds a server to `addr`, such as `127.0.0.1:8080`. Port 0 picks a free port,
+    /// see [`Server::local_addr`].
+    pub fn bind(addr: &str, config: ServerConfig) -> io::Result<Server> {
+        let http = tiny_http::Server::http(addr)
+            .map_err(|e| io::Error::new(io::ErrorKind::AddrNotAvailable, e))?;
+        Ok(Server {
+            http: Arc::new(http),
+            config,
+        })
+    }
+
+    /// The address the server is bound to.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.http
+            .server_addr()
+            .to_ip()
+            .expect("servers are bound to IP addresses")
+    }
+
+    /// Returns a handle that stops the server.
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        ShutdownHandle {
+            http: Arc::clone(&self.http),
+        }
+    }
+
+    /// Handles requests, each on its own thread, until the server is shut down.
+    pub fn run(&self) {
+        thread::scope(|scope| {
+            for request in self.http.incoming_requests() {
+                scope.spawn(|| self.handle(request));
+            }
+        });
+    }
+
+    fn handle(&self, mut request: Request) {
+        let (status, body) = match self.route(&mut request) {
+            Ok(body) => (200, body),
+            Err(error) => (
+                error.status,
+                json!({"error": {"code": error.code, "message": error.message}}),
+            ),
+        };
+        let content_type =
+            Header::from_bytes("Content-Type", "application/json").expect("the header is valid");
+        let response = Response::from_string(body.to_string())
+            .with_status_code(status)
+            .with_header(content_type)
+            .with_chunked_threshold(usize::MAX);
+        // The client may be gone, and there is no one else to tell.
+        let _ = request.respond(response);
+    }
+
+    fn route(&self, request: &mut Request) -> Result<Value, RequestError> {
+        let url = request.url().to_string();
+        let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+        match (path, request.method()) {
+            ("/healthz", Method::Get) => Ok(json!({"status": "ok"})),
+            ("/schema", Method::Post) => self.schema(request, query),
+            ("/healthz" | "/schema", method) => Err(RequestError::new(
+                405,
+                "method-not-allowed",
+                format!("{} is not allowed on {}", method, path),
+            )),
+            _ => Err(RequestError::new(
+                404,
+                "not-found",
+                format!("no endpoint at {}", path),
+            )),
+        }
+    }
+
+    fn schema(&self, request: &mut Request, query: &str) -> Result<Value, RequestError> {
+        let (mut options, envelope) = query_options(query)?;
+        let ndjson = request.headers().iter().any(|header| {
+            header.field.equiv("Content-Type")
+                && matches!(
+                    header.value.as_str().split(';').next().map(str::trim),
+                    Some("application/x-ndjson" | "application/jsonl")
+                )
+        });
+        let body = self.read_body(request)?;
+
+        let records = if envelope {
+            if ndjson {
+                return Err(RequestError::invalid_options(
+                    "an envelope holds a single JSON document".to_string(),
+                ));
+            }
+            let envelope: Envelope = serde_json::from_slice(&body).map_err(invalid_input)?;
+            if envelope.options != EmbedOptions::default() {
+                if options != EmbedOptions::default() {
+                    return Err(RequestError::invalid_options(
+                        "options go in the query string or the envelope, not both".to_string(),
+                    ));
+                }
+                options = envelope.options;
+            }
+            vec![envelope.input]
+        } else if ndjson {
+            parse_ndjson(&body)?
+        } else {
+            vec![serde_json::from_slice(&body).map_err(invalid_input)?]
+        };
+
+        // The watchdog cancels generation unless the sender is dropped first.
+        let cancel = Arc::new(AtomicBool::new(false));
+        let (done, watchdog) = mpsc::channel::<()>();
+        let timeout = self.config.timeout;
+        let flag = Arc::clone(&cancel);
+        thread::spawn(move || {
+            if let Err(mpsc::RecvTimeoutError::Timeout) = watchdog.recv_timeout(timeout) {
+                flag.store(true, Ordering::Relaxed);
+            }
+        });
+        let result = embed::generate_records(&records, &options, Some(cancel));
+        drop(done);
+
+        result.map_err(|error| match error {
+            EmbedError::Generator(GeneratorError::Cancelled) => RequestError::new(
+                503,
+                "timeout",
+                format!("generation took longer than {:?}", timeout),
+            ),
+            EmbedError::InvalidInput(e) => invalid_input(e),
+            EmbedError::InvalidOptions(e) => RequestError::invalid_options(e.to_string()),
+            error @ EmbedError::DeniedWarning(_) => {
+                RequestError::new(422, "denied-warning", error.to_string())
+            }
+            EmbedError::Generator(
+                error @ (GeneratorError::Json(_) | GeneratorError::InvalidInput(_)),
+            ) => RequestError::new(400, "invalid-input", error.to_string()),
+            EmbedError::Generator(error @ GeneratorError::Conflict { .. }) => {
+                RequestError::new(422, "conflict", error.to_string())
+            }
+            EmbedError::Generator(error @ GeneratorError::LimitExceeded(_)) => {
+                RequestError::new(422, "limit-exceeded", error.to_string())
+            }
+            EmbedError::Generator(error @ GeneratorError::Transform(_)) => {
+                RequestError::new(500, "transform-failed", error.to_string())
+            }
+            #[cfg(feature = "validation")]
+            EmbedError::Generator(error @ GeneratorError::Verification(_)) => {
+                RequestError::new(422, "verification-failed", error.to_string())
+            }
+        })
+    }
+
+    /// Reads the body, failing if it is larger than [`ServerConfig::max_body_bytes`].
+    fn read_body(&self, request: &mut Request) -> Result<Vec<u8>, RequestError> {
+        let limit = self.config.max_body_bytes;
+        let too_large = || {
+            RequestError::new(
+                413,
+                "payload-too-large",
+                format!("the body is larger than {} bytes", limit),
+            )
+        };
+        if request.body_length().is_some_and(|length| length > limit) {
+            return Err(too_large());
+        }
+        let mut body = Vec::new();
+        request
+            .as_reader()
+            .take(limit as u64 + 1)
+            .read_to_end(&mut body)
+            .map_err(|e| RequestError::new(400, "invalid-input", e.to_string()))?;
+        if body.len() > limit {
+            return Err(too_large());
+        }
+        Ok(body)
+    }
+}
+
+/// A request body with `?envelope=true`.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Envelope {
+    input: Value,
+    #[serde(default)]
+    options: EmbedOptions,
+}
+
+/// A failed request: the response status and the `code` and `message` of its body.
+#[derive(Debug)]
+struct RequestError {
+    status: u16,
+    code: &'static str,
+    message: String,
+}
+
+impl RequestError {
+    fn new(status: u16, code: &'static str, message: String) -> Self {
+        RequestError {
+            status,
+            code,
+            message,
+        }
+    }
+
+    fn invalid_options(message: String) -> Self {
+        RequestError::new(400, "invalid-options", message)
+    }
+}
+
+fn invalid_input(error: serde_json::Error) -> RequestError {
+    RequestError::new(400, "invalid-input", format!("invalid JSON: {}", error))
+}
+
+/// Parses the options in the query string, and whether the body is an envelope.
+fn query_options(query: &str) -> Result<(EmbedOptions, bool), RequestError> {
+    let mut options = EmbedOptions::default();
+    let mut envelope = false;
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        let value = decode(value);
+        let flag = || match value.as_str() {
+            "" | "true" | "1" => Ok(true),
+            "false" | "0" => Ok(false),
+            other => Err(RequestError::invalid_options(format!(
+                "{} must be true or false, not '{}'",
+                key, other
+            ))),
+        };
+        match key {
+            "deny_lints" => options.deny_lints.extend(
+                value
+                    .split(',')
+                    .filter(|code| !code.is_empty())
+                    .map(str::to_string),
+            ),
+            #[cfg(feature = "validation")]
+            "verify" => options.verify = flag()?,
+            "strict" => options.strict = flag()?,
+            "envelope" => envelope = flag()?,
+            other => {
+                return Err(RequestError::invalid_options(format!(
+                    "unknown option '{}'",
+                    other
+                )))
+            }
+        }
+    }
+    Ok((options, envelope))
+}
+
+/// Decodes a percent-encoded query string value.
+fn decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (b'+', _) => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Parses one JSON document per non-blank line.
+fn parse_ndjson(body: &[u8]) -> Result<Vec<Value>, RequestError> {
+    body.split(|&byte| byte == b'\n')
+        .enumerate()
+        .filter(|(_, line)| !line.iter().all(u8::is_ascii_whitespace))
+        .map(|(index, line)| {
+            serde_json::from_slice(line).map_err(|e| {
+                RequestError::new(
+                    400,
+                    "invalid-input",
+                    format!("invalid JSON on line {}: {}", index + 1, e),
+                )
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_options() {
+        let (options, envelope) = query_options("deny_lints=always-null%2Cempty-items").unwrap();
+        assert_eq!(options.deny_lints, ["always-null", "empty-items"]);
+        assert!(!envelope);
+
+        assert!(query_options("envelope").unwrap().1);
+        assert!(!query_options("envelope=false").unwrap().1);
+        assert_eq!(
+            query_options("colour=red").err().unwrap().message,
+            "unknown option 'colour'"
+        );
+        assert_eq!(
+            query_options("envelope=maybe").err().unwrap().code,
+            "invalid-options"
+        );
+    }
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(decode("a%2Cb+c%zz%4"), "a,b c%zz%4");
+    }
+
+    #[test]
+    fn test_parse_ndjson() {
+        let records = parse_ndjson(b"{\"a\": 1}\n\n  \r\n{\"a\": 2}\r\n").unwrap();
+        assert_eq!(records, [json!({"a": 1}), json!({"a": 2})]);
+
+        let error = parse_ndjson(b"1\n\n{\n").err().unwrap();
+        assert!(error.message.starts_with("invalid JSON on line 3"));
+    }
+}
@@ -0,0 +1,455 @@
+//! Object schemas repeated across a schema, such as an address under several properties,
+//! hoisted into the root `$defs` and referred to by `$ref`, see
+//! [`SchemaOptions::shared_definitions`](crate::SchemaOptions::shared_definitions).
+
+use crate::anchors::{self, definition_name};
+use crate::canonical::to_canonical_string;
+use crate::Draft;
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, HashMap};
+
+/// Settings for hoisting repeated object schemas into definitions, see
+/// [`SchemaOptions::shared_definitions`](crate::SchemaOptions::shared_definitions).
+///
+/// # Examples
+///
+/// ```
+/// use serde_json::json;
+/// use json_schema_generator::{try_generate_json_schema, SchemaOptions, SharedDefinitions};
+///
+/// let address = json!({"street": "1 Main St", "city": "Springfield"});
+/// let order = json!({"billing": address, "shipping": address});
+/// let options = SchemaOptions {
+///     shared_definitions: Some(SharedDefinitions::default()),
+///     ..Default::default()
+/// };
+/// let schema = try_generate_json_schema(&order, &options).unwrap();
+/// assert_eq!(schema["properties"]["billing"], json!({"$ref": "#/definitions/Billing"}));
+/// assert_eq!(schema["properties"]["shipping"], json!({"$ref": "#/definitions/Billing"}));
+/// assert_eq!(schema["definitions"]["Billing"]["required"], json!(["city", "street"]));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SharedDefinitions {
+    /// How many times an object schema must appear, identical, to be hoisted.
+    pub min_occurrences: usize,
+}
+
+impl Default for SharedDefinitions {
+    fn default() -> Self {
+        SharedDefinitions { min_occurrences: 2 }
+    }
+}
+
+/// Hoists the object schemas that appear identical more than once in `schema`, which
+/// need not have been generated, into its definitions, and replaces them by references.
+///
+/// The definitions are those of the draft `schema` declares: `definitions` up to draft-07
+/// and `$defs` otherwise. Definitions it already has are kept, and copies of one are
+/// replaced by references to it rather than hoisted again.
+///
+/// # Examples
+///
+/// ```
+/// use serde_json::json;
+/// use json_schema_generator::deduplicate_defs;
+///
+/// let address = json!({"type": "object", "properties": {"city": {"type": "string"}}});
+/// let mut schema = json!({
+///     "$schema": "http://json-schema.org/draft-07/schema#",
+///     "type": "object",
+///     "properties": {"billing_address": address, "shipping_address": address}
+/// });
+/// deduplicate_defs(&mut schema);
+/// assert_eq!(
+///     schema["properties"]["shipping_address"],
+///     json!({"$ref": "#/definitions/BillingAddress"})
+/// );
+/// assert_eq!(schema["definitions"]["BillingAddress"], address);
+/// ```
+pub fn deduplicate_defs(schema: &mut Value) {
+    let draft = Draft::from_schema(schema);
+    let Some(obj) = schema.as_object_mut() else {
+        return;
+    };
+    let earlier = match draft {
+        Some(draft) => draft <= Draft::Draft7,
+        None => obj.contains_key("definitions") && !obj.contains_key("$defs"),
+    };
+    if earlier {
+        if let Some(definitions) = obj.remove("definitions") {
+            obj.insert("$defs".to_string(), definitions);
+        }
+    }
+    extract(schema, &SharedDefinitions::default());
+    if earlier {
+        // Only the references to the new definitions use `#/$defs/`.
+        anchors::relocate(schema, draft.unwrap_or(Draft::Draft7));
+    }
+}
+
+/// The occurrences of one object schema.
+#[derive(Default)]
+struct Shape {
+    count: usize,
+    /// How often it appears under each property name, for naming its definition.
+    names: BTreeMap<String, usize>,
+}
+
+/// Hoists the object schemas in `schema` that appear at least
+/// [`SharedDefinitions::min_occurrences`] times into its root `$defs`, replacing them by
+/// references. The largest are hoisted first, so that a repeated schema nested in
+/// another is counted once in its definition rather than once in each copy. A schema
+/// identical to a definition already in `$defs` refers to that one instead.
+pub(crate) fn extract(schema: &mut Value, settings: &SharedDefinitions) {
+    if !schema.is_object() {
+        return;
+    }
+    loop {
+        let mut shapes = HashMap::new();
+        count(schema, None, &mut shapes);
+        let repeated = shapes
+            .into_iter()
+            .filter(|(_, shape)| shape.count >= settings.min_occurrences.max(2))
+            .max_by(|(a, _), (b, _)| a.len().cmp(&b.len()).then_with(|| b.cmp(a)));
+        let Some((key, shape)) = repeated else {
+            return;
+        };
+
+        // A definition it already has is referred to rather than copied.
+        let existing = match schema.get("$defs") {
+            Some(Value::Object(definitions)) => definitions
+                .iter()
+                .find(|(_, definition)| to_canonical_string(*definition) == key)
+                .map(|(name, _)| name.clone()),
+            _ => None,
+        };
+        if let Some(name) = existing {
+            let reference = json!({ "$ref": format!("#/$defs/{}", name) });
+            replace(schema, &key, &reference, &mut None);
+            continue;
+        }
+
+        // The name it appears under most often, the first of them in order.
+        let mut names: Vec<(&String, &usize)> = shape.names.iter().collect();
+        names.sort_by(|a, b| b.1.cmp(a.1));
+        let hint = names.first().map_or("Shape", |(name, _)| name.as_str());
+        let name = match schema.get("$defs") {
+            Some(Value::Object(definitions)) => definition_name(hint, definitions),
+            _ => definition_name(hint, &Map::new()),
+        };
+        let reference = json!({ "$ref": format!("#/$defs/{}", name) });
+        let mut definition = None;
+        replace(schema, &key, &reference, &mut definition);
+        let (Some(definition), Some(obj)) = (definition, schema.as_object_mut()) else {
+            return;
+        };
+        if let Value::Object(definitions) = obj
+            .entry("$defs")
+            .or_insert_with(|| Value::Object(Map::new()))
+        {
+            definitions.insert(name, definition);
+        }
+    }
+}
+
+/// Returns whether `schema` is an object schema with properties, the only ones worth
+/// sharing.
+fn is_shape(schema: &Value) -> bool {
+    schema.get("type").and_then(Value::as_str) == Some("object")
+        && schema
+            .get("properties")
+            .and_then(Value::as_object)
+            .is_some_and(|properties| !properties.is_empty())
+}
+
+/// Counts the object schemas below `schema`, found under the property `name`.
+fn count(schema: &mut Value, name: Option<&str>, shapes: &mut HashMap<String, Shape>) {
+    for (child, name, definition) in children(schema, name) {
+        if !definition && is_shape(child) {
+            let shape = shapes.entry(to_canonical_string(&*child)).or_default();
+            shape.count += 1;
+            if let Some(name) = &name {
+                *shape.names.entry(name.clone()).or_default() += 1;
+            }
+        }
+        count(child, name.as_deref(), shapes);
+    }
+}
+
+/// Replaces the schemas below `schema` whose canonical form is `key` by `reference`,
+/// keeping the first in `definition`.
+fn replace(schema: &mut Value, key: &str, reference: &Value, definition: &mut Option<Value>) {
+    for (child, _, is_definition) in children(schema, None) {
+        if !is_definition && is_shape(child) && to_canonical_string(&*child) == key {
+            let shape = std::mem::replace(child, reference.clone());
+            definition.get_or_insert(shape);
+            continue;
+        }
+        replace(child, key, reference, definition);
+    }
+}
+
+/// Returns the subschemas of `schema`, with the property they are found under, which
+/// those of items and unions inherit from `name`, and whether they are definitions.
+fn children<'a>(
+    schema: &'a mut Value,
+    name: Option<&str>,
+) -> Vec<(&'a mut Value, Option<String>, bool)> {
+    let Some(obj) = schema.as_object_mut() else {
+        return Vec::new();
+    };
+    let inherited = name.map(str::to_string);
+    let mut children = Vec::new();
+    for (keyword, value) in obj.iter_mut() {
+        match (keyword.as_str(), value) {
+            ("properties" | "patternProperties", Value::Object(properties)) => {
+                for (name, property) in properties.iter_mut() {
+                    children.push((property, Some(name.clone()), false));
+                }
+            }
+            ("$defs" | "definitions", Value::Object(definitions)) => {
+                for definition in definitions.values_mut() {
+                    children.push((definition, None, true));
+                }
+            }
+            ("items" | "prefixItems" | "oneOf" | "anyOf" | "allOf", Value::Array(schemas)) => {
+                for schema in schemas.iter_mut() {
+                    children.push((schema, inherited.clone(), false));
+                }
+            }
+            (
+                "items"
+                | "additionalItems"
+                | "additionalProperties"
+                | "unevaluatedProperties"
+                | "not"
+                | "if"
+                | "then"
+                | "else",
+                schema @ Value::Object(_),
+            ) => children.push((schema, inherited.clone(), false)),
+            _ => {}
+        }
+    }
+    children
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Draft, SchemaOptions};
+
+    fn generate(instance: &Value, settings: Option<SharedDefinitions>) -> Value {
+        let options = SchemaOptions {
+            shared_definitions: settings,
+            ..Default::default()
+        };
+        crate::try_generate_json_schema(instance, &options).unwrap()
+    }
+
+    /// Replaces the references to the root `definitions` of `schema` by their definitions.
+    fn dereference(schema: &Value) -> Value {
+        fn inline(schema: &mut Value, definitions: &Map<String, Value>) {
+            let target = schema
+                .get("$ref")
+                .and_then(Value::as_str)
+                .and_then(|reference| reference.strip_prefix("#/definitions/"))
+                .and_then(|name| definitions.get(name));
+            if let Some(definition) = target {
+                *schema = definition.clone();
+                inline(schema, definitions);
+                return;
+            }
+            match schema {
+                Value::Object(obj) => obj.values_mut().for_each(|v| inline(v, definitions)),
+                Value::Array(values) => values.iter_mut().for_each(|v| inline(v, definitions)),
+                _ => {}
+            }
+        }
+        let mut schema = schema.clone();
+        let definitions = match schema
+            .as_object_mut()
+            .and_then(|obj| obj.remove("definitions"))
+        {
+            Some(Value::Object(definitions)) => definitions,
+            _ => Map::new(),
+        };
+        inline(&mut schema, &definitions);
+        schema
+    }
+
+    fn response() -> Value {
+        let address = |city: &str| json!({"street": "1 Main St", "city": city, "geo": {"lat": 1.5, "lng": 2.5}});
+        json!({
+            "home": address("A"),
+            "work": address("B"),
+            "contacts": [
+                {"name": "x", "address": address("C")},
+                {"name": "y", "address": address("D")}
+            ],
+            "billing": {"address": address("E"), "vat": "1"},
+            "office": {"geo": {"lat": 0.5, "lng": 0.5}}
+        })
+    }
+
+    #[test]
+    fn test_repeated_addresses() {
+        let schema = generate(&response(), Some(SharedDefinitions::default()));
+        let properties = &schema["properties"];
+        assert_eq!(properties["home"], json!({"$ref": "#/definitions/Address"}));
+        assert_eq!(properties["work"], json!({"$ref": "#/definitions/Address"}));
+        assert_eq!(
+            properties["contacts"]["items"]["properties"]["address"],
+            json!({"$ref": "#/definitions/Address"})
+        );
+        // The coordinates nested in the address are shared with the office.
+        assert_eq!(
+            schema["definitions"]["Address"]["properties"]["geo"],
+            json!({"$ref": "#/definitions/Geo"})
+        );
+        assert_eq!(
+            properties["office"]["properties"]["geo"],
+            json!({"$ref": "#/definitions/Geo"})
+        );
+        assert_eq!(
+            schema["definitions"]["Geo"]["properties"]["lat"],
+            json!({"type": "number"})
+        );
+        assert_eq!(dereference(&schema), generate(&response(), None));
+    }
+
+    #[test]
+    fn test_thresholds_and_names() {
+        let settings = SharedDefinitions { min_occurrences: 4 };
+        let schema = generate(&response(), Some(settings));
+        assert_eq!(
+            schema["definitions"]
+                .as_object()
+                .unwrap()
+                .keys()
+                .collect::<Vec<_>>(),
+            ["Address"]
+        );
+        assert!(schema["definitions"]["Address"]["properties"]["geo"]
+            .get("$ref")
+            .is_none());
+        let settings = SharedDefinitions { min_occurrences: 6 };
+        assert_eq!(
+            generate(&response(), Some(settings)),
+            generate(&response(), None)
+        );
+
+        // A name that is taken is numbered.
+        let point = json!({"x": 1, "y": 2});
+        let pair = json!({"a": {"b": point, "c": point}, "d": {"b": point.clone(), "c": {"x": "1"}},
+            "e": {"b": {"x": "1"}, "f": {"x": "1"}}});
+        let schema = generate(&pair, Some(SharedDefinitions::default()));
+        assert_eq!(
+            schema["properties"]["a"]["properties"]["c"],
+            json!({"$ref": "#/definitions/B"})
+        );
+        assert_eq!(
+            schema["properties"]["e"]["properties"]["b"],
+            json!({"$ref": "#/definitions/B2"})
+        );
+        assert_eq!(dereference(&schema), generate(&pair, None));
+    }
+
+    #[test]
+    fn test_draft_definitions() {
+        let generate = |draft| {
+            let options = SchemaOptions {
+                shared_definitions: Some(SharedDefinitions::default()),
+                draft,
+                ..Default::default()
+            };
+            crate::try_generate_json_schema(&response(), &options).unwrap()
+        };
+        // Draft-07, which the schema declares without a draft, has definitions.
+        let schema = generate(Some(Draft::Draft7));
+        assert_eq!(schema, generate(None));
+        assert!(schema.get("$defs").is_none());
+        assert_eq!(
+            schema["properties"]["home"],
+            json!({"$ref": "#/definitions/Address"})
+        );
+        assert!(schema["definitions"]["Address"].is_object());
+
+        let schema = generate(Some(Draft::Draft202012));
+        assert!(schema.get("definitions").is_none());
+        assert_eq!(
+            schema["properties"]["home"],
+            json!({"$ref": "#/$defs/Address"})
+        );
+        assert!(schema["$defs"]["Address"].is_object());
+    }
+
+    #[test]
+    fn test_deduplicate_defs() {
+        let options = |shared_definitions| SchemaOptions {
+            shared_definitions,
+            draft: Some(Draft::Draft7),
+            ..Default::default()
+        };
+        let mut schema = crate::try_generate_json_schema(&response(), &options(None)).unwrap();
+        deduplicate_defs(&mut schema);
+        let shared = options(Some(SharedDefinitions::default()));
+        assert_eq!(
+            schema,
+            crate::try_generate_json_schema(&response(), &shared).unwrap()
+        );
+
+        // Definitions already there are kept, and referred to by their copies.
+        let point = json!({"type": "object", "properties": {"x": {"type": "integer"}}});
+        let line = json!({"type": "object", "properties": {"from": point, "to": point}});
+        let mut schema = json!({
+            "$schema": Draft::Draft4.uri(),
+            "definitions": {"Point": point},
+            "properties": {"a": point, "b": point, "c": {"$ref": "#/definitions/Point"}, "d": line, "e": line}
+        });
+        deduplicate_defs(&mut schema);
+        assert_eq!(
+            schema,
+            json!({
+                "$schema": Draft::Draft4.uri(),
+                "definitions": {
+                    "Point": point,
+                    "D": {
+                        "type": "object",
+                        "properties": {
+                            "from": {"$ref": "#/definitions/Point"},
+                            "to": {"$ref": "#/definitions/Point"}
+                        }
+                    }
+                },
+                "properties": {
+                    "a": {"$ref": "#/definitions/Point"},
+                    "b": {"$ref": "#/definitions/Point"},
+                    "c": {"$ref": "#/definitions/Point"},
+                    "d": {"$ref": "#/definitions/D"},
+                    "e": {"$ref": "#/definitions/D"}
+                }
+            })
+        );
+        let mut unchanged = json!({"type": "array", "items": point});
+        deduplicate_defs(&mut unchanged);
+        assert_eq!(unchanged, json!({"type": "array", "items": point}));
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_samples_validate() {
+        for draft in [Draft::Draft7, Draft::Draft202012] {
+            let options = SchemaOptions {
+                shared_definitions: Some(SharedDefinitions::default()),
+                draft: Some(draft),
+                ..Default::default()
+            };
+            let schema = crate::try_generate_json_schema(&response(), &options).unwrap();
+            assert!(crate::validation::validate_instance(&schema, &response()).is_ok());
+            let mut broken = response();
+            broken["contacts"][1]["address"]["geo"]["lat"] = json!("north");
+            assert!(crate::validation::validate_instance(&schema, &broken).is_err());
+        }
+    }
+}
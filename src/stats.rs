@@ -0,0 +1,205 @@
+//! Quantitative facts about a schema, for dashboards and for measuring the effect of
+//! options on generated schemas.
+
+use crate::to_canonical_string;
+use serde::Serialize;
+use serde_json::Value;
+
+/// Counts describing a schema, see [`schema_stats`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct SchemaStats {
+    /// The number of entries under `properties`, at any depth.
+    pub properties: usize,
+    /// The number of properties listed in `required`.
+    pub required: usize,
+    /// The number of properties not listed in `required`.
+    pub optional: usize,
+    /// The deepest nesting of subschemas, where the root schema has depth 0.
+    pub max_depth: usize,
+    /// The number of entries under `$defs` and `definitions`.
+    pub definitions: usize,
+    /// The number of branches of each `oneOf` and `anyOf`, in schema order.
+    pub unions: Vec<usize>,
+    /// The number of schemas with a `format`.
+    pub formats: usize,
+    /// The length of the schema's canonical serialization in bytes.
+    pub serialized_bytes: usize,
+}
+
+/// Computes [`SchemaStats`] for `schema`.
+///
+/// # Examples
+///
+/// ```
+/// use serde_json::json;
+/// use json_schema_generator::{generate_json_schema, schema_stats};
+///
+/// let schema = generate_json_schema(&json!({"id": 1, "tags": ["a"], "owner": {"name": "x"}}));
+/// let stats = schema_stats(&schema);
+/// assert_eq!(stats.properties, 4);
+/// assert_eq!(stats.max_depth, 2);
+/// ```
+pub fn schema_stats(schema: &Value) -> SchemaStats {
+    let mut stats = SchemaStats {
+        serialized_bytes: to_canonical_string(schema).len(),
+        ..Default::default()
+    };
+    walk(schema, 0, &mut stats);
+    stats
+}
+
+fn walk(schema: &Value, depth: usize, stats: &mut SchemaStats) {
+    let Some(schema) = schema.as_object() else {
+        return;
+    };
+    stats.max_depth = stats.max_depth.max(depth);
+
+    if schema.contains_key("format") {
+        stats.formats += 1;
+    }
+
+    if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+        let required = schema.get("required").and_then(Value::as_array);
+        for (name, property) in properties {
+            stats.properties += 1;
+            let is_required = required
+                .is_some_and(|required| required.iter().any(|entry| entry.as_str() == Some(name)));
+            if is_required {
+                stats.required += 1;
+            } else {
+                stats.optional += 1;
+            }
+            walk(property, depth + 1, stats);
+        }
+    }
+
+    for keyword in ["$defs", "definitions"] {
+        if let Some(definitions) = schema.get(keyword).and_then(Value::as_object) {
+            stats.definitions += definitions.len();
+            walk_all(definitions.values(), depth + 1, stats);
+        }
+    }
+
+    if let Some(patterns) = schema.get("patternProperties").and_then(Value::as_object) {
+        walk_all(patterns.values(), depth + 1, stats);
+    }
+
+    for keyword in ["oneOf", "anyOf"] {
+        if let Some(branches) = schema.get(keyword).and_then(Value::as_array) {
+            stats.unions.push(branches.len());
+            walk_all(branches, depth + 1, stats);
+        }
+    }
+
+    for keyword in ["allOf", "prefixItems"] {
+        if let Some(schemas) = schema.get(keyword).and_then(Value::as_array) {
+            walk_all(schemas, depth + 1, stats);
+        }
+    }
+
+    for keyword in ["items", "additionalProperties", "not"] {
+        match schema.get(keyword) {
+            Some(Value::Array(schemas)) => walk_all(schemas, depth + 1, stats),
+            Some(subschema) => walk(subschema, depth + 1, stats),
+            None => {}
+        }
+    }
+}
+
+fn walk_all<'a>(
+    schemas: impl IntoIterator<Item = &'a Value>,
+    depth: usize,
+    stats: &mut SchemaStats,
+) {
+    for schema in schemas {
+        walk(schema, depth, stats);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_empty_schema() {
+        assert_eq!(
+            schema_stats(&json!({})),
+            SchemaStats {
+                serialized_bytes: 2,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_hand_counted_fixture() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "id": {"type": "integer"},
+                "email": {"type": "string", "format": "email"},
+                "address": {
+                    "type": "object",
+                    "properties": {
+                        "city": {"type": "string"},
+                        "zip": {"oneOf": [{"type": "string"}, {"type": "integer"}]}
+                    },
+                    "required": ["city"]
+                },
+                "tags": {"type": "array", "items": {"$ref": "#/$defs/tag"}}
+            },
+            "required": ["id", "email"],
+            "$defs": {
+                "tag": {"type": "object", "properties": {"label": {"type": "string"}}}
+            }
+        });
+
+        let stats = schema_stats(&schema);
+        assert_eq!(stats.properties, 7);
+        assert_eq!(stats.required, 3);
+        assert_eq!(stats.optional, 4);
+        // The branches of `zip` sit at /properties/address/properties/zip/oneOf/n.
+        assert_eq!(stats.max_depth, 3);
+        assert_eq!(stats.definitions, 1);
+        assert_eq!(stats.unions, vec![2]);
+        assert_eq!(stats.formats, 1);
+        assert_eq!(stats.serialized_bytes, to_canonical_string(&schema).len());
+    }
+
+    #[test]
+    fn test_nested_unions_and_arrays() {
+        let schema = json!({
+            "type": "array",
+            "items": {
+                "oneOf": [
+                    {"oneOf": [{"type": "integer"}, {"type": "string"}]},
+                    {"type": "array", "items": {"type": "array", "items": {}}}
+                ]
+            }
+        });
+
+        let stats = schema_stats(&schema);
+        assert_eq!(stats.unions, vec![2, 2]);
+        assert_eq!(stats.max_depth, 4);
+        assert_eq!(stats.properties, 0);
+    }
+
+    #[test]
+    fn test_serialization() {
+        let stats = schema_stats(&json!({"properties": {"a": {}}, "required": ["a"]}));
+        assert_eq!(
+            serde_json::to_value(&stats).unwrap(),
+            json!({
+                "properties": 1,
+                "required": 1,
+                "optional": 0,
+                "max_depth": 1,
+                "definitions": 0,
+                "unions": [],
+                "formats": 0,
+                "serialized_bytes": 40
+            })
+        );
+    }
+}
@@ -0,0 +1,249 @@
+//! Schema generation from TOML documents, such as Cargo manifests, whose dates and times
+//! keep their format in the schema.
+
+use crate::{GeneratorError, SchemaOptions};
+use ::toml::value::Datetime;
+use serde_json::{Map, Number, Value};
+use std::collections::HashMap;
+
+/// Generates a JSON schema for the TOML document `toml`, as
+/// [`generate_json_schema`](crate::generate_json_schema) does for the same document in
+/// JSON, failing with [`GeneratorError::InvalidInput`] if it isn't TOML.
+///
+/// Dates and times are strings whose schema has the `format` they are written in:
+/// `date-time` for an offset date-time such as `1979-05-27T07:32:00Z`, `date` for a
+/// local date and `time` for a local time. A local date-time, which has no offset, is a
+/// plain string. NaN and infinite floats, which JSON can't hold, are numbers.
+///
+/// # Examples
+///
+/// ```
+/// use serde_json::json;
+/// use json_schema_generator::generate_json_schema_from_toml;
+///
+/// let schema = generate_json_schema_from_toml(r#"
+///     [package]
+///     name = "demo"
+///     published = 2024-01-15T10:30:00Z
+/// "#).unwrap();
+/// assert_eq!(
+///     schema["properties"]["package"]["properties"]["published"],
+///     json!({"type": "string", "format": "date-time"})
+/// );
+/// ```
+pub fn generate_json_schema_from_toml(toml: &str) -> Result<Value, GeneratorError> {
+    try_generate_json_schema_from_toml(toml, &SchemaOptions::default())
+}
+
+/// Generates a JSON schema for the TOML document `toml` using the given options, as
+/// [`generate_json_schema_from_toml`] does.
+pub fn try_generate_json_schema_from_toml(
+    toml: &str,
+    options: &SchemaOptions,
+) -> Result<Value, GeneratorError> {
+    let (instance, formats) = convert_document(toml)?;
+    let mut schema = crate::try_generate_json_schema(&instance, options)?;
+    for (pointer, format) in formats {
+        if let (Some(format), Some(schema)) = (format, schema.pointer_mut(&pointer)) {
+            write_format(schema, format);
+        }
+    }
+    Ok(schema)
+}
+
+/// Converts the TOML document `toml` to JSON, with its dates and times as strings, as
+/// [`generate_json_schema_from_toml`] describes it.
+///
+/// # Examples
+///
+/// ```
+/// use serde_json::json;
+/// use json_schema_generator::toml_to_json;
+///
+/// let document = toml_to_json("when = 1979-05-27\nports = [80, 443]").unwrap();
+/// assert_eq!(document, json!({"when": "1979-05-27", "ports": [80, 443]}));
+/// ```
+pub fn toml_to_json(toml: &str) -> Result<Value, GeneratorError> {
+    convert_document(toml).map(|(document, _)| document)
+}
+
+fn convert_document(toml: &str) -> Result<(Value, Formats), GeneratorError> {
+    let document: ::toml::Table = toml
+        .parse()
+        .map_err(|e: ::toml::de::Error| GeneratorError::InvalidInput(e.to_string()))?;
+    let mut formats = HashMap::new();
+    let document = table(document, "", &mut formats);
+    Ok((document, formats))
+}
+
+/// The formats of the strings at each location of the schema, by its JSON Pointer: the
+/// format all of them have, or `None` once one has another or none.
+type Formats = HashMap<String, Option<&'static str>>;
+
+fn table(table: ::toml::Table, pointer: &str, formats: &mut Formats) -> Value {
+    let obj: Map<String, Value> = table
+        .into_iter()
+        .map(|(key, value)| {
+            let at = format!(
+                "{}/properties/{}",
+                pointer,
+                key.replace('~', "~0").replace('/', "~1")
+            );
+            let value = convert(value, &at, formats);
+            (key, value)
+        })
+        .collect();
+    Value::Object(obj)
+}
+
+/// Converts the TOML `value`, whose schema is at `pointer`, to JSON, recording the
+/// format of its strings in `formats`.
+fn convert(value: ::toml::Value, pointer: &str, formats: &mut Formats) -> Value {
+    let string = |format: Option<&'static str>, formats: &mut Formats| {
+        formats
+            .entry(pointer.to_string())
+            .and_modify(|seen| {
+                if *seen != format {
+                    *seen = None;
+                }
+            })
+            .or_insert(format);
+    };
+    match value {
+        ::toml::Value::String(s) => {
+            string(None, formats);
+            Value::String(s)
+        }
+        ::toml::Value::Integer(n) => Value::from(n),
+        ::toml::Value::Float(f) => {
+            let finite = match f {
+                f if f.is_nan() => 0.0,
+                f if f.is_infinite() => f.signum() * f64::MAX,
+                f => f,
+            };
+            Number::from_f64(finite).map_or(Value::Null, Value::Number)
+        }
+        ::toml::Value::Boolean(b) => Value::Bool(b),
+        ::toml::Value::Datetime(datetime) => {
+            string(datetime_format(&datetime), formats);
+            Value::String(datetime.to_string())
+        }
+        ::toml::Value::Array(elements) => {
+            let at = format!("{}/items", pointer);
+            Value::Array(
+                elements
+                    .into_iter()
+                    .map(|element| convert(element, &at, formats))
+                    .collect(),
+            )
+        }
+        ::toml::Value::Table(t) => table(t, pointer, formats),
+    }
+}
+
+/// The JSON Schema format of `datetime`, if there is one for what it holds.
+fn datetime_format(datetime: &Datetime) -> Option<&'static str> {
+    match (&datetime.date, &datetime.time, &datetime.offset) {
+        (Some(_), Some(_), Some(_)) => Some("date-time"),
+        (Some(_), None, None) => Some("date"),
+        (None, Some(_), None) => Some("time"),
+        _ => None,
+    }
+}
+
+/// Writes `format` on the string schema `schema`, or on its string branch.
+fn write_format(schema: &mut Value, format: &str) {
+    if schema.get("type") == Some(&Value::from("string")) {
+        schema["format"] = Value::from(format);
+        return;
+    }
+    for keyword in ["oneOf", "anyOf"] {
+        for branch in schema
+            .get_mut(keyword)
+            .and_then(Value::as_array_mut)
+            .into_iter()
+            .flatten()
+        {
+            if branch.get("type") == Some(&Value::from("string")) {
+                branch["format"] = Value::from(format);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const MANIFEST: &str = r#"
+        title = "TOML Example"
+        ratio = 0.5
+        limit = inf
+
+        [owner]
+        name = "Tom"
+        dob = 1979-05-27T07:32:00-08:00
+        joined = 1979-05-27
+        alarm = 07:32:00
+        local = 1979-05-27T07:32:00
+
+        [[servers]]
+        ip = "10.0.0.1"
+        started = 2024-01-15T10:30:00Z
+        ports = [8000, 8001]
+
+        [[servers]]
+        ip = "10.0.0.2"
+        started = 2024-01-16T10:30:00Z
+    "#;
+
+    #[test]
+    fn test_types() {
+        let schema = generate_json_schema_from_toml(MANIFEST).unwrap();
+        let properties = &schema["properties"];
+        assert_eq!(properties["title"], json!({"type": "string"}));
+        assert_eq!(properties["ratio"], json!({"type": "number"}));
+        assert_eq!(properties["limit"], json!({"type": "number"}));
+        let owner = &properties["owner"]["properties"];
+        assert_eq!(
+            owner["dob"],
+            json!({"type": "string", "format": "date-time"})
+        );
+        assert_eq!(owner["joined"], json!({"type": "string", "format": "date"}));
+        assert_eq!(owner["alarm"], json!({"type": "string", "format": "time"}));
+        assert_eq!(owner["local"], json!({"type": "string"}));
+
+        let servers = &properties["servers"]["items"];
+        assert_eq!(servers["required"], json!(["ip", "started"]));
+        assert_eq!(
+            servers["properties"]["started"],
+            json!({"type": "string", "format": "date-time"})
+        );
+        assert_eq!(
+            servers["properties"]["ports"]["items"],
+            json!({"type": "integer"})
+        );
+    }
+
+    #[test]
+    fn test_mixed_strings() {
+        // A format only some of the strings have is no format of theirs.
+        let schema = generate_json_schema_from_toml(
+            "dates = [1979-05-27, \"soon\"]\nwhen = [1979-05-27, 3]\n\"a/b\" = 07:32:00\n",
+        )
+        .unwrap();
+        let properties = &schema["properties"];
+        assert_eq!(properties["dates"]["items"], json!({"type": "string"}));
+        assert_eq!(
+            properties["when"]["items"],
+            json!({"oneOf": [{"type": "string", "format": "date"}, {"type": "integer"}]})
+        );
+        assert_eq!(properties["a/b"]["format"], "time");
+
+        assert!(matches!(
+            generate_json_schema_from_toml("a = "),
+            Err(GeneratorError::InvalidInput(_))
+        ));
+    }
+}
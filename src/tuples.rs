@@ -0,0 +1,222 @@
+//! Short arrays of differently typed elements, such as `["GET", "/api/users", 200]`,
+//! written as tuples with a schema for each position.
+
+use serde_json::{Map, Value};
+
+/// Settings for writing arrays as tuples, see
+/// [`SchemaOptions::tuples`](crate::SchemaOptions::tuples).
+///
+/// An array of at most `max_len` elements of at least two types, integers and numbers
+/// counting as one, gets a schema for each position: `"items": [...]` with
+/// `"additionalItems": false`, or `prefixItems` with `"items": false` for draft 2020-12.
+/// Merged tuples of the same length merge position by position; an array of another
+/// length, or of a single type, merges all of their elements into one `items` schema.
+///
+/// # Examples
+///
+/// ```
+/// use serde_json::json;
+/// use json_schema_generator::{try_generate_json_schema, SchemaOptions, Tuples};
+///
+/// let options = SchemaOptions {
+///     tuples: Some(Tuples::default()),
+///     ..Default::default()
+/// };
+/// let schema = try_generate_json_schema(&json!(["GET", "/api/users", 200]), &options).unwrap();
+/// assert_eq!(
+///     schema,
+///     json!({
+///         "type": "array",
+///         "items": [{"type": "string"}, {"type": "string"}, {"type": "integer"}],
+///         "additionalItems": false
+///     })
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tuples {
+    /// The most elements an array written as a tuple may have.
+    pub max_len: usize,
+}
+
+impl Default for Tuples {
+    fn default() -> Self {
+        Tuples { max_len: 8 }
+    }
+}
+
+/// Returns whether the schemas of the elements of an array, in order, make a tuple: at
+/// least two of them, and of different types.
+pub(crate) fn is_tuple(positions: &[Value]) -> bool {
+    fn kind(schema: &Value) -> Option<&str> {
+        match schema.get("type").and_then(Value::as_str) {
+            Some("integer") => Some("number"),
+            kind => kind,
+        }
+    }
+    positions.len() >= 2
+        && positions
+            .iter()
+            .any(|position| kind(position) != kind(&positions[0]))
+}
+
+/// Rewrites the tuple in the array schema `obj` as draft 2020-12 writes it:
+/// `prefixItems` with `"items": false`.
+pub(crate) fn to_prefix_items(obj: &mut Map<String, Value>) {
+    if !obj.get("items").is_some_and(Value::is_array) {
+        return;
+    }
+    if let Some(positions) = obj.remove("items") {
+        obj.insert("prefixItems".to_string(), positions);
+    }
+    if let Some(closed) = obj.remove("additionalItems") {
+        obj.insert("items".to_string(), closed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Draft, SchemaGenerator, SchemaOptions};
+    use serde_json::json;
+
+    fn generate(records: &[Value], draft: Option<Draft>) -> Value {
+        let mut generator = SchemaGenerator::new(SchemaOptions {
+            tuples: Some(Tuples::default()),
+            draft,
+            ..Default::default()
+        });
+        for record in records {
+            generator.observe(record).unwrap();
+        }
+        generator.schema()
+    }
+
+    fn log_lines() -> Vec<Value> {
+        vec![
+            json!({"line": ["GET", "/api/users", 200, 12.5]}),
+            json!({"line": ["POST", "/api/users", 201, 30]}),
+            json!({"line": ["GET", "/api/users/7", 404, null]}),
+        ]
+    }
+
+    #[test]
+    fn test_log_line() {
+        let line = generate(&log_lines(), None)["properties"]["line"].clone();
+        assert_eq!(
+            line,
+            json!({
+                "type": "array",
+                "items": [
+                    {"type": "string"},
+                    {"type": "string"},
+                    {"type": "integer"},
+                    {"type": ["number", "null"]}
+                ],
+                "additionalItems": false
+            })
+        );
+
+        let line = generate(&log_lines(), Some(Draft::Draft202012))["properties"]["line"].clone();
+        assert_eq!(line["prefixItems"][2], json!({"type": "integer"}));
+        assert_eq!(line["items"], false);
+        assert!(line.get("additionalItems").is_none());
+    }
+
+    #[test]
+    fn test_positions_across_samples() {
+        let records = [json!([1, "hello", true]), json!([2, "world", false])];
+        let positions = json!([{"type": "integer"}, {"type": "string"}, {"type": "boolean"}]);
+        let schema = generate(&records, Some(Draft::Draft7));
+        assert_eq!(schema["items"], positions);
+        let schema = generate(&records, Some(Draft::Draft202012));
+        assert_eq!(schema["prefixItems"], positions);
+
+        // A position whose type changes becomes a union there only.
+        let records = [json!([1, "hello", true]), json!(["one", "world", false])];
+        let schema = generate(&records, None);
+        assert_eq!(
+            schema["items"][0],
+            json!({"oneOf": [{"type": "integer"}, {"type": "string"}]})
+        );
+        assert_eq!(schema["items"][2], json!({"type": "boolean"}));
+    }
+
+    #[test]
+    fn test_length_mismatch_falls_back() {
+        let mut records = log_lines();
+        records.push(json!({"line": ["GET", "/health"]}));
+        let line = generate(&records, None)["properties"]["line"].clone();
+        assert_eq!(
+            line,
+            json!({
+                "type": "array",
+                "items": {"oneOf": [{"type": "string"}, {"type": "number"}, {"type": "null"}]}
+            })
+        );
+
+        // A tuple merged with an array of one type, either way round.
+        let records = [json!(["a", 1]), json!(["b", "c"])];
+        let homogeneous =
+            json!({"type": "array", "items": {"oneOf": [{"type": "string"}, {"type": "integer"}]}});
+        assert_eq!(generate(&records, None), homogeneous);
+        let reversed: Vec<Value> = records.iter().rev().cloned().collect();
+        let schema = generate(&reversed, None);
+        assert!(schema["items"].is_object());
+        assert!(schema.get("additionalItems").is_none());
+    }
+
+    #[test]
+    fn test_what_is_not_a_tuple() {
+        let plain = |records: &[Value]| generate(records, None)["items"].clone();
+        assert_eq!(plain(&[json!([1, 2.5, 3])]), json!({"type": "number"}));
+        assert_eq!(plain(&[json!(["a"])]), json!({"type": "string"}));
+        let long: Vec<Value> = (0..9)
+            .map(|i| if i % 2 == 0 { json!(i) } else { json!("s") })
+            .collect();
+        assert!(plain(&[json!(long)]).get("oneOf").is_some());
+
+        // Without the option, heterogeneous arrays stay homogeneous.
+        let schema = crate::generate_json_schema(&json!(["GET", 200]));
+        assert_eq!(
+            schema["items"],
+            json!({"oneOf": [{"type": "string"}, {"type": "integer"}]})
+        );
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_samples_validate() {
+        let records = log_lines();
+        for draft in [None, Some(Draft::Draft202012)] {
+            let schema = generate(&records, draft);
+            for record in &records {
+                assert!(crate::validation::validate_instance(&schema, record).is_ok());
+            }
+            let extra = json!({"line": ["GET", "/", 200, 1, "extra"]});
+            assert!(crate::validation::validate_instance(&schema, &extra).is_err());
+            let swapped = json!({"line": [200, "/", "GET", 1]});
+            assert!(crate::validation::validate_instance(&schema, &swapped).is_err());
+        }
+    }
+
+    #[test]
+    fn test_raw_matches() {
+        let options = SchemaOptions {
+            tuples: Some(Tuples::default()),
+            ..Default::default()
+        };
+        for instance in [
+            json!(["GET", "/", 200]),
+            json!([["a", 1], ["b", 2, 3]]),
+            json!([1, "a", 2, "b", 3, "c", 4, "d", 5]),
+        ] {
+            let raw = serde_json::value::to_raw_value(&instance).unwrap();
+            assert_eq!(
+                crate::generate_from_raw(&raw, &options).unwrap(),
+                crate::try_generate_json_schema(&instance, &options).unwrap(),
+                "{}",
+                instance
+            );
+        }
+    }
+}
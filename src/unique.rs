@@ -0,0 +1,158 @@
+//! Whether the elements of an array are all distinct, for its `uniqueItems`, see
+//! [`SchemaOptions::unique_items`](crate::SchemaOptions::unique_items).
+
+use serde_json::{Number, Value};
+use std::collections::HashSet;
+
+/// The elements of an array seen so far, as keys that are equal whenever JSON Schema
+/// deems two values equal.
+#[derive(Debug, Default)]
+pub(crate) struct Distinct {
+    seen: HashSet<String>,
+    repeated: bool,
+    elements: usize,
+}
+
+impl Distinct {
+    /// Counts the element `value`.
+    pub(crate) fn record(&mut self, value: &Value) {
+        self.elements += 1;
+        if !self.repeated && !self.seen.insert(key(value)) {
+            self.repeated = true;
+            self.seen.clear();
+        }
+    }
+
+    /// Whether the array is evidence of distinct elements: an array of fewer than two
+    /// elements is distinct whatever the elements of others.
+    pub(crate) fn corroborates(&self) -> bool {
+        self.elements >= 2
+    }
+
+    /// Writes `"uniqueItems": true` into the array schema `schema` if no element was
+    /// seen twice.
+    pub(crate) fn write(self, schema: &mut Value) {
+        if let (false, Some(obj)) = (self.repeated, schema.as_object_mut()) {
+            obj.insert("uniqueItems".to_string(), Value::Bool(true));
+        }
+    }
+}
+
+/// Writes `value` as compact JSON with its object keys sorted and its numbers compared by
+/// value, so that `1` and `1.0` are the same element.
+fn key(value: &Value) -> String {
+    match value {
+        Value::Number(n) => number_key(n),
+        Value::Array(elements) => {
+            let elements: Vec<String> = elements.iter().map(key).collect();
+            format!("[{}]", elements.join(","))
+        }
+        Value::Object(obj) => {
+            let mut entries: Vec<String> = obj
+                .iter()
+                .map(|(name, value)| format!("{}:{}", Value::String(name.clone()), key(value)))
+                .collect();
+            entries.sort_unstable();
+            format!("{{{}}}", entries.join(","))
+        }
+        scalar => scalar.to_string(),
+    }
+}
+
+fn number_key(n: &Number) -> String {
+    if n.is_i64() || n.is_u64() {
+        return n.to_string();
+    }
+    let f = n.as_f64().unwrap_or_default();
+    // Covers every integer of a JSON number, which fits in an i64 or u64.
+    if f.fract() == 0.0 && f.abs() < 2f64.powi(70) {
+        return (f as i128).to_string();
+    }
+    f.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::SchemaOptions;
+    use serde_json::{json, Value};
+
+    fn generate(records: &[Value]) -> Value {
+        let options = SchemaOptions {
+            unique_items: true,
+            ..Default::default()
+        };
+        crate::try_generate_schema_from_samples(records, &options).unwrap()
+    }
+
+    #[test]
+    fn test_distinct_elements() {
+        let records = [
+            json!({"tags": ["a", "b"], "scores": [1, 2, 1]}),
+            json!({"tags": ["c"], "scores": [3]}),
+        ];
+        let schema = generate(&records);
+        assert_eq!(
+            schema["properties"]["tags"],
+            json!({"type": "array", "items": {"type": "string"}, "uniqueItems": true})
+        );
+        // One array with a repeated element leaves the merge without it.
+        assert_eq!(
+            schema["properties"]["scores"],
+            json!({"type": "array", "items": {"type": "integer"}})
+        );
+
+        let options = SchemaOptions {
+            unique_items: true,
+            ..Default::default()
+        };
+        let raw = serde_json::value::to_raw_value(&records).unwrap();
+        assert_eq!(
+            crate::generate_from_raw(&raw, &options).unwrap()["items"]["properties"],
+            schema["properties"]
+        );
+        // Off by default.
+        let plain = crate::generate_schema_from_samples(&records);
+        assert!(plain["properties"]["tags"].get("uniqueItems").is_none());
+    }
+
+    #[test]
+    fn test_equal_by_value() {
+        let repeated = |elements: Value| generate(&[elements]).get("uniqueItems").is_none();
+        assert!(repeated(json!([1, 1.0])));
+        assert!(repeated(json!([{"a": 1, "b": [2]}, {"b": [2.0], "a": 1}])));
+        assert!(repeated(json!([null, null])));
+        assert!(!repeated(json!([1, "1", [1], {"1": 1}, true, null])));
+        assert!(!repeated(json!([u64::MAX, u64::MAX - 1])));
+    }
+
+    #[test]
+    fn test_vacuous_arrays() {
+        let tags = |records: &[Value]| generate(records)["properties"]["tags"].clone();
+        let single = [json!({"tags": ["a"]}), json!({"tags": ["b"]})];
+        assert_eq!(
+            tags(&single),
+            json!({"type": "array", "items": {"type": "string"}})
+        );
+        assert!(generate(&[json!([])]).get("uniqueItems").is_none());
+        // Corroborated by an array of distinct elements.
+        let corroborated = [json!({"tags": ["a"]}), json!({"tags": ["a", "b"]})];
+        assert_eq!(tags(&corroborated)["uniqueItems"], true);
+        let duplicate = [
+            json!({"tags": [{"a": 1}, {"a": 1}]}),
+            json!({"tags": [{"a": 2}]}),
+        ];
+        assert!(tags(&duplicate).get("uniqueItems").is_none());
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_samples_validate() {
+        let records = [json!({"ids": [1, 2.5, "x"]}), json!({"ids": [[1], [2]]})];
+        let schema = generate(&records);
+        for record in &records {
+            assert!(crate::validation::validate_instance(&schema, record).is_ok());
+        }
+        let repeated = json!({"ids": [1, 1.0]});
+        assert!(crate::validation::validate_instance(&schema, &repeated).is_err());
+    }
+}
@@ -0,0 +1,501 @@
+//! Validation of instances against schemas, built on the `jsonschema` crate.
+//!
+//! The validator draft is taken from the schema's `$schema` keyword. Schemas without one
+//! are validated as draft-07, which is the dialect this crate generates.
+
+use crate::Draft;
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt;
+
+#[cfg(feature = "validation")]
+use crate::{schema_stats, GeneratorError, SchemaGenerator, SchemaOptions, SchemaStats, Warning};
+
+/// A single validation failure.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ValidationIssue {
+    /// JSON Pointer to the offending part of the instance.
+    pub instance_path: String,
+    /// JSON Pointer to the schema keyword that failed.
+    pub schema_path: String,
+    /// A human-readable description of the failure.
+    pub message: String,
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let at = if self.instance_path.is_empty() {
+            "/"
+        } else {
+            &self.instance_path
+        };
+        write!(f, "{}: {} (schema: {})", at, self.message, self.schema_path)
+    }
+}
+
+impl From<jsonschema::ValidationError<'_>> for ValidationIssue {
+    fn from(error: jsonschema::ValidationError<'_>) -> Self {
+        ValidationIssue {
+            instance_path: error.instance_path().to_string(),
+            schema_path: error.schema_path().to_string(),
+            message: error.to_string(),
+        }
+    }
+}
+
+/// A source sample rejected by the schema generated from it, see
+/// [`SchemaOptions::verify`](crate::SchemaOptions::verify).
+#[cfg(feature = "validation")]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct VerificationFailure {
+    /// The index of the rejected sample, or `None` if the generated schema could not
+    /// be compiled at all.
+    pub sample: Option<usize>,
+    /// Why the sample was rejected.
+    pub issues: Vec<ValidationIssue>,
+}
+
+/// A schema compiled once for repeated validation.
+pub struct CompiledSchema {
+    validator: jsonschema::Validator,
+}
+
+impl fmt::Debug for CompiledSchema {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CompiledSchema")
+            .field("draft", &self.validator.draft())
+            .finish_non_exhaustive()
+    }
+}
+
+impl CompiledSchema {
+    /// Returns the draft the schema was compiled as, or `None` for a custom meta-schema.
+    pub fn draft(&self) -> Option<Draft> {
+        Draft::try_from(self.validator.draft()).ok()
+    }
+
+    /// Validates `instance`, returning every failure found.
+    pub fn validate(&self, instance: &Value) -> Result<(), Vec<ValidationIssue>> {
+        let issues: Vec<ValidationIssue> = self
+            .validator
+            .iter_errors(instance)
+            .map(ValidationIssue::from)
+            .collect();
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(issues)
+        }
+    }
+
+    /// Returns whether `instance` is valid, without collecting failures.
+    pub fn is_valid(&self, instance: &Value) -> bool {
+        self.validator.is_valid(instance)
+    }
+}
+
+/// Compiles `schema` for validation, as the draft named by its `$schema` keyword.
+///
+/// Schemas without `$schema` are compiled as draft-07. A `$schema` naming a meta-schema
+/// other than the known drafts is resolved by the validator, which fails unless the
+/// meta-schema can be fetched.
+///
+/// If the schema itself is invalid, the returned issue locates the offending keyword,
+/// with `instance_path` pointing into the schema document.
+pub fn compile_schema(schema: &Value) -> Result<CompiledSchema, ValidationIssue> {
+    let draft = match schema.get("$schema") {
+        None => Some(Draft::default()),
+        Some(_) => Draft::from_schema(schema),
+    };
+    match draft {
+        Some(draft) => compile_schema_with_draft(schema, draft),
+        None => jsonschema::validator_for(schema)
+            .map(|validator| CompiledSchema { validator })
+            .map_err(ValidationIssue::from),
+    }
+}
+
+/// Compiles `schema` for validation as `draft`, whatever its `$schema` keyword says.
+///
+/// # Examples
+///
+/// ```
+/// use serde_json::json;
+/// use json_schema_generator::{compile_schema_with_draft, Draft};
+///
+/// // exclusiveMaximum is a boolean modifier of maximum in draft-04.
+/// let schema = json!({"maximum": 5, "exclusiveMaximum": true});
+/// let compiled = compile_schema_with_draft(&schema, Draft::Draft4).unwrap();
+/// assert!(!compiled.is_valid(&json!(5)));
+/// ```
+pub fn compile_schema_with_draft(
+    schema: &Value,
+    draft: Draft,
+) -> Result<CompiledSchema, ValidationIssue> {
+    jsonschema::options()
+        .with_draft(draft.into())
+        .build(schema)
+        .map(|validator| CompiledSchema { validator })
+        .map_err(ValidationIssue::from)
+}
+
+/// Validates `instance` against `schema`.
+///
+/// # Examples
+///
+/// ```
+/// use serde_json::json;
+/// use json_schema_generator::{generate_json_schema, validate_instance};
+///
+/// let schema = generate_json_schema(&json!({"name": "John"}));
+/// assert!(validate_instance(&schema, &json!({"name": "Jane"})).is_ok());
+///
+/// let issues = validate_instance(&schema, &json!({"name": 7})).unwrap_err();
+/// assert_eq!(issues[0].instance_path, "/name");
+/// ```
+pub fn validate_instance(schema: &Value, instance: &Value) -> Result<(), Vec<ValidationIssue>> {
+    compile_schema(schema)
+        .map_err(|issue| vec![issue])?
+        .validate(instance)
+}
+
+/// Validates every sample against `schema`, returning the rejected ones.
+#[cfg(feature = "validation")]
+fn collect_failures<'a>(
+    schema: &Value,
+    samples: impl IntoIterator<Item = &'a Value>,
+) -> Vec<VerificationFailure> {
+    let compiled = match compile_schema(schema) {
+        Ok(compiled) => compiled,
+        Err(issue) => {
+            return vec![VerificationFailure {
+                sample: None,
+                issues: vec![issue],
+            }]
+        }
+    };
+
+    samples
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, sample)| {
+            compiled
+                .validate(sample)
+                .err()
+                .map(|issues| VerificationFailure {
+                    sample: Some(index),
+                    issues,
+                })
+        })
+        .collect()
+}
+
+/// Validates every sample against `schema`, failing with all rejected samples.
+#[cfg(feature = "validation")]
+pub(crate) fn verify_samples<'a>(
+    schema: &Value,
+    samples: impl IntoIterator<Item = &'a Value>,
+) -> Result<(), GeneratorError> {
+    let failures = collect_failures(schema, samples);
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(GeneratorError::Verification(failures))
+    }
+}
+
+/// The result of [`generate_and_verify`].
+#[cfg(feature = "validation")]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GenerationOutcome {
+    /// The schema generated from all samples.
+    pub schema: Value,
+    /// The number of samples the schema was generated from.
+    pub samples: usize,
+    /// The samples rejected by the schema.
+    pub failures: Vec<VerificationFailure>,
+    /// The warnings raised during generation.
+    pub warnings: Vec<Warning>,
+    /// The statistics of the schema, if [`SchemaOptions::stats`] is set.
+    pub stats: Option<SchemaStats>,
+}
+
+#[cfg(feature = "validation")]
+impl GenerationOutcome {
+    /// Returns whether every sample validates against the schema.
+    pub fn all_valid(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// A one-line summary such as `3 warnings, all 120 samples validate`.
+#[cfg(feature = "validation")]
+impl fmt::Display for GenerationOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let plural = |n: usize| if n == 1 { "" } else { "s" };
+        write!(
+            f,
+            "{} warning{}, ",
+            self.warnings.len(),
+            plural(self.warnings.len())
+        )?;
+        if self.all_valid() && self.samples == 1 {
+            f.write_str("the sample validates")
+        } else if self.all_valid() {
+            write!(f, "all {} samples validate", self.samples)
+        } else if self.failures.iter().any(|failure| failure.sample.is_none()) {
+            f.write_str("the generated schema does not compile")
+        } else {
+            write!(
+                f,
+                "{} of {} sample{} fail validation",
+                self.failures.len(),
+                self.samples,
+                plural(self.samples)
+            )
+        }
+    }
+}
+
+/// Generates one schema from `samples` and validates each sample against it.
+///
+/// Unlike [`SchemaOptions::verify`], rejected samples don't make this fail: they are
+/// reported in the outcome together with the warnings raised during generation.
+///
+/// # Examples
+///
+/// ```
+/// use serde_json::json;
+/// use json_schema_generator::{generate_and_verify, SchemaOptions};
+///
+/// let samples = [json!({"id": 1}), json!({"id": 2.5})];
+/// let outcome = generate_and_verify(&samples, &SchemaOptions::default()).unwrap();
+///
+/// assert!(outcome.all_valid());
+/// assert_eq!(outcome.to_string(), "0 warnings, all 2 samples validate");
+/// ```
+#[cfg(feature = "validation")]
+pub fn generate_and_verify(
+    samples: &[Value],
+    options: &SchemaOptions,
+) -> Result<GenerationOutcome, GeneratorError> {
+    let mut generator = SchemaGenerator::new(SchemaOptions {
+        verify: false,
+        ..options.clone()
+    });
+    for sample in samples {
+        generator.observe(sample)?;
+    }
+    let warnings = generator.warnings();
+    let schema = generator.finish()?;
+    let failures = collect_failures(&schema, samples);
+    let stats = options.stats.then(|| schema_stats(&schema));
+
+    Ok(GenerationOutcome {
+        schema,
+        samples: samples.len(),
+        failures,
+        warnings,
+        stats,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate_json_schema;
+    use serde_json::json;
+
+    fn sample() -> Value {
+        json!({
+            "name": "John",
+            "address": {"city": "New York", "zip": "10001"},
+            "tags": ["a", "b"]
+        })
+    }
+
+    #[test]
+    fn test_valid_instances() {
+        let schema = generate_json_schema(&sample());
+        assert_eq!(validate_instance(&schema, &sample()), Ok(()));
+        assert_eq!(
+            validate_instance(
+                &schema,
+                &json!({"name": "Jane", "address": {"city": "Paris", "zip": "75001"}, "tags": []})
+            ),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_invalid_instance_paths() {
+        let compiled = compile_schema(&generate_json_schema(&sample())).unwrap();
+        let issues = compiled
+            .validate(
+                &json!({"name": "Jane", "address": {"city": 1, "zip": "75001"}, "tags": [true]}),
+            )
+            .unwrap_err();
+
+        let mut paths: Vec<(&str, &str)> = issues
+            .iter()
+            .map(|i| (i.instance_path.as_str(), i.schema_path.as_str()))
+            .collect();
+        paths.sort();
+        assert_eq!(
+            paths,
+            vec![
+                ("/address/city", "/properties/address/properties/city/type"),
+                ("/tags/0", "/properties/tags/items/type"),
+            ]
+        );
+        assert!(!compiled.is_valid(&json!({})));
+    }
+
+    #[test]
+    fn test_missing_required_property() {
+        let schema = generate_json_schema(&sample());
+        let issues = validate_instance(&schema, &json!({"name": "Jane", "tags": []})).unwrap_err();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].instance_path, "");
+        assert_eq!(issues[0].schema_path, "/required");
+        assert!(issues[0].message.contains("address"));
+    }
+
+    #[cfg(feature = "validation")]
+    #[test]
+    fn test_verify_samples() {
+        let schema = json!({"type": "object", "properties": {"a": {"type": "integer"}}});
+        let samples = [json!({"a": 1}), json!({"a": "x"}), json!({})];
+        match verify_samples(&schema, &samples) {
+            Err(GeneratorError::Verification(failures)) => {
+                assert_eq!(failures.len(), 1);
+                assert_eq!(failures[0].sample, Some(1));
+                assert_eq!(failures[0].issues[0].instance_path, "/a");
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert_eq!(verify_samples(&schema, &samples[..1]), Ok(()));
+    }
+
+    #[cfg(feature = "validation")]
+    #[test]
+    fn test_verify_option() {
+        use crate::{try_generate_json_schema, SchemaGenerator, SchemaOptions};
+
+        let options = SchemaOptions {
+            verify: true,
+            ..Default::default()
+        };
+        let instance = json!({"a": [1, "x", 2.5, {"b": null}]});
+        assert!(try_generate_json_schema(&instance, &options).is_ok());
+
+        let mut generator = SchemaGenerator::new(options);
+        generator.observe(&json!({"a": 1})).unwrap();
+        generator.observe(&json!({"a": 1.5, "b": true})).unwrap();
+        assert!(generator.finish().is_ok());
+    }
+
+    #[cfg(feature = "validation")]
+    #[test]
+    fn test_generate_and_verify_reports_failures_and_warnings() {
+        let samples = [
+            json!({"name": "a", "address": {"city": "x"}}),
+            json!({"name": "b", "shipping": {"$ref": "#/definitions/address"}}),
+        ];
+        let options = SchemaOptions {
+            stats: true,
+            ..Default::default()
+        };
+        let outcome = generate_and_verify(&samples, &options).unwrap();
+
+        assert_eq!(outcome.samples, 2);
+        assert_eq!(outcome.warnings.len(), 1);
+        assert_eq!(outcome.warnings[0].code, "ref-passthrough");
+        assert_eq!(outcome.warnings[0].pointer, "/properties/shipping");
+
+        // The copied reference points at a definition that doesn't exist.
+        assert!(!outcome.all_valid());
+        assert_eq!(outcome.failures[0].sample, None);
+        assert_eq!(
+            outcome.to_string(),
+            "1 warning, the generated schema does not compile"
+        );
+
+        let stats = outcome.stats.unwrap();
+        assert_eq!(stats, schema_stats(&outcome.schema));
+        assert_eq!((stats.properties, stats.required), (4, 2));
+        let unrequested = generate_and_verify(&samples, &SchemaOptions::default()).unwrap();
+        assert_eq!(unrequested.stats, None);
+    }
+
+    #[cfg(feature = "validation")]
+    #[test]
+    fn test_generate_and_verify_rejected_sample() {
+        // A verifying generator never produces such a schema, so check the summary of
+        // rejected samples on a hand-built outcome.
+        let outcome = GenerationOutcome {
+            schema: json!({"type": "integer"}),
+            samples: 3,
+            failures: collect_failures(
+                &json!({"type": "integer"}),
+                &[json!(1), json!("x"), json!(2)],
+            ),
+            warnings: Vec::new(),
+            stats: None,
+        };
+        assert_eq!(outcome.failures.len(), 1);
+        assert_eq!(outcome.failures[0].sample, Some(1));
+        assert_eq!(
+            outcome.to_string(),
+            "0 warnings, 1 of 3 samples fail validation"
+        );
+    }
+
+    #[test]
+    fn test_draft_from_schema_uri() {
+        let uris = [
+            ("http://json-schema.org/draft-04/schema#", Draft::Draft4),
+            ("http://json-schema.org/draft-06/schema", Draft::Draft6),
+            ("http://json-schema.org/draft-07/schema", Draft::Draft7),
+            (
+                "https://json-schema.org/draft/2019-09/schema#",
+                Draft::Draft201909,
+            ),
+            (
+                "https://json-schema.org/draft/2020-12/schema",
+                Draft::Draft202012,
+            ),
+        ];
+        for (uri, draft) in uris {
+            let compiled = compile_schema(&json!({"$schema": uri})).unwrap();
+            assert_eq!(compiled.draft(), Some(draft), "uri: {}", uri);
+        }
+        let generated = compile_schema(&generate_json_schema(&sample())).unwrap();
+        assert_eq!(generated.draft(), Some(Draft::Draft7));
+        assert_eq!(
+            compile_schema(&json!({})).unwrap().draft(),
+            Some(Draft::Draft7)
+        );
+    }
+
+    #[test]
+    fn test_draft_override() {
+        // In draft-04 exclusiveMaximum modifies maximum, later drafts reject a boolean.
+        let schema = json!({
+            "$schema": "http://json-schema.org/draft-07/schema#",
+            "maximum": 5,
+            "exclusiveMaximum": true
+        });
+        assert!(compile_schema(&schema).is_err());
+        let compiled = compile_schema_with_draft(&schema, Draft::Draft4).unwrap();
+        assert_eq!(compiled.draft(), Some(Draft::Draft4));
+        assert!(compiled.is_valid(&json!(4)));
+        assert!(!compiled.is_valid(&json!(5)));
+    }
+
+    #[test]
+    fn test_invalid_schema() {
+        let issue = compile_schema(&json!({"type": 12})).unwrap_err();
+        assert_eq!(issue.instance_path, "/type");
+    }
+}
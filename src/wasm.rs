@@ -0,0 +1,33 @@
+//! A `wasm-bindgen` entry point for running the generator in a browser.
+
+use wasm_bindgen::prelude::*;
+
+/// Generates the schema of the JSON document `input_json`, returning it serialized.
+///
+/// `options_json` is a JSON object such as `{"deny_lints": ["always-null"]}`, or an
+/// empty string for the default options. Errors are returned as strings.
+#[wasm_bindgen]
+pub fn generate_schema(input_json: &str, options_json: &str) -> Result<String, JsValue> {
+    crate::embed::generate(input_json, options_json)
+        .map_err(|error| JsValue::from_str(&error.to_string()))
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    #[wasm_bindgen_test]
+    fn generates_nested_object() {
+        let schema = generate_schema(r#"{"a": {"b": true}}"#, "").unwrap();
+        assert!(schema.contains(r#""b":{"type":"boolean"}"#));
+    }
+
+    #[wasm_bindgen_test]
+    fn round_trips_options() {
+        let options = r#"{"deny_lints": ["empty-items"]}"#;
+        assert!(generate_schema(r#"{"a": [1]}"#, options).is_ok());
+        let error = generate_schema(r#"{"a": []}"#, options).unwrap_err();
+        assert!(error.as_string().unwrap().contains("[empty-items]"));
+    }
+}
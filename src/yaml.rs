@@ -0,0 +1,57 @@
+//! Schemas written as YAML, for OpenAPI documents and validator configurations.
+
+use crate::AsSchemaValue;
+
+/// Serializes `schema` as a YAML document, with its keys in the order the JSON output
+/// has them and its numbers as they are.
+///
+/// A string spanning several lines, such as a description, is a block scalar, and a
+/// string that would read as another type, such as `"true"` or `"1.5"`, is quoted, so
+/// that the YAML parses back to the same schema. `schema` can be a `Value` or, with the
+/// `schemars` feature, a schemars schema.
+///
+/// # Examples
+///
+/// ```
+/// use serde_json::json;
+/// use json_schema_generator::schema_to_yaml_string;
+///
+/// let schema = json!({"type": "string", "description": "An id\nor a name", "maxLength": 10});
+/// assert_eq!(
+///     schema_to_yaml_string(&schema),
+///     "description: |-\n  An id\n  or a name\nmaxLength: 10\ntype: string\n"
+/// );
+/// ```
+pub fn schema_to_yaml_string(schema: &impl AsSchemaValue) -> String {
+    serde_yaml::to_string(&*schema.as_schema_value()).expect("JSON values serialize as YAML")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    #[test]
+    fn test_round_trip() {
+        let instance = json!({
+            "id": 18446744073709551615u64,
+            "ratio": 1.0,
+            "tags": ["true", "null", "1.5", "", "- x", "#c", null],
+            "notes": "first line\nsecond line\n",
+            "$ref": "#/$defs/a"
+        });
+        let schema = crate::generate_json_schema(&instance);
+        for value in [&instance, &schema] {
+            let yaml = schema_to_yaml_string(value);
+            let parsed: Value = serde_yaml::from_str(&yaml).unwrap();
+            assert_eq!(&parsed, value, "{}", yaml);
+        }
+
+        let yaml = schema_to_yaml_string(&instance);
+        assert!(
+            yaml.contains("notes: |\n  first line\n  second line\n"),
+            "{}",
+            yaml
+        );
+    }
+}
@@ -0,0 +1,929 @@
+//! Runs the json_schema_generator binary on files in a scratch directory.
+#![cfg(feature = "cli")]
+
+use serde_json::{json, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// An empty directory of its own for the test `name`.
+fn scratch(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("jsg-cli-{}-{}", std::process::id(), name));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Runs the binary in `dir` with the arguments `args`.
+fn run(dir: &Path, args: &[&str]) {
+    let status = Command::new(env!("CARGO_BIN_EXE_json_schema_generator"))
+        .current_dir(dir)
+        .args(args)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+/// Generates the schema of `input` into `output` with the extra arguments `args`.
+fn generate(dir: &Path, input: &str, output: &str, args: &[&str]) {
+    fs::write(dir.join("input.json"), input).unwrap();
+    run(dir, &[&["input.json", "--output", output], args].concat());
+}
+
+/// The names of the files in `dir`, sorted.
+fn files(dir: &Path) -> Vec<String> {
+    let mut names: Vec<_> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    names.sort();
+    names
+}
+
+fn read(dir: &Path, name: &str) -> String {
+    fs::read_to_string(dir.join(name)).unwrap()
+}
+
+#[test]
+fn test_backup_rotation() {
+    let dir = scratch("rotation");
+    generate(&dir, r#"{"a": 1}"#, "schema.json", &[]);
+    let first = read(&dir, "schema.json");
+    generate(&dir, r#"{"a": "x"}"#, "schema.json", &["--backup"]);
+    assert_eq!(
+        files(&dir),
+        ["input.json", "schema.json", "schema.json.bak"]
+    );
+    assert_eq!(read(&dir, "schema.json.bak"), first);
+
+    let second = read(&dir, "schema.json");
+    let keep = ["--backup=.old", "--backup-keep", "2"];
+    generate(&dir, r#"{"a": true}"#, "schema.json", &keep);
+    let third = read(&dir, "schema.json");
+    generate(&dir, r#"{"a": 1.5}"#, "schema.json", &keep);
+    generate(&dir, r#"{"a": [1]}"#, "schema.json", &keep);
+    assert_eq!(
+        files(&dir),
+        [
+            "input.json",
+            "schema.json",
+            "schema.json.bak",
+            "schema.json.old",
+            "schema.json.old.1"
+        ]
+    );
+    assert!(read(&dir, "schema.json").contains("array"));
+    assert!(read(&dir, "schema.json.old").contains("number"));
+    assert_eq!(read(&dir, "schema.json.old.1"), third);
+    assert_ne!(third, second);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_unchanged_schema_is_not_rewritten() {
+    let dir = scratch("unchanged");
+    generate(&dir, r#"{"a": 1}"#, "schema.json", &[]);
+    let modified = fs::metadata(dir.join("schema.json"))
+        .unwrap()
+        .modified()
+        .unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    generate(&dir, r#"{"a": 2}"#, "schema.json", &["--backup"]);
+    assert_eq!(files(&dir), ["input.json", "schema.json"]);
+    let metadata = fs::metadata(dir.join("schema.json")).unwrap();
+    assert_eq!(metadata.modified().unwrap(), modified);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_no_backup_by_default() {
+    let dir = scratch("default");
+    generate(&dir, r#"{"a": 1}"#, "schema.json", &[]);
+    generate(&dir, r#"{"a": "x"}"#, "schema.json", &["--force"]);
+    assert_eq!(files(&dir), ["input.json", "schema.json"]);
+    assert!(read(&dir, "schema.json").contains("string"));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_output_location() {
+    let dir = scratch("location");
+    fs::create_dir_all(dir.join("data")).unwrap();
+    fs::write(dir.join("data/users.json"), r#"{"a": 1}"#).unwrap();
+    // Next to the input, not in the working directory.
+    run(&dir, &["data/users.json"]);
+    assert_eq!(files(&dir), ["data"]);
+    assert_eq!(files(&dir.join("data")), ["users.json", "users.jsonschema"]);
+
+    run(&dir, &["data/users.json", "-o", "schema.json"]);
+    assert_eq!(
+        read(&dir, "schema.json"),
+        read(&dir, "data/users.jsonschema")
+    );
+    let output = Command::new(env!("CARGO_BIN_EXE_json_schema_generator"))
+        .current_dir(&dir)
+        .args(["data/users.json", "-o", "-"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let schema: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(schema["properties"]["a"], json!({"type": "integer"}));
+    assert_eq!(files(&dir), ["data", "schema.json"]);
+
+    // A different schema doesn't replace the file without --force.
+    fs::write(dir.join("data/users.json"), r#"{"a": "x"}"#).unwrap();
+    let before = read(&dir, "schema.json");
+    let output = Command::new(env!("CARGO_BIN_EXE_json_schema_generator"))
+        .current_dir(&dir)
+        .args(["data/users.json", "-o", "schema.json"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("--force"), "{}", stderr);
+    assert_eq!(read(&dir, "schema.json"), before);
+    assert_eq!(files(&dir), ["data", "schema.json"]);
+    run(&dir, &["data/users.json", "-o", "schema.json", "--force"]);
+    assert!(read(&dir, "schema.json").contains("string"));
+
+    run(&dir, &["data/users.json", "--output-dir", "out"]);
+    assert_eq!(
+        read(&dir, "out/users.jsonschema"),
+        read(&dir, "schema.json")
+    );
+    let status = Command::new(env!("CARGO_BIN_EXE_json_schema_generator"))
+        .current_dir(&dir)
+        .args(["data/users.json", "--output-dir", "out", "-o", "other.json"])
+        .status()
+        .unwrap();
+    assert!(!status.success());
+    assert!(!dir.join("other.json").exists());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+/// Decodes the percent-encoded `reference`.
+fn percent_decode(reference: &str) -> String {
+    let bytes = reference.as_bytes();
+    let mut decoded = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            decoded.push(u8::from_str_radix(&reference[i + 1..i + 3], 16).unwrap());
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).unwrap()
+}
+
+/// Writes three inputs, one with a name needing escaping, to `dir/fixtures`.
+fn fixtures(dir: &Path) {
+    let fixtures = dir.join("fixtures");
+    fs::create_dir_all(&fixtures).unwrap();
+    fs::write(fixtures.join("user.json"), r#"{"name": "a"}"#).unwrap();
+    fs::write(fixtures.join("order.json"), r#"{"total": 9.5}"#).unwrap();
+    fs::write(fixtures.join("line item#1.json"), r#"{"sku": 1}"#).unwrap();
+}
+
+#[test]
+fn test_directory_index() {
+    let dir = scratch("index");
+    fixtures(&dir);
+    run(&dir, &["fixtures", "--index", "catalog/index.json"]);
+    let index: Value = serde_json::from_str(&read(&dir, "catalog/index.json")).unwrap();
+    let refs: Vec<&str> = index["oneOf"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|branch| branch["$ref"].as_str().unwrap())
+        .collect();
+    // Sorted by file name, relative to the index and escaped.
+    assert_eq!(
+        refs,
+        [
+            "../fixtures/line%20item%231.jsonschema",
+            "../fixtures/order.jsonschema",
+            "../fixtures/user.jsonschema",
+        ]
+    );
+    for (reference, property) in refs.iter().zip(["sku", "total", "name"]) {
+        let path = dir.join("catalog").join(percent_decode(reference));
+        let schema: Value = serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+        assert!(
+            schema["properties"].get(property).is_some(),
+            "{}",
+            reference
+        );
+    }
+
+    // Rerunning changes nothing, and picks up neither the schemas nor their backups.
+    let before = read(&dir, "catalog/index.json");
+    fs::write(dir.join("fixtures/user.json"), r#"{"name": 1}"#).unwrap();
+    run(
+        &dir,
+        &[
+            "fixtures",
+            "--index",
+            "catalog/index.json",
+            "--backup=.old.json",
+        ],
+    );
+    assert_eq!(read(&dir, "catalog/index.json"), before);
+    assert_eq!(
+        files(&dir.join("fixtures")),
+        [
+            "line item#1.json",
+            "line item#1.jsonschema",
+            "order.json",
+            "order.jsonschema",
+            "user.json",
+            "user.jsonschema",
+            "user.jsonschema.old.json",
+        ]
+    );
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_directory_index_defs() {
+    let dir = scratch("index-defs");
+    fixtures(&dir);
+    run(
+        &dir,
+        &[
+            "fixtures",
+            "--output-dir",
+            "schemas",
+            "--index",
+            "index.json",
+            "--index-style",
+            "defs",
+            "--draft",
+            "2020-12",
+        ],
+    );
+    let index: Value = serde_json::from_str(&read(&dir, "index.json")).unwrap();
+    assert_eq!(
+        index["$schema"],
+        "https://json-schema.org/draft/2020-12/schema"
+    );
+    assert_eq!(
+        index["$defs"],
+        json!({
+            "line item#1": {"$ref": "schemas/line%20item%231.jsonschema"},
+            "order": {"$ref": "schemas/order.jsonschema"},
+            "user": {"$ref": "schemas/user.jsonschema"},
+        })
+    );
+    assert_eq!(files(&dir.join("schemas")).len(), 3);
+
+    // Up to draft-07 the entries are definitions.
+    run(
+        &dir,
+        &[
+            "fixtures",
+            "--output-dir",
+            "schemas",
+            "--index",
+            "index.json",
+            "--index-style",
+            "defs",
+            "--force",
+        ],
+    );
+    let index: Value = serde_json::from_str(&read(&dir, "index.json")).unwrap();
+    assert_eq!(index["$schema"], "http://json-schema.org/draft-07/schema#");
+    assert!(index.get("$defs").is_none());
+    assert_eq!(index["definitions"].as_object().unwrap().len(), 3);
+
+    // --output names a single file.
+    let status = Command::new(env!("CARGO_BIN_EXE_json_schema_generator"))
+        .current_dir(&dir)
+        .args(["fixtures", "--output", "schema.json"])
+        .status()
+        .unwrap();
+    assert!(!status.success());
+    assert!(!dir.join("schema.json").exists());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_directory_carries_on_past_failures() {
+    let dir = scratch("directory-failures");
+    fixtures(&dir);
+    fs::write(dir.join("fixtures/broken.json"), "{").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_json_schema_generator"))
+        .current_dir(&dir)
+        .args(["fixtures", "--index", "index.json"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("broken.json: error:"), "{}", stderr);
+    assert!(
+        stderr.contains("3 of 4 inputs succeeded, 1 failed"),
+        "{}",
+        stderr
+    );
+
+    // The others are written, and the index refers to them.
+    let index: Value = serde_json::from_str(&read(&dir, "index.json")).unwrap();
+    assert_eq!(index["oneOf"].as_array().unwrap().len(), 3);
+    assert_eq!(files(&dir.join("fixtures")).len(), 7);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_bundle() {
+    let dir = scratch("bundle");
+    fs::create_dir_all(dir.join("schemas")).unwrap();
+    fs::write(
+        dir.join("schemas/user.json"),
+        r#"{"type": "object", "properties": {"team": {"$ref": "team.json"}}}"#,
+    )
+    .unwrap();
+    fs::write(
+        dir.join("schemas/team.json"),
+        r#"{"type": "object", "properties": {"lead": {"$ref": "user.json"}}}"#,
+    )
+    .unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_json_schema_generator"))
+        .current_dir(&dir)
+        .args(["bundle", "schemas/user.json", "--bundle-mode", "defs"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let bundled: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        bundled["properties"]["team"],
+        json!({"$ref": "#/$defs/Team"})
+    );
+    assert_eq!(
+        bundled["$defs"]["Team"]["properties"]["lead"],
+        json!({"$ref": "#/$defs/User"})
+    );
+
+    fs::remove_file(dir.join("schemas/team.json")).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_json_schema_generator"))
+        .current_dir(&dir)
+        .args(["bundle", "schemas/user.json"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.starts_with("error: cannot resolve $ref 'team.json' at #/properties/team"),
+        "{}",
+        stderr
+    );
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_infer_formats() {
+    let dir = scratch("formats");
+    let input = r#"[
+        {"id": "550e8400-e29b-41d4-a716-446655440000", "at": "2024-01-15T10:30:00Z", "home": "https://example.com", "email": "a@example.com"},
+        {"id": "not-a-uuid", "at": "2024-01-16T08:00:00+01:00", "home": "https://example.org/x", "email": "not-an-email@"}
+    ]"#;
+    generate(&dir, input, "schema.json", &["--infer-formats"]);
+    let schema: Value = serde_json::from_str(&read(&dir, "schema.json")).unwrap();
+    let properties = &schema["items"]["properties"];
+    assert_eq!(properties["at"]["format"], "date-time");
+    assert_eq!(properties["home"]["format"], "uri");
+    // Formats some values lack are dropped.
+    assert_eq!(properties["id"], json!({"type": "string"}));
+    assert_eq!(properties["email"], json!({"type": "string"}));
+
+    generate(&dir, input, "plain.json", &[]);
+    let plain: Value = serde_json::from_str(&read(&dir, "plain.json")).unwrap();
+    assert_eq!(
+        plain["items"]["properties"]["at"],
+        json!({"type": "string"})
+    );
+}
+
+#[test]
+fn test_nullable_one_of() {
+    let dir = scratch("nullable");
+    let input = r#"[{"note": "a"}, {"note": null}]"#;
+    generate(&dir, input, "schema.json", &[]);
+    let schema: Value = serde_json::from_str(&read(&dir, "schema.json")).unwrap();
+    assert_eq!(
+        schema["items"]["properties"]["note"],
+        json!({"type": ["string", "null"]})
+    );
+
+    generate(&dir, input, "one_of.json", &["--nullable-one-of"]);
+    let schema: Value = serde_json::from_str(&read(&dir, "one_of.json")).unwrap();
+    assert_eq!(
+        schema["items"]["properties"]["note"],
+        json!({"oneOf": [{"type": "string"}, {"type": "null"}]})
+    );
+}
+
+#[test]
+fn test_no_additional_properties() {
+    let dir = scratch("closed");
+    let input = r#"{"id": 1, "address": {"city": "Oslo"}, "tags": [{"name": "a"}]}"#;
+    generate(&dir, input, "schema.json", &["--no-additional-properties"]);
+    let schema: Value = serde_json::from_str(&read(&dir, "schema.json")).unwrap();
+    assert_eq!(schema["additionalProperties"], false);
+    assert_eq!(
+        schema["properties"]["address"]["additionalProperties"],
+        false
+    );
+
+    let validator = jsonschema::validator_for(&schema).unwrap();
+    assert!(validator.is_valid(&serde_json::from_str(input).unwrap()));
+    for extra in [
+        json!({"id": 1, "address": {"city": "Oslo"}, "tags": [], "admin": true}),
+        json!({"id": 1, "address": {"city": "Oslo", "zip": "0150"}, "tags": []}),
+        json!({"id": 1, "address": {"city": "Oslo"}, "tags": [{"name": "a", "x": 1}]}),
+    ] {
+        assert!(!validator.is_valid(&extra), "{}", extra);
+    }
+
+    generate(&dir, input, "closed.json", &["--closed"]);
+    assert_eq!(read(&dir, "closed.json"), read(&dir, "schema.json"));
+
+    generate(&dir, input, "open.json", &[]);
+    let open: Value = serde_json::from_str(&read(&dir, "open.json")).unwrap();
+    assert!(open.get("additionalProperties").is_none());
+}
+
+#[test]
+fn test_stdin_dash() {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let dir = scratch("stdin");
+    let pipe = |input: &str, args: &[&str]| {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_json_schema_generator"))
+            .current_dir(&dir)
+            .arg("-")
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(input.as_bytes())
+            .unwrap();
+        child.wait_with_output().unwrap()
+    };
+    let output = pipe(r#"{"a": 1}"#, &[]);
+    assert!(output.status.success());
+    let schema: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(schema["properties"]["a"], json!({"type": "integer"}));
+    // Nothing is written next to the input.
+    assert!(files(&dir).is_empty());
+
+    let output = pipe("{\"a\": ", &[]);
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(!output.stderr.is_empty());
+
+    // Or into --output.
+    let output = pipe(r#"{"a": 1}"#, &["-o", "schema.json"]);
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    let written: Value = serde_json::from_str(&read(&dir, "schema.json")).unwrap();
+    assert_eq!(written, schema);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_several_inputs() {
+    let dir = scratch("several");
+    fixtures(&dir);
+    fs::write(dir.join("fixtures/broken.json"), "{\"a\": ").unwrap();
+    fs::write(dir.join("fixtures/notes.txt"), "not json").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_json_schema_generator"))
+        .current_dir(&dir)
+        .args(["fixtures/*.json", "--output-dir", "schemas"])
+        .output()
+        .unwrap();
+    // The other files are generated despite the broken one.
+    assert!(!output.status.success());
+    assert_eq!(
+        files(&dir.join("schemas")),
+        [
+            "line item#1.jsonschema",
+            "order.jsonschema",
+            "user.jsonschema"
+        ]
+    );
+    let schema: Value = serde_json::from_str(&read(&dir, "schemas/user.jsonschema")).unwrap();
+    assert_eq!(schema["properties"]["name"], json!({"type": "string"}));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let lines: Vec<&str> = stderr.lines().collect();
+    assert_eq!(lines.len(), 5, "{}", stderr);
+    assert!(lines[0].starts_with("fixtures/broken.json: error: "));
+    assert_eq!(lines[4], "3 of 4 inputs succeeded, 1 failed");
+
+    // Next to each input without --output-dir.
+    fs::remove_file(dir.join("fixtures/broken.json")).unwrap();
+    run(&dir, &["fixtures/order.json", "fixtures/user.json"]);
+    assert_eq!(
+        read(&dir, "fixtures/user.jsonschema"),
+        read(&dir, "schemas/user.jsonschema")
+    );
+    assert!(!dir.join("fixtures/line item#1.jsonschema").exists());
+
+    let output = Command::new(env!("CARGO_BIN_EXE_json_schema_generator"))
+        .current_dir(&dir)
+        .args(["fixtures/*.yaml"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_merge_inputs() {
+    let dir = scratch("merge");
+    fs::write(dir.join("a.json"), r#"{"id": 1, "name": "a"}"#).unwrap();
+    fs::write(dir.join("b.json"), r#"{"id": 2}"#).unwrap();
+    fs::write(dir.join("broken.json"), "{\"id\": ").unwrap();
+    run(&dir, &["a.json", "b.json", "-o", "schema.jsonschema"]);
+    let schema: Value = serde_json::from_str(&read(&dir, "schema.jsonschema")).unwrap();
+    assert_eq!(schema["required"], json!(["id"]));
+    assert_eq!(schema["properties"]["name"], json!({"type": "string"}));
+
+    // Named after the first input without --output.
+    run(&dir, &["a.json", "b.json", "--merge"]);
+    assert_eq!(read(&dir, "a.jsonschema"), read(&dir, "schema.jsonschema"));
+    assert!(!dir.join("b.jsonschema").exists());
+
+    // A broken input is reported and skipped, or stops everything with --strict.
+    let merge = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_json_schema_generator"))
+            .current_dir(&dir)
+            .args([&["a.json", "broken.json", "b.json", "--stdout"], args].concat())
+            .output()
+            .unwrap()
+    };
+    let output = merge(&[]);
+    assert!(!output.status.success());
+    let merged: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(merged["properties"], schema["properties"]);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.starts_with("broken.json: error: "), "{}", stderr);
+    assert!(
+        stderr.ends_with("2 of 3 inputs merged, 1 failed\n"),
+        "{}",
+        stderr
+    );
+    let output = merge(&["--strict"]);
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("broken.json"));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_draft() {
+    let dir = scratch("draft");
+    let input = r#"{"pair": [1, "a"]}"#;
+    generate(&dir, input, "schema.json", &["--draft", "2020-12"]);
+    let schema: Value = serde_json::from_str(&read(&dir, "schema.json")).unwrap();
+    assert_eq!(
+        schema["$schema"],
+        "https://json-schema.org/draft/2020-12/schema"
+    );
+    generate(&dir, input, "seven.json", &["--draft", "07"]);
+    let schema: Value = serde_json::from_str(&read(&dir, "seven.json")).unwrap();
+    assert_eq!(schema["$schema"], "http://json-schema.org/draft-07/schema#");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_json_schema_generator"))
+        .current_dir(&dir)
+        .args(["input.json", "--draft", "2021-01"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("draft-07, 2019-09 or 2020-12"),
+        "{}",
+        stderr
+    );
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_ndjson() {
+    let dir = scratch("ndjson");
+    let events =
+        "{\"type\": \"click\", \"target\": \"buy\"}\n\n{\"type\": \"view\", \"page\": 1}\n";
+    fs::write(dir.join("events.ndjson"), events).unwrap();
+    run(&dir, &["events.ndjson", "--ndjson"]);
+    let schema: Value = serde_json::from_str(&read(&dir, "events.jsonschema")).unwrap();
+    assert_eq!(schema["required"], json!(["type"]));
+    assert_eq!(schema["properties"]["page"], json!({"type": "integer"}));
+
+    fs::write(dir.join("broken.ndjson"), "{\"a\": 1}\n{\"a\"\n").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_json_schema_generator"))
+        .current_dir(&dir)
+        .args(["broken.ndjson", "--ndjson", "--stdout"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("line 2"), "{}", stderr);
+
+    // Files named .jsonl are NDJSON, and so is what --format says is.
+    fs::write(dir.join("a.jsonl"), "# day 1\n{\"id\": 1}\n").unwrap();
+    fs::write(dir.join("b.txt"), "{\"id\": 2, \"retry\": true}\n").unwrap();
+    run(&dir, &["a.jsonl"]);
+    let schema: Value = serde_json::from_str(&read(&dir, "a.jsonschema")).unwrap();
+    assert_eq!(schema["type"], "object");
+    run(
+        &dir,
+        &[
+            "a.jsonl", "b.txt", "--merge", "--format", "ndjson", "-o", "ab.json",
+        ],
+    );
+    let schema: Value = serde_json::from_str(&read(&dir, "ab.json")).unwrap();
+    assert_eq!(schema["required"], json!(["id"]));
+    assert_eq!(schema["properties"]["retry"], json!({"type": "boolean"}));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_emit_types() {
+    let dir = scratch("emit-types");
+    let input = r#"{"orderId": 1, "lines": [{"sku": "a", "qty": 2}, {"sku": "b"}]}"#;
+    fs::write(dir.join("order.json"), input).unwrap();
+    run(&dir, &["order.json", "--emit", "rust", "-o", "types.rs"]);
+    let code = read(&dir, "types.rs");
+    assert!(code.contains("pub struct Root {"), "{}", code);
+    assert!(
+        code.contains("    pub lines: Vec<LinesItem>,\n"),
+        "{}",
+        code
+    );
+    assert!(code.contains("    pub qty: Option<i64>,\n"), "{}", code);
+
+    // Next to the input by default.
+    run(&dir, &["order.json", "--emit", "rust"]);
+    assert_eq!(read(&dir, "order.rs"), code);
+
+    run(&dir, &["order.json", "--emit", "typescript"]);
+    let code = read(&dir, "order.ts");
+    assert!(code.contains("export interface LinesItem {\n"), "{}", code);
+    assert!(code.contains("  qty?: number;\n"), "{}", code);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_no_required() {
+    let dir = scratch("optional");
+    let input = r#"[{"id": 1, "owner": {"name": "a", "tags": [{"k": "v"}]}}]"#;
+    generate(&dir, input, "schema.json", &["--no-required"]);
+    let schema = read(&dir, "schema.json");
+    assert!(!schema.contains("required"), "{}", schema);
+    generate(&dir, input, "optional.json", &["--all-optional"]);
+    assert_eq!(read(&dir, "optional.json"), schema);
+    generate(&dir, input, "plain.json", &[]);
+    let plain: Value = serde_json::from_str(&read(&dir, "plain.json")).unwrap();
+    assert_eq!(
+        plain["items"]["properties"]["owner"]["required"],
+        json!(["name", "tags"])
+    );
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_formatting() {
+    let dir = scratch("formatting");
+    let input = r#"{"a": {"b": 1}}"#;
+    let stdout = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_json_schema_generator"))
+            .current_dir(&dir)
+            .args([&["input.json", "--stdout"], args].concat())
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+    generate(&dir, input, "schema.json", &[]);
+    let pretty = read(&dir, "schema.json");
+    assert!(pretty.ends_with("}\n"));
+    assert!(pretty.contains("\n  \"properties\""));
+    assert_eq!(stdout(&[]), pretty);
+
+    generate(&dir, input, "four.json", &["--indent", "4"]);
+    let four = read(&dir, "four.json");
+    assert!(
+        four.contains("\n    \"properties\": {\n        \"a\""),
+        "{}",
+        four
+    );
+    assert_eq!(stdout(&["--indent", "4"]), four);
+    assert!(stdout(&["--indent", "tab"]).contains("\n\t\"properties\""));
+
+    let compact = stdout(&["--compact"]);
+    assert!(!compact.contains('\n'));
+    let schema: Value = serde_json::from_str(&compact).unwrap();
+    assert_eq!(schema, serde_json::from_str::<Value>(&pretty).unwrap());
+    generate(&dir, input, "compact.json", &["--compact"]);
+    assert_eq!(read(&dir, "compact.json"), compact);
+
+    for args in [&["--compact", "--indent", "4"][..], &["--indent", "x"]] {
+        let status = Command::new(env!("CARGO_BIN_EXE_json_schema_generator"))
+            .current_dir(&dir)
+            .args([&["input.json", "--stdout"], args].concat())
+            .output()
+            .unwrap()
+            .status;
+        assert!(!status.success(), "{:?}", args);
+    }
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_same_schema_as_library() {
+    let dir = scratch("library");
+    let input = r##"{"zeta": 1, "alpha": {"$ref": "#/x", "b": [{"c": null}]}, "mid": [{"x": 1}, {"x": "a", "y": true}]}"##;
+    generate(&dir, input, "schema.json", &[]);
+    let instance: Value = serde_json::from_str(input).unwrap();
+    let options = json_schema_generator::SchemaOptions::default();
+    let schema = json_schema_generator::try_generate_json_schema(&instance, &options).unwrap();
+    assert_eq!(
+        read(&dir, "schema.json"),
+        serde_json::to_string_pretty(&schema).unwrap() + "\n"
+    );
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(feature = "yaml")]
+#[test]
+fn test_yaml_input() {
+    let dir = scratch("yaml");
+    let config = "\
+defaults: &defaults
+  retries: 3
+  timeout: 1.5
+services:
+  - name: api
+    <<: *defaults
+  - name: worker
+    settings: *defaults
+";
+    fs::write(dir.join("config.yaml"), config).unwrap();
+    run(&dir, &["config.yaml"]);
+    let schema: Value = serde_json::from_str(&read(&dir, "config.jsonschema")).unwrap();
+    let services = &schema["properties"]["services"]["items"];
+    assert_eq!(services["required"], json!(["name"]));
+    assert_eq!(
+        services["properties"]["settings"]["properties"]["retries"],
+        json!({"type": "integer"})
+    );
+
+    // Or as --input-format says.
+    fs::write(dir.join("config.txt"), "a: [1, true]\n").unwrap();
+    run(
+        &dir,
+        &["config.txt", "--input-format", "yaml", "-o", "txt.json"],
+    );
+    let schema: Value = serde_json::from_str(&read(&dir, "txt.json")).unwrap();
+    assert_eq!(
+        schema["properties"]["a"]["items"],
+        json!({"oneOf": [{"type": "integer"}, {"type": "boolean"}]})
+    );
+
+    fs::write(dir.join("broken.yml"), "a: [1\n").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_json_schema_generator"))
+        .current_dir(&dir)
+        .arg("broken.yml")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(!dir.join("broken.jsonschema").exists());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(feature = "yaml")]
+#[test]
+fn test_yaml_output() {
+    let dir = scratch("yaml-output");
+    let input = r#"{"id": 12345678901234567890, "ratio": 1.0, "flags": ["true", "1.5"]}"#;
+    fs::write(dir.join("record.json"), input).unwrap();
+    run(&dir, &["record.json", "--root-examples", "1"]);
+    run(
+        &dir,
+        &[
+            "record.json",
+            "--root-examples",
+            "1",
+            "--schema-format",
+            "yaml",
+        ],
+    );
+    let json: Value = serde_json::from_str(&read(&dir, "record.jsonschema")).unwrap();
+    let yaml: Value = serde_yaml::from_str(&read(&dir, "record.schema.yaml")).unwrap();
+    assert_eq!(yaml, json);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(not(feature = "yaml"))]
+#[test]
+fn test_yaml_needs_its_feature() {
+    let dir = scratch("no-yaml");
+    fs::write(dir.join("config.yaml"), "a: 1\n").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_json_schema_generator"))
+        .current_dir(&dir)
+        .arg("config.yaml")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("yaml feature"), "{}", stderr);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn test_toml_input() {
+    let dir = scratch("toml");
+    let manifest = "\
+[package]
+name = \"demo\"
+published = 2024-01-15T10:30:00Z
+
+[[bin]]
+name = \"demo\"
+";
+    fs::write(dir.join("Cargo.toml"), manifest).unwrap();
+    run(&dir, &["Cargo.toml"]);
+    let schema: Value = serde_json::from_str(&read(&dir, "Cargo.jsonschema")).unwrap();
+    assert_eq!(
+        schema["properties"]["package"]["properties"]["published"],
+        json!({"type": "string", "format": "date-time"})
+    );
+    assert_eq!(schema["properties"]["bin"]["type"], "array");
+
+    fs::write(dir.join("broken.toml"), "a = \n").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_json_schema_generator"))
+        .current_dir(&dir)
+        .arg("broken.toml")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_csv_input() {
+    let dir = scratch("csv");
+    let users = "id,name,age,active\n\"007\",Bond,42,true\n\"008\",,,false\n";
+    fs::write(dir.join("users.csv"), users).unwrap();
+    run(&dir, &["users.csv"]);
+    let schema: Value = serde_json::from_str(&read(&dir, "users.jsonschema")).unwrap();
+    assert_eq!(schema["type"], "array");
+    let properties = &schema["items"]["properties"];
+    assert_eq!(properties["id"], json!({"type": "string"}));
+    assert_eq!(properties["age"], json!({"type": ["integer", "null"]}));
+    assert_eq!(properties["active"], json!({"type": "boolean"}));
+
+    fs::write(dir.join("users.txt"), users.replace(',', ";")).unwrap();
+    let args = [
+        "users.txt",
+        "--format",
+        "csv",
+        "--csv-delimiter",
+        ";",
+        "--csv-empty",
+        "omit",
+        "-o",
+        "omitted.json",
+    ];
+    run(&dir, &args);
+    let schema: Value = serde_json::from_str(&read(&dir, "omitted.json")).unwrap();
+    assert_eq!(schema["items"]["required"], json!(["active", "id"]));
+    assert_eq!(
+        schema["items"]["properties"]["age"],
+        json!({"type": "integer"})
+    );
+
+    fs::write(dir.join("ragged.csv"), "a,b\n1,2,3\n").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_json_schema_generator"))
+        .current_dir(&dir)
+        .arg("ragged.csv")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("line 2: 3 cells"), "{}", stderr);
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,54 @@
+//! Checks that the library depends on serde and serde_json only, once the default `cli`
+//! and `hash` features are turned off.
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::collections::BTreeSet;
+use std::process::Command;
+
+/// Returns the names of the direct normal dependencies of the library with `args`.
+fn direct_dependencies(args: &[&str]) -> BTreeSet<String> {
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    let manifest = concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml");
+    let output = Command::new(cargo)
+        .args([
+            "tree",
+            "--manifest-path",
+            manifest,
+            "-p",
+            env!("CARGO_PKG_NAME"),
+        ])
+        .args([
+            "--edges", "normal", "--depth", "1", "--prefix", "none", "--format", "{p}",
+        ])
+        .args(args)
+        .output()
+        .expect("cargo runs");
+    assert!(
+        output.status.success(),
+        "cargo tree failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .filter_map(|line| line.split_whitespace().next())
+        .filter(|name| *name != env!("CARGO_PKG_NAME"))
+        .map(str::to_string)
+        .collect()
+}
+
+#[test]
+fn test_no_default_features_is_lean() {
+    assert_eq!(
+        direct_dependencies(&["--no-default-features"]),
+        BTreeSet::from(["serde".to_string(), "serde_json".to_string()])
+    );
+}
+
+#[test]
+fn test_default_features_include_the_cli() {
+    let dependencies = direct_dependencies(&[]);
+    assert!(dependencies.contains("clap"), "{:?}", dependencies);
+    assert!(dependencies.contains("sha2"), "{:?}", dependencies);
+}
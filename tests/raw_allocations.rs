@@ -0,0 +1,83 @@
+//! Compares the allocations made generating a schema from a large document through
+//! `generate_from_raw` and through a parsed `Value`.
+//!
+//! Run with `cargo test --release --test raw_allocations -- --nocapture` to see the counts.
+
+use json_schema_generator::{generate_from_raw, try_generate_json_schema, SchemaOptions};
+use serde_json::value::RawValue;
+use serde_json::Value;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+/// Returns the number of allocations and allocated bytes made by `f`.
+fn measure<T>(f: impl FnOnce() -> T) -> (T, usize, usize) {
+    let (allocations, bytes) = (
+        ALLOCATIONS.load(Ordering::Relaxed),
+        BYTES.load(Ordering::Relaxed),
+    );
+    let result = f();
+    (
+        result,
+        ALLOCATIONS.load(Ordering::Relaxed) - allocations,
+        BYTES.load(Ordering::Relaxed) - bytes,
+    )
+}
+
+fn large_document() -> String {
+    let records: Vec<String> = (0..20_000)
+        .map(|i| {
+            format!(
+                r#"{{"id": {}, "name": "user number {}", "email": "user{}@example.com", "score": {}.5, "tags": ["a", "b", "c"], "active": {}}}"#,
+                i,
+                i,
+                i,
+                i,
+                i % 2 == 0
+            )
+        })
+        .collect();
+    format!(r#"{{"users": [{}]}}"#, records.join(","))
+}
+
+#[test]
+fn test_raw_generation_allocates_less() {
+    let json = large_document();
+    let options = SchemaOptions::default();
+
+    let (from_value, value_allocations, value_bytes) = measure(|| {
+        let instance: Value = serde_json::from_str(&json).unwrap();
+        try_generate_json_schema(&instance, &options).unwrap()
+    });
+    let (from_raw, raw_allocations, raw_bytes) = measure(|| {
+        let raw: &RawValue = serde_json::from_str(&json).unwrap();
+        generate_from_raw(raw, &options).unwrap()
+    });
+
+    println!(
+        "Value: {} allocations, {} bytes; RawValue: {} allocations, {} bytes",
+        value_allocations, value_bytes, raw_allocations, raw_bytes
+    );
+    assert_eq!(from_raw, from_value);
+    assert!(raw_allocations < value_allocations);
+    assert!(raw_bytes < value_bytes);
+}
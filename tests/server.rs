@@ -0,0 +1,189 @@
+//! Runs the HTTP server on an ephemeral port and talks to it over plain TCP.
+#![cfg(feature = "server")]
+
+use json_schema_generator::server::{Server, ServerConfig, ShutdownHandle};
+use serde_json::{json, Value};
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+struct Running {
+    addr: SocketAddr,
+    handle: ShutdownHandle,
+    thread: JoinHandle<()>,
+}
+
+impl Running {
+    fn start(config: ServerConfig) -> Running {
+        let server = Server::bind("127.0.0.1:0", config).unwrap();
+        let addr = server.local_addr();
+        let handle = server.shutdown_handle();
+        let thread = thread::spawn(move || server.run());
+        Running {
+            addr,
+            handle,
+            thread,
+        }
+    }
+
+    /// Sends a request, returning the response status and JSON body.
+    fn request(&self, head: &str, body: &[u8]) -> (u16, Value) {
+        let mut stream = TcpStream::connect(self.addr).unwrap();
+        write!(stream, "{}\r\nConnection: close\r\n\r\n", head).unwrap();
+        stream.write_all(body).unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let status = response[9..12].parse().unwrap();
+        let (_, body) = response.split_once("\r\n\r\n").unwrap();
+        (status, serde_json::from_str(body).unwrap())
+    }
+
+    fn post(&self, path: &str, content_type: &str, body: &str) -> (u16, Value) {
+        let head = format!(
+            "POST {} HTTP/1.1\r\nHost: localhost\r\nContent-Type: {}\r\nContent-Length: {}",
+            path,
+            content_type,
+            body.len()
+        );
+        self.request(&head, body.as_bytes())
+    }
+
+    fn stop(self) {
+        self.handle.shutdown();
+        self.thread.join().unwrap();
+    }
+}
+
+#[test]
+fn test_generate() {
+    let server = Running::start(ServerConfig::default());
+
+    let (status, schema) = server.post("/schema", "application/json", r#"{"a": [1, 2]}"#);
+    assert_eq!(status, 200);
+    assert_eq!(
+        schema["properties"]["a"]["items"],
+        json!({"type": "integer"})
+    );
+
+    let records = "{\"id\": 1}\n{\"id\": 2.5, \"name\": \"x\"}\n";
+    let (status, schema) = server.post("/schema", "application/x-ndjson", records);
+    assert_eq!(status, 200);
+    assert_eq!(schema["properties"]["id"], json!({"type": "number"}));
+    assert_eq!(schema["properties"]["name"], json!({"type": "string"}));
+
+    let (status, health) = server.request("GET /healthz HTTP/1.1\r\nHost: localhost", b"");
+    assert_eq!((status, health), (200, json!({"status": "ok"})));
+
+    server.stop();
+}
+
+#[test]
+fn test_options() {
+    let server = Running::start(ServerConfig::default());
+    let input = r#"{"deleted_at": null}"#;
+
+    let (status, error) = server.post("/schema?deny_lints=always-null", "application/json", input);
+    assert_eq!(status, 422);
+    assert_eq!(error["error"]["code"], "denied-warning");
+
+    let envelope =
+        json!({"input": {"deleted_at": null}, "options": {"deny_lints": ["always-null"]}});
+    let (status, _) = server.post(
+        "/schema?envelope=true",
+        "application/json",
+        &envelope.to_string(),
+    );
+    assert_eq!(status, 422);
+
+    let envelope = json!({"input": {"deleted_at": null}});
+    let (status, schema) = server.post(
+        "/schema?envelope=true",
+        "application/json",
+        &envelope.to_string(),
+    );
+    assert_eq!(status, 200);
+    assert_eq!(schema["properties"]["deleted_at"], json!({"type": "null"}));
+
+    let (status, error) = server.post("/schema?strict", "application/json", "[1, \"a\"]");
+    assert_eq!(status, 422);
+    assert_eq!(error["error"]["code"], "conflict");
+
+    let (status, error) = server.post("/schema?colour=red", "application/json", input);
+    assert_eq!(status, 400);
+    assert_eq!(error["error"]["code"], "invalid-options");
+
+    server.stop();
+}
+
+#[test]
+fn test_invalid_requests() {
+    let server = Running::start(ServerConfig::default());
+
+    let (status, error) = server.post("/schema", "application/json", "{\"a\": ");
+    assert_eq!(status, 400);
+    assert_eq!(error["error"]["code"], "invalid-input");
+    assert!(error["error"]["message"]
+        .as_str()
+        .unwrap()
+        .starts_with("invalid JSON"));
+
+    let (status, error) = server.post("/schema", "application/x-ndjson", "1\n{\n");
+    assert_eq!(status, 400);
+    assert!(error["error"]["message"]
+        .as_str()
+        .unwrap()
+        .starts_with("invalid JSON on line 2"));
+
+    let (status, error) = server.request("GET /schema HTTP/1.1\r\nHost: localhost", b"");
+    assert_eq!(status, 405);
+    assert_eq!(error["error"]["code"], "method-not-allowed");
+
+    let (status, error) = server.request("GET /nowhere HTTP/1.1\r\nHost: localhost", b"");
+    assert_eq!(status, 404);
+    assert_eq!(error["error"]["code"], "not-found");
+
+    server.stop();
+}
+
+#[test]
+fn test_oversized_body() {
+    let server = Running::start(ServerConfig {
+        max_body_bytes: 64,
+        ..Default::default()
+    });
+    let large = json!({"text": "x".repeat(256)}).to_string();
+
+    let (status, error) = server.post("/schema", "application/json", &large);
+    assert_eq!(status, 413);
+    assert_eq!(error["error"]["code"], "payload-too-large");
+
+    // Without a Content-Length the limit is applied while reading.
+    let chunk = format!("{:x}\r\n{}\r\n0\r\n\r\n", large.len(), large);
+    let (status, _) = server.request(
+        "POST /schema HTTP/1.1\r\nHost: localhost\r\nTransfer-Encoding: chunked",
+        chunk.as_bytes(),
+    );
+    assert_eq!(status, 413);
+
+    let (status, _) = server.post("/schema", "application/json", r#"{"a": 1}"#);
+    assert_eq!(status, 200);
+
+    server.stop();
+}
+
+#[test]
+fn test_timeout() {
+    let server = Running::start(ServerConfig {
+        timeout: Duration::ZERO,
+        ..Default::default()
+    });
+    let records = "{\"a\": [1, 2, 3], \"b\": {\"c\": \"x\"}}\n".repeat(20_000);
+
+    let (status, error) = server.post("/schema", "application/x-ndjson", &records);
+    assert_eq!(status, 503);
+    assert_eq!(error["error"]["code"], "timeout");
+
+    server.stop();
+}